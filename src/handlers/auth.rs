@@ -0,0 +1,186 @@
+//! Authentication middleware for API keys and JWT bearer tokens.
+//!
+//! Applied to every route with `route_layer`, so it runs after routing and
+//! can decide the required scope from the matched route pattern. Requests
+//! present either a JWT in `Authorization: Bearer <token>` or an API key in
+//! the `X-API-Key` header.
+//!
+//! # Route Scopes
+//!
+//! - `GET /`, `/health`, `/metrics`, `/samples`, `OPTIONS /uploads/tus`: Public
+//! - `GET /analyze/{url}`: `analyze`
+//! - `POST /pipelines`: `custom_pipelines`
+//! - `POST /templates`, `DELETE /templates/{name}`: `templates:write`
+//! - Other `GET` routes (pipelines, outputs, uploads): `pipelines:read`
+//! - Everything else (jobs, template instantiation, stopping, uploading, tus): `pipelines:write`
+//!
+//! When the service runs without `--api-keys`, `--jwt-secret` and `--jwks`,
+//! every request proceeds as [`Caller::Anonymous`].
+
+use axum::{
+    extract::{MatchedPath, Request, State},
+    http::{header, HeaderMap, HeaderName, Method, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Json, Response},
+};
+use tracing::warn;
+
+// ---
+
+// Import through gateway
+use crate::models::{ApiError, Caller, Scope};
+
+// ---
+
+// Shared state
+use super::AppState;
+
+/// Header carrying the client's API key
+const API_KEY_HEADER: HeaderName = HeaderName::from_static("x-api-key");
+
+/// Authenticates the request and checks the scope its route requires.
+///
+/// On success the [`Caller`] is inserted into the request extensions for
+/// handlers to extract. A bearer token takes precedence over an API key.
+///
+/// # Response Behavior
+/// - **401 Unauthorized**: No credentials, an unknown API key, or a token
+///   that fails verification (carries `WWW-Authenticate: Bearer` when JWTs
+///   are accepted)
+/// - **403 Forbidden**: The credentials lack the scope the route requires
+///
+/// # Example Usage
+/// ```bash
+/// curl -H "X-API-Key: $MEDIA_API_KEY" http://localhost:8080/pipelines
+/// curl -H "Authorization: Bearer $TOKEN" http://localhost:8080/pipelines
+/// ```
+pub async fn authenticate(
+    State(state): State<AppState>,
+    mut request: Request,
+    next: Next,
+) -> Response {
+    // ---
+    if state.api_keys.is_none() && state.jwt.is_none() {
+        request.extensions_mut().insert(Caller::Anonymous);
+        return next.run(request).await;
+    }
+
+    let route = request
+        .extensions()
+        .get::<MatchedPath>()
+        .map(|path| path.as_str().to_string())
+        .unwrap_or_default();
+    let Some(scope) = required_scope(request.method(), &route) else {
+        request.extensions_mut().insert(Caller::Anonymous);
+        return next.run(request).await;
+    };
+
+    let caller = match identify(&state, request.headers()) {
+        Ok(caller) => caller,
+        Err((error, details)) => {
+            warn!("Rejected request to {}: {}", route, details);
+            return reject(&state, StatusCode::UNAUTHORIZED, error, &details);
+        }
+    };
+
+    if !caller.has_scope(scope) {
+        warn!(
+            "Refused {} on {}: missing scope '{}'",
+            caller.principal(),
+            route,
+            scope.name()
+        );
+        return reject(
+            &state,
+            StatusCode::FORBIDDEN,
+            "Insufficient scope",
+            &format!("This operation requires the '{}' scope", scope.name()),
+        );
+    }
+
+    request.extensions_mut().insert(caller);
+    next.run(request).await
+}
+
+/// Verifies the credentials presented in `headers`.
+///
+/// # Errors
+/// Returns the error message and details of the 401 response.
+fn identify(state: &AppState, headers: &HeaderMap) -> Result<Caller, (&'static str, String)> {
+    // ---
+    let bearer = headers
+        .get(header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "));
+    if let Some(token) = bearer {
+        let Some(jwt) = &state.jwt else {
+            return Err((
+                "Invalid credentials",
+                "Bearer tokens are not accepted".to_string(),
+            ));
+        };
+        return jwt.verify(token.trim()).map_err(|e| ("Invalid token", e));
+    }
+
+    if let Some(key) = headers
+        .get(API_KEY_HEADER)
+        .and_then(|value| value.to_str().ok())
+    {
+        let Some(api_keys) = &state.api_keys else {
+            return Err((
+                "Invalid credentials",
+                "API keys are not accepted".to_string(),
+            ));
+        };
+        return api_keys.authenticate(key).ok_or_else(|| {
+            (
+                "Invalid API key",
+                "The presented API key is not configured".to_string(),
+            )
+        });
+    }
+
+    let expected = match (&state.api_keys, &state.jwt) {
+        (Some(_), Some(_)) => {
+            "Send a bearer token in the Authorization header or an API key in X-API-Key"
+        }
+        (None, Some(_)) => "Send a bearer token in the Authorization header",
+        _ => "Send an API key in the X-API-Key header",
+    };
+    Err(("Authentication required", expected.to_string()))
+}
+
+/// Returns the scope needed for `method` on the route pattern `route`.
+///
+/// Returns None for public routes. Templates are shared by every caller, so
+/// changing them takes `templates:write`; instantiating one only runs a
+/// pipeline its author vetted and needs `pipelines:write`.
+fn required_scope(method: &Method, route: &str) -> Option<Scope> {
+    // ---
+    match (method, route) {
+        (_, "/" | "/health" | "/metrics" | "/samples") => None,
+        (&Method::OPTIONS, "/uploads/tus") => None,
+        (_, "/uploads/tus" | "/uploads/tus/:id") => Some(Scope::PipelinesWrite),
+        (_, "/analyze/*url") => Some(Scope::Analyze),
+        (&Method::POST, "/pipelines") => Some(Scope::CustomPipelines),
+        (&Method::POST, "/templates") | (&Method::DELETE, "/templates/:name") => {
+            Some(Scope::TemplatesWrite)
+        }
+        (&Method::POST, "/templates/:name/instantiate") => Some(Scope::PipelinesWrite),
+        (&Method::GET | &Method::HEAD, _) => Some(Scope::PipelinesRead),
+        _ => Some(Scope::PipelinesWrite),
+    }
+}
+
+/// Builds an authentication failure response.
+fn reject(state: &AppState, status: StatusCode, error: &str, details: &str) -> Response {
+    // ---
+    let mut response = (status, Json(ApiError::with_details(error, details))).into_response();
+    if status == StatusCode::UNAUTHORIZED && state.jwt.is_some() {
+        response.headers_mut().insert(
+            header::WWW_AUTHENTICATE,
+            header::HeaderValue::from_static("Bearer"),
+        );
+    }
+    response
+}
@@ -0,0 +1,69 @@
+//! Element catalog HTTP endpoint handlers.
+//!
+//! Exposes the GStreamer registry the way `gst-inspect-1.0` does, so clients
+//! building custom pipelines or graphs can discover which elements are
+//! installed, which pads they offer and which properties they accept.
+
+use axum::{
+    extract::{Path, Query},
+    http::StatusCode,
+    response::Json,
+};
+
+// ---
+
+// Import through gateways
+use crate::models::{ApiError, ElementDetails, ElementListQuery, ElementListResponse};
+use crate::services;
+
+/// Lists the element factories installed on this server.
+///
+/// # Query Parameters
+/// - `klass`: Keep factories whose classification contains every
+///   `/`-separated component, case-insensitively (e.g. `Codec/Encoder/Video`
+///   or just `Sink`)
+///
+/// # Response Behavior
+/// - **200 OK**: `{"elements": [...]}` in name order, possibly empty
+///
+/// # Example Usage
+/// ```bash
+/// curl "http://localhost:8080/elements?klass=Codec/Encoder/Video"
+/// ```
+pub async fn list_elements(Query(query): Query<ElementListQuery>) -> Json<ElementListResponse> {
+    // ---
+    Json(ElementListResponse {
+        elements: services::list_elements(query.klass.as_deref()),
+    })
+}
+
+/// Describes one element factory: metadata, pad templates with their caps,
+/// and properties with their types, defaults and ranges.
+///
+/// # Path Parameters
+/// - `factory`: Factory name as used in pipeline strings (e.g. `x264enc`)
+///
+/// # Response Behavior
+/// - **200 OK**: The factory's details
+/// - **404 Not Found**: No installed plugin provides this factory
+///
+/// # Example Usage
+/// ```bash
+/// curl http://localhost:8080/elements/x264enc
+/// ```
+pub async fn get_element(
+    Path(factory): Path<String>,
+) -> Result<Json<ElementDetails>, (StatusCode, Json<ApiError>)> {
+    // ---
+    services::describe_element(&factory)
+        .map(Json)
+        .ok_or_else(|| {
+            (
+                StatusCode::NOT_FOUND,
+                Json(ApiError::with_details(
+                    "Element not found",
+                    &format!("No installed plugin provides element '{factory}'"),
+                )),
+            )
+        })
+}
@@ -0,0 +1,220 @@
+//! Live pipeline control HTTP endpoint handlers.
+//!
+//! Adjusts pipelines while a worker is executing them, without restarting
+//! the job: changes apply to the GStreamer elements directly and are not
+//! persisted to the pipeline record.
+//!
+//! All endpoints here answer 409 Conflict for pipelines that are not
+//! running, since there is no live GStreamer pipeline to act on.
+
+use axum::{
+    extract::{Extension, Path, State},
+    http::StatusCode,
+    response::Json,
+};
+
+// ---
+
+// Import through gateways
+use crate::models::{
+    ApiError, Caller, ElementUpdateResponse, PipelineInfo, PipelinePosition, SeekRequest,
+    UpdateElementRequest,
+};
+use crate::services::{self, ControlError};
+
+// ---
+
+// Shared state and error helpers
+use super::{find_pipeline, AppState};
+
+/// Sets writable properties of an element in a running pipeline.
+///
+/// Every property is validated against the element's `ParamSpec` (existence,
+/// writability while playing, type and range) before any is applied, so a
+/// rejected request leaves the element unchanged.
+///
+/// # Path Parameters
+/// - `id`: The unique UUID identifier of the pipeline
+/// - `name`: Element name, as given with `name=` in the pipeline string or
+///   reported by the topology endpoint
+///
+/// # Request Body
+/// - `properties`: New values keyed by property name. Enums and flags take
+///   their nick (e.g. `"zero"`), caps their string form.
+///
+/// # Response Behavior
+/// - **200 OK**: Properties applied, with their old and new values
+/// - **400 Bad Request**: Unknown or read-only property, or a value of the wrong type or out of range
+/// - **404 Not Found**: No pipeline exists with the specified ID (or it was created by another
+///   API key), or the pipeline has no element with that name
+/// - **409 Conflict**: The pipeline is not running
+///
+/// # Example Usage
+/// ```bash
+/// curl -X PATCH http://localhost:8080/pipelines/550e8400-e29b-41d4-a716-446655440000/elements/enc \
+///   -H "Content-Type: application/json" \
+///   -d '{"properties": {"bitrate": 1500}}'
+/// ```
+///
+/// # Response Example
+/// ```json
+/// {
+///   "pipeline_id": "550e8400-e29b-41d4-a716-446655440000",
+///   "element": "enc",
+///   "factory": "x264enc",
+///   "properties": [{ "name": "bitrate", "old_value": 2048, "new_value": 1500 }]
+/// }
+/// ```
+pub async fn update_element(
+    State(state): State<AppState>,
+    Extension(caller): Extension<Caller>,
+    Path((id, name)): Path<(String, String)>,
+    Json(request): Json<UpdateElementRequest>,
+) -> Result<Json<ElementUpdateResponse>, (StatusCode, Json<ApiError>)> {
+    // ---
+    let pipeline = find_pipeline(&state, &caller, &id)?;
+
+    let response = services::update_element(&state.runtime, &id, &name, &request.properties)
+        .map_err(|e| control_error(&pipeline, e))?;
+
+    for change in &response.properties {
+        tracing::info!(
+            "Set {}.{} on pipeline {}: {} -> {}",
+            name,
+            change.name,
+            id,
+            change.old_value,
+            change.new_value
+        );
+    }
+
+    Ok(Json(response))
+}
+
+/// Reports the playback position, duration and rate of a running pipeline.
+///
+/// # Path Parameters
+/// - `id`: The unique UUID identifier of the pipeline
+///
+/// # Response Behavior
+/// - **200 OK**: Position and duration in seconds (null when unknown), rate and seekability
+/// - **404 Not Found**: No pipeline exists with the specified ID, or it was created by another API key
+/// - **409 Conflict**: The pipeline is not running
+///
+/// # Example Usage
+/// ```bash
+/// curl http://localhost:8080/pipelines/550e8400-e29b-41d4-a716-446655440000/position
+/// ```
+///
+/// # Response Example
+/// ```json
+/// {
+///   "pipeline_id": "550e8400-e29b-41d4-a716-446655440000",
+///   "position": 12.48,
+///   "duration": 125.4,
+///   "rate": 1.0,
+///   "seekable": true
+/// }
+/// ```
+pub async fn get_pipeline_position(
+    State(state): State<AppState>,
+    Extension(caller): Extension<Caller>,
+    Path(id): Path<String>,
+) -> Result<Json<PipelinePosition>, (StatusCode, Json<ApiError>)> {
+    // ---
+    let pipeline = find_pipeline(&state, &caller, &id)?;
+    let position = services::pipeline_position(&state.runtime, &id)
+        .map_err(|e| control_error(&pipeline, e))?;
+
+    Ok(Json(position))
+}
+
+/// Seeks a running pipeline and/or changes its playback rate.
+///
+/// Flushing seeks (the default) wait briefly for the pipeline to settle at
+/// the new position before responding. Negative rates play backwards, where
+/// the pipeline's elements support it.
+///
+/// # Path Parameters
+/// - `id`: The unique UUID identifier of the pipeline
+///
+/// # Request Body
+/// - `position`: Target in seconds; omit to keep the current position and change only the rate
+/// - `rate`: Playback rate, default 1.0
+/// - `flush`: Default true; `accurate` and `key_unit`: default false
+///
+/// # Response Behavior
+/// - **200 OK**: Position after the seek
+/// - **400 Bad Request**: Zero rate or negative position
+/// - **404 Not Found**: No pipeline exists with the specified ID, or it was created by another API key
+/// - **409 Conflict**: The pipeline is not running
+/// - **422 Unprocessable Entity**: The pipeline refused the seek (e.g. a live source)
+///
+/// # Example Usage
+/// ```bash
+/// curl -X POST http://localhost:8080/pipelines/550e8400-e29b-41d4-a716-446655440000/seek \
+///   -H "Content-Type: application/json" \
+///   -d '{"position": 30.0, "accurate": true}'
+/// ```
+pub async fn seek_pipeline(
+    State(state): State<AppState>,
+    Extension(caller): Extension<Caller>,
+    Path(id): Path<String>,
+    Json(request): Json<SeekRequest>,
+) -> Result<Json<PipelinePosition>, (StatusCode, Json<ApiError>)> {
+    // ---
+    let pipeline = find_pipeline(&state, &caller, &id)?;
+
+    // Flushing seeks block until the pipeline has prerolled again
+    let runtime = state.runtime.clone();
+    let pipeline_id = id.clone();
+    let position = tokio::task::spawn_blocking(move || {
+        services::seek_pipeline(&runtime, &pipeline_id, &request)
+    })
+    .await
+    .map_err(|e| control_error(&pipeline, ControlError::Rejected(e.to_string())))?
+    .map_err(|e| control_error(&pipeline, e))?;
+
+    tracing::info!(
+        "Seeked pipeline {} to {:?}s at rate {}",
+        id,
+        position.position,
+        position.rate
+    );
+    Ok(Json(position))
+}
+
+/// Converts a live control failure into an error response.
+fn control_error(pipeline: &PipelineInfo, error: ControlError) -> (StatusCode, Json<ApiError>) {
+    // ---
+    match error {
+        ControlError::NotRunning => (
+            StatusCode::CONFLICT,
+            Json(ApiError::with_details(
+                "Pipeline is not running",
+                &format!(
+                    "Only running pipelines can be controlled; this one is {:?}",
+                    pipeline.state
+                ),
+            )),
+        ),
+        ControlError::ElementNotFound(name) => (
+            StatusCode::NOT_FOUND,
+            Json(ApiError::with_details(
+                "Element not found",
+                &format!("The pipeline has no element named '{name}'"),
+            )),
+        ),
+        ControlError::Invalid(details) => (
+            StatusCode::BAD_REQUEST,
+            Json(ApiError::with_details("Invalid control request", &details)),
+        ),
+        ControlError::Rejected(details) => (
+            StatusCode::UNPROCESSABLE_ENTITY,
+            Json(ApiError::with_details(
+                "Pipeline refused the request",
+                &details,
+            )),
+        ),
+    }
+}
@@ -0,0 +1,199 @@
+//! Pipeline introspection HTTP endpoint handlers.
+//!
+//! Exposes what GStreamer actually built from a pipeline string, which is
+//! what users need when a custom pipeline fails to link or negotiates
+//! unexpected caps.
+//!
+//! # Pipeline Instances
+//!
+//! Running pipelines are inspected live. Pipelines that have not run yet are
+//! instantiated and prerolled, and finished ones are instantiated without
+//! being started. The graph's `X-Pipeline-Graph-Source` header and the
+//! topology's `source` field report which (`live`, `prerolled` or `parsed`).
+
+use axum::{
+    extract::{Extension, Path, Query, State},
+    http::{header, HeaderName, StatusCode},
+    response::{IntoResponse, Json, Response},
+};
+
+// ---
+
+// Import through gateways
+use crate::models::{ApiError, Caller, GraphQuery, PipelineTopology};
+use crate::services::{
+    parse_graph_details, pipeline_graph, pipeline_topology, render_svg, GraphFormat, RenderError,
+    GRAPH_DETAIL_NAMES,
+};
+
+// ---
+
+// Shared state and error helpers
+use super::{find_pipeline, AppState};
+
+/// Response header naming where the inspected pipeline instance came from
+const GRAPH_SOURCE: HeaderName = HeaderName::from_static("x-pipeline-graph-source");
+
+/// Exports a pipeline's element graph as Graphviz DOT or SVG.
+///
+/// The graph shows every element, its pads and links and, for live and
+/// prerolled pipelines, the negotiated caps. SVG rendering requires the
+/// Graphviz `dot` binary on the server.
+///
+/// # Path Parameters
+/// - `id`: The unique UUID identifier of the pipeline
+///
+/// # Query Parameters
+/// - `format`: `dot` (default) or `svg`
+/// - `details`: Comma-separated detail levels (`media-type`, `caps-details`,
+///   `non-default-params`, `states`, `full-params`, `all`, `verbose`);
+///   defaults to `all`
+///
+/// # Response Behavior
+/// - **200 OK**: Graph as `text/vnd.graphviz` or `image/svg+xml`, with
+///   `X-Pipeline-Graph-Source`
+/// - **400 Bad Request**: Unknown format or detail level
+/// - **404 Not Found**: No pipeline exists with the specified ID, or it was created by another API key
+/// - **422 Unprocessable Entity**: The pipeline string can no longer be instantiated
+/// - **501 Not Implemented**: SVG requested but `dot` is not installed
+/// - **500 Internal Server Error**: `dot` failed to render the graph
+///
+/// # Example Usage
+/// ```bash
+/// curl "http://localhost:8080/pipelines/550e8400-e29b-41d4-a716-446655440000/graph?details=media-type,caps-details" \
+///   | dot -Tpng > pipeline.png
+/// ```
+pub async fn get_pipeline_graph(
+    State(state): State<AppState>,
+    Extension(caller): Extension<Caller>,
+    Path(id): Path<String>,
+    Query(query): Query<GraphQuery>,
+) -> Result<Response, (StatusCode, Json<ApiError>)> {
+    // ---
+    let format = match query.format.as_deref() {
+        None => GraphFormat::Dot,
+        Some(value) => GraphFormat::parse(value).ok_or_else(|| {
+            (
+                StatusCode::BAD_REQUEST,
+                Json(ApiError::with_details(
+                    "Invalid graph format",
+                    &format!("Unknown format '{value}'; expected dot or svg"),
+                )),
+            )
+        })?,
+    };
+    let details =
+        parse_graph_details(query.details.as_deref().unwrap_or("all")).map_err(|name| {
+            (
+                StatusCode::BAD_REQUEST,
+                Json(ApiError::with_details(
+                    "Invalid graph details",
+                    &format!(
+                        "Unknown detail level '{name}'; expected any of {}",
+                        GRAPH_DETAIL_NAMES.join(", ")
+                    ),
+                )),
+            )
+        })?;
+
+    let pipeline = find_pipeline(&state, &caller, &id)?;
+    let runtime = state.runtime.clone();
+    let (dot, source) =
+        tokio::task::spawn_blocking(move || pipeline_graph(&runtime, &pipeline, details))
+            .await
+            .map_err(|e| instantiation_error(e.into()))?
+            .map_err(instantiation_error)?;
+
+    let body = match format {
+        GraphFormat::Dot => dot.into_bytes(),
+        GraphFormat::Svg => render_svg(&dot).await.map_err(|e| match e {
+            RenderError::Unavailable => (
+                StatusCode::NOT_IMPLEMENTED,
+                Json(ApiError::with_details(
+                    "SVG rendering unavailable",
+                    "Graphviz 'dot' is not installed on the server; request format=dot instead",
+                )),
+            ),
+            RenderError::Failed(details) => {
+                tracing::error!("Failed to render graph of {}: {}", id, details);
+                (
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    Json(ApiError::with_details("Failed to render graph", &details)),
+                )
+            }
+        })?,
+    };
+
+    Ok((
+        [
+            (header::CONTENT_TYPE, format.content_type()),
+            (GRAPH_SOURCE, source.name()),
+        ],
+        body,
+    )
+        .into_response())
+}
+
+/// Describes a pipeline's elements, pads and links as JSON.
+///
+/// Intended for clients that draw pipelines themselves, such as a node
+/// editor: each element lists its factory, readable properties and pads
+/// (direction, peer and current caps), and each link joins a source pad to
+/// a sink pad. Caps are only known for live and prerolled pipelines.
+///
+/// # Path Parameters
+/// - `id`: The unique UUID identifier of the pipeline
+///
+/// # Response Behavior
+/// - **200 OK**: Pipeline topology
+/// - **404 Not Found**: No pipeline exists with the specified ID, or it was created by another API key
+/// - **422 Unprocessable Entity**: The pipeline string can no longer be instantiated
+///
+/// # Example Usage
+/// ```bash
+/// curl http://localhost:8080/pipelines/550e8400-e29b-41d4-a716-446655440000/topology
+/// ```
+///
+/// # Response Example
+/// ```json
+/// {
+///   "pipeline_id": "550e8400-e29b-41d4-a716-446655440000",
+///   "source": "parsed",
+///   "elements": [
+///     {
+///       "name": "filesrc0",
+///       "factory": "filesrc",
+///       "properties": { "blocksize": 4096, "location": "/media/in.mp4", "...": "..." },
+///       "pads": [{ "name": "src", "direction": "src", "peer": "fakesink0.sink", "caps": null }]
+///     }
+///   ],
+///   "links": [{ "from": "filesrc0.src", "to": "fakesink0.sink", "caps": null }]
+/// }
+/// ```
+pub async fn get_pipeline_topology(
+    State(state): State<AppState>,
+    Extension(caller): Extension<Caller>,
+    Path(id): Path<String>,
+) -> Result<Json<PipelineTopology>, (StatusCode, Json<ApiError>)> {
+    // ---
+    let pipeline = find_pipeline(&state, &caller, &id)?;
+    let runtime = state.runtime.clone();
+    let topology = tokio::task::spawn_blocking(move || pipeline_topology(&runtime, &pipeline))
+        .await
+        .map_err(|e| instantiation_error(e.into()))?
+        .map_err(instantiation_error)?;
+
+    Ok(Json(topology))
+}
+
+/// Converts a failure to instantiate a stored pipeline into a 422 response.
+fn instantiation_error(error: anyhow::Error) -> (StatusCode, Json<ApiError>) {
+    // ---
+    (
+        StatusCode::UNPROCESSABLE_ENTITY,
+        Json(ApiError::with_details(
+            "Failed to instantiate pipeline",
+            &error.to_string(),
+        )),
+    )
+}
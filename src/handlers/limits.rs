@@ -0,0 +1,213 @@
+//! Rate limiting and job quota middleware.
+//!
+//! Runs after [`authenticate`](super::authenticate), so clients can be
+//! identified by their owner rather than only by IP address. Every limited
+//! response carries the client's remaining allowance:
+//!
+//! - `X-RateLimit-Limit` / `X-RateLimit-Remaining`: Token bucket of the route group
+//! - `X-Quota-Running-Limit` / `X-Quota-Running-Remaining`: Active pipelines (job submissions)
+//! - `X-Quota-Output-Bytes-Limit` / `X-Quota-Output-Bytes-Remaining`: Output bytes (job submissions)
+//!
+//! Refused requests get `429 Too Many Requests` with `Retry-After`.
+
+use axum::{
+    extract::{ConnectInfo, MatchedPath, Request, State},
+    http::{header, HeaderMap, HeaderName, HeaderValue, Method, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Json, Response},
+};
+use std::net::SocketAddr;
+use std::time::{Duration, Instant};
+use tracing::warn;
+
+// ---
+
+// Import through gateways
+use crate::models::{ApiError, Caller};
+use crate::services::{QuotaError, QuotaUsage, RouteGroup};
+
+// ---
+
+// Shared state
+use super::AppState;
+
+const RATE_LIMIT_LIMIT: HeaderName = HeaderName::from_static("x-ratelimit-limit");
+const RATE_LIMIT_REMAINING: HeaderName = HeaderName::from_static("x-ratelimit-remaining");
+const QUOTA_RUNNING_LIMIT: HeaderName = HeaderName::from_static("x-quota-running-limit");
+const QUOTA_RUNNING_REMAINING: HeaderName = HeaderName::from_static("x-quota-running-remaining");
+const QUOTA_BYTES_LIMIT: HeaderName = HeaderName::from_static("x-quota-output-bytes-limit");
+const QUOTA_BYTES_REMAINING: HeaderName = HeaderName::from_static("x-quota-output-bytes-remaining");
+
+/// Suggested wait after a quota refusal; quotas free up as jobs finish or expire
+const QUOTA_RETRY_AFTER: Duration = Duration::from_secs(30);
+
+/// Applies the route group's rate limit and, for job submissions, the
+/// caller's quotas.
+///
+/// # Response Behavior
+/// - **429 Too Many Requests**: Rate limit or quota exhausted; `Retry-After`
+///   gives the seconds to wait
+/// - **500 Internal Server Error**: Quota usage could not be determined
+pub async fn limit_requests(
+    State(state): State<AppState>,
+    request: Request,
+    next: Next,
+) -> Response {
+    // ---
+    let route = request
+        .extensions()
+        .get::<MatchedPath>()
+        .map(|path| path.as_str().to_string())
+        .unwrap_or_default();
+    let Some(group) = route_group(request.method(), &route) else {
+        return next.run(request).await;
+    };
+
+    let owner = request.extensions().get::<Caller>().and_then(Caller::owner);
+    let client = match &owner {
+        Some(owner) => format!("owner:{owner}"),
+        None => request
+            .extensions()
+            .get::<ConnectInfo<SocketAddr>>()
+            .map(|ConnectInfo(address)| format!("ip:{}", address.ip()))
+            .unwrap_or_else(|| "ip:unknown".to_string()),
+    };
+
+    let mut headers = HeaderMap::new();
+
+    if let Some(decision) = state.limiter.check(&client, group, Instant::now()) {
+        headers.insert(RATE_LIMIT_LIMIT, HeaderValue::from(decision.limit));
+        headers.insert(RATE_LIMIT_REMAINING, HeaderValue::from(decision.remaining));
+
+        if let Some(wait) = decision.retry_after {
+            warn!("Rate limited {} on {} group", client, group.name());
+            return too_many_requests(
+                headers,
+                wait,
+                "Rate limit exceeded",
+                &format!(
+                    "At most {} {} requests are allowed in a burst",
+                    decision.limit,
+                    group.name()
+                ),
+            );
+        }
+    }
+
+    let submitted_job = group == RouteGroup::Jobs;
+    // The reservation is held until the handler has stored (or refused) the job
+    let reservation = match owner.filter(|_| submitted_job) {
+        Some(owner) => {
+            let quotas = state.quotas.clone();
+            let reserve = tokio::task::spawn_blocking(move || quotas.reserve(&owner)).await;
+            match reserve {
+                Ok(Ok(reservation)) => Some(reservation),
+                Ok(Err(QuotaError::Running(running))) => {
+                    return too_many_requests(
+                        headers,
+                        QUOTA_RETRY_AFTER,
+                        "Quota exceeded",
+                        &format!("{running} pipelines are already queued or running"),
+                    );
+                }
+                Ok(Err(QuotaError::OutputBytes(bytes))) => {
+                    return too_many_requests(
+                        headers,
+                        QUOTA_RETRY_AFTER,
+                        "Quota exceeded",
+                        &format!("Outputs already take {bytes} bytes"),
+                    );
+                }
+                Ok(Err(QuotaError::Io(e))) => return quota_error(e),
+                Err(e) => return quota_error(e.into()),
+            }
+        }
+        None => None,
+    };
+
+    let mut response = next.run(request).await;
+    if let Some(reservation) = reservation {
+        let created = response.status().is_success();
+        add_quota_headers(&state, &mut headers, reservation.usage(), created);
+    }
+    response.headers_mut().extend(headers);
+    response
+}
+
+/// Returns the rate limit group of `method` on the route pattern `route`.
+///
+/// Returns None for routes that are never limited.
+fn route_group(method: &Method, route: &str) -> Option<RouteGroup> {
+    // ---
+    match (method, route) {
+        (_, "/" | "/health" | "/metrics" | "/samples") => None,
+        (_, "/analyze/*url") => Some(RouteGroup::Analyze),
+        (
+            &Method::POST,
+            "/convert"
+            | "/thumbnail"
+            | "/stream"
+            | "/pipelines"
+            | "/pipelines/:id/retry"
+            | "/templates/:name/instantiate",
+        ) => Some(RouteGroup::Jobs),
+        (_, route) if route.starts_with("/uploads") => Some(RouteGroup::Uploads),
+        _ => Some(RouteGroup::Api),
+    }
+}
+
+/// Adds the quota headers for a job submission.
+///
+/// `created` accounts for the job just accepted in the remaining allowance.
+fn add_quota_headers(state: &AppState, headers: &mut HeaderMap, usage: QuotaUsage, created: bool) {
+    // ---
+    let limits = state.quotas.limits();
+
+    if let Some(max_running) = limits.max_running {
+        let running = usage.running + usize::from(created);
+        headers.insert(QUOTA_RUNNING_LIMIT, HeaderValue::from(max_running));
+        headers.insert(
+            QUOTA_RUNNING_REMAINING,
+            HeaderValue::from(max_running.saturating_sub(running)),
+        );
+    }
+    if let Some(max_bytes) = limits.max_output_bytes {
+        headers.insert(QUOTA_BYTES_LIMIT, HeaderValue::from(max_bytes));
+        headers.insert(
+            QUOTA_BYTES_REMAINING,
+            HeaderValue::from(max_bytes.saturating_sub(usage.output_bytes)),
+        );
+    }
+}
+
+/// Builds a 429 response with `Retry-After` rounded up to whole seconds.
+fn too_many_requests(
+    mut headers: HeaderMap,
+    wait: Duration,
+    error: &str,
+    details: &str,
+) -> Response {
+    // ---
+    let seconds = wait.as_secs() + u64::from(wait.subsec_nanos() > 0);
+    headers.insert(header::RETRY_AFTER, HeaderValue::from(seconds.max(1)));
+    (
+        StatusCode::TOO_MANY_REQUESTS,
+        headers,
+        Json(ApiError::with_details(error, details)),
+    )
+        .into_response()
+}
+
+/// Builds the 500 response for quota usage that could not be determined.
+fn quota_error(error: anyhow::Error) -> Response {
+    // ---
+    tracing::error!("Quota check failed: {:#}", error);
+    (
+        StatusCode::INTERNAL_SERVER_ERROR,
+        Json(ApiError::with_details(
+            "Quota check failed",
+            &error.to_string(),
+        )),
+    )
+        .into_response()
+}
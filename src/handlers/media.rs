@@ -14,20 +14,30 @@
 //! # Processing Architecture
 //!
 //! All handlers follow a consistent pattern:
-//! 1. URL validation and media analysis
+//! 1. Source resolution (HTTP(S) URL or `upload://<id>`) and media analysis
 //! 2. Pipeline string generation using optimized templates
 //! 3. Pipeline validation before storage
 //! 4. State management with unique tracking IDs
 //! 5. Asynchronous execution with status tracking
 //!
+//! # Output Locations
+//!
+//! Every job writes into its own directory, `{--output-dir}/{pipeline_id}/`
+//! (or `{--output-dir}/{owner}/{pipeline_id}/` for authenticated callers),
+//! provided by the configured output storage backend.
+//!
 //! # Error Handling Strategy
 //!
 //! Handlers provide comprehensive error reporting with HTTP status codes
 //! that distinguish between client errors (validation failures) and server
 //! errors (processing issues), enabling appropriate client retry logic.
 
-use super::AppState;
-use axum::{extract::State, http::StatusCode, response::Json};
+use super::{check_capacity, resolve_limits, resolve_retry, storage_error, store_error, AppState};
+use axum::{
+    extract::{Extension, State},
+    http::StatusCode,
+    response::Json,
+};
 use chrono::Utc;
 use tracing::{info, warn};
 use uuid::Uuid;
@@ -36,12 +46,13 @@ use uuid::Uuid;
 
 // Import through gateways
 use crate::models::{
-    ApiError, ConvertRequest, ConvertResponse, PipelineInfo, PipelineState, StreamRequest,
-    StreamResponse, ThumbnailInfo, ThumbnailRequest, ThumbnailResponse,
+    ApiError, Caller, ConvertRequest, ConvertResponse, PipelineInfo, PipelineKind, PipelineState,
+    StreamRequest, StreamResponse, ThumbnailInfo, ThumbnailRequest, ThumbnailResponse,
 };
 use crate::services::{
     create_conversion_pipeline, create_hls_stream_pipeline, create_thumbnail_pipeline,
-    get_media_info, validate_pipeline_string,
+    get_media_info, public_link, validate_callback_url, validate_pipeline_string,
+    validate_source_url,
 };
 
 // ---
@@ -67,7 +78,7 @@ use crate::services::{
 /// - **avi**: H.264 video codec with AVI container (legacy compatibility)
 ///
 /// # Validation Process
-/// 1. **URL Validation**: Ensures source URL uses HTTP(S) protocol
+/// 1. **URL Validation**: Ensures source URL uses HTTP(S) or names a completed upload
 /// 2. **Media Analysis**: Attempts to probe source media characteristics
 /// 3. **Pipeline Generation**: Creates optimized conversion pipeline
 /// 4. **Pipeline Validation**: Verifies generated pipeline syntax
@@ -75,8 +86,9 @@ use crate::services::{
 ///
 /// # Response Behavior
 /// - **200 OK**: Conversion pipeline created successfully
-/// - **400 Bad Request**: Invalid source URL or unsupported format
+/// - **400 Bad Request**: Invalid source URL, callback URL, execution limits, retry policy, or unsupported format
 /// - **500 Internal Server Error**: Pipeline generation or validation failure
+/// - **507 Insufficient Storage**: Output storage is above its high watermark
 ///
 /// # Processing Characteristics
 /// - **Asynchronous**: Conversion runs independently of HTTP request
@@ -105,6 +117,7 @@ use crate::services::{
 /// ```
 pub async fn convert_media(
     State(state): State<AppState>,
+    Extension(caller): Extension<Caller>,
     Json(payload): Json<ConvertRequest>,
 ) -> Result<Json<ConvertResponse>, (StatusCode, Json<ApiError>)> {
     // ---
@@ -116,16 +129,21 @@ pub async fn convert_media(
         payload.source_url, payload.output_format
     );
 
-    // Validate URL format
-    if !payload.source_url.starts_with("http") {
+    // Resolve the source URL or upload reference
+    let source = resolve_source(&state, &caller, &payload.source_url)?;
+
+    // Validate completion webhook target
+    if let Err(e) = validate_callback_url(payload.callback_url.as_deref()) {
         return Err((
             StatusCode::BAD_REQUEST,
-            Json(ApiError::new("Source URL must be a valid HTTP(S) URL")),
+            Json(ApiError::with_details("Invalid callback URL", &e)),
         ));
     }
+    let limits = resolve_limits(&state, payload.limits)?;
+    let retry = resolve_retry(payload.retry)?;
 
     // Try to get media info first to validate the source
-    match get_media_info(&payload.source_url) {
+    match get_media_info(&source) {
         Ok(media_info) => {
             info!("Source media format: {}", media_info.format);
         }
@@ -135,13 +153,17 @@ pub async fn convert_media(
         }
     }
 
-    // Create output path
-    let output_path = format!("output_{}.{}", pipeline_id, payload.output_format);
+    // Create output path inside the job directory
+    let output_path = state
+        .storage
+        .job_dir(caller.owner().as_deref(), &pipeline_id)
+        .join(format!("output.{}", payload.output_format))
+        .display()
+        .to_string();
 
     // Use validation service to create proper pipeline
     let pipeline_string =
-        match create_conversion_pipeline(&payload.source_url, &payload.output_format, &output_path)
-        {
+        match create_conversion_pipeline(&source, &payload.output_format, &output_path) {
             Ok(pipeline) => pipeline,
             Err(e) => {
                 return Err((
@@ -166,16 +188,30 @@ pub async fn convert_media(
     let pipeline_info = PipelineInfo {
         id: pipeline_id.clone(),
         description: format!("Convert to {}", payload.output_format),
+        kind: PipelineKind::Convert,
+        owner: caller.owner(),
         state: PipelineState::Created,
         pipeline_string,
+        graph: None,
         created_at: Utc::now().to_rfc3339(),
+        finished_at: None,
         source_url: Some(payload.source_url),
+        outputs: vec![output_path],
+        published_outputs: Vec::new(),
+        callback_url: payload.callback_url,
+        webhook_deliveries: Vec::new(),
+        limits,
+        retry,
+        attempts: Vec::new(),
     };
 
-    {
-        let mut pipelines = state.lock().unwrap();
-        pipelines.insert(pipeline_id.clone(), pipeline_info);
-    }
+    check_capacity(&state.retention)?;
+    state
+        .storage
+        .prepare_job_dir(caller.owner().as_deref(), &pipeline_id)
+        .map_err(storage_error)?;
+    state.store.insert(pipeline_info).map_err(store_error)?;
+    state.runtime.submit(&pipeline_id);
 
     Ok(Json(ConvertResponse {
         pipeline_id,
@@ -223,8 +259,9 @@ pub async fn convert_media(
 ///
 /// # Response Behavior
 /// - **200 OK**: Thumbnail generation pipeline created successfully
-/// - **400 Bad Request**: Invalid source URL or parameters
+/// - **400 Bad Request**: Invalid source URL, callback URL, execution limits, retry policy, or parameters
 /// - **500 Internal Server Error**: Pipeline generation failure
+/// - **507 Insufficient Storage**: Output storage is above its high watermark
 ///
 /// # Use Cases
 /// - **Video Previews**: Generate preview images for video catalogs
@@ -260,6 +297,7 @@ pub async fn convert_media(
 /// ```
 pub async fn generate_thumbnail(
     State(state): State<AppState>,
+    Extension(caller): Extension<Caller>,
     Json(payload): Json<ThumbnailRequest>,
 ) -> Result<Json<ThumbnailResponse>, (StatusCode, Json<ApiError>)> {
     // ---
@@ -274,16 +312,21 @@ pub async fn generate_thumbnail(
         payload.source_url, timestamp
     );
 
-    // Validate source URL
-    if !payload.source_url.starts_with("http") {
+    // Resolve the source URL or upload reference
+    let source = resolve_source(&state, &caller, &payload.source_url)?;
+
+    // Validate completion webhook target
+    if let Err(e) = validate_callback_url(payload.callback_url.as_deref()) {
         return Err((
             StatusCode::BAD_REQUEST,
-            Json(ApiError::new("Source URL must be a valid HTTP(S) URL")),
+            Json(ApiError::with_details("Invalid callback URL", &e)),
         ));
     }
+    let limits = resolve_limits(&state, payload.limits)?;
+    let retry = resolve_retry(payload.retry)?;
 
     // Try to get media info to validate it's actually video content
-    match get_media_info(&payload.source_url) {
+    match get_media_info(&source) {
         Ok(media_info) => {
             if media_info.width.is_none() || media_info.height.is_none() {
                 warn!("Source may not be video content - proceeding anyway");
@@ -300,12 +343,17 @@ pub async fn generate_thumbnail(
         }
     }
 
-    // Create output path
-    let output_path = format!("thumb_{pipeline_id}.png");
+    // Create output path inside the job directory
+    let output_path = state
+        .storage
+        .job_dir(caller.owner().as_deref(), &pipeline_id)
+        .join("thumbnail.png")
+        .display()
+        .to_string();
 
     // Use validation service to create thumbnail pipeline
     let pipeline_string =
-        create_thumbnail_pipeline(&payload.source_url, &output_path, width, height, &timestamp);
+        create_thumbnail_pipeline(&source, &output_path, width, height, &timestamp);
 
     // Validate the generated pipeline
     if let Err(validation_error) = validate_pipeline_string(&pipeline_string) {
@@ -322,16 +370,30 @@ pub async fn generate_thumbnail(
     let pipeline_info = PipelineInfo {
         id: pipeline_id.clone(),
         description: "Generate thumbnail".to_string(),
+        kind: PipelineKind::Thumbnail,
+        owner: caller.owner(),
         state: PipelineState::Created,
         pipeline_string,
+        graph: None,
         created_at: Utc::now().to_rfc3339(),
+        finished_at: None,
         source_url: Some(payload.source_url),
+        outputs: vec![output_path],
+        published_outputs: Vec::new(),
+        callback_url: payload.callback_url,
+        webhook_deliveries: Vec::new(),
+        limits,
+        retry,
+        attempts: Vec::new(),
     };
 
-    {
-        let mut pipelines = state.lock().unwrap();
-        pipelines.insert(pipeline_id.clone(), pipeline_info);
-    }
+    check_capacity(&state.retention)?;
+    state
+        .storage
+        .prepare_job_dir(caller.owner().as_deref(), &pipeline_id)
+        .map_err(storage_error)?;
+    state.store.insert(pipeline_info).map_err(store_error)?;
+    state.runtime.submit(&pipeline_id);
 
     Ok(Json(ThumbnailResponse {
         pipeline_id,
@@ -392,8 +454,9 @@ pub async fn generate_thumbnail(
 ///
 /// # Response Behavior
 /// - **200 OK**: Streaming pipeline created with access URL
-/// - **400 Bad Request**: Invalid source URL or unsupported stream type
+/// - **400 Bad Request**: Invalid source URL, callback URL, execution limits, retry policy, or unsupported stream type
 /// - **500 Internal Server Error**: Pipeline generation failure
+/// - **507 Insufficient Storage**: Output storage is above its high watermark
 ///
 /// # Client Integration
 /// The returned stream URL can be used directly with:
@@ -417,12 +480,13 @@ pub async fn generate_thumbnail(
 /// {
 ///   "pipeline_id": "550e8400-e29b-41d4-a716-446655440002",
 ///   "status": "created",
-///   "stream_url": "http://localhost:8080/stream/550e8400-e29b-41d4-a716-446655440002/playlist.m3u8",
+///   "stream_url": "/pipelines/550e8400-e29b-41d4-a716-446655440002/outputs/playlist.m3u8",
 ///   "message": "HLS stream created successfully"
 /// }
 /// ```
 pub async fn create_stream(
     State(state): State<AppState>,
+    Extension(caller): Extension<Caller>,
     Json(payload): Json<StreamRequest>,
 ) -> Result<Json<StreamResponse>, (StatusCode, Json<ApiError>)> {
     // ---
@@ -434,13 +498,18 @@ pub async fn create_stream(
         payload.stream_type, payload.source_url
     );
 
-    // Validate source URL
-    if !payload.source_url.starts_with("http") {
+    // Resolve the source URL or upload reference
+    let source = resolve_source(&state, &caller, &payload.source_url)?;
+
+    // Validate completion webhook target
+    if let Err(e) = validate_callback_url(payload.callback_url.as_deref()) {
         return Err((
             StatusCode::BAD_REQUEST,
-            Json(ApiError::new("Source URL must be a valid HTTP(S) URL")),
+            Json(ApiError::with_details("Invalid callback URL", &e)),
         ));
     }
+    let limits = resolve_limits(&state, payload.limits)?;
+    let retry = resolve_retry(payload.retry)?;
 
     // Validate supported stream types
     if payload.stream_type != "hls" {
@@ -452,11 +521,15 @@ pub async fn create_stream(
         ));
     }
 
-    // Create output directory path
-    let output_dir = format!("stream_{pipeline_id}");
+    // The job directory receives the playlist and segments
+    let output_dir = state
+        .storage
+        .job_dir(caller.owner().as_deref(), &pipeline_id)
+        .display()
+        .to_string();
 
     // Use validation service to create streaming pipeline
-    let pipeline_string = create_hls_stream_pipeline(&payload.source_url, &output_dir);
+    let pipeline_string = create_hls_stream_pipeline(&source, &output_dir);
 
     // Validate the generated pipeline
     if let Err(validation_error) = validate_pipeline_string(&pipeline_string) {
@@ -473,19 +546,34 @@ pub async fn create_stream(
     let pipeline_info = PipelineInfo {
         id: pipeline_id.clone(),
         description: format!("{} streaming", payload.stream_type.to_uppercase()),
+        kind: PipelineKind::Stream,
+        owner: caller.owner(),
         state: PipelineState::Created,
         pipeline_string,
+        graph: None,
         created_at: Utc::now().to_rfc3339(),
+        finished_at: None,
         source_url: Some(payload.source_url),
+        outputs: vec![output_dir],
+        published_outputs: Vec::new(),
+        callback_url: payload.callback_url,
+        webhook_deliveries: Vec::new(),
+        limits,
+        retry,
+        attempts: Vec::new(),
     };
 
-    {
-        let mut pipelines = state.lock().unwrap();
-        pipelines.insert(pipeline_id.clone(), pipeline_info);
-    }
-
-    let stream_url = Some(format!(
-        "http://localhost:8080/stream/{pipeline_id}/playlist.m3u8",
+    check_capacity(&state.retention)?;
+    state
+        .storage
+        .prepare_job_dir(caller.owner().as_deref(), &pipeline_id)
+        .map_err(storage_error)?;
+    state.store.insert(pipeline_info).map_err(store_error)?;
+    state.runtime.submit(&pipeline_id);
+
+    let stream_url = Some(public_link(
+        state.public_url.as_deref(),
+        &format!("/pipelines/{pipeline_id}/outputs/playlist.m3u8"),
     ));
 
     Ok(Json(StreamResponse {
@@ -498,3 +586,33 @@ pub async fn create_stream(
         ),
     }))
 }
+
+/// Resolves a job's `source_url` to the location its pipeline reads from.
+///
+/// HTTP(S) URLs are used as given; `upload://<id>` references resolve to one
+/// of the caller's uploaded files on disk. Anything else is rejected, so a job
+/// never reads an arbitrary local path or another owner's upload.
+fn resolve_source(
+    state: &AppState,
+    caller: &Caller,
+    source_url: &str,
+) -> Result<String, (StatusCode, Json<ApiError>)> {
+    // ---
+    match state.uploads.resolve(caller.owner().as_deref(), source_url) {
+        Ok(Some(path)) => Ok(path.display().to_string()),
+        Ok(None) => match validate_source_url(source_url) {
+            Ok(()) => Ok(source_url.to_string()),
+            Err(e) => Err((
+                StatusCode::BAD_REQUEST,
+                Json(ApiError::with_details(
+                    "Source URL must be a valid HTTP(S) URL or upload://<id>",
+                    &e,
+                )),
+            )),
+        },
+        Err(e) => Err((
+            StatusCode::BAD_REQUEST,
+            Json(ApiError::with_details("Unknown upload", &e)),
+        )),
+    }
+}
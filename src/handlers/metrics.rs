@@ -0,0 +1,89 @@
+//! Prometheus metrics endpoint and request instrumentation.
+//!
+//! [`track_requests`] is the outermost route layer, so it also counts
+//! requests refused by authentication or rate limiting. Requests are labeled
+//! with their route pattern (e.g. `/pipelines/:id`) rather than the concrete
+//! path, keeping one series per endpoint.
+
+use axum::{
+    extract::{MatchedPath, Request, State},
+    http::{header, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Json, Response},
+};
+use std::time::Instant;
+
+// ---
+
+// Import through gateways
+use crate::models::ApiError;
+use crate::services::METRICS_CONTENT_TYPE;
+
+// ---
+
+// Shared state
+use super::{store_error, AppState};
+
+/// Records the count and latency of every routed request.
+pub async fn track_requests(
+    State(state): State<AppState>,
+    request: Request,
+    next: Next,
+) -> Response {
+    // ---
+    let route = request
+        .extensions()
+        .get::<MatchedPath>()
+        .map(|path| path.as_str().to_string())
+        .unwrap_or_default();
+    let method = request.method().clone();
+    let started = Instant::now();
+
+    let response = next.run(request).await;
+
+    state.metrics.observe_request(
+        method.as_str(),
+        &route,
+        response.status().as_u16(),
+        started.elapsed(),
+    );
+    response
+}
+
+/// Exports service metrics in the Prometheus text exposition format.
+///
+/// Public like `/health`, so Prometheus can scrape it without credentials.
+/// Labels carry route patterns, pipeline kinds and states, and element
+/// factories, never pipeline IDs, owners or URLs.
+///
+/// # Response Behavior
+/// - **200 OK**: Metrics as `text/plain; version=0.0.4`
+/// - **500 Internal Server Error**: Pipeline records could not be counted
+///
+/// # Example Usage
+/// ```bash
+/// curl http://localhost:8080/metrics
+/// ```
+///
+/// # Response Example
+/// ```text
+/// # HELP media_pipeline_queue_depth Pipelines submitted but not yet running
+/// # TYPE media_pipeline_queue_depth gauge
+/// media_pipeline_queue_depth 3
+/// # HELP media_pipelines Pipeline records by kind and state
+/// # TYPE media_pipelines gauge
+/// media_pipelines{kind="convert",state="Playing"} 2
+/// ```
+pub async fn export_metrics(
+    State(state): State<AppState>,
+) -> Result<Response, (StatusCode, Json<ApiError>)> {
+    // ---
+    let metrics = state.metrics.clone();
+    let store = state.store.clone();
+    let text = tokio::task::spawn_blocking(move || metrics.render(&store))
+        .await
+        .map_err(|e| store_error(e.into()))?
+        .map_err(store_error)?;
+
+    Ok(([(header::CONTENT_TYPE, METRICS_CONTENT_TYPE)], text).into_response())
+}
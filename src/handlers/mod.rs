@@ -6,10 +6,25 @@
 //!
 //! # Handler Organization
 //!
-//! Handlers are logically grouped into three categories based on functionality:
+//! Handlers are logically grouped into twelve categories based on functionality:
 //! - **Media Processing**   : Core media operations (conversion, thumbnails, streaming)
 //! - **Pipeline Management**: CRUD operations for custom pipeline lifecycles
+//! - **Introspection**      : Graphs and topology of the elements GStreamer built for a pipeline
+//! - **Live Control**       : Element properties, seeking and position of running pipelines
+//! - **Snapshots**          : JPEG/PNG stills of the latest frame of running pipelines
+//! - **Templates**          : Stored pipeline templates and creating pipelines from them
+//! - **Element Catalog**    : Installed element factories, their pads and properties
+//! - **Output Downloads**   : Streaming finished artifacts with range support
+//! - **Uploads**            : Receiving source media for jobs, directly or resumably (tus)
 //! - **Service Operations** : Health checks, samples, and service discovery
+//! - **Metrics**            : Prometheus export and request instrumentation
+//! - **Telemetry**          : Request spans for OpenTelemetry tracing
+//!
+//! Every route runs inside a span opened by the [`trace_requests`] middleware,
+//! is counted by the [`track_requests`] middleware and passes
+//! through the [`authenticate`] middleware, which checks
+//! the caller's API key or bearer token and scope, and then through the
+//! [`limit_requests`] middleware, which applies rate limits and job quotas.
 //!
 //! # EMBP Implementation
 //!
@@ -27,31 +42,326 @@
 //!
 //! # State Management
 //!
-//! Handlers share application state through the `AppState` type alias,
-//! providing thread-safe access to pipeline tracking and service configuration.
+//! Handlers share application state through the `AppState` struct,
+//! providing thread-safe access to the pipeline store, the execution runtime,
+//! the output storage backend, the retention manager, the upload and template
+//! stores, the configured credentials, the rate limiter, quotas and execution
+//! limits, the metrics, the captured pipeline logs and the public base URL.
 //! This enables coordinated management of pipeline lifecycles across endpoints.
 
 // ---
 
 // EMBP Handlers Gateway: Controls public API for all handler functions
+mod auth;
+mod catalog;
+mod control;
+mod introspection;
+mod limits;
 mod media;
+mod metrics;
+mod outputs;
 mod pipeline;
 mod samples;
+mod snapshot;
+mod telemetry;
+mod templates;
+mod tus;
+mod uploads;
 
 // ---
 
 // Public exports - this defines the entire public handlers API
+pub use auth::authenticate;
+pub use catalog::{get_element, list_elements};
+pub use control::{get_pipeline_position, seek_pipeline, update_element};
+pub use introspection::{get_pipeline_graph, get_pipeline_topology};
+pub use limits::limit_requests;
 pub use media::{convert_media, create_stream, generate_thumbnail};
-pub use pipeline::{analyze_media, create_pipeline, get_pipeline, list_pipelines, stop_pipeline};
+pub use metrics::{export_metrics, track_requests};
+pub use outputs::{download_named_output, download_output};
+pub use pipeline::{
+    analyze_media, create_pipeline, get_pipeline, get_pipeline_logs, list_pipelines,
+    retry_pipeline, stop_pipeline,
+};
 pub use samples::{health_check, list_sample_media};
+pub use snapshot::{get_snapshot_jpeg, get_snapshot_png};
+pub use telemetry::trace_requests;
+pub use templates::{
+    create_template, delete_template, get_template, instantiate_template, list_templates,
+};
+pub use tus::{tus_create, tus_delete, tus_head, tus_options, tus_patch};
+pub use uploads::{get_upload, upload_media};
 
 // Import stuff needed to define AppState below
-use crate::models::PipelineInfo;
-use std::collections::HashMap;
-use std::sync::{Arc, Mutex};
+use crate::models::{
+    ApiError, Caller, ExecutionLimits, PipelineGraph, PipelineInfo, PipelineKind, PipelineState,
+    RetryPolicy,
+};
+use crate::services::{
+    confine_outputs, resolve_retry_policy, validate_callback_url, validate_pipeline_graph,
+    validate_pipeline_string, ApiKeys, ExecutionLimitPolicy, JwtVerifier, Metrics, PipelineLogs,
+    PipelineRuntime, Quotas, RateLimiter, RetentionManager, SharedOutputStorage,
+    SharedPipelineStore, TemplateStore, TusUploads, UploadStore,
+};
+use axum::{http::StatusCode, response::Json};
+use chrono::Utc;
+use std::sync::Arc;
+use uuid::Uuid;
 
 /// Shared application state for pipeline tracking across all handlers.
 ///
-/// Provides thread-safe access to the pipeline registry, enabling
-/// coordinated management of pipeline lifecycles across all HTTP endpoints.
-pub type AppState = Arc<Mutex<HashMap<String, PipelineInfo>>>;
+/// Provides thread-safe access to the pipeline store and the execution
+/// runtime, enabling coordinated management of pipeline lifecycles across
+/// all HTTP endpoints.
+#[derive(Clone)]
+pub struct AppState {
+    // ---
+    /// Store holding every pipeline record known to the service
+    pub store: SharedPipelineStore,
+
+    /// Runtime that executes submitted pipelines and drives state transitions
+    pub runtime: PipelineRuntime,
+
+    /// Backend deciding where job outputs are written and published
+    pub storage: SharedOutputStorage,
+
+    /// Retention policy enforcement: purging records and guarding disk capacity
+    pub retention: RetentionManager,
+
+    /// Directory of uploaded source media referenced as `upload://<id>`
+    pub uploads: UploadStore,
+
+    /// Resumable (tus) uploads completing into the upload store
+    pub tus: TusUploads,
+
+    /// Stored pipeline templates
+    pub templates: TemplateStore,
+
+    /// Accepted API keys (API key authentication is disabled when None)
+    pub api_keys: Option<Arc<ApiKeys>>,
+
+    /// Bearer token verifier (JWT authentication is disabled when None)
+    pub jwt: Option<Arc<JwtVerifier>>,
+
+    /// Per-client token buckets for each route group
+    pub limiter: RateLimiter,
+
+    /// Per-owner limits on active pipelines and output bytes
+    pub quotas: Quotas,
+
+    /// Defaults and caps of each job's runtime and output size
+    pub execution_limits: ExecutionLimitPolicy,
+
+    /// Prometheus metrics exported by `GET /metrics`
+    pub metrics: Metrics,
+
+    /// Captured GStreamer messages of each pipeline
+    pub logs: PipelineLogs,
+
+    /// Base URL prefixed to links in responses (links are relative when None)
+    pub public_url: Option<String>,
+}
+
+/// Loads a pipeline the caller is allowed to see.
+///
+/// Pipelines of other owners (API keys or tenants) are reported as not
+/// found, so callers cannot probe for each other's job IDs.
+fn find_pipeline(
+    state: &AppState,
+    caller: &Caller,
+    id: &str,
+) -> Result<PipelineInfo, (StatusCode, Json<ApiError>)> {
+    // ---
+    state
+        .store
+        .get(id)
+        .map_err(store_error)?
+        .filter(|pipeline| caller.can_access(pipeline))
+        .ok_or_else(|| {
+            (
+                StatusCode::NOT_FOUND,
+                Json(ApiError::new("Pipeline not found")),
+            )
+        })
+}
+
+/// Per-job settings every job request accepts alongside its pipeline.
+struct JobSettings {
+    // ---
+    /// Completion webhook target
+    callback_url: Option<String>,
+
+    /// Requested runtime and output size limits
+    limits: ExecutionLimits,
+
+    /// Requested automatic retry policy
+    retry: Option<RetryPolicy>,
+}
+
+/// Validates a custom pipeline, records it and queues it for execution.
+///
+/// Shared by `POST /pipelines` and template instantiation, so both apply the
+/// same validation, capacity check and ownership. Pipelines created from a
+/// `graph` pass it along with its display string and are built from it.
+/// `filesink` locations are resolved against the job directory, and
+/// locations outside it are refused.
+fn submit_custom_pipeline(
+    state: &AppState,
+    caller: &Caller,
+    description: String,
+    pipeline: String,
+    graph: Option<PipelineGraph>,
+    settings: JobSettings,
+) -> Result<PipelineInfo, (StatusCode, Json<ApiError>)> {
+    // ---
+    let pipeline_id = Uuid::new_v4().to_string();
+
+    tracing::info!("Creating pipeline: {} - {}", pipeline_id, description);
+
+    // Validate the pipeline using our validation service
+    let validated = match &graph {
+        Some(graph) => validate_pipeline_graph(graph),
+        None => validate_pipeline_string(&pipeline),
+    };
+    if let Err(validation_error) = validated {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            Json(ApiError::with_details(
+                "Invalid pipeline configuration",
+                &validation_error,
+            )),
+        ));
+    }
+
+    // Validate completion webhook target
+    if let Err(e) = validate_callback_url(settings.callback_url.as_deref()) {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            Json(ApiError::with_details("Invalid callback URL", &e)),
+        ));
+    }
+    let limits = resolve_limits(state, settings.limits)?;
+    let retry = resolve_retry(settings.retry)?;
+
+    // File sinks may only write into the job directory
+    let job_dir = state
+        .storage
+        .job_dir(caller.owner().as_deref(), &pipeline_id);
+    let outputs = confine_outputs(&pipeline, graph.as_ref(), &job_dir).map_err(|details| {
+        (
+            StatusCode::BAD_REQUEST,
+            Json(ApiError::with_details(
+                "Invalid pipeline configuration",
+                &details,
+            )),
+        )
+    })?;
+
+    check_capacity(&state.retention)?;
+
+    let pipeline_info = PipelineInfo {
+        id: pipeline_id.clone(),
+        description,
+        kind: PipelineKind::Custom,
+        owner: caller.owner(),
+        state: PipelineState::Created,
+        outputs,
+        published_outputs: Vec::new(),
+        pipeline_string: pipeline,
+        graph,
+        created_at: Utc::now().to_rfc3339(),
+        finished_at: None,
+        source_url: None,
+        callback_url: settings.callback_url,
+        webhook_deliveries: Vec::new(),
+        limits,
+        retry,
+        attempts: Vec::new(),
+    };
+
+    // Store the pipeline info and queue it for execution
+    state
+        .storage
+        .prepare_job_dir(caller.owner().as_deref(), &pipeline_id)
+        .map_err(storage_error)?;
+    state
+        .store
+        .insert(pipeline_info.clone())
+        .map_err(store_error)?;
+    state.runtime.submit(&pipeline_id);
+
+    Ok(pipeline_info)
+}
+
+/// Applies the server's defaults and caps to a job's requested execution
+/// limits, refusing limits above the caps with 400.
+fn resolve_limits(
+    state: &AppState,
+    requested: ExecutionLimits,
+) -> Result<ExecutionLimits, (StatusCode, Json<ApiError>)> {
+    // ---
+    state
+        .execution_limits
+        .resolve(requested)
+        .map_err(|details| {
+            (
+                StatusCode::BAD_REQUEST,
+                Json(ApiError::with_details("Invalid execution limits", &details)),
+            )
+        })
+}
+
+/// Validates a job's requested retry policy and fills in its defaults,
+/// refusing invalid settings with 400.
+fn resolve_retry(
+    requested: Option<RetryPolicy>,
+) -> Result<Option<RetryPolicy>, (StatusCode, Json<ApiError>)> {
+    // ---
+    requested
+        .map(resolve_retry_policy)
+        .transpose()
+        .map_err(|details| {
+            (
+                StatusCode::BAD_REQUEST,
+                Json(ApiError::with_details("Invalid retry policy", &details)),
+            )
+        })
+}
+
+/// Refuses new jobs with 507 while output storage is above its high watermark.
+fn check_capacity(retention: &RetentionManager) -> Result<(), (StatusCode, Json<ApiError>)> {
+    // ---
+    retention.check_capacity().map_err(|message| {
+        tracing::warn!("Refusing new job: {}", message);
+        (
+            StatusCode::INSUFFICIENT_STORAGE,
+            Json(ApiError::with_details("Insufficient storage", &message)),
+        )
+    })
+}
+
+/// Converts an output storage failure into a 500 response for handlers.
+fn storage_error(error: anyhow::Error) -> (StatusCode, Json<ApiError>) {
+    // ---
+    tracing::error!("Output storage error: {:#}", error);
+    (
+        StatusCode::INTERNAL_SERVER_ERROR,
+        Json(ApiError::with_details(
+            "Output storage error",
+            &error.to_string(),
+        )),
+    )
+}
+
+/// Converts a pipeline store failure into a 500 response for handlers.
+fn store_error(error: anyhow::Error) -> (StatusCode, Json<ApiError>) {
+    // ---
+    tracing::error!("Pipeline store error: {:#}", error);
+    (
+        StatusCode::INTERNAL_SERVER_ERROR,
+        Json(ApiError::with_details(
+            "Pipeline store error",
+            &error.to_string(),
+        )),
+    )
+}
@@ -0,0 +1,211 @@
+//! Output artifact download HTTP endpoint handlers.
+//!
+//! Serves the files written by finished pipelines (converted media,
+//! thumbnails, HLS playlists and segments) directly over the API, so clients
+//! no longer need access to the service's working directory.
+//!
+//! # HTTP Semantics
+//!
+//! Downloads carry `Content-Type`, `Content-Length`, `ETag` and
+//! `Accept-Ranges: bytes`. Single byte ranges are answered with
+//! `206 Partial Content`, which lets browsers seek in converted video;
+//! `If-None-Match` and `If-Range` are honored using the entity tag.
+//!
+//! # Streaming
+//!
+//! File contents are streamed from disk rather than buffered, so large
+//! artifacts do not inflate server memory.
+
+use axum::{
+    body::Body,
+    extract::{Extension, Path, State},
+    http::{header, HeaderMap, HeaderValue, StatusCode},
+    response::{IntoResponse, Json, Response},
+};
+use std::io::SeekFrom;
+use tokio::io::{AsyncReadExt, AsyncSeekExt};
+use tokio_util::io::ReaderStream;
+
+// ---
+
+// Import through gateways
+use crate::models::{ApiError, Caller};
+use crate::services::{
+    content_type_for, etag_for, parse_range, resolve_artifact, ArtifactError, ByteRange,
+};
+
+// ---
+
+// Shared state and error helpers
+use super::{find_pipeline, AppState};
+
+/// Downloads the primary output of a pipeline.
+///
+/// Serves the single file written by conversion and thumbnail jobs. Jobs that
+/// write a directory of files (HLS streams) must use
+/// `GET /pipelines/{id}/outputs/{name}` instead.
+///
+/// # Path Parameters
+/// - `id`: The unique UUID identifier of the pipeline
+///
+/// # Request Headers
+/// - `Range`: Optional single byte range, e.g. `bytes=0-1023`
+/// - `If-None-Match`: Entity tag from a previous download
+/// - `If-Range`: Entity tag the `Range` applies to
+///
+/// # Response Behavior
+/// - **200 OK**: Complete artifact streamed with its content type
+/// - **206 Partial Content**: Requested byte range with `Content-Range`
+/// - **304 Not Modified**: `If-None-Match` matched the current entity tag
+/// - **404 Not Found**: Unknown pipeline, one created by another API key, or output not written yet
+/// - **409 Conflict**: The output is a directory; request files by name
+/// - **416 Range Not Satisfiable**: Range starts beyond the end of the file
+///
+/// # Example Usage
+/// ```bash
+/// curl -o output.webm http://localhost:8080/pipelines/550e8400-e29b-41d4-a716-446655440000/output
+/// curl -H "Range: bytes=0-1023" http://localhost:8080/pipelines/550e8400-e29b-41d4-a716-446655440000/output
+/// ```
+pub async fn download_output(
+    State(state): State<AppState>,
+    Extension(caller): Extension<Caller>,
+    Path(id): Path<String>,
+    headers: HeaderMap,
+) -> Result<Response, (StatusCode, Json<ApiError>)> {
+    // ---
+    serve_artifact(&state, &caller, &id, None, &headers).await
+}
+
+/// Downloads a named file from a pipeline's outputs.
+///
+/// For directory outputs (HLS streams) `name` is a file inside the directory,
+/// such as `playlist.m3u8` or `segment_00000.ts`. For single-file outputs it is
+/// the output's file name. Supports the same conditional and range headers as
+/// [`download_output`].
+///
+/// # Path Parameters
+/// - `id`: The unique UUID identifier of the pipeline
+/// - `name`: Plain file name; path separators and `..` are rejected
+///
+/// # Response Behavior
+/// - **200/206/304/416**: As for [`download_output`]
+/// - **400 Bad Request**: `name` is not a plain file name
+/// - **404 Not Found**: Unknown pipeline, one created by another API key, or no output with that name
+///
+/// # Example Usage
+/// ```bash
+/// curl http://localhost:8080/pipelines/550e8400-e29b-41d4-a716-446655440002/outputs/playlist.m3u8
+/// ```
+pub async fn download_named_output(
+    State(state): State<AppState>,
+    Extension(caller): Extension<Caller>,
+    Path((id, name)): Path<(String, String)>,
+    headers: HeaderMap,
+) -> Result<Response, (StatusCode, Json<ApiError>)> {
+    // ---
+    serve_artifact(&state, &caller, &id, Some(&name), &headers).await
+}
+
+/// Looks up the artifact and streams it, honoring conditional and range headers.
+async fn serve_artifact(
+    state: &AppState,
+    caller: &Caller,
+    id: &str,
+    name: Option<&str>,
+    headers: &HeaderMap,
+) -> Result<Response, (StatusCode, Json<ApiError>)> {
+    // ---
+    let pipeline = find_pipeline(state, caller, id)?;
+
+    let job_dir = state
+        .storage
+        .job_dir(pipeline.owner.as_deref(), &pipeline.id);
+    let path = resolve_artifact(&pipeline, &job_dir, name).map_err(|e| match e {
+        ArtifactError::NotFound(details) => (
+            StatusCode::NOT_FOUND,
+            Json(ApiError::with_details("Output not available", &details)),
+        ),
+        ArtifactError::IsDirectory => (
+            StatusCode::CONFLICT,
+            Json(ApiError::with_details(
+                "Output is a directory",
+                &format!("Request individual files via /pipelines/{id}/outputs/{{name}}"),
+            )),
+        ),
+        ArtifactError::InvalidName(details) => (
+            StatusCode::BAD_REQUEST,
+            Json(ApiError::with_details("Invalid output name", &details)),
+        ),
+    })?;
+
+    let io_error = |e: std::io::Error| {
+        tracing::error!("Failed to read output {}: {}", path.display(), e);
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(ApiError::with_details(
+                "Failed to read output",
+                &e.to_string(),
+            )),
+        )
+    };
+
+    let mut file = tokio::fs::File::open(&path).await.map_err(io_error)?;
+    let metadata = file.metadata().await.map_err(io_error)?;
+    let len = metadata.len();
+    let etag = etag_for(&metadata);
+    let content_type = content_type_for(&path);
+
+    if header_str(headers, header::IF_NONE_MATCH).is_some_and(|tags| {
+        tags.split(',')
+            .any(|tag| tag.trim() == etag || tag.trim() == "*")
+    }) {
+        return Ok((StatusCode::NOT_MODIFIED, [(header::ETAG, etag)]).into_response());
+    }
+
+    // A Range is only honored when If-Range (if present) still names this version
+    let range_header = match header_str(headers, header::IF_RANGE) {
+        Some(tag) if tag != etag => None,
+        _ => header_str(headers, header::RANGE),
+    };
+
+    let mut response = Response::builder()
+        .header(header::CONTENT_TYPE, content_type)
+        .header(header::ETAG, &etag)
+        .header(header::ACCEPT_RANGES, "bytes");
+
+    let body = match parse_range(range_header, len) {
+        ByteRange::Full => {
+            response = response
+                .status(StatusCode::OK)
+                .header(header::CONTENT_LENGTH, len);
+            Body::from_stream(ReaderStream::new(file))
+        }
+        ByteRange::Partial { start, end } => {
+            file.seek(SeekFrom::Start(start)).await.map_err(io_error)?;
+            let length = end - start + 1;
+            response = response
+                .status(StatusCode::PARTIAL_CONTENT)
+                .header(header::CONTENT_LENGTH, length)
+                .header(header::CONTENT_RANGE, format!("bytes {start}-{end}/{len}"));
+            Body::from_stream(ReaderStream::new(file.take(length)))
+        }
+        ByteRange::Unsatisfiable => {
+            response = response
+                .status(StatusCode::RANGE_NOT_SATISFIABLE)
+                .header(header::CONTENT_RANGE, format!("bytes */{len}"));
+            Body::empty()
+        }
+    };
+
+    Ok(response
+        .body(body)
+        .expect("artifact response headers are valid"))
+}
+
+/// Returns a request header as a string, ignoring values that are not ASCII.
+fn header_str(headers: &HeaderMap, name: header::HeaderName) -> Option<&str> {
+    // ---
+    headers
+        .get(name)
+        .and_then(|value: &HeaderValue| value.to_str().ok())
+}
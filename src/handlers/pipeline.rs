@@ -15,7 +15,7 @@
 //! Pipelines are managed through a simple state machine with full CRUD operations:
 //! - Creation with validation and unique ID assignment
 //! - Status querying for monitoring and debugging
-//! - Listing for operational overview
+//! - Filtered, paginated listing for operational overview
 //! - Termination for resource management
 //!
 //! # Media Analysis Integration
@@ -25,30 +25,46 @@
 //! and validate source accessibility before initiating expensive operations.
 
 use axum::{
-    extract::{Path, State},
+    extract::{Extension, Path, Query, State},
     http::StatusCode,
     response::Json,
 };
-use chrono::Utc;
+use chrono::{DateTime, Utc};
+use std::time::Instant;
 use tracing::{info, warn};
-use uuid::Uuid;
 
 // ---
 
 // Import through gateways
-use crate::models::{ApiError, CreatePipelineRequest, PipelineInfo, PipelineState};
-use crate::services::{get_media_info, validate_pipeline_string};
+use crate::models::{
+    ApiError, Caller, CreatePipelineQuery, CreatePipelineRequest, ListPipelinesQuery, LogLevel,
+    PipelineInfo, PipelineListResponse, PipelineLogsResponse, PipelineState, StopPipelineQuery,
+};
+use crate::services::{
+    describe_graph, dry_run_pipeline, get_media_info, validate_source_url, Cursor, PipelineQuery,
+    SortOrder,
+};
 
 // ---
 
-// Type alias for shared state
-use super::AppState;
+// Shared state and error helpers
+use super::{
+    check_capacity, find_pipeline, storage_error, store_error, submit_custom_pipeline, AppState,
+    JobSettings,
+};
+
+/// Page size used when the client does not pass `limit`
+const DEFAULT_PAGE_SIZE: usize = 100;
+
+/// Largest accepted `limit`, bounding the cost of a single listing request
+const MAX_PAGE_SIZE: usize = 1000;
 
 /// Creates a new custom GStreamer pipeline from user-provided configuration.
 ///
 /// Accepts a complete GStreamer pipeline string, validates its syntax and structure,
 /// then creates a new pipeline entry with a unique identifier. The pipeline is
-/// initially in the Created state and ready for execution by external systems.
+/// initially in the Created state and is queued for execution when the runtime
+/// has workers enabled.
 ///
 /// # Request Body
 /// Expects a JSON payload with pipeline description and GStreamer pipeline string:
@@ -59,124 +75,274 @@ use super::AppState;
 /// }
 /// ```
 ///
+/// or, instead of `pipeline`, a structured `graph` of elements and links:
+/// ```json
+/// {
+///   "description": "Test tone",
+///   "graph": {
+///     "elements": [
+///       { "factory": "audiotestsrc", "name": "tone", "properties": { "num-buffers": 100 } },
+///       { "factory": "autoaudiosink", "name": "out" }
+///     ],
+///     "links": [{ "from": "tone", "to": "out", "caps": "audio/x-raw,rate=48000" }]
+///   }
+/// }
+/// ```
+///
 /// # Validation Process
 /// - Ensures pipeline string is not empty or whitespace-only
 /// - Verifies proper element connectivity (presence of ! operators)
 /// - Uses GStreamer's built-in parser to catch syntax errors
 /// - Validates that all referenced elements are available
+/// - For graphs, builds every element, checks property values against their
+///   types and makes every link
+///
+/// With `?validate=deep` the pipeline is also prerolled in a dry-run (up to
+/// five seconds) before it is accepted, catching unlinked pads, caps that
+/// cannot be negotiated and element errors such as missing input files.
+/// Failures list every problem found in `diagnostics`.
 ///
 /// # Response Behavior
 /// - **200 OK**: Pipeline created successfully with metadata
-/// - **400 Bad Request**: Invalid pipeline configuration with detailed error message
+/// - **400 Bad Request**: Invalid pipeline configuration, callback URL, execution limits or retry policy with detailed error
+///   message, or an unknown `validate` mode
+/// - **507 Insufficient Storage**: Output storage is above its high watermark
 ///
 /// # State Management
 /// Created pipelines are stored in application state with:
 /// - Unique UUID v4 identifier for tracking
 /// - ISO 8601 creation timestamp
 /// - Initial state of Created
-/// - Complete pipeline string for execution
+/// - Complete pipeline string for execution (for graphs, an equivalent
+///   string for display, plus the graph that is executed)
+/// - Every `filesink` location as an output, downloadable via `/pipelines/{id}/output`
 ///
 /// # Example Usage
 /// ```bash
 /// curl -X POST http://localhost:8080/pipelines \
 ///   -H "Content-Type: application/json" \
 ///   -d '{"description": "Custom audio extraction", "pipeline": "..."}'
+///
+/// curl -X POST "http://localhost:8080/pipelines?validate=deep" \
+///   -H "Content-Type: application/json" \
+///   -d '{"description": "Checked before queueing", "pipeline": "..."}'
 /// ```
 pub async fn create_pipeline(
     State(state): State<AppState>,
+    Extension(caller): Extension<Caller>,
+    Query(query): Query<CreatePipelineQuery>,
     Json(payload): Json<CreatePipelineRequest>,
 ) -> Result<Json<PipelineInfo>, (StatusCode, Json<ApiError>)> {
     // ---
+    let deep = match query.validate.as_deref() {
+        None | Some("basic") => false,
+        Some("deep") => true,
+        Some(other) => {
+            return Err((
+                StatusCode::BAD_REQUEST,
+                Json(ApiError::with_details(
+                    "Invalid validation mode",
+                    &format!("Unknown validate value '{other}'; use 'basic' or 'deep'"),
+                )),
+            ))
+        }
+    };
 
-    let pipeline_id = Uuid::new_v4().to_string();
-
-    info!(
-        "Creating pipeline: {} - {}",
-        pipeline_id, payload.description
-    );
-
-    // Validate the pipeline string using our validation service
-    if let Err(validation_error) = validate_pipeline_string(&payload.pipeline) {
-        return Err((
-            StatusCode::BAD_REQUEST,
-            Json(ApiError::with_details(
-                "Invalid pipeline configuration",
-                &validation_error,
-            )),
-        ));
-    }
-
-    let pipeline_info = PipelineInfo {
-        id: pipeline_id.clone(),
-        description: payload.description,
-        state: PipelineState::Created,
-        pipeline_string: payload.pipeline,
-        created_at: Utc::now().to_rfc3339(),
-        source_url: None,
+    let (pipeline, graph) = match (payload.pipeline, payload.graph) {
+        (Some(pipeline), None) => (pipeline, None),
+        (None, Some(graph)) => (describe_graph(&graph), Some(graph)),
+        _ => {
+            return Err((
+                StatusCode::BAD_REQUEST,
+                Json(ApiError::with_details(
+                    "Invalid pipeline configuration",
+                    "Provide exactly one of 'pipeline' or 'graph'",
+                )),
+            ))
+        }
     };
 
-    // Store the pipeline info
-    {
-        let mut pipelines = state.lock().unwrap();
-        pipelines.insert(pipeline_id.clone(), pipeline_info.clone());
+    if deep {
+        let (dry_run, dry_run_graph) = (pipeline.clone(), graph.clone());
+        let diagnostics =
+            tokio::task::spawn_blocking(move || dry_run_pipeline(&dry_run, dry_run_graph.as_ref()))
+                .await
+                .map_err(|e| {
+                    (
+                        StatusCode::INTERNAL_SERVER_ERROR,
+                        Json(ApiError::with_details(
+                            "Pipeline validation failed",
+                            &e.to_string(),
+                        )),
+                    )
+                })?;
+        if diagnostics
+            .iter()
+            .any(|diagnostic| diagnostic.severity == LogLevel::Error)
+        {
+            return Err((
+                StatusCode::BAD_REQUEST,
+                Json(ApiError::with_diagnostics(
+                    "Invalid pipeline configuration",
+                    diagnostics,
+                )),
+            ));
+        }
     }
 
+    let pipeline_info = submit_custom_pipeline(
+        &state,
+        &caller,
+        payload.description,
+        pipeline,
+        graph,
+        JobSettings {
+            callback_url: payload.callback_url,
+            limits: payload.limits,
+            retry: payload.retry,
+        },
+    )?;
+
     Ok(Json(pipeline_info))
 }
 
-/// Lists all currently tracked pipelines with their current states.
+/// Lists tracked pipelines with filtering, sorting and cursor pagination.
+///
+/// Returns one page of pipelines ordered by creation time (newest first by
+/// default), together with a `next_cursor` for fetching the following page.
+/// Filtering and ordering happen in the pipeline store, so listing stays fast
+/// with tens of thousands of jobs. Callers authenticated with an API key
+/// only see the pipelines that key created.
+///
+/// # Query Parameters
+/// - `state`: Only pipelines in this state ("Created", "Playing", "Paused", "Stopped", "Error")
+/// - `description`: Case-insensitive substring of the pipeline description
+/// - `source_url`: Exact source URL of media processing jobs
+/// - `created_after` / `created_before`: Exclusive RFC 3339 creation time bounds
+/// - `order`: "desc" (default) or "asc"
+/// - `limit`: Page size, 1-1000 (default 100)
+/// - `cursor`: `next_cursor` from the previous response
+///
+/// # Pagination
+/// Cursors encode the position of the last returned pipeline rather than an
+/// offset, so pipelines created while a client is paging do not cause records
+/// to be skipped or repeated. A cursor is only meaningful with the same
+/// filters and order it was issued for. `next_cursor` is null on the last page.
 ///
-/// Returns a comprehensive overview of all pipelines in the system, including
-/// their current execution states, creation timestamps, and configuration details.
-/// This endpoint is useful for operational monitoring and pipeline management.
-///
-/// # Response Format
-/// Returns an array of `PipelineInfo` objects containing complete pipeline metadata:
-/// - Unique pipeline identifiers
-/// - Human-readable descriptions
-/// - Current execution states
-/// - Creation timestamps
-/// - GStreamer pipeline strings
-/// - Source URLs (when applicable)
-///
-/// # State Information
-/// Pipeline states provide insight into execution status:
-/// - **Created**: Validated and ready for execution
-/// - **Playing**: Currently processing media
-/// - **Paused**: Temporarily suspended
-/// - **Stopped**: Completed or manually terminated
-/// - **Error**: Failed with diagnostic information
+/// # Response Behavior
+/// - **200 OK**: Page of pipelines returned
+/// - **400 Bad Request**: Unknown state or order, malformed timestamp or cursor, or limit out of range
 ///
 /// # Operational Use Cases
-/// - **Monitoring Dashboards**: Real-time pipeline status overview
-/// - **Resource Management**: Identifying active vs completed pipelines
-/// - **Debugging**: Historical view of pipeline creation and execution
+/// - **Monitoring Dashboards**: Recent pipelines and their states
+/// - **Debugging**: Failed pipelines in a time window (`state=Error`)
 /// - **Cleanup Operations**: Bulk identification of stopped/error pipelines
 ///
 /// # Example Usage
 /// ```bash
-/// curl http://localhost:8080/pipelines
+/// curl "http://localhost:8080/pipelines?state=Error&limit=20"
+/// curl "http://localhost:8080/pipelines?state=Error&limit=20&cursor=<next_cursor>"
 /// ```
 ///
 /// # Response Example
 /// ```json
-/// [
-///   {
-///     "id": "550e8400-e29b-41d4-a716-446655440000",
-///     "description": "Convert to webm",
-///     "state": "Created",
-///     "pipeline_string": "souphttpsrc location=...",
-///     "created_at": "2024-09-21T10:30:00Z",
-///     "source_url": "https://example.com/video.mp4"
-///   }
-/// ]
+/// {
+///   "pipelines": [
+///     {
+///       "id": "550e8400-e29b-41d4-a716-446655440000",
+///       "description": "Convert to webm",
+///       "state": "Created",
+///       "pipeline_string": "souphttpsrc location=...",
+///       "created_at": "2024-09-21T10:30:00Z",
+///       "source_url": "https://example.com/video.mp4"
+///     }
+///   ],
+///   "next_cursor": null
+/// }
 /// ```
-pub async fn list_pipelines(State(state): State<AppState>) -> Json<Vec<PipelineInfo>> {
+pub async fn list_pipelines(
+    State(state): State<AppState>,
+    Extension(caller): Extension<Caller>,
+    Query(params): Query<ListPipelinesQuery>,
+) -> Result<Json<PipelineListResponse>, (StatusCode, Json<ApiError>)> {
     // ---
 
-    let pipelines = state.lock().unwrap();
-    let pipeline_list: Vec<PipelineInfo> = pipelines.values().cloned().collect();
-    Json(pipeline_list)
+    let mut query = build_pipeline_query(params).map_err(|e| {
+        (
+            StatusCode::BAD_REQUEST,
+            Json(ApiError::with_details("Invalid list parameters", &e)),
+        )
+    })?;
+    query.owner = caller.owner();
+
+    let page = state.store.page(&query).map_err(store_error)?;
+
+    Ok(Json(PipelineListResponse {
+        pipelines: page.pipelines,
+        next_cursor: page.next_cursor.map(|cursor| cursor.encode()),
+    }))
+}
+
+/// Validates listing query parameters and converts them into a store query.
+///
+/// Timestamps are normalized to UTC so they compare correctly with the stored
+/// `created_at` values.
+fn build_pipeline_query(params: ListPipelinesQuery) -> Result<PipelineQuery, String> {
+    // ---
+    let state = params
+        .state
+        .map(|state| {
+            PipelineState::NAMES
+                .into_iter()
+                .find(|name| name.eq_ignore_ascii_case(&state))
+                .ok_or_else(|| {
+                    format!(
+                        "Unknown state '{state}', expected one of: {}",
+                        PipelineState::NAMES.join(", ")
+                    )
+                })
+        })
+        .transpose()?;
+
+    let normalize = |name: &str, value: Option<String>| {
+        value
+            .map(|value| {
+                DateTime::parse_from_rfc3339(&value)
+                    .map(|time| time.with_timezone(&Utc).to_rfc3339())
+                    .map_err(|e| format!("{name} must be an RFC 3339 timestamp: {e}"))
+            })
+            .transpose()
+    };
+    let created_after = normalize("created_after", params.created_after)?;
+    let created_before = normalize("created_before", params.created_before)?;
+
+    let order = match params.order.as_deref() {
+        None | Some("desc") => SortOrder::Descending,
+        Some("asc") => SortOrder::Ascending,
+        Some(other) => return Err(format!("Unknown order '{other}', expected asc or desc")),
+    };
+
+    let limit = params.limit.unwrap_or(DEFAULT_PAGE_SIZE);
+    if !(1..=MAX_PAGE_SIZE).contains(&limit) {
+        return Err(format!("limit must be between 1 and {MAX_PAGE_SIZE}"));
+    }
+
+    let after = params
+        .cursor
+        .map(|cursor| Cursor::decode(&cursor).ok_or_else(|| "Malformed cursor".to_string()))
+        .transpose()?;
+
+    Ok(PipelineQuery {
+        state,
+        description: params.description,
+        source_url: params.source_url,
+        owner: None,
+        created_after,
+        created_before,
+        order,
+        after,
+        limit,
+    })
 }
 
 /// Retrieves detailed information about a specific pipeline by ID.
@@ -190,7 +356,7 @@ pub async fn list_pipelines(State(state): State<AppState>) -> Json<Vec<PipelineI
 ///
 /// # Response Behavior
 /// - **200 OK**: Pipeline found and returned with complete metadata
-/// - **404 Not Found**: No pipeline exists with the specified ID
+/// - **404 Not Found**: No pipeline exists with the specified ID, or it was created by another API key
 ///
 /// # Use Cases
 /// - **Status Monitoring**: Checking individual pipeline execution progress
@@ -210,19 +376,60 @@ pub async fn list_pipelines(State(state): State<AppState>) -> Json<Vec<PipelineI
 /// ```
 pub async fn get_pipeline(
     State(state): State<AppState>,
+    Extension(caller): Extension<Caller>,
     Path(id): Path<String>,
 ) -> Result<Json<PipelineInfo>, (StatusCode, Json<ApiError>)> {
     // ---
 
-    let pipelines = state.lock().unwrap();
+    find_pipeline(&state, &caller, &id).map(Json)
+}
 
-    match pipelines.get(&id) {
-        Some(pipeline) => Ok(Json(pipeline.clone())),
-        None => Err((
-            StatusCode::NOT_FOUND,
-            Json(ApiError::new("Pipeline not found")),
-        )),
-    }
+/// Returns the messages captured while a pipeline executed.
+///
+/// Lets clients see why a job failed without access to the server logs:
+/// GStreamer errors, warnings and info messages from the pipeline's bus,
+/// plus runtime events such as start, end of stream and failed uploads.
+/// Only the most recent `--log-buffer-size` entries are kept; `dropped`
+/// counts the discarded ones.
+///
+/// # Path Parameters
+/// - `id`: The unique UUID identifier of the pipeline
+///
+/// # Response Behavior
+/// - **200 OK**: Captured messages, oldest first (empty if none were captured,
+///   e.g. for pipelines that have not run or after a restart)
+/// - **404 Not Found**: No pipeline exists with the specified ID, or it was created by another API key
+///
+/// # Example Usage
+/// ```bash
+/// curl http://localhost:8080/pipelines/550e8400-e29b-41d4-a716-446655440000/logs
+/// ```
+///
+/// # Response Example
+/// ```json
+/// {
+///   "pipeline_id": "550e8400-e29b-41d4-a716-446655440000",
+///   "entries": [
+///     {
+///       "timestamp": "2024-09-21T10:35:00Z",
+///       "level": "error",
+///       "source": "filesrc0",
+///       "message": "Resource not found.",
+///       "debug": "../plugins/elements/gstfilesrc.c(532): gst_file_src_start (): ..."
+///     }
+///   ],
+///   "dropped": 0
+/// }
+/// ```
+pub async fn get_pipeline_logs(
+    State(state): State<AppState>,
+    Extension(caller): Extension<Caller>,
+    Path(id): Path<String>,
+) -> Result<Json<PipelineLogsResponse>, (StatusCode, Json<ApiError>)> {
+    // ---
+
+    find_pipeline(&state, &caller, &id)?;
+    Ok(Json(state.logs.get(&id)))
 }
 
 /// Stops a running pipeline and updates its state to Stopped.
@@ -231,16 +438,26 @@ pub async fn get_pipeline(
 /// This operation is useful for resource management, canceling long-running
 /// operations, and manual intervention in pipeline execution.
 ///
+/// Running pipelines are sent EOS so their outputs are finalized, and move to
+/// `Stopped` once drained; pipelines that never started are stopped immediately.
+///
 /// # Path Parameters
 /// - `id`: The unique UUID identifier of the pipeline to stop
 ///
 /// # State Transition
 /// The pipeline state is updated to `Stopped` regardless of its previous state.
-/// This operation is idempotent - stopping an already stopped pipeline is safe.
+/// This operation is idempotent - stopping an already stopped pipeline is safe,
+/// and the completion webhook is only sent for the first terminal transition.
+///
+/// # Query Parameters
+/// - `purge`: When `true`, also deletes the pipeline's artifacts (local and
+///   published) and its record, as the retention reaper would on expiry
 ///
 /// # Response Behavior
-/// - **200 OK**: Pipeline successfully stopped with confirmation message
-/// - **404 Not Found**: No pipeline exists with the specified ID
+/// - **200 OK**: Pipeline successfully stopped (or purged) with confirmation message
+/// - **404 Not Found**: No pipeline exists with the specified ID, or it was created by another API key
+/// - **409 Conflict**: Purge requested while the pipeline is still draining
+/// - **500 Internal Server Error**: Artifacts could not be deleted; the record is kept
 ///
 /// # Resource Management
 /// Stopping pipelines is important for:
@@ -261,27 +478,103 @@ pub async fn get_pipeline(
 /// # Example Usage
 /// ```bash
 /// curl -X DELETE http://localhost:8080/pipelines/550e8400-e29b-41d4-a716-446655440000
+/// curl -X DELETE "http://localhost:8080/pipelines/550e8400-e29b-41d4-a716-446655440000?purge=true"
 /// ```
 pub async fn stop_pipeline(
     State(state): State<AppState>,
+    Extension(caller): Extension<Caller>,
     Path(id): Path<String>,
+    Query(query): Query<StopPipelineQuery>,
 ) -> Result<Json<serde_json::Value>, (StatusCode, Json<ApiError>)> {
     // ---
 
-    let mut pipelines = state.lock().unwrap();
+    find_pipeline(&state, &caller, &id)?;
+    if !state.runtime.stop(&id).map_err(store_error)? {
+        return Err((
+            StatusCode::NOT_FOUND,
+            Json(ApiError::new("Pipeline not found")),
+        ));
+    }
+    info!("Stopped pipeline: {}", id);
 
-    match pipelines.get_mut(&id) {
+    if !query.purge {
+        return Ok(Json(serde_json::json!({
+            "message": "Pipeline stopped successfully",
+            "pipeline_id": id
+        })));
+    }
+
+    // A running pipeline only stops once its sinks have drained; deleting its
+    // outputs before then would race with the final writes.
+    let stopped = state
+        .store
+        .get(&id)
+        .map_err(store_error)?
+        .is_none_or(|pipeline| pipeline.state.is_terminal());
+    if !stopped {
+        return Err((
+            StatusCode::CONFLICT,
+            Json(ApiError::with_details(
+                "Pipeline is still stopping",
+                "Retry the purge once the pipeline has reached a terminal state",
+            )),
+        ));
+    }
+
+    let retention = state.retention.clone();
+    let pipeline_id = id.clone();
+    tokio::task::spawn_blocking(move || retention.purge(&pipeline_id))
+        .await
+        .map_err(|e| storage_error(e.into()))?
+        .map_err(storage_error)?;
+
+    Ok(Json(serde_json::json!({
+        "message": "Pipeline purged",
+        "pipeline_id": id
+    })))
+}
+
+/// Runs a finished pipeline again.
+///
+/// Queues a `Stopped` or `Error` pipeline for another attempt under the same
+/// ID, e.g. once a failing source is reachable again. The pipeline keeps its
+/// settings and attempt history; its published outputs are cleared and
+/// replaced by those of the new run. A manual retry also starts a new series
+/// of automatic retries when the pipeline has a retry policy.
+///
+/// # Path Parameters
+/// - `id`: The unique UUID identifier of the pipeline to retry
+///
+/// # Response Behavior
+/// - **200 OK**: The pipeline record, back in `Created`
+/// - **404 Not Found**: No pipeline exists with the specified ID, or it was created by another API key
+/// - **409 Conflict**: The pipeline has not finished yet
+/// - **507 Insufficient Storage**: Output storage is above its high watermark
+///
+/// # Example Usage
+/// ```bash
+/// curl -X POST http://localhost:8080/pipelines/550e8400-e29b-41d4-a716-446655440000/retry
+/// ```
+pub async fn retry_pipeline(
+    State(state): State<AppState>,
+    Extension(caller): Extension<Caller>,
+    Path(id): Path<String>,
+) -> Result<Json<PipelineInfo>, (StatusCode, Json<ApiError>)> {
+    // ---
+    find_pipeline(&state, &caller, &id)?;
+    check_capacity(&state.retention)?;
+
+    match state.runtime.retry(&id).map_err(store_error)? {
         Some(pipeline) => {
-            pipeline.state = PipelineState::Stopped;
-            info!("Stopped pipeline: {}", id);
-            Ok(Json(serde_json::json!({
-                "message": "Pipeline stopped successfully",
-                "pipeline_id": id
-            })))
+            info!("Retrying pipeline: {}", id);
+            Ok(Json(pipeline))
         }
         None => Err((
-            StatusCode::NOT_FOUND,
-            Json(ApiError::new("Pipeline not found")),
+            StatusCode::CONFLICT,
+            Json(ApiError::with_details(
+                "Pipeline is not finished",
+                "Only Stopped or Error pipelines can be retried",
+            )),
         )),
     }
 }
@@ -309,6 +602,7 @@ pub async fn stop_pipeline(
 /// - Extracts technical metadata and format information
 /// - Implements timeout protection to prevent hanging
 /// - Properly cleans up resources after analysis
+/// - Records its latency in `media_analysis_duration_seconds`
 ///
 /// # Response Information
 /// Returns comprehensive media metadata including:
@@ -321,7 +615,7 @@ pub async fn stop_pipeline(
 /// # Response Behavior
 /// - **200 OK**: Analysis completed successfully with media information
 /// - **400 Bad Request**: Invalid URL encoding or malformed URL
-/// - **422 Unprocessable Entity**: Media file inaccessible or analysis failed
+/// - **422 Unprocessable Entity**: Not an HTTP(S) URL, media file inaccessible or analysis failed
 ///
 /// # Use Cases
 /// - **Pre-processing Validation**: Verify media accessibility before expensive operations
@@ -348,6 +642,7 @@ pub async fn stop_pipeline(
 /// }
 /// ```
 pub async fn analyze_media(
+    State(state): State<AppState>,
     Path(url): Path<String>,
 ) -> Result<Json<serde_json::Value>, (StatusCode, Json<ApiError>)> {
     // ---
@@ -362,7 +657,15 @@ pub async fn analyze_media(
         )
     })?;
 
-    match get_media_info(&decoded_url) {
+    let started = Instant::now();
+    let analysis = validate_source_url(&decoded_url)
+        .map_err(anyhow::Error::msg)
+        .and_then(|()| get_media_info(&decoded_url));
+    state
+        .metrics
+        .observe_analysis(analysis.is_ok(), started.elapsed());
+
+    match analysis {
         Ok(media_info) => Ok(Json(serde_json::json!({
             "url": decoded_url.as_ref(),
             "format": media_info.format,
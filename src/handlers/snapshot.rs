@@ -0,0 +1,147 @@
+//! Pipeline snapshot HTTP endpoint handlers.
+//!
+//! Serves the most recent video frame of a running pipeline as an image,
+//! e.g. for thumbnails of live restreams. The frame is copied from a sink,
+//! so the running job is not interrupted.
+
+use axum::{
+    extract::{Extension, Path, Query, State},
+    http::{header, StatusCode},
+    response::{IntoResponse, Json, Response},
+};
+
+// ---
+
+// Import through gateways
+use crate::models::{ApiError, Caller, SnapshotQuery};
+use crate::services::{take_snapshot, SnapshotError, SnapshotFormat};
+
+// ---
+
+// Shared state and error helpers
+use super::{find_pipeline, AppState};
+
+/// Encodes the latest video frame of a running pipeline as JPEG.
+///
+/// The frame comes from the `last-sample` of a sink receiving raw video.
+/// Pipelines that only produce encoded output need a raw branch for this,
+/// e.g. `t. ! queue leaky=downstream ! fakesink name=snapshot` after a `tee`.
+///
+/// # Path Parameters
+/// - `id`: The unique UUID identifier of the pipeline
+///
+/// # Query Parameters
+/// - `element`: Sink to take the frame from (default: first sink holding a raw video frame)
+///
+/// # Response Behavior
+/// - **200 OK**: `image/jpeg` body, not cacheable
+/// - **404 Not Found**: No pipeline exists with the specified ID (or it was created by another
+///   API key), no element has the requested name, or no sink holds a video frame yet
+/// - **409 Conflict**: The pipeline is not running
+/// - **501 Not Implemented**: The GStreamer elements needed for encoding are not installed
+/// - **500 Internal Server Error**: Encoding failed
+///
+/// # Example Usage
+/// ```bash
+/// curl -o frame.jpg http://localhost:8080/pipelines/550e8400-e29b-41d4-a716-446655440000/snapshot.jpg
+/// ```
+pub async fn get_snapshot_jpeg(
+    State(state): State<AppState>,
+    Extension(caller): Extension<Caller>,
+    Path(id): Path<String>,
+    Query(query): Query<SnapshotQuery>,
+) -> Result<Response, (StatusCode, Json<ApiError>)> {
+    // ---
+    snapshot(state, caller, id, query, SnapshotFormat::Jpeg).await
+}
+
+/// Encodes the latest video frame of a running pipeline as PNG.
+///
+/// Behaves like [`get_snapshot_jpeg`], with an `image/png` body.
+///
+/// # Example Usage
+/// ```bash
+/// curl -o frame.png "http://localhost:8080/pipelines/550e8400-e29b-41d4-a716-446655440000/snapshot.png?element=snapshot"
+/// ```
+pub async fn get_snapshot_png(
+    State(state): State<AppState>,
+    Extension(caller): Extension<Caller>,
+    Path(id): Path<String>,
+    Query(query): Query<SnapshotQuery>,
+) -> Result<Response, (StatusCode, Json<ApiError>)> {
+    // ---
+    snapshot(state, caller, id, query, SnapshotFormat::Png).await
+}
+
+/// Takes a snapshot in `format` and wraps it in a response.
+async fn snapshot(
+    state: AppState,
+    caller: Caller,
+    id: String,
+    query: SnapshotQuery,
+    format: SnapshotFormat,
+) -> Result<Response, (StatusCode, Json<ApiError>)> {
+    // ---
+    let pipeline = find_pipeline(&state, &caller, &id)?;
+
+    let runtime = state.runtime.clone();
+    let pipeline_id = id.clone();
+    let image = tokio::task::spawn_blocking(move || {
+        take_snapshot(&runtime, &pipeline_id, query.element.as_deref(), format)
+    })
+    .await
+    .map_err(|e| SnapshotError::Failed(e.to_string()))
+    .and_then(|result| result)
+    .map_err(|e| match e {
+        SnapshotError::NotRunning => (
+            StatusCode::CONFLICT,
+            Json(ApiError::with_details(
+                "Pipeline is not running",
+                &format!(
+                    "Snapshots can only be taken while the pipeline is running; it is {:?}",
+                    pipeline.state
+                ),
+            )),
+        ),
+        SnapshotError::ElementNotFound(name) => (
+            StatusCode::NOT_FOUND,
+            Json(ApiError::with_details(
+                "Element not found",
+                &format!("The pipeline has no element named '{name}'"),
+            )),
+        ),
+        SnapshotError::NoFrame => (
+            StatusCode::NOT_FOUND,
+            Json(ApiError::with_details(
+                "No video frame available",
+                "No sink has received raw video yet; add a raw video branch ending in a sink",
+            )),
+        ),
+        SnapshotError::Unavailable(element) => (
+            StatusCode::NOT_IMPLEMENTED,
+            Json(ApiError::with_details(
+                "Snapshots unavailable",
+                &format!("The GStreamer element '{element}' is not installed on the server"),
+            )),
+        ),
+        SnapshotError::Failed(details) => {
+            tracing::error!("Failed to snapshot pipeline {}: {}", id, details);
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(ApiError::with_details(
+                    "Failed to encode snapshot",
+                    &details,
+                )),
+            )
+        }
+    })?;
+
+    Ok((
+        [
+            (header::CONTENT_TYPE, format.content_type()),
+            (header::CACHE_CONTROL, "no-store"),
+        ],
+        image,
+    )
+        .into_response())
+}
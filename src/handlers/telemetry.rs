@@ -0,0 +1,59 @@
+//! Request tracing middleware.
+//!
+//! [`trace_requests`] wraps every routed request in an `http_request` span,
+//! the root of the spans recorded while handling it and of the pipeline jobs
+//! it submits. A W3C `traceparent` header from the caller makes the span a
+//! child of the caller's trace.
+
+use axum::{
+    extract::{MatchedPath, Request},
+    middleware::Next,
+    response::Response,
+};
+use std::collections::HashMap;
+use tracing::{field, info_span, Instrument};
+
+// ---
+
+// Import through gateways
+use crate::services::continue_trace;
+
+/// Incoming headers that carry the caller's trace context.
+const TRACE_HEADERS: [&str; 2] = ["traceparent", "tracestate"];
+
+/// Runs the request inside an `http_request` span labeled with its route.
+///
+/// Handlers that submit a pipeline record its ID on this span as
+/// `pipeline.id`.
+pub async fn trace_requests(request: Request, next: Next) -> Response {
+    // ---
+    let route = request
+        .extensions()
+        .get::<MatchedPath>()
+        .map(|path| path.as_str().to_string())
+        .unwrap_or_default();
+    let method = request.method().clone();
+
+    let span = info_span!(
+        "http_request",
+        otel.name = %format!("{} {}", method, route),
+        otel.kind = "server",
+        http.method = %method,
+        http.route = %route,
+        http.status_code = field::Empty,
+        pipeline.id = field::Empty,
+    );
+
+    let carrier: HashMap<String, String> = TRACE_HEADERS
+        .iter()
+        .filter_map(|name| {
+            let value = request.headers().get(*name)?.to_str().ok()?;
+            Some((name.to_string(), value.to_string()))
+        })
+        .collect();
+    continue_trace(&span, &carrier);
+
+    let response = next.run(request).instrument(span.clone()).await;
+    span.record("http.status_code", response.status().as_u16());
+    response
+}
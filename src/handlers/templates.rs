@@ -0,0 +1,270 @@
+//! Pipeline template HTTP endpoint handlers.
+//!
+//! Templates let operators publish vetted pipeline strings once and let
+//! clients create pipelines from them by supplying typed values only.
+//!
+//! # Scopes
+//!
+//! Templates are shared by every caller, so saving and deleting them requires
+//! the dedicated `templates:write` scope; a key allowed to submit its own
+//! custom pipelines still cannot change what other callers instantiate.
+//! Instantiating one only requires `pipelines:write`, since callers can no
+//! longer alter the pipeline beyond its placeholders.
+
+use axum::{
+    extract::{Extension, Path, State},
+    http::StatusCode,
+    response::Json,
+};
+use serde_json::json;
+
+// ---
+
+// Import through gateways
+use crate::models::{
+    ApiError, Caller, CreateTemplateRequest, InstantiateTemplateRequest, PipelineInfo,
+    PipelineTemplate, TemplateListResponse,
+};
+use crate::services::{render_template, TemplateError};
+
+// ---
+
+// Shared state and error helpers
+use super::{submit_custom_pipeline, AppState, JobSettings};
+
+/// Saves a named pipeline template.
+///
+/// The template is a GStreamer pipeline string with typed placeholders such
+/// as `{{source_url:url}}` or `{{bitrate:int:100..20000}}`. It is validated
+/// by substituting a sample value for each placeholder and running the
+/// result through the same validation as `POST /pipelines`.
+///
+/// # Request Body
+/// - `name`: Unique name (lowercase letters, digits, `-` and `_`)
+/// - `description`: Optional description for pipelines created from it
+/// - `template`: Pipeline string with placeholders
+///
+/// # Response Behavior
+/// - **200 OK**: Template saved; the response lists its parsed parameters
+/// - **400 Bad Request**: Invalid name, malformed placeholder or failed validation
+/// - **409 Conflict**: A template with this name already exists
+/// - **500 Internal Server Error**: The template could not be stored
+///
+/// # Example Usage
+/// ```bash
+/// curl -X POST http://localhost:8080/templates \
+///   -H "Content-Type: application/json" \
+///   -d '{"name": "hls-restream", "template": "souphttpsrc location={{source_url:url}} ! decodebin ! videoconvert ! x264enc bitrate={{bitrate:int:100..20000}} ! mpegtsmux ! hlssink"}'
+/// ```
+pub async fn create_template(
+    State(state): State<AppState>,
+    Json(payload): Json<CreateTemplateRequest>,
+) -> Result<Json<PipelineTemplate>, (StatusCode, Json<ApiError>)> {
+    // ---
+    let templates = state.templates.clone();
+    let template = tokio::task::spawn_blocking(move || {
+        templates.save(&payload.name, payload.description, &payload.template)
+    })
+    .await
+    .map_err(|e| template_store_error(e.into()))?
+    .map_err(|e| match e {
+        TemplateError::InvalidName(details) => (
+            StatusCode::BAD_REQUEST,
+            Json(ApiError::with_details("Invalid template name", &details)),
+        ),
+        TemplateError::Invalid(details) => (
+            StatusCode::BAD_REQUEST,
+            Json(ApiError::with_details("Invalid template", &details)),
+        ),
+        TemplateError::Exists(name) => (
+            StatusCode::CONFLICT,
+            Json(ApiError::with_details(
+                "Template already exists",
+                &format!("Delete template '{name}' first to replace it"),
+            )),
+        ),
+        TemplateError::Io(e) => template_store_error(e),
+    })?;
+
+    tracing::info!(
+        "Saved template {} with {} parameters",
+        template.name,
+        template.parameters.len()
+    );
+    Ok(Json(template))
+}
+
+/// Lists stored templates in name order.
+///
+/// # Response Behavior
+/// - **200 OK**: `{"templates": [...]}`
+/// - **500 Internal Server Error**: The template directory could not be read
+///
+/// # Example Usage
+/// ```bash
+/// curl http://localhost:8080/templates
+/// ```
+pub async fn list_templates(
+    State(state): State<AppState>,
+) -> Result<Json<TemplateListResponse>, (StatusCode, Json<ApiError>)> {
+    // ---
+    let templates = state.templates.list().map_err(template_store_error)?;
+    Ok(Json(TemplateListResponse { templates }))
+}
+
+/// Returns a stored template with its parameters.
+///
+/// # Path Parameters
+/// - `name`: Template name
+///
+/// # Response Behavior
+/// - **200 OK**: The template
+/// - **404 Not Found**: No template has this name
+///
+/// # Example Usage
+/// ```bash
+/// curl http://localhost:8080/templates/hls-restream
+/// ```
+pub async fn get_template(
+    State(state): State<AppState>,
+    Path(name): Path<String>,
+) -> Result<Json<PipelineTemplate>, (StatusCode, Json<ApiError>)> {
+    // ---
+    find_template(&state, &name).map(Json)
+}
+
+/// Deletes a stored template. Pipelines created from it are not affected.
+///
+/// # Path Parameters
+/// - `name`: Template name
+///
+/// # Response Behavior
+/// - **200 OK**: Template deleted
+/// - **404 Not Found**: No template has this name
+///
+/// # Example Usage
+/// ```bash
+/// curl -X DELETE http://localhost:8080/templates/hls-restream
+/// ```
+pub async fn delete_template(
+    State(state): State<AppState>,
+    Path(name): Path<String>,
+) -> Result<Json<serde_json::Value>, (StatusCode, Json<ApiError>)> {
+    // ---
+    if !state
+        .templates
+        .remove(&name)
+        .map_err(template_store_error)?
+    {
+        return Err(template_not_found());
+    }
+
+    tracing::info!("Deleted template {}", name);
+    Ok(Json(json!({
+        "message": "Template deleted successfully",
+        "name": name
+    })))
+}
+
+/// Creates a pipeline from a template.
+///
+/// Each value is checked against its placeholder's type and range and
+/// substituted as an escaped literal, so values cannot change the pipeline's
+/// structure. The resulting pipeline is validated and queued exactly like
+/// one submitted to `POST /pipelines`.
+///
+/// # Path Parameters
+/// - `name`: Template name
+///
+/// # Request Body
+/// - `parameters`: Value of every placeholder, keyed by name
+/// - `description`: Pipeline description (default: the template's)
+/// - `callback_url`: Optional completion webhook
+/// - `max_runtime_seconds`, `max_output_bytes`: Optional execution limits
+/// - `retry`: Optional automatic retry policy
+///
+/// # Response Behavior
+/// - **200 OK**: Pipeline created, same response as `POST /pipelines`
+/// - **400 Bad Request**: Missing, unknown or invalid parameter values,
+///   execution limits or retry policy, or the resulting pipeline fails validation
+/// - **404 Not Found**: No template has this name
+/// - **507 Insufficient Storage**: Output storage is above its high watermark
+///
+/// # Example Usage
+/// ```bash
+/// curl -X POST http://localhost:8080/templates/hls-restream/instantiate \
+///   -H "Content-Type: application/json" \
+///   -d '{"parameters": {"source_url": "https://example.com/live.m3u8", "bitrate": 2500}}'
+/// ```
+pub async fn instantiate_template(
+    State(state): State<AppState>,
+    Extension(caller): Extension<Caller>,
+    Path(name): Path<String>,
+    Json(payload): Json<InstantiateTemplateRequest>,
+) -> Result<Json<PipelineInfo>, (StatusCode, Json<ApiError>)> {
+    // ---
+    let template = find_template(&state, &name)?;
+    let pipeline = render_template(&template, &payload.parameters).map_err(|details| {
+        (
+            StatusCode::BAD_REQUEST,
+            Json(ApiError::with_details(
+                "Invalid template parameters",
+                &details,
+            )),
+        )
+    })?;
+
+    let description = payload
+        .description
+        .or(template.description)
+        .unwrap_or_else(|| format!("From template {name}"));
+    let pipeline_info = submit_custom_pipeline(
+        &state,
+        &caller,
+        description,
+        pipeline,
+        None,
+        JobSettings {
+            callback_url: payload.callback_url,
+            limits: payload.limits,
+            retry: payload.retry,
+        },
+    )?;
+
+    Ok(Json(pipeline_info))
+}
+
+/// Loads a template or fails with 404.
+fn find_template(
+    state: &AppState,
+    name: &str,
+) -> Result<PipelineTemplate, (StatusCode, Json<ApiError>)> {
+    // ---
+    state
+        .templates
+        .get(name)
+        .map_err(template_store_error)?
+        .ok_or_else(template_not_found)
+}
+
+/// Builds the 404 response for unknown template names.
+fn template_not_found() -> (StatusCode, Json<ApiError>) {
+    // ---
+    (
+        StatusCode::NOT_FOUND,
+        Json(ApiError::new("Template not found")),
+    )
+}
+
+/// Converts a template storage failure into a 500 response.
+fn template_store_error(error: anyhow::Error) -> (StatusCode, Json<ApiError>) {
+    // ---
+    tracing::error!("Template store error: {:#}", error);
+    (
+        StatusCode::INTERNAL_SERVER_ERROR,
+        Json(ApiError::with_details(
+            "Template store error",
+            &error.to_string(),
+        )),
+    )
+}
@@ -0,0 +1,380 @@
+//! tus 1.0 resumable upload HTTP endpoint handlers.
+//!
+//! Exposes [`TusUploads`](crate::services::TusUploads) under `/uploads/tus`
+//! for clients sending large recordings over unreliable links. Any tus 1.0
+//! client (tus-js-client, tusd's CLI, Uppy, ...) can talk to these endpoints.
+//!
+//! # Protocol Summary
+//!
+//! - `OPTIONS /uploads/tus`: Advertises version, extensions and maximum size
+//! - `POST /uploads/tus`: Creates an upload from `Upload-Length` (creation)
+//! - `HEAD /uploads/tus/{id}`: Reports `Upload-Offset` to resume from
+//! - `PATCH /uploads/tus/{id}`: Appends bytes at `Upload-Offset`
+//! - `DELETE /uploads/tus/{id}`: Discards an unfinished upload (termination)
+//!
+//! Every request except `OPTIONS` must carry `Tus-Resumable: 1.0.0`, and
+//! every response carries it back. Uploads belong to the caller that created
+//! them; `HEAD`, `PATCH` and `DELETE` from other callers get 404.
+
+use axum::{
+    extract::{Extension, Path, Request, State},
+    http::{header, HeaderMap, HeaderName, HeaderValue, StatusCode},
+    response::{IntoResponse, Json, Response},
+};
+use tracing::{info, warn};
+
+// ---
+
+// Import through gateways
+use crate::models::{ApiError, Caller};
+use crate::services::{TusError, UploadError, TUS_EXTENSIONS, TUS_VERSION};
+
+// ---
+
+// Shared state and error helpers
+use super::{check_capacity, AppState};
+
+const TUS_RESUMABLE: HeaderName = HeaderName::from_static("tus-resumable");
+const TUS_VERSION_HEADER: HeaderName = HeaderName::from_static("tus-version");
+const TUS_EXTENSION: HeaderName = HeaderName::from_static("tus-extension");
+const TUS_MAX_SIZE: HeaderName = HeaderName::from_static("tus-max-size");
+const UPLOAD_LENGTH: HeaderName = HeaderName::from_static("upload-length");
+const UPLOAD_OFFSET: HeaderName = HeaderName::from_static("upload-offset");
+const UPLOAD_METADATA: HeaderName = HeaderName::from_static("upload-metadata");
+
+/// Content type required on `PATCH` requests
+const OFFSET_OCTET_STREAM: &str = "application/offset+octet-stream";
+
+/// Error response carrying the `Tus-Resumable` header.
+///
+/// Boxed so handler results stay small.
+pub struct TusRejection(Box<Response>);
+
+impl IntoResponse for TusRejection {
+    // ---
+    fn into_response(self) -> Response {
+        // ---
+        *self.0
+    }
+}
+
+/// Advertises the server's tus capabilities.
+///
+/// # Response Behavior
+/// - **204 No Content**: With `Tus-Version`, `Tus-Extension` and `Tus-Max-Size`
+///
+/// # Example Usage
+/// ```bash
+/// curl -i -X OPTIONS http://localhost:8080/uploads/tus
+/// ```
+pub async fn tus_options(State(state): State<AppState>) -> Response {
+    // ---
+    (
+        StatusCode::NO_CONTENT,
+        [
+            (TUS_RESUMABLE, TUS_VERSION.to_string()),
+            (TUS_VERSION_HEADER, TUS_VERSION.to_string()),
+            (TUS_EXTENSION, TUS_EXTENSIONS.to_string()),
+            (TUS_MAX_SIZE, state.tus.max_size().to_string()),
+        ],
+    )
+        .into_response()
+}
+
+/// Creates a resumable upload (tus `creation` extension).
+///
+/// # Request Headers
+/// - `Upload-Length`: Total size of the file in bytes (required)
+/// - `Upload-Metadata`: Optional `key base64value` pairs; `filename` is recorded
+///
+/// # Response Behavior
+/// - **201 Created**: `Location` points at the new upload resource
+/// - **400 Bad Request**: Missing or invalid `Upload-Length`
+/// - **412 Precondition Failed**: Missing or unsupported `Tus-Resumable`
+/// - **413 Payload Too Large**: `Upload-Length` exceeds `--max-upload-size`
+/// - **507 Insufficient Storage**: Output storage is above its high watermark
+///
+/// # Example Usage
+/// ```bash
+/// curl -i -X POST http://localhost:8080/uploads/tus \
+///   -H "Tus-Resumable: 1.0.0" -H "Upload-Length: 1048576" \
+///   -H "Upload-Metadata: filename Y2xpcC5tcDQ="
+/// ```
+pub async fn tus_create(
+    State(state): State<AppState>,
+    Extension(caller): Extension<Caller>,
+    headers: HeaderMap,
+) -> Result<Response, TusRejection> {
+    // ---
+    check_version(&headers)?;
+    check_capacity(&state.retention)
+        .map_err(|rejection| TusRejection(Box::new(with_tus(rejection.into_response()))))?;
+
+    let length = header_u64(&headers, &UPLOAD_LENGTH).ok_or_else(|| {
+        tus_error(
+            StatusCode::BAD_REQUEST,
+            "Invalid upload",
+            "Upload-Length must be a non-negative integer",
+        )
+    })?;
+    let metadata = headers
+        .get(&UPLOAD_METADATA)
+        .and_then(|value| value.to_str().ok());
+
+    let id = state
+        .tus
+        .create(caller.owner().as_deref(), length, metadata)
+        .map_err(tus_rejection)?;
+
+    Ok((
+        StatusCode::CREATED,
+        [
+            (TUS_RESUMABLE, TUS_VERSION.to_string()),
+            (header::LOCATION, format!("/uploads/tus/{id}")),
+        ],
+    )
+        .into_response())
+}
+
+/// Reports how much of an upload the server has stored.
+///
+/// # Response Behavior
+/// - **200 OK**: `Upload-Offset` and `Upload-Length` headers, never cached
+/// - **404 Not Found**: Unknown or terminated upload, or one of another caller
+/// - **412 Precondition Failed**: Missing or unsupported `Tus-Resumable`
+///
+/// # Example Usage
+/// ```bash
+/// curl -I http://localhost:8080/uploads/tus/7c9e6679-7425-40de-944b-e07fc1f90ae7 -H "Tus-Resumable: 1.0.0"
+/// ```
+pub async fn tus_head(
+    State(state): State<AppState>,
+    Extension(caller): Extension<Caller>,
+    Path(id): Path<String>,
+    headers: HeaderMap,
+) -> Result<Response, TusRejection> {
+    // ---
+    check_version(&headers)?;
+
+    let (offset, length) = state
+        .tus
+        .status(caller.owner().as_deref(), &id)
+        .map_err(tus_rejection)?;
+
+    Ok((
+        StatusCode::OK,
+        [
+            (TUS_RESUMABLE, TUS_VERSION.to_string()),
+            (UPLOAD_OFFSET, offset.to_string()),
+            (UPLOAD_LENGTH, length.to_string()),
+            (header::CACHE_CONTROL, "no-store".to_string()),
+        ],
+    )
+        .into_response())
+}
+
+/// Appends bytes to an upload.
+///
+/// Bytes received before a dropped connection are kept; the client resumes
+/// after asking `HEAD` for the current offset. The request that completes the
+/// upload also makes it available as `upload://{id}`.
+///
+/// # Request Headers
+/// - `Content-Type`: Must be `application/offset+octet-stream`
+/// - `Upload-Offset`: Offset the body starts at; must equal the stored offset
+///
+/// # Response Behavior
+/// - **204 No Content**: Bytes stored; `Upload-Offset` reports the new offset
+/// - **404 Not Found**: Unknown or terminated upload, or one of another caller
+/// - **409 Conflict**: Offset mismatch, or another request is writing to the upload
+/// - **412 Precondition Failed**: Missing or unsupported `Tus-Resumable`
+/// - **413 Payload Too Large**: Body extends past `Upload-Length`
+/// - **415 Unsupported Media Type**: Wrong content type, or the completed file is not media
+///
+/// # Example Usage
+/// ```bash
+/// curl -i -X PATCH http://localhost:8080/uploads/tus/7c9e6679-7425-40de-944b-e07fc1f90ae7 \
+///   -H "Tus-Resumable: 1.0.0" -H "Upload-Offset: 0" \
+///   -H "Content-Type: application/offset+octet-stream" --data-binary @clip.mp4
+/// ```
+pub async fn tus_patch(
+    State(state): State<AppState>,
+    Extension(caller): Extension<Caller>,
+    Path(id): Path<String>,
+    request: Request,
+) -> Result<Response, TusRejection> {
+    // ---
+    let headers = request.headers();
+    check_version(headers)?;
+
+    let content_type = headers
+        .get(header::CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok());
+    if content_type != Some(OFFSET_OCTET_STREAM) {
+        return Err(tus_error(
+            StatusCode::UNSUPPORTED_MEDIA_TYPE,
+            "Invalid upload",
+            "PATCH requests must use Content-Type: application/offset+octet-stream",
+        ));
+    }
+
+    let offset = header_u64(headers, &UPLOAD_OFFSET).ok_or_else(|| {
+        tus_error(
+            StatusCode::BAD_REQUEST,
+            "Invalid upload",
+            "Upload-Offset must be a non-negative integer",
+        )
+    })?;
+
+    let body = request.into_body().into_data_stream();
+    let progress = state
+        .tus
+        .append(caller.owner().as_deref(), &id, offset, body)
+        .await
+        .map_err(tus_rejection)?;
+
+    if let Some(upload) = &progress.upload {
+        info!(
+            "Completed resumable upload {} ({} bytes), available as {}",
+            upload.id, upload.size, upload.source_url
+        );
+    }
+
+    Ok((
+        StatusCode::NO_CONTENT,
+        [
+            (TUS_RESUMABLE, TUS_VERSION.to_string()),
+            (UPLOAD_OFFSET, progress.offset.to_string()),
+        ],
+    )
+        .into_response())
+}
+
+/// Discards an unfinished upload (tus `termination` extension).
+///
+/// # Response Behavior
+/// - **204 No Content**: Upload and its partial data deleted
+/// - **404 Not Found**: Unknown upload, one of another caller, or one that has already completed
+/// - **409 Conflict**: A request is still writing to the upload
+/// - **412 Precondition Failed**: Missing or unsupported `Tus-Resumable`
+///
+/// # Example Usage
+/// ```bash
+/// curl -i -X DELETE http://localhost:8080/uploads/tus/7c9e6679-7425-40de-944b-e07fc1f90ae7 -H "Tus-Resumable: 1.0.0"
+/// ```
+pub async fn tus_delete(
+    State(state): State<AppState>,
+    Extension(caller): Extension<Caller>,
+    Path(id): Path<String>,
+    headers: HeaderMap,
+) -> Result<Response, TusRejection> {
+    // ---
+    check_version(&headers)?;
+
+    state
+        .tus
+        .terminate(caller.owner().as_deref(), &id)
+        .map_err(tus_rejection)?;
+
+    Ok((StatusCode::NO_CONTENT, [(TUS_RESUMABLE, TUS_VERSION)]).into_response())
+}
+
+/// Rejects requests that do not speak tus 1.0.0 with 412.
+fn check_version(headers: &HeaderMap) -> Result<(), TusRejection> {
+    // ---
+    if headers.get(&TUS_RESUMABLE).map(HeaderValue::as_bytes) == Some(TUS_VERSION.as_bytes()) {
+        return Ok(());
+    }
+
+    let mut rejection = tus_error(
+        StatusCode::PRECONDITION_FAILED,
+        "Unsupported tus version",
+        "Requests must carry Tus-Resumable: 1.0.0",
+    );
+    rejection
+        .0
+        .headers_mut()
+        .insert(TUS_VERSION_HEADER, HeaderValue::from_static(TUS_VERSION));
+    Err(rejection)
+}
+
+/// Parses a non-negative integer header.
+fn header_u64(headers: &HeaderMap, name: &HeaderName) -> Option<u64> {
+    // ---
+    headers.get(name)?.to_str().ok()?.parse().ok()
+}
+
+/// Maps service errors onto tus status codes.
+fn tus_rejection(error: TusError) -> TusRejection {
+    // ---
+    match error {
+        TusError::NotFound => tus_error(
+            StatusCode::NOT_FOUND,
+            "Upload not found",
+            "No unfinished upload with that ID",
+        ),
+        TusError::OffsetMismatch(current) => {
+            let mut rejection = tus_error(
+                StatusCode::CONFLICT,
+                "Upload offset mismatch",
+                &format!("The upload is at offset {current}"),
+            );
+            rejection
+                .0
+                .headers_mut()
+                .insert(UPLOAD_OFFSET, HeaderValue::from(current));
+            rejection
+        }
+        TusError::TooLarge(limit) => tus_error(
+            StatusCode::PAYLOAD_TOO_LARGE,
+            "Upload too large",
+            &format!("Uploads are limited to {limit} bytes"),
+        ),
+        TusError::Busy => tus_error(
+            StatusCode::CONFLICT,
+            "Upload busy",
+            "Another request is writing to this upload",
+        ),
+        TusError::Upload(UploadError::UnsupportedType(media_type)) => tus_error(
+            StatusCode::UNSUPPORTED_MEDIA_TYPE,
+            "Unsupported media type",
+            &format!(
+                "Upload was detected as {media_type}, which is not audio, video or image media"
+            ),
+        ),
+        TusError::Upload(UploadError::UnknownType) => tus_error(
+            StatusCode::UNSUPPORTED_MEDIA_TYPE,
+            "Unsupported media type",
+            "Upload could not be identified as audio, video or image media",
+        ),
+        TusError::Upload(UploadError::TooLarge(limit)) => tus_rejection(TusError::TooLarge(limit)),
+        TusError::Upload(UploadError::Body(message)) => {
+            tus_error(StatusCode::BAD_REQUEST, "Invalid upload", &message)
+        }
+        TusError::Upload(UploadError::Io(e)) => {
+            warn!("Failed to store resumable upload: {:#}", e);
+            tus_error(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Upload storage error",
+                &e.to_string(),
+            )
+        }
+    }
+}
+
+/// Builds an `ApiError` response carrying `Tus-Resumable`.
+fn tus_error(status: StatusCode, error: &str, details: &str) -> TusRejection {
+    // ---
+    TusRejection(Box::new(with_tus(
+        (status, Json(ApiError::with_details(error, details))).into_response(),
+    )))
+}
+
+/// Adds `Tus-Resumable` to a response built elsewhere.
+fn with_tus(mut response: Response) -> Response {
+    // ---
+    response
+        .headers_mut()
+        .insert(TUS_RESUMABLE, HeaderValue::from_static(TUS_VERSION));
+    response
+}
@@ -0,0 +1,195 @@
+//! Source media upload HTTP endpoint handlers.
+//!
+//! Lets clients send media directly to the service instead of hosting it at
+//! a public URL. A completed upload is referenced in `ConvertRequest`,
+//! `ThumbnailRequest` and `StreamRequest` as `source_url: "upload://<id>"`.
+//!
+//! # Request Formats
+//!
+//! - **Raw body**: The request body is the file; an optional `filename` query
+//!   parameter records its original name
+//! - **Multipart**: `multipart/form-data` with the file in the first part that
+//!   carries a file name (conventionally named `file`)
+//!
+//! Both are streamed to disk without buffering the file in memory.
+//!
+//! # Ownership
+//!
+//! Uploads belong to the caller that sent them. `GET /uploads/{id}` and
+//! `upload://` job sources only resolve the caller's own uploads; other
+//! callers get 404 / 400 as if the ID did not exist.
+
+use axum::{
+    extract::{Extension, FromRequest, Multipart, Path, Query, Request, State},
+    http::{header, StatusCode},
+    response::Json,
+};
+use tracing::{info, warn};
+
+// ---
+
+// Import through gateways
+use crate::models::{ApiError, Caller, UploadInfo, UploadQuery};
+use crate::services::UploadError;
+
+// ---
+
+// Shared state and error helpers
+use super::{check_capacity, AppState};
+
+/// Uploads a media file for use as a job source.
+///
+/// # Query Parameters
+/// - `filename`: Original file name, recorded for raw-body uploads
+///
+/// # Processing
+/// - The body is written to the caller's directory below `--upload-dir` as it arrives
+/// - Uploads exceeding `--max-upload-size` are aborted and discarded
+/// - The completed file is sniffed with GStreamer's typefind; data recognised
+///   as a non-media type (text, archives, documents), or not recognised at
+///   all, is rejected
+///
+/// # Response Behavior
+/// - **200 OK**: Upload stored; the response carries its `source_url`
+/// - **400 Bad Request**: Malformed multipart body, missing file part or aborted transfer
+/// - **413 Payload Too Large**: The upload exceeds `--max-upload-size`
+/// - **415 Unsupported Media Type**: typefind identified the data as non-media, or could not identify it
+/// - **507 Insufficient Storage**: Output storage is above its high watermark
+///
+/// # Example Usage
+/// ```bash
+/// curl -X POST "http://localhost:8080/uploads?filename=clip.mp4" --data-binary @clip.mp4
+/// curl -X POST http://localhost:8080/uploads -F file=@clip.mp4
+/// ```
+pub async fn upload_media(
+    State(state): State<AppState>,
+    Extension(caller): Extension<Caller>,
+    Query(query): Query<UploadQuery>,
+    request: Request,
+) -> Result<Json<UploadInfo>, (StatusCode, Json<ApiError>)> {
+    // ---
+    let owner = caller.owner();
+
+    check_capacity(&state.retention)?;
+
+    let is_multipart = request
+        .headers()
+        .get(header::CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .is_some_and(|value| value.starts_with("multipart/form-data"));
+
+    let result = if is_multipart {
+        let mut multipart = Multipart::from_request(request, &state)
+            .await
+            .map_err(|e| bad_upload(&e.body_text()))?;
+
+        loop {
+            let Some(field) = multipart
+                .next_field()
+                .await
+                .map_err(|e| bad_upload(&e.body_text()))?
+            else {
+                return Err(bad_upload("Multipart body has no file part"));
+            };
+
+            if let Some(filename) = field.file_name().map(str::to_string) {
+                break state
+                    .uploads
+                    .receive(owner.as_deref(), field, Some(filename))
+                    .await;
+            }
+        }
+    } else {
+        let body = request.into_body().into_data_stream();
+        state
+            .uploads
+            .receive(owner.as_deref(), body, query.filename)
+            .await
+    };
+
+    match result {
+        Ok(upload) => {
+            info!("Received upload {} ({} bytes)", upload.id, upload.size);
+            Ok(Json(upload))
+        }
+        Err(UploadError::TooLarge(limit)) => Err((
+            StatusCode::PAYLOAD_TOO_LARGE,
+            Json(ApiError::with_details(
+                "Upload too large",
+                &format!("Uploads are limited to {limit} bytes"),
+            )),
+        )),
+        Err(UploadError::UnsupportedType(media_type)) => Err((
+            StatusCode::UNSUPPORTED_MEDIA_TYPE,
+            Json(ApiError::with_details(
+                "Unsupported media type",
+                &format!(
+                    "Upload was detected as {media_type}, which is not audio, video or image media"
+                ),
+            )),
+        )),
+        Err(UploadError::UnknownType) => Err((
+            StatusCode::UNSUPPORTED_MEDIA_TYPE,
+            Json(ApiError::with_details(
+                "Unsupported media type",
+                "Upload could not be identified as audio, video or image media",
+            )),
+        )),
+        Err(UploadError::Body(message)) => Err(bad_upload(&message)),
+        Err(UploadError::Io(e)) => {
+            warn!("Failed to store upload: {:#}", e);
+            Err((
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(ApiError::with_details(
+                    "Upload storage error",
+                    &e.to_string(),
+                )),
+            ))
+        }
+    }
+}
+
+/// Returns the metadata of a completed upload.
+///
+/// # Path Parameters
+/// - `id`: The upload ID returned by `POST /uploads`
+///
+/// # Response Behavior
+/// - **200 OK**: Upload found
+/// - **404 Not Found**: No completed upload with that ID owned by the caller
+///
+/// # Example Usage
+/// ```bash
+/// curl http://localhost:8080/uploads/7c9e6679-7425-40de-944b-e07fc1f90ae7
+/// ```
+pub async fn get_upload(
+    State(state): State<AppState>,
+    Extension(caller): Extension<Caller>,
+    Path(id): Path<String>,
+) -> Result<Json<UploadInfo>, (StatusCode, Json<ApiError>)> {
+    // ---
+
+    match state.uploads.get(caller.owner().as_deref(), &id) {
+        Ok(Some(upload)) => Ok(Json(upload)),
+        Ok(None) => Err((
+            StatusCode::NOT_FOUND,
+            Json(ApiError::new("Upload not found")),
+        )),
+        Err(e) => Err((
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(ApiError::with_details(
+                "Upload storage error",
+                &e.to_string(),
+            )),
+        )),
+    }
+}
+
+/// Builds the 400 response for malformed or aborted uploads.
+fn bad_upload(details: &str) -> (StatusCode, Json<ApiError>) {
+    // ---
+    (
+        StatusCode::BAD_REQUEST,
+        Json(ApiError::with_details("Invalid upload", details)),
+    )
+}
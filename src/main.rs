@@ -13,6 +13,18 @@
 //! - **Custom Pipelines**    : Support for user-defined GStreamer pipeline execution
 //! - **Media Analysis**      : Extract metadata and technical information from remote media files
 //! - **Pipeline Management** : Full CRUD operations for pipeline lifecycle management
+//! - **Pipeline Execution**  : Optional worker pool running queued pipelines to completion
+//! - **Completion Webhooks** : Signed, retried callbacks when pipelines finish
+//! - **Output Storage**      : Per-job output directories with optional S3 upload
+//! - **Retention**           : Per-kind TTLs, background cleanup and disk watermarks
+//! - **Media Uploads**       : Direct uploads usable as job sources via `upload://{id}`
+//! - **Templates**           : Named pipeline templates with typed, escaped parameters
+//! - **Element Catalog**     : Installed element factories, pad templates and properties
+//! - **Authentication**      : Hashed API keys and JWT bearer tokens with scopes and tenant isolation
+//! - **Rate Limiting**       : Per-client token buckets per route group and per-owner job quotas
+//! - **Execution Limits**    : Per-job runtime and output size limits with server defaults and caps
+//! - **Automatic Retries**   : Per-job retry policies with backoff for GStreamer error domains
+//! - **Metrics**             : Prometheus export of request, pipeline and GStreamer metrics
 //!
 //! # Architecture
 //!
@@ -35,10 +47,31 @@
 //! - `GET /analyze/{url}` - Analyze remote media file metadata
 //!
 //! ## Pipeline Management
-//! - `GET /pipelines`         - List all active pipelines
-//! - `POST /pipelines`        - Create custom GStreamer pipelines
-//! - `GET /pipelines/{id}`    - Get specific pipeline status
-//! - `DELETE /pipelines/{id}` - Stop pipeline execution
+//! - `GET /pipelines`             - List pipelines (filtered, cursor-paginated)
+//! - `POST /pipelines`            - Create custom GStreamer pipelines (string or structured graph)
+//! - `GET /pipelines/{id}`        - Get specific pipeline status
+//! - `DELETE /pipelines/{id}`     - Stop pipeline execution (`?purge=true` also deletes it)
+//! - `POST /pipelines/{id}/retry` - Run a finished pipeline again
+//!
+//! ## Output Downloads
+//! - `GET /pipelines/{id}/output`         - Download the primary output (Range supported)
+//! - `GET /pipelines/{id}/outputs/{name}` - Download a named output file
+//!
+//! ## Uploads
+//! - `POST /uploads`     - Upload source media (raw body or multipart), used as `upload://{id}`
+//! - `GET /uploads/{id}` - Get upload metadata
+//! - `/uploads/tus`      - Resumable uploads (tus 1.0 core, creation, termination)
+//!
+//! ## Templates
+//! - `GET /templates`                     - List pipeline templates
+//! - `POST /templates`                    - Save a template with typed placeholders
+//! - `GET /templates/{name}`              - Get a template
+//! - `DELETE /templates/{name}`           - Delete a template
+//! - `POST /templates/{name}/instantiate` - Create a pipeline from a template
+//!
+//! ## Element Catalog
+//! - `GET /elements`           - List installed element factories, optionally by klass
+//! - `GET /elements/{factory}` - Pad templates and properties of an element factory
 //!
 //! ## Service Operations
 //! - `GET /health`  - Service health check and capability reporting
@@ -67,12 +100,16 @@ mod models;
 mod services;
 
 use axum::{
-    routing::{delete, get, post},
+    extract::DefaultBodyLimit,
+    middleware,
+    routing::{delete, get, head, options, patch, post},
     Router,
 };
 use clap::{Parser, ValueEnum};
-use std::collections::HashMap;
-use std::sync::{Arc, Mutex};
+use std::net::SocketAddr;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Duration;
 use tokio::net::TcpListener;
 use tokio::signal;
 use tracing::info;
@@ -81,8 +118,23 @@ use tracing::info;
 
 // Import through module gateways
 use handlers::{
-    analyze_media, convert_media, create_pipeline, create_stream, generate_thumbnail, get_pipeline,
-    health_check, list_pipelines, list_sample_media, stop_pipeline, AppState,
+    analyze_media, authenticate, convert_media, create_pipeline, create_stream, create_template,
+    delete_template, download_named_output, download_output, export_metrics, generate_thumbnail,
+    get_element, get_pipeline, get_pipeline_graph, get_pipeline_logs, get_pipeline_position,
+    get_pipeline_topology, get_snapshot_jpeg, get_snapshot_png, get_template, get_upload,
+    health_check, instantiate_template, limit_requests, list_elements, list_pipelines,
+    list_sample_media, list_templates, retry_pipeline, seek_pipeline, stop_pipeline,
+    trace_requests, track_requests, tus_create, tus_delete, tus_head, tus_options, tus_patch,
+    update_element, upload_media, AppState,
+};
+use models::ExecutionLimits;
+use services::{
+    hash_api_key, ApiKeys, DiskWatermarks, ExecutionLimitPolicy, JwtConfig, JwtVerifier,
+    LocalOutputStorage, LogFormat, MemoryPipelineStore, Metrics, PipelineLogs, PipelineRuntime,
+    QuotaLimits, Quotas, RateLimitRule, RateLimiter, RestartPolicy, RetentionManager,
+    RetentionPolicy, RetentionRule, S3Config, S3OutputStorage, SharedOutputStorage,
+    SharedPipelineStore, SqlitePipelineStore, Telemetry, TelemetryConfig, TemplateStore,
+    TusUploads, UploadStore, WebhookNotifier,
 };
 
 /// Color output control for terminal compatibility.
@@ -116,9 +168,158 @@ struct Cli {
     #[arg(long, default_value = "0.0.0.0")]
     host: String,
 
+    /// Externally reachable base URL used in returned links, e.g. "https://media.example.com"
+    /// (links are relative paths when omitted)
+    #[arg(long, value_name = "URL")]
+    public_url: Option<String>,
+
     /// Control colored log output for terminal compatibility
     #[arg(long, value_enum, default_value_t = ColorWhen::Auto)]
     color: ColorWhen,
+
+    /// Number of pipelines executed concurrently (0 records pipelines without running them)
+    #[arg(long, default_value_t = 0)]
+    workers: usize,
+
+    /// Shared secret used to sign completion webhooks with HMAC-SHA256
+    #[arg(long)]
+    webhook_secret: Option<String>,
+
+    /// Maximum delivery attempts per completion webhook
+    #[arg(long, default_value_t = 5)]
+    webhook_max_attempts: u32,
+
+    /// SQLite database file for persistent pipeline records (in-memory when omitted)
+    #[arg(long)]
+    database: Option<PathBuf>,
+
+    /// How to handle pipelines that were running when the service last exited
+    #[arg(long, value_enum, default_value_t = RestartPolicy::Fail)]
+    on_restart: RestartPolicy,
+
+    /// Directory receiving per-job output subdirectories
+    #[arg(long, default_value = "outputs")]
+    output_dir: PathBuf,
+
+    /// S3 bucket receiving finished artifacts (local storage only when omitted)
+    #[arg(long)]
+    s3_bucket: Option<String>,
+
+    /// S3-compatible endpoint URL [default: https://s3.<region>.amazonaws.com]
+    #[arg(long)]
+    s3_endpoint: Option<String>,
+
+    /// Region used to sign S3 requests
+    #[arg(long, default_value = "us-east-1")]
+    s3_region: String,
+
+    /// Key prefix for uploaded artifacts, e.g. "media/"
+    #[arg(long, default_value = "")]
+    s3_prefix: String,
+
+    /// S3 access key ID
+    #[arg(long, env = "AWS_ACCESS_KEY_ID", hide_env_values = true)]
+    s3_access_key: Option<String>,
+
+    /// S3 secret access key
+    #[arg(long, env = "AWS_SECRET_ACCESS_KEY", hide_env_values = true)]
+    s3_secret_key: Option<String>,
+
+    /// Retention rule KIND:STATE=DURATION, e.g. "stream=6h" or "convert:error=1d" (repeatable)
+    #[arg(long = "retention", value_name = "RULE")]
+    retention_rules: Vec<RetentionRule>,
+
+    /// Seconds between passes of the retention reaper
+    #[arg(long, default_value_t = 60)]
+    reap_interval: u64,
+
+    /// Refuse new jobs once the output filesystem is this full (percent)
+    #[arg(long, value_name = "PERCENT")]
+    disk_high_watermark: Option<f64>,
+
+    /// Accept new jobs again once usage drops below this (percent) [default: high watermark]
+    #[arg(long, value_name = "PERCENT", requires = "disk_high_watermark")]
+    disk_low_watermark: Option<f64>,
+
+    /// Directory receiving uploaded source media
+    #[arg(long, default_value = "uploads")]
+    upload_dir: PathBuf,
+
+    /// Largest accepted upload in bytes
+    #[arg(long, default_value_t = 4 * 1024 * 1024 * 1024)]
+    max_upload_size: u64,
+
+    /// Directory holding saved pipeline templates
+    #[arg(long, default_value = "templates")]
+    template_dir: PathBuf,
+
+    /// JSON file of hashed API keys and their scopes (authentication disabled when omitted)
+    #[arg(long, value_name = "FILE")]
+    api_keys: Option<PathBuf>,
+
+    /// Print the key_hash for an API key file entry and exit
+    #[arg(long, value_name = "KEY")]
+    hash_api_key: Option<String>,
+
+    /// Shared secret accepting HS256 bearer tokens
+    #[arg(long, env = "JWT_SECRET", hide_env_values = true)]
+    jwt_secret: Option<String>,
+
+    /// JWKS file of RSA public keys accepting RS256 bearer tokens
+    #[arg(long, value_name = "FILE")]
+    jwks: Option<PathBuf>,
+
+    /// Required `iss` claim of bearer tokens
+    #[arg(long)]
+    jwt_issuer: Option<String>,
+
+    /// Required `aud` claim of bearer tokens
+    #[arg(long)]
+    jwt_audience: Option<String>,
+
+    /// Rate limit GROUP=COUNT/DURATION per client, e.g. "analyze=10/1m" (repeatable)
+    #[arg(long = "rate-limit", value_name = "RULE")]
+    rate_limits: Vec<RateLimitRule>,
+
+    /// Most pipelines an authenticated owner may have queued or running
+    #[arg(long, value_name = "N")]
+    max_running_per_owner: Option<usize>,
+
+    /// Most bytes an authenticated owner's job outputs and uploads may hold
+    #[arg(long, value_name = "BYTES")]
+    max_output_bytes_per_owner: Option<u64>,
+
+    /// Runtime limit of jobs that do not set `max_runtime_seconds`
+    #[arg(long, value_name = "SECONDS")]
+    default_max_runtime_seconds: Option<u64>,
+
+    /// Largest `max_runtime_seconds` a job may request; also the default when no default is set
+    #[arg(long, value_name = "SECONDS")]
+    max_runtime_seconds_cap: Option<u64>,
+
+    /// Output size limit of jobs that do not set `max_output_bytes`
+    #[arg(long, value_name = "BYTES")]
+    default_max_output_bytes: Option<u64>,
+
+    /// Largest `max_output_bytes` a job may request; also the default when no default is set
+    #[arg(long, value_name = "BYTES")]
+    max_output_bytes_cap: Option<u64>,
+
+    /// Log output format
+    #[arg(long, value_enum, default_value_t = LogFormat::Text)]
+    log_format: LogFormat,
+
+    /// Most captured messages kept per pipeline for GET /pipelines/{id}/logs
+    #[arg(long, value_name = "N", default_value_t = 200)]
+    log_buffer_size: usize,
+
+    /// OTLP/HTTP collector URL to export traces to, e.g. "http://localhost:4318"
+    #[arg(long, env = "OTEL_EXPORTER_OTLP_ENDPOINT", value_name = "URL")]
+    otlp_endpoint: Option<String>,
+
+    /// Service name reported on exported traces
+    #[arg(long, default_value = "media-pipeline-service")]
+    otel_service_name: String,
 }
 
 /// Application entry point and service initialization.
@@ -129,11 +330,13 @@ struct Cli {
 ///
 /// # Initialization Sequence
 /// 1. Parse command-line arguments for service configuration
-/// 2. Initialize structured logging with terminal-aware colorization
+/// 2. Initialize structured logging with terminal-aware colorization and optional trace export
 /// 3. Initialize GStreamer multimedia framework
-/// 4. Create shared application state for pipeline tracking
-/// 5. Configure HTTP routing with all API endpoints
-/// 6. Start HTTP server with graceful shutdown handling
+/// 4. Open the pipeline store, start the runtime and recover interrupted jobs
+/// 5. Configure retention, start the background reaper and open the upload directory
+/// 6. Load API keys and JWT keys when authentication is enabled
+/// 7. Configure HTTP routing with all API endpoints behind the metrics, auth and rate limit middleware
+/// 8. Start HTTP server with graceful shutdown handling
 ///
 /// # Error Handling
 /// All initialization errors are propagated using `anyhow::Error` for
@@ -150,6 +353,11 @@ async fn main() -> anyhow::Result<()> {
     // Parse command line arguments
     let cli = Cli::parse();
 
+    if let Some(key) = &cli.hash_api_key {
+        println!("{}", hash_api_key(key));
+        return Ok(());
+    }
+
     // ---
 
     // Initialize tracing with smart colorization
@@ -162,7 +370,12 @@ async fn main() -> anyhow::Result<()> {
         }
     };
 
-    tracing_subscriber::fmt().with_ansi(use_color).init();
+    let telemetry = Telemetry::init(TelemetryConfig {
+        use_color,
+        log_format: cli.log_format,
+        otlp_endpoint: cli.otlp_endpoint.clone(),
+        service_name: cli.otel_service_name.clone(),
+    })?;
 
     // Initialize GStreamer
     gstreamer::init()?;
@@ -171,22 +384,226 @@ async fn main() -> anyhow::Result<()> {
 
     // ---
 
+    // Open the pipeline store
+    let store: SharedPipelineStore = match &cli.database {
+        Some(path) => {
+            info!("Persisting pipelines to {}", path.display());
+            Arc::new(SqlitePipelineStore::open(path)?)
+        }
+        None => Arc::new(MemoryPipelineStore::new()),
+    };
+
+    // Configure output storage
+    let storage: SharedOutputStorage = match cli.s3_bucket {
+        Some(bucket) => {
+            let (Some(access_key), Some(secret_key)) = (cli.s3_access_key, cli.s3_secret_key)
+            else {
+                anyhow::bail!("--s3-bucket requires --s3-access-key and --s3-secret-key");
+            };
+            let endpoint = cli
+                .s3_endpoint
+                .unwrap_or_else(|| format!("https://s3.{}.amazonaws.com", cli.s3_region));
+            info!("Uploading outputs to bucket {} at {}", bucket, endpoint);
+
+            Arc::new(S3OutputStorage::new(
+                &cli.output_dir,
+                S3Config {
+                    endpoint,
+                    bucket,
+                    region: cli.s3_region,
+                    prefix: cli.s3_prefix,
+                    access_key,
+                    secret_key,
+                },
+            ))
+        }
+        None => Arc::new(LocalOutputStorage::new(&cli.output_dir)),
+    };
+    std::fs::create_dir_all(&cli.output_dir)?;
+    info!("Writing outputs below {}", cli.output_dir.display());
+
+    // Configure retention and start the reaper
+    let watermarks = cli.disk_high_watermark.map(|high| DiskWatermarks {
+        high,
+        low: cli.disk_low_watermark.unwrap_or(high).min(high),
+    });
+    let logs = PipelineLogs::new(cli.log_buffer_size);
+    let retention = RetentionManager::new(
+        store.clone(),
+        storage.clone(),
+        logs.clone(),
+        RetentionPolicy::new(cli.retention_rules),
+        watermarks,
+        cli.output_dir.clone(),
+    );
+    retention.start_reaper(Duration::from_secs(cli.reap_interval.max(1)));
+
+    // Open the upload directory
+    let uploads = UploadStore::open(&cli.upload_dir, cli.max_upload_size)?;
+    let tus = TusUploads::new(uploads.clone());
+    info!("Storing uploads in {}", cli.upload_dir.display());
+
+    // Open the template directory
+    let templates = TemplateStore::open(&cli.template_dir)?;
+    info!("Storing templates in {}", cli.template_dir.display());
+
+    // Load API keys and bearer token keys
+    let api_keys = match &cli.api_keys {
+        Some(path) => {
+            let keys = ApiKeys::load(path)?;
+            if keys.is_empty() {
+                tracing::warn!("{} defines no keys", path.display());
+            }
+            info!("Accepting {} API keys", keys.len());
+            Some(Arc::new(keys))
+        }
+        None => None,
+    };
+    let jwt = JwtVerifier::new(JwtConfig {
+        secret: cli.jwt_secret,
+        jwks: cli.jwks,
+        issuer: cli.jwt_issuer,
+        audience: cli.jwt_audience,
+    })?
+    .map(Arc::new);
+    if jwt.is_some() {
+        info!("Accepting JWT bearer tokens");
+    }
+    if api_keys.is_none() && jwt.is_none() {
+        tracing::warn!("No API keys or JWT keys configured; authentication is disabled");
+    }
+
+    // Links handed to clients are relative unless a public URL is configured
+    let public_url = match cli.public_url {
+        Some(url) => {
+            let parsed = reqwest::Url::parse(&url)
+                .map_err(|e| anyhow::anyhow!("Invalid --public-url {url}: {e}"))?;
+            if parsed.scheme() != "http" && parsed.scheme() != "https" {
+                anyhow::bail!("--public-url must use http or https");
+            }
+            Some(url.trim_end_matches('/').to_string())
+        }
+        None => None,
+    };
+
     // Create shared application state
-    let app_state: AppState = Arc::new(Mutex::new(HashMap::new()));
+    let webhooks = WebhookNotifier::new(
+        store.clone(),
+        cli.webhook_secret,
+        cli.webhook_max_attempts,
+        public_url.clone(),
+    );
+    let metrics = Metrics::new();
+    let runtime = PipelineRuntime::new(
+        store.clone(),
+        webhooks,
+        storage.clone(),
+        metrics.clone(),
+        logs.clone(),
+        cli.workers,
+    );
+    runtime.recover(cli.on_restart)?;
+    let limiter = RateLimiter::new(cli.rate_limits);
+    let quotas = Quotas::new(
+        store.clone(),
+        cli.output_dir.clone(),
+        cli.upload_dir.clone(),
+        QuotaLimits {
+            max_running: cli.max_running_per_owner,
+            max_output_bytes: cli.max_output_bytes_per_owner,
+        },
+    );
+    let execution_limits = ExecutionLimitPolicy {
+        defaults: ExecutionLimits {
+            max_runtime_seconds: cli.default_max_runtime_seconds,
+            max_output_bytes: cli.default_max_output_bytes,
+        },
+        caps: ExecutionLimits {
+            max_runtime_seconds: cli.max_runtime_seconds_cap,
+            max_output_bytes: cli.max_output_bytes_cap,
+        },
+    };
+    // Defaults must themselves be acceptable job limits
+    execution_limits
+        .resolve(execution_limits.defaults)
+        .map_err(|e| anyhow::anyhow!("Invalid default execution limits: {e}"))?;
+    let app_state = AppState {
+        store,
+        runtime,
+        storage,
+        retention,
+        uploads,
+        tus,
+        templates,
+        api_keys,
+        jwt,
+        limiter,
+        quotas,
+        execution_limits,
+        metrics,
+        logs,
+        public_url,
+    };
 
     // Build our application with routes
     let app = Router::new()
         .route("/", get(health_check))
         .route("/analyze/*url", get(analyze_media))
         .route("/convert", post(convert_media))
+        .route("/elements", get(list_elements))
+        .route("/elements/:factory", get(get_element))
         .route("/health", get(health_check))
+        .route("/metrics", get(export_metrics))
         .route("/pipelines", get(list_pipelines))
         .route("/pipelines", post(create_pipeline))
         .route("/pipelines/:id", delete(stop_pipeline))
         .route("/pipelines/:id", get(get_pipeline))
+        .route("/pipelines/:id/elements/:name", patch(update_element))
+        .route("/pipelines/:id/graph", get(get_pipeline_graph))
+        .route("/pipelines/:id/logs", get(get_pipeline_logs))
+        .route("/pipelines/:id/position", get(get_pipeline_position))
+        .route("/pipelines/:id/retry", post(retry_pipeline))
+        .route("/pipelines/:id/seek", post(seek_pipeline))
+        .route("/pipelines/:id/snapshot.jpg", get(get_snapshot_jpeg))
+        .route("/pipelines/:id/snapshot.png", get(get_snapshot_png))
+        .route("/pipelines/:id/topology", get(get_pipeline_topology))
+        .route("/pipelines/:id/output", get(download_output))
+        .route("/pipelines/:id/outputs/:name", get(download_named_output))
         .route("/samples", get(list_sample_media))
         .route("/stream", post(create_stream))
+        .route("/templates", get(list_templates).post(create_template))
+        .route(
+            "/templates/:name",
+            get(get_template).delete(delete_template),
+        )
+        .route("/templates/:name/instantiate", post(instantiate_template))
         .route("/thumbnail", post(generate_thumbnail))
+        .route(
+            "/uploads",
+            post(upload_media).layer(DefaultBodyLimit::disable()),
+        )
+        .route("/uploads/tus", options(tus_options).post(tus_create))
+        .route(
+            "/uploads/tus/:id",
+            head(tus_head)
+                .patch(tus_patch)
+                .delete(tus_delete)
+                .layer(DefaultBodyLimit::disable()),
+        )
+        .route("/uploads/:id", get(get_upload))
+        .route_layer(middleware::from_fn_with_state(
+            app_state.clone(),
+            limit_requests,
+        ))
+        .route_layer(middleware::from_fn_with_state(
+            app_state.clone(),
+            authenticate,
+        ))
+        .route_layer(middleware::from_fn_with_state(
+            app_state.clone(),
+            track_requests,
+        ))
+        .route_layer(middleware::from_fn(trace_requests))
         .with_state(app_state);
 
     // ---
@@ -206,7 +623,7 @@ async fn main() -> anyhow::Result<()> {
     // - Handle additional signals (SIGTERM) for containerized environments
 
     let result = tokio::select! {
-        result = axum::serve(listener, app) => {
+        result = axum::serve(listener, app.into_make_service_with_connect_info::<SocketAddr>()) => {
             result
         }
         _ = signal::ctrl_c() => {
@@ -219,5 +636,8 @@ async fn main() -> anyhow::Result<()> {
         tracing::error!("Server error: {}", err);
     }
 
+    // Flush spans still waiting to be exported
+    telemetry.shutdown().await;
+
     result.map_err(anyhow::Error::from)
 }
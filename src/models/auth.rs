@@ -0,0 +1,163 @@
+//! Authentication principals and permission scopes.
+//!
+//! When the service runs with `--api-keys` or JWT verification enabled, every
+//! request must present an API key or a bearer token. Each credential grants
+//! a set of [`Scope`]s and maps onto an owner: `key:<id>` for an API key, or
+//! `tenant:<tenant>` for a token. Pipelines record their owner so other
+//! owners cannot see or stop them.
+//!
+//! # Scopes
+//!
+//! - **pipelines:read**  : List and inspect pipelines, download outputs, read uploads
+//! - **pipelines:write** : Start convert/thumbnail/stream jobs, stop pipelines, upload media
+//! - **custom_pipelines**: Submit arbitrary GStreamer pipeline strings
+//! - **templates:write** : Save and delete the pipeline templates shared by all callers
+//! - **analyze**         : Probe remote media with `/analyze`
+
+use serde::Deserialize;
+
+// ---
+
+// Import from sibling module
+use super::pipeline::PipelineInfo;
+
+/// Permission granted to an API key.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+pub enum Scope {
+    // ---
+    /// Read pipeline records, outputs and uploads
+    #[serde(rename = "pipelines:read")]
+    PipelinesRead,
+
+    /// Create media processing jobs, stop pipelines and upload media
+    #[serde(rename = "pipelines:write")]
+    PipelinesWrite,
+
+    /// Submit custom GStreamer pipeline strings
+    #[serde(rename = "custom_pipelines")]
+    CustomPipelines,
+
+    /// Save and delete pipeline templates
+    #[serde(rename = "templates:write")]
+    TemplatesWrite,
+
+    /// Analyze remote media
+    #[serde(rename = "analyze")]
+    Analyze,
+}
+
+impl Scope {
+    // ---
+
+    /// Every scope, for parsing and documentation.
+    pub const ALL: [Scope; 5] = [
+        Self::PipelinesRead,
+        Self::PipelinesWrite,
+        Self::CustomPipelines,
+        Self::TemplatesWrite,
+        Self::Analyze,
+    ];
+
+    /// Looks up a scope by its name, e.g. from a token's `scope` claim.
+    pub fn from_name(name: &str) -> Option<Scope> {
+        // ---
+        Self::ALL.into_iter().find(|scope| scope.name() == name)
+    }
+
+    /// Returns the name used in key files and error messages (e.g. "pipelines:read").
+    pub fn name(&self) -> &'static str {
+        // ---
+        match self {
+            Self::PipelinesRead => "pipelines:read",
+            Self::PipelinesWrite => "pipelines:write",
+            Self::CustomPipelines => "custom_pipelines",
+            Self::TemplatesWrite => "templates:write",
+            Self::Analyze => "analyze",
+        }
+    }
+}
+
+/// The authenticated principal behind a request.
+///
+/// Inserted into request extensions by the authentication middleware, so
+/// handlers can extract it with `Extension<Caller>`.
+#[derive(Debug, Clone)]
+pub enum Caller {
+    // ---
+    /// Authentication is disabled; the caller may do everything
+    Anonymous,
+
+    /// A request authenticated with a configured API key
+    ApiKey {
+        /// Identifier of the key (never the key itself)
+        id: String,
+
+        /// Scopes granted to the key
+        scopes: Vec<Scope>,
+    },
+
+    /// A request authenticated with a verified JWT bearer token
+    Token {
+        /// The token's `sub` claim
+        subject: String,
+
+        /// The token's `tenant` claim, or its subject when absent
+        tenant: String,
+
+        /// Scopes listed in the token's `scope` claim
+        scopes: Vec<Scope>,
+    },
+}
+
+impl Caller {
+    // ---
+
+    /// Returns the owner recorded on pipelines this caller creates.
+    ///
+    /// Key IDs and tenants are prefixed with their kind (`key:ingest`,
+    /// `tenant:acme`), so a key named after a tenant never sees that
+    /// tenant's pipelines. Neither name may contain `:` or `/`, so the owner
+    /// stays a single path segment.
+    pub fn owner(&self) -> Option<String> {
+        // ---
+        match self {
+            Self::Anonymous => None,
+            Self::ApiKey { id, .. } => Some(format!("key:{id}")),
+            Self::Token { tenant, .. } => Some(format!("tenant:{tenant}")),
+        }
+    }
+
+    /// Describes the caller for log messages, without any secret material.
+    pub fn principal(&self) -> String {
+        // ---
+        match self {
+            Self::Anonymous => "anonymous caller".to_string(),
+            Self::ApiKey { id, .. } => format!("API key '{id}'"),
+            Self::Token {
+                subject, tenant, ..
+            } => {
+                format!("subject '{subject}' of tenant '{tenant}'")
+            }
+        }
+    }
+
+    /// Returns true if the caller was granted `scope`.
+    pub fn has_scope(&self, scope: Scope) -> bool {
+        // ---
+        match self {
+            Self::Anonymous => true,
+            Self::ApiKey { scopes, .. } | Self::Token { scopes, .. } => scopes.contains(&scope),
+        }
+    }
+
+    /// Returns true if the caller may see and stop `pipeline`.
+    ///
+    /// Authenticated callers only reach the pipelines of their own owner.
+    pub fn can_access(&self, pipeline: &PipelineInfo) -> bool {
+        // ---
+        match self {
+            Self::Anonymous => true,
+            _ => pipeline.owner == self.owner(),
+        }
+    }
+}
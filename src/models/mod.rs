@@ -6,10 +6,13 @@
 //!
 //! # Module Organization
 //!
-//! The models are organized into three logical categories:
-//! - **Pipeline Models** : Core pipeline state management and metadata
+//! The models are organized into six logical categories:
+//! - **Pipeline Models** : Core pipeline state management, metadata and structured graphs
 //! - **Request Models**  : Input DTOs for API endpoints accepting JSON payloads
 //! - **Response Models** : Output DTOs for API responses and error handling
+//! - **Upload Models**   : Metadata of uploaded source media
+//! - **Template Models** : Parameterized pipeline templates and their placeholders
+//! - **Auth Models**     : Authenticated callers and the scopes granted to them
 //!
 //! # EMBP Implementation
 //!
@@ -27,15 +30,33 @@
 // ---
 
 // EMBP Models Gateway: Controls public API for all model types
+mod auth;
 mod pipeline;
 mod requests;
 mod responses;
+mod template;
+mod upload;
 
 // ---
 
 // Public exports - this defines the entire public models API
-pub use pipeline::{PipelineInfo, PipelineState};
-pub use requests::{ConvertRequest, CreatePipelineRequest, StreamRequest, ThumbnailRequest};
+pub use auth::{Caller, Scope};
+pub use pipeline::{
+    ExecutionLimits, GraphElement, LogEntry, LogLevel, PipelineAttempt, PipelineGraph,
+    PipelineInfo, PipelineKind, PipelineState, RetryPolicy, WebhookDelivery,
+};
+pub use requests::{
+    ConvertRequest, CreatePipelineQuery, CreatePipelineRequest, CreateTemplateRequest,
+    ElementListQuery, GraphQuery, InstantiateTemplateRequest, ListPipelinesQuery, SeekRequest,
+    SnapshotQuery, StopPipelineQuery, StreamRequest, ThumbnailRequest, UpdateElementRequest,
+    UploadQuery,
+};
 pub use responses::{
-    ApiError, ConvertResponse, SampleMedia, StreamResponse, ThumbnailInfo, ThumbnailResponse,
+    ApiError, ConvertResponse, DiagnosticKind, ElementDetails, ElementListResponse, ElementSummary,
+    ElementUpdateResponse, PadTemplateInfo, PipelineDiagnostic, PipelineListResponse,
+    PipelineLogsResponse, PipelinePosition, PipelineTopology, PropertyChange, PropertyInfo,
+    SampleMedia, StreamResponse, TemplateListResponse, ThumbnailInfo, ThumbnailResponse,
+    TopologyElement, TopologyLink, TopologyPad, WebhookPayload, WebhookPipeline,
 };
+pub use template::{ParameterKind, PipelineTemplate, TemplateParameter};
+pub use upload::UploadInfo;
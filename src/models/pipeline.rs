@@ -18,6 +18,7 @@
 //! - **Error**: Pipeline failed with diagnostic information
 
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 
 /// Comprehensive information about a GStreamer pipeline instance.
 ///
@@ -30,10 +31,19 @@ use serde::{Deserialize, Serialize};
 /// {
 ///   "id": "550e8400-e29b-41d4-a716-446655440000",
 ///   "description": "Convert to webm",
+///   "kind": "Convert",
+///   "owner": "key:ingest-service",
 ///   "state": "Created",
 ///   "pipeline_string": "souphttpsrc location=... ! decodebin ! ...",
 ///   "created_at": "2024-09-21T10:30:00Z",
-///   "source_url": "https://example.com/video.mp4"
+///   "finished_at": null,
+///   "source_url": "https://example.com/video.mp4",
+///   "outputs": ["outputs/550e8400-e29b-41d4-a716-446655440000/output.webm"],
+///   "published_outputs": [],
+///   "max_runtime_seconds": 3600,
+///   "attempts": [],
+///   "callback_url": "https://example.com/hooks/media",
+///   "webhook_deliveries": []
 /// }
 /// ```
 ///
@@ -50,18 +60,388 @@ pub struct PipelineInfo {
     /// Human-readable description of what this pipeline does
     pub description: String,
 
+    /// Kind of job that created this pipeline
+    #[serde(default)]
+    pub kind: PipelineKind,
+
+    /// Owner that created this pipeline, `key:<id>` for API keys or
+    /// `tenant:<tenant>` for JWT callers (None when authentication is disabled)
+    #[serde(default)]
+    pub owner: Option<String>,
+
     /// Current execution state of the pipeline
     pub state: PipelineState,
 
-    /// Complete GStreamer pipeline string used for execution
+    /// Complete GStreamer pipeline string used for execution; for pipelines
+    /// created from a `graph`, an equivalent string for display only
     pub pipeline_string: String,
 
+    /// Structured description the pipeline is built from, when it was
+    /// created from a `graph` instead of a pipeline string
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub graph: Option<PipelineGraph>,
+
     /// ISO 8601 timestamp when the pipeline was created
     pub created_at: String,
 
+    /// ISO 8601 timestamp when the pipeline reached a terminal state
+    #[serde(default)]
+    pub finished_at: Option<String>,
+
     /// Optional source URL if this pipeline processes remote media
     /// None for pipelines that don't use network sources
     pub source_url: Option<String>,
+
+    /// Files or directories this pipeline writes its results to
+    #[serde(default)]
+    pub outputs: Vec<String>,
+
+    /// Durable copies of the outputs made by the storage backend after the
+    /// pipeline finished (e.g. `s3://bucket/key`); empty for local storage
+    #[serde(default)]
+    pub published_outputs: Vec<String>,
+
+    /// Limits the runtime enforces while the pipeline plays, after server
+    /// defaults and caps were applied
+    #[serde(flatten)]
+    pub limits: ExecutionLimits,
+
+    /// Automatic retry settings, with defaults filled in; None when failed
+    /// pipelines are not retried
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub retry: Option<RetryPolicy>,
+
+    /// Every execution attempt, oldest first
+    #[serde(default)]
+    pub attempts: Vec<PipelineAttempt>,
+
+    /// Optional URL notified with a signed POST when the pipeline finishes
+    pub callback_url: Option<String>,
+
+    /// History of completion webhook delivery attempts, oldest first
+    #[serde(default)]
+    pub webhook_deliveries: Vec<WebhookDelivery>,
+}
+
+#[cfg(test)]
+impl PipelineInfo {
+    // ---
+
+    /// Creates a minimal custom pipeline record for unit tests.
+    ///
+    /// Tests adjust the fields they care about with struct update syntax,
+    /// e.g. `PipelineInfo { kind, ..PipelineInfo::test("a", None, state) }`.
+    pub fn test(id: &str, owner: Option<&str>, state: PipelineState) -> Self {
+        // ---
+        Self {
+            id: id.to_string(),
+            description: "test".to_string(),
+            kind: PipelineKind::Custom,
+            owner: owner.map(str::to_string),
+            state,
+            pipeline_string: "fakesrc ! fakesink".to_string(),
+            graph: None,
+            created_at: "2024-09-21T10:30:00+00:00".to_string(),
+            finished_at: None,
+            source_url: None,
+            outputs: Vec::new(),
+            published_outputs: Vec::new(),
+            callback_url: None,
+            webhook_deliveries: Vec::new(),
+            limits: ExecutionLimits::default(),
+            retry: None,
+            attempts: Vec::new(),
+        }
+    }
+}
+
+/// Structured pipeline description, an alternative to pipeline strings.
+///
+/// Elements are created by factory name and linked programmatically, so
+/// names and property values can never be interpreted as pipeline syntax.
+///
+/// # Example JSON Representation
+/// ```json
+/// {
+///   "elements": [
+///     { "factory": "souphttpsrc", "name": "src", "properties": { "location": "https://example.com/video.mp4" } },
+///     { "factory": "decodebin", "name": "dec" },
+///     { "factory": "audioconvert", "name": "conv" },
+///     { "factory": "vorbisenc", "name": "enc", "properties": { "quality": 0.6 } },
+///     { "factory": "oggmux", "name": "mux" },
+///     { "factory": "filesink", "name": "out", "properties": { "location": "output.ogg" } }
+///   ],
+///   "links": [
+///     { "from": "src", "to": "dec" },
+///     { "from": "dec", "to": "conv", "caps": "audio/x-raw" },
+///     { "from": "conv", "to": "enc" },
+///     { "from": "enc", "to": "mux.audio_%u" },
+///     { "from": "mux", "to": "out" }
+///   ]
+/// }
+/// ```
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PipelineGraph {
+    // ---
+    /// Elements in the order they are created
+    pub elements: Vec<GraphElement>,
+
+    /// Connections between the elements
+    #[serde(default)]
+    pub links: Vec<GraphLink>,
+}
+
+/// An element of a [`PipelineGraph`].
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct GraphElement {
+    // ---
+    /// GStreamer element factory (e.g. "x264enc")
+    pub factory: String,
+
+    /// Unique element name, referenced by links
+    pub name: String,
+
+    /// Property values, checked against the element's property types
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub properties: BTreeMap<String, serde_json::Value>,
+}
+
+/// A connection between two elements of a [`PipelineGraph`].
+///
+/// Endpoints are an element name, linking any compatible pads, or
+/// `element.pad` for a specific pad (including request pad templates such as
+/// `mux.video_%u`). Links from pads that only appear while running, like
+/// those of `decodebin`, are made when the pad is added.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct GraphLink {
+    // ---
+    /// Upstream element or `element.pad`
+    pub from: String,
+
+    /// Downstream element or `element.pad`
+    pub to: String,
+
+    /// Optional caps restricting the formats negotiated over this link
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub caps: Option<String>,
+}
+
+/// Execution limits of a pipeline, accepted by every pipeline-creating
+/// request and recorded on [`PipelineInfo`].
+///
+/// A pipeline exceeding either limit receives EOS, so muxers can finalize
+/// what was written, and then fails with an error naming the limit.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ExecutionLimits {
+    // ---
+    /// Longest the pipeline may play, in seconds
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_runtime_seconds: Option<u64>,
+
+    /// Most bytes the pipeline's outputs may hold
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_output_bytes: Option<u64>,
+}
+
+/// Automatic retry settings of a pipeline, accepted as `retry` by every
+/// pipeline-creating request.
+///
+/// A pipeline failing with one of the `retry_on` errors is queued again after
+/// a backoff delay, until `max_attempts` attempts were made.
+///
+/// # Example JSON Representation
+/// ```json
+/// {
+///   "max_attempts": 3,
+///   "backoff_seconds": 1.0,
+///   "backoff_multiplier": 2.0,
+///   "retry_on": ["ResourceError::Read", "ResourceError::OpenRead"]
+/// }
+/// ```
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RetryPolicy {
+    // ---
+    /// Most attempts, including the first
+    pub max_attempts: u32,
+
+    /// Delay before the first retry in seconds (default 1)
+    #[serde(default)]
+    pub backoff_seconds: Option<f64>,
+
+    /// Factor the delay grows by after every retry (default 2)
+    #[serde(default)]
+    pub backoff_multiplier: Option<f64>,
+
+    /// GStreamer errors worth retrying, as `Domain::Code` (e.g.
+    /// `ResourceError::Read`) or a whole `Domain` (e.g. `ResourceError`);
+    /// defaults to `ResourceError::Read` and `ResourceError::OpenRead`
+    #[serde(default)]
+    pub retry_on: Option<Vec<String>>,
+}
+
+/// Record of one execution attempt of a pipeline.
+///
+/// Appended to [`PipelineInfo::attempts`] whenever an attempt ends.
+///
+/// # Example JSON Representation
+/// ```json
+/// {
+///   "attempt": 1,
+///   "started_at": "2024-09-21T10:30:00Z",
+///   "finished_at": "2024-09-21T10:30:04Z",
+///   "error": "GStreamer error from source: Could not read from resource.",
+///   "error_code": "ResourceError::Read",
+///   "retry_at": "2024-09-21T10:30:05Z"
+/// }
+/// ```
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PipelineAttempt {
+    // ---
+    /// Attempt number, starting at 1
+    pub attempt: u32,
+
+    /// ISO 8601 timestamp when the attempt started
+    pub started_at: String,
+
+    /// ISO 8601 timestamp when the attempt ended
+    pub finished_at: String,
+
+    /// Error the attempt failed with; None when it completed or was stopped
+    #[serde(default)]
+    pub error: Option<String>,
+
+    /// GStreamer error as `Domain::Code`, when GStreamer reported the failure
+    #[serde(default)]
+    pub error_code: Option<String>,
+
+    /// ISO 8601 timestamp when the automatic retry after this attempt was
+    /// due; None when the attempt was not retried automatically
+    #[serde(default)]
+    pub retry_at: Option<String>,
+}
+
+/// Kind of job a pipeline was created for.
+///
+/// Used to apply per-kind retention rules. Records written before kinds were
+/// tracked deserialize as `Custom`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum PipelineKind {
+    // ---
+    /// Format conversion created by `POST /convert`
+    Convert,
+
+    /// Thumbnail extraction created by `POST /thumbnail`
+    Thumbnail,
+
+    /// HLS stream created by `POST /stream`
+    Stream,
+
+    /// User-supplied pipeline created by `POST /pipelines`
+    #[default]
+    Custom,
+}
+
+impl PipelineKind {
+    // ---
+
+    /// Every kind, for parsing and documentation.
+    pub const ALL: [PipelineKind; 4] = [Self::Convert, Self::Thumbnail, Self::Stream, Self::Custom];
+
+    /// Returns the lowercase name used on the command line (e.g. "convert").
+    pub fn name(&self) -> &'static str {
+        // ---
+        match self {
+            Self::Convert => "convert",
+            Self::Thumbnail => "thumbnail",
+            Self::Stream => "stream",
+            Self::Custom => "custom",
+        }
+    }
+}
+
+/// Record of a single attempt to deliver a completion webhook.
+///
+/// Appended to [`PipelineInfo::webhook_deliveries`] after every attempt so
+/// clients can see whether their receiver was reached, and why not if it
+/// wasn't.
+///
+/// # Example JSON Representation
+/// ```json
+/// {
+///   "attempt": 1,
+///   "attempted_at": "2024-09-21T10:35:00Z",
+///   "status_code": 500,
+///   "delivered": false,
+///   "error": "Receiver responded with HTTP 500"
+/// }
+/// ```
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WebhookDelivery {
+    // ---
+    /// 1-based attempt number
+    pub attempt: u32,
+
+    /// ISO 8601 timestamp when the attempt was made
+    pub attempted_at: String,
+
+    /// HTTP status returned by the receiver, None if no response was received
+    pub status_code: Option<u16>,
+
+    /// Whether the receiver acknowledged the payload with a 2xx status
+    pub delivered: bool,
+
+    /// Reason the attempt failed, None on success
+    pub error: Option<String>,
+}
+
+/// Severity of a [`LogEntry`], mirroring GStreamer's bus message types.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LogLevel {
+    // ---
+    /// Informational message or lifecycle event
+    Info,
+
+    /// Problem the pipeline recovered from
+    Warning,
+
+    /// Failure that ended the pipeline
+    Error,
+}
+
+/// A message captured while a pipeline executed.
+///
+/// Recorded from the pipeline's GStreamer bus (errors, warnings and info
+/// messages) and from the runtime itself (start, end of stream, failures
+/// outside GStreamer), so clients can see why a job failed.
+///
+/// # Example JSON Representation
+/// ```json
+/// {
+///   "timestamp": "2024-09-21T10:35:00Z",
+///   "level": "error",
+///   "source": "filesrc0",
+///   "message": "Resource not found.",
+///   "debug": "../plugins/elements/gstfilesrc.c(532): gst_file_src_start (): /GstPipeline:pipeline0/GstFileSrc:filesrc0:\nNo such file \"/missing.mp4\""
+/// }
+/// ```
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LogEntry {
+    // ---
+    /// ISO 8601 timestamp when the message was captured
+    pub timestamp: String,
+
+    /// Message severity
+    pub level: LogLevel,
+
+    /// Name of the element that posted the message, None for runtime events
+    pub source: Option<String>,
+
+    /// Human-readable message
+    pub message: String,
+
+    /// GStreamer debug details (source location, element path), if any
+    pub debug: Option<String>,
 }
 
 /// Enumeration of all possible pipeline execution states.
@@ -123,3 +503,33 @@ pub enum PipelineState {
     /// - "Invalid pipeline syntax: unknown element 'badelem'"
     Error(String),
 }
+
+impl PipelineState {
+    // ---
+
+    /// Every value [`PipelineState::name`] can return, for filter validation.
+    pub const NAMES: [&'static str; 5] = ["Created", "Playing", "Paused", "Stopped", "Error"];
+
+    /// Returns true for states a pipeline never leaves once reached.
+    ///
+    /// `Stopped` and `Error` are terminal; reaching one of them triggers the
+    /// completion webhook when a `callback_url` was supplied.
+    pub fn is_terminal(&self) -> bool {
+        // ---
+        matches!(self, Self::Stopped | Self::Error(_))
+    }
+
+    /// Returns the variant name without error details (e.g. "Error").
+    ///
+    /// Used wherever a state must be stored or compared as a plain label.
+    pub fn name(&self) -> &'static str {
+        // ---
+        match self {
+            Self::Created => "Created",
+            Self::Playing => "Playing",
+            Self::Paused => "Paused",
+            Self::Stopped => "Stopped",
+            Self::Error(_) => "Error",
+        }
+    }
+}
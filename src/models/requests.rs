@@ -10,14 +10,32 @@
 //! - **Pipeline Creation**: Requests for creating custom GStreamer pipelines
 //! - **Media Processing**: Requests for format conversion, thumbnails, and streaming
 //! - **Validation**: All requests include implicit validation through type constraints
+//!
+//! # Completion Webhooks
+//!
+//! Every job-creating request accepts an optional `callback_url`. When the
+//! resulting pipeline reaches a terminal state the service POSTs a signed
+//! JSON payload describing the outcome to that URL.
+//!
+//! # Query Parameters
+//!
+//! [`ListPipelinesQuery`] is deserialized from the query string of
+//! `GET /pipelines` rather than from a JSON body.
 
 use serde::Deserialize;
+use std::collections::BTreeMap;
+
+// ---
+
+use super::{ExecutionLimits, PipelineGraph, RetryPolicy};
 
 /// Request to create a custom GStreamer pipeline.
 ///
 /// Allows clients to submit custom GStreamer pipeline strings for execution.
 /// The pipeline string is validated before execution to ensure it contains
-/// valid GStreamer syntax and elements.
+/// valid GStreamer syntax and elements. Instead of `pipeline`, clients may
+/// send a structured `graph` (see [`PipelineGraph`]); exactly one of the two
+/// is required.
 ///
 /// # Example Request
 /// ```json
@@ -39,7 +57,139 @@ pub struct CreatePipelineRequest {
     pub description: String,
 
     /// Complete GStreamer pipeline string for execution
-    pub pipeline: String,
+    pub pipeline: Option<String>,
+
+    /// Structured alternative to `pipeline`
+    pub graph: Option<PipelineGraph>,
+
+    /// Optional HTTP(S) URL notified with a signed POST when the pipeline finishes
+    pub callback_url: Option<String>,
+
+    /// Optional `max_runtime_seconds` and `max_output_bytes`; server defaults apply when omitted
+    #[serde(flatten)]
+    pub limits: ExecutionLimits,
+
+    /// Optional automatic retry of failures such as network errors
+    pub retry: Option<RetryPolicy>,
+}
+
+/// Request to save a named pipeline template.
+///
+/// The template is a GStreamer pipeline string with typed placeholders. It is
+/// validated when saved by substituting sample values for every placeholder.
+///
+/// # Example Request
+/// ```json
+/// {
+///   "name": "hls-restream",
+///   "description": "Restream a source as HLS",
+///   "template": "souphttpsrc location={{source_url:url}} ! decodebin ! videoconvert ! x264enc bitrate={{bitrate:int:100..20000}} ! mpegtsmux ! hlssink"
+/// }
+/// ```
+///
+/// # Placeholder Syntax
+/// - `{{name}}` or `{{name:string}}`: Text
+/// - `{{name:url}}`: Absolute http(s), rtsp(s), rtmp(s) or srt URL
+/// - `{{name:bool}}`: `true` or `false`
+/// - `{{name:int}}`, `{{name:int:MIN..MAX}}`: Integer, either bound may be omitted
+/// - `{{name:float}}`, `{{name:float:MIN..MAX}}`: Number, either bound may be omitted
+/// - `{{name:choice:a|b|c}}`: One of the listed words
+#[derive(Debug, Deserialize)]
+pub struct CreateTemplateRequest {
+    // ---
+    /// Unique template name: lowercase letters, digits, `-` and `_`
+    pub name: String,
+
+    /// Human-readable description of what pipelines from this template do
+    pub description: Option<String>,
+
+    /// GStreamer pipeline string with placeholders
+    pub template: String,
+}
+
+/// Request to create a pipeline from a template.
+///
+/// # Example Request
+/// ```json
+/// {
+///   "parameters": { "source_url": "https://example.com/live.m3u8", "bitrate": 2500 },
+///   "callback_url": "https://example.com/hooks/pipeline"
+/// }
+/// ```
+#[derive(Debug, Deserialize)]
+pub struct InstantiateTemplateRequest {
+    // ---
+    /// Value of every placeholder, keyed by name
+    #[serde(default)]
+    pub parameters: BTreeMap<String, serde_json::Value>,
+
+    /// Description of the created pipeline (default: the template's description)
+    pub description: Option<String>,
+
+    /// Optional URL that receives a signed POST when the pipeline finishes
+    pub callback_url: Option<String>,
+
+    /// Optional `max_runtime_seconds` and `max_output_bytes`; server defaults apply when omitted
+    #[serde(flatten)]
+    pub limits: ExecutionLimits,
+
+    /// Optional automatic retry of failures such as network errors
+    pub retry: Option<RetryPolicy>,
+}
+
+/// Request to change properties of an element in a running pipeline.
+///
+/// Values are given as JSON booleans, numbers or strings; enums and flags
+/// accept their nick (e.g. `"sine"`), and other types their GStreamer
+/// serialized string form (e.g. caps). Either every property is applied or
+/// none is.
+///
+/// # Example Request
+/// ```json
+/// {
+///   "properties": { "bitrate": 2500, "speed-preset": "veryfast" }
+/// }
+/// ```
+#[derive(Debug, Deserialize)]
+pub struct UpdateElementRequest {
+    // ---
+    /// New values keyed by property name
+    pub properties: BTreeMap<String, serde_json::Value>,
+}
+
+/// Request to seek a running pipeline or change its playback rate.
+///
+/// # Example Request
+/// ```json
+/// { "position": 30.0, "rate": 1.0, "accurate": true }
+/// ```
+///
+/// # Fields
+/// - **position**: Target in seconds; defaults to the current position,
+///   which changes only the rate
+/// - **rate**: Playback rate, negative for reverse playback; defaults to 1.0
+/// - **flush**: Discard queued data for an immediate seek; defaults to true
+/// - **accurate**: Seek to the exact position rather than a nearby one
+/// - **key_unit**: Snap to the nearest keyframe, which is faster
+#[derive(Debug, Default, Deserialize)]
+pub struct SeekRequest {
+    // ---
+    /// Target position in seconds
+    pub position: Option<f64>,
+
+    /// Playback rate (1.0 is normal speed)
+    pub rate: Option<f64>,
+
+    /// Flush the pipeline before seeking
+    pub flush: Option<bool>,
+
+    /// Seek to the exact position
+    #[serde(default)]
+    pub accurate: bool,
+
+    /// Seek to the nearest keyframe
+    #[serde(default)]
+    pub key_unit: bool,
 }
 
 /// Request to convert media between different formats.
@@ -52,7 +202,8 @@ pub struct CreatePipelineRequest {
 /// ```json
 /// {
 ///   "source_url": "https://commondatastorage.googleapis.com/gtv-videos-bucket/sample/BigBuckBunny.mp4",
-///   "output_format": "webm"
+///   "output_format": "webm",
+///   "callback_url": "https://example.com/hooks/media"
 /// }
 /// ```
 ///
@@ -63,11 +214,21 @@ pub struct CreatePipelineRequest {
 #[derive(Debug, Deserialize)]
 pub struct ConvertRequest {
     // ---
-    /// HTTP(S) URL of the source media file to convert
+    /// HTTP(S) URL of the source media file to convert, or `upload://<id>`
     pub source_url: String,
 
     /// Target output format ("webm", "mp4", "avi")
     pub output_format: String,
+
+    /// Optional HTTP(S) URL notified with a signed POST when the pipeline finishes
+    pub callback_url: Option<String>,
+
+    /// Optional `max_runtime_seconds` and `max_output_bytes`; server defaults apply when omitted
+    #[serde(flatten)]
+    pub limits: ExecutionLimits,
+
+    /// Optional automatic retry of failures such as network errors
+    pub retry: Option<RetryPolicy>,
 }
 
 /// Request to generate a thumbnail image from a video source.
@@ -93,7 +254,7 @@ pub struct ConvertRequest {
 #[derive(Debug, Deserialize)]
 pub struct ThumbnailRequest {
     // ---
-    /// HTTP(S) URL of the source video file
+    /// HTTP(S) URL of the source video file, or `upload://<id>`
     pub source_url: String,
 
     /// Optional timestamp to extract thumbnail from (HH:MM:SS format)
@@ -107,6 +268,16 @@ pub struct ThumbnailRequest {
     /// Optional height of the generated thumbnail in pixels  
     /// Defaults to 240 if not provided
     pub height: Option<u32>,
+
+    /// Optional HTTP(S) URL notified with a signed POST when the pipeline finishes
+    pub callback_url: Option<String>,
+
+    /// Optional `max_runtime_seconds` and `max_output_bytes`; server defaults apply when omitted
+    #[serde(flatten)]
+    pub limits: ExecutionLimits,
+
+    /// Optional automatic retry of failures such as network errors
+    pub retry: Option<RetryPolicy>,
 }
 
 /// Request to create a streaming pipeline.
@@ -130,10 +301,139 @@ pub struct ThumbnailRequest {
 #[derive(Debug, Deserialize)]
 pub struct StreamRequest {
     // ---
-    /// HTTP(S) URL of the source media file to stream
+    /// HTTP(S) URL of the source media file to stream, or `upload://<id>`
     pub source_url: String,
 
     /// Type of streaming format to create ("hls", "dash", "rtmp")
     /// Currently only "hls" is fully supported
     pub stream_type: String,
+
+    /// Optional HTTP(S) URL notified with a signed POST when the pipeline finishes
+    pub callback_url: Option<String>,
+
+    /// Optional `max_runtime_seconds` and `max_output_bytes`; server defaults apply when omitted
+    #[serde(flatten)]
+    pub limits: ExecutionLimits,
+
+    /// Optional automatic retry of failures such as network errors
+    pub retry: Option<RetryPolicy>,
+}
+
+/// Query parameters for filtering and paginating `GET /pipelines`.
+///
+/// All parameters are optional; without any, the newest 100 pipelines are
+/// returned. Pass the `next_cursor` of a response as `cursor` to fetch the
+/// following page with the same filters and order.
+///
+/// # Example Request
+/// ```bash
+/// curl "http://localhost:8080/pipelines?state=Error&created_after=2024-09-21T00:00:00Z&limit=50"
+/// ```
+#[derive(Debug, Default, Deserialize)]
+pub struct ListPipelinesQuery {
+    // ---
+    /// Only pipelines in this state ("Created", "Playing", "Paused", "Stopped", "Error")
+    pub state: Option<String>,
+
+    /// Only pipelines whose description contains this text (case-insensitive)
+    pub description: Option<String>,
+
+    /// Only pipelines processing exactly this source URL
+    pub source_url: Option<String>,
+
+    /// Only pipelines created after this RFC 3339 timestamp
+    pub created_after: Option<String>,
+
+    /// Only pipelines created before this RFC 3339 timestamp
+    pub created_before: Option<String>,
+
+    /// Sort order by creation time: "desc" (default, newest first) or "asc"
+    pub order: Option<String>,
+
+    /// Maximum pipelines per page (1-1000, default 100)
+    pub limit: Option<usize>,
+
+    /// Opaque `next_cursor` value from the previous page
+    pub cursor: Option<String>,
+}
+
+/// Query parameters accepted by `POST /pipelines`.
+///
+/// # Example Request
+/// ```bash
+/// curl -X POST "http://localhost:8080/pipelines?validate=deep" \
+///   -H "Content-Type: application/json" \
+///   -d '{"description": "Checked pipeline", "pipeline": "..."}'
+/// ```
+#[derive(Debug, Default, Deserialize)]
+pub struct CreatePipelineQuery {
+    // ---
+    /// "basic" (default) parses the pipeline; "deep" also prerolls it
+    pub validate: Option<String>,
+}
+
+/// Query parameters accepted by `DELETE /pipelines/{id}`.
+///
+/// # Example Request
+/// ```bash
+/// curl -X DELETE "http://localhost:8080/pipelines/550e8400-e29b-41d4-a716-446655440000?purge=true"
+/// ```
+#[derive(Debug, Default, Deserialize)]
+pub struct StopPipelineQuery {
+    // ---
+    /// Also delete the pipeline's artifacts and record once it has stopped
+    #[serde(default)]
+    pub purge: bool,
+}
+
+/// Query parameters accepted by `GET /pipelines/{id}/graph`.
+///
+/// # Example Request
+/// ```bash
+/// curl "http://localhost:8080/pipelines/550e8400-e29b-41d4-a716-446655440000/graph?format=svg&details=media-type,states"
+/// ```
+#[derive(Debug, Default, Deserialize)]
+pub struct GraphQuery {
+    // ---
+    /// "dot" (default) or "svg"
+    pub format: Option<String>,
+
+    /// Comma-separated detail levels: "media-type", "caps-details",
+    /// "non-default-params", "states", "full-params", "all" (default), "verbose"
+    pub details: Option<String>,
+}
+
+/// Query parameters accepted by `GET /pipelines/{id}/snapshot.jpg` and
+/// `GET /pipelines/{id}/snapshot.png`.
+///
+/// # Example Request
+/// ```bash
+/// curl -o frame.jpg "http://localhost:8080/pipelines/550e8400-e29b-41d4-a716-446655440000/snapshot.jpg?element=preview"
+/// ```
+#[derive(Debug, Default, Deserialize)]
+pub struct SnapshotQuery {
+    // ---
+    /// Sink to take the frame from; defaults to the first sink holding a raw video frame
+    pub element: Option<String>,
+}
+
+/// Query parameters accepted by `POST /uploads`.
+#[derive(Debug, Default, Deserialize)]
+pub struct UploadQuery {
+    // ---
+    /// Original file name of a raw-body upload (multipart uploads carry their own)
+    pub filename: Option<String>,
+}
+
+/// Query parameters accepted by `GET /elements`.
+///
+/// # Example Request
+/// ```bash
+/// curl "http://localhost:8080/elements?klass=Codec/Encoder/Video"
+/// ```
+#[derive(Debug, Default, Deserialize)]
+pub struct ElementListQuery {
+    // ---
+    /// Keep factories whose klass contains every `/`-separated component
+    pub klass: Option<String>,
 }
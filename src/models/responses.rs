@@ -11,6 +11,14 @@
 //! - **Error Responses**: Standardized error information with optional details
 
 use serde::Serialize;
+use std::collections::BTreeMap;
+
+// ---
+
+use super::{
+    LogEntry, LogLevel, PipelineAttempt, PipelineInfo, PipelineKind, PipelineState,
+    PipelineTemplate,
+};
 
 /// Response returned after initiating a media format conversion operation.
 ///
@@ -108,7 +116,7 @@ pub struct ThumbnailInfo {
 /// {
 ///   "pipeline_id": "550e8400-e29b-41d4-a716-446655440002",
 ///   "status": "created",
-///   "stream_url": "http://localhost:8080/stream/550e8400-e29b-41d4-a716-446655440002/playlist.m3u8",
+///   "stream_url": "/pipelines/550e8400-e29b-41d4-a716-446655440002/outputs/playlist.m3u8",
 ///   "message": "HLS stream created successfully"
 /// }
 /// ```
@@ -121,7 +129,8 @@ pub struct StreamResponse {
     /// Current status of the streaming request (typically "created")
     pub status: String,
 
-    /// Optional URL where the stream will be accessible (for HLS: .m3u8 playlist)
+    /// Optional URL where the stream will be accessible (for HLS: .m3u8 playlist);
+    /// a path relative to the service unless `--public-url` is configured
     pub stream_url: Option<String>,
 
     /// Human-readable description of the operation status
@@ -162,6 +171,496 @@ pub struct SampleMedia {
     pub description: String,
 }
 
+/// Payload POSTed to a pipeline's `callback_url` when it reaches a terminal state.
+///
+/// The raw JSON body is signed with HMAC-SHA256 using the server's webhook
+/// secret; receivers should verify the `X-Webhook-Signature` header before
+/// trusting the contents.
+///
+/// # Example Payload
+/// ```json
+/// {
+///   "event": "pipeline.failed",
+///   "pipeline": { "id": "550e8400-e29b-41d4-a716-446655440000", "state": { "Error": "..." }, "...": "..." },
+///   "outputs": ["https://media.example.com/pipelines/550e8400-e29b-41d4-a716-446655440000/outputs/output.webm"],
+///   "error": "GStreamer error from souphttpsrc0: Not Found"
+/// }
+/// ```
+#[derive(Debug, Serialize)]
+pub struct WebhookPayload {
+    // ---
+    /// Event name: "pipeline.stopped" or "pipeline.failed"
+    pub event: String,
+
+    /// Snapshot of the pipeline record at the time of delivery
+    pub pipeline: WebhookPipeline,
+
+    /// Published locations of the results, or their download links
+    /// (`/pipelines/{id}/outputs/{name}`, prefixed with `--public-url` when
+    /// configured) when they are only stored locally
+    pub outputs: Vec<String>,
+
+    /// Error details when the pipeline failed, None otherwise
+    pub error: Option<String>,
+}
+
+/// The parts of a [`PipelineInfo`] sent to webhook receivers.
+///
+/// Leaves out the pipeline string, graph and output paths, which name
+/// directories on the server.
+#[derive(Debug, Serialize)]
+pub struct WebhookPipeline {
+    // ---
+    /// Unique identifier of the pipeline
+    pub id: String,
+
+    /// Human-readable description of what the pipeline does
+    pub description: String,
+
+    /// Kind of job that created the pipeline
+    pub kind: PipelineKind,
+
+    /// Owner of the pipeline (`key:<id>` or `tenant:<tenant>`), if any
+    pub owner: Option<String>,
+
+    /// Terminal state the pipeline reached
+    pub state: PipelineState,
+
+    /// ISO 8601 timestamp when the pipeline was created
+    pub created_at: String,
+
+    /// ISO 8601 timestamp when the pipeline reached its terminal state
+    pub finished_at: Option<String>,
+
+    /// Source URL the job was submitted with, if any
+    pub source_url: Option<String>,
+
+    /// Every execution attempt, oldest first
+    pub attempts: Vec<PipelineAttempt>,
+}
+
+/// Response for `GET /pipelines`: one page of pipeline records.
+///
+/// # Example Response
+/// ```json
+/// {
+///   "pipelines": [{ "id": "550e8400-e29b-41d4-a716-446655440000", "...": "..." }],
+///   "next_cursor": "323032342d30392d32315431303a33303a30302b30303a30300a353530..."
+/// }
+/// ```
+#[derive(Debug, Serialize)]
+pub struct PipelineListResponse {
+    // ---
+    /// Matching pipelines in the requested order
+    pub pipelines: Vec<PipelineInfo>,
+
+    /// Cursor for the next page, or None when this is the last page
+    pub next_cursor: Option<String>,
+}
+
+/// Response for `GET /templates`: every stored template, by name.
+#[derive(Debug, Serialize)]
+pub struct TemplateListResponse {
+    // ---
+    /// Stored templates in name order
+    pub templates: Vec<PipelineTemplate>,
+}
+
+/// Response for `GET /pipelines/{id}/logs`: the pipeline's captured messages.
+///
+/// # Example Response
+/// ```json
+/// {
+///   "pipeline_id": "550e8400-e29b-41d4-a716-446655440000",
+///   "entries": [{ "level": "info", "message": "Pipeline is playing", "...": "..." }],
+///   "dropped": 0
+/// }
+/// ```
+#[derive(Debug, Serialize)]
+pub struct PipelineLogsResponse {
+    // ---
+    /// ID of the pipeline the messages belong to
+    pub pipeline_id: String,
+
+    /// Captured messages, oldest first
+    pub entries: Vec<LogEntry>,
+
+    /// Older messages discarded because the buffer was full
+    pub dropped: u64,
+}
+
+/// Response for `GET /pipelines/{id}/topology`: the elements GStreamer built
+/// for a pipeline and how their pads are linked.
+///
+/// # Example Response
+/// ```json
+/// {
+///   "pipeline_id": "550e8400-e29b-41d4-a716-446655440000",
+///   "source": "live",
+///   "elements": [
+///     {
+///       "name": "audiotestsrc0",
+///       "factory": "audiotestsrc",
+///       "properties": { "freq": 440.0, "is-live": false, "wave": "sine" },
+///       "pads": [
+///         {
+///           "name": "src",
+///           "direction": "src",
+///           "peer": "capsfilter0.sink",
+///           "caps": "audio/x-raw, format=(string)S16LE, rate=(int)8000, channels=(int)1"
+///         }
+///       ]
+///     }
+///   ],
+///   "links": [
+///     {
+///       "from": "audiotestsrc0.src",
+///       "to": "capsfilter0.sink",
+///       "caps": "audio/x-raw, format=(string)S16LE, rate=(int)8000, channels=(int)1"
+///     }
+///   ]
+/// }
+/// ```
+#[derive(Debug, Serialize)]
+pub struct PipelineTopology {
+    // ---
+    /// ID of the inspected pipeline
+    pub pipeline_id: String,
+
+    /// Where the inspected instance came from: "live", "prerolled" or "parsed"
+    pub source: String,
+
+    /// Top-level elements in the order they were added
+    pub elements: Vec<TopologyElement>,
+
+    /// One entry per linked source pad
+    pub links: Vec<TopologyLink>,
+}
+
+/// An element of a [`PipelineTopology`].
+#[derive(Debug, Serialize)]
+pub struct TopologyElement {
+    // ---
+    /// Unique element name within the pipeline (e.g. "filesrc0")
+    pub name: String,
+
+    /// Factory the element was created from (e.g. "filesrc"), None for bins
+    /// built in code
+    pub factory: Option<String>,
+
+    /// Readable properties; numbers and booleans as JSON values, everything
+    /// else in GStreamer's serialized string form
+    pub properties: BTreeMap<String, serde_json::Value>,
+
+    /// The element's pads, including dynamic pads created so far
+    pub pads: Vec<TopologyPad>,
+}
+
+/// A pad of a [`TopologyElement`].
+#[derive(Debug, Serialize)]
+pub struct TopologyPad {
+    // ---
+    /// Pad name (e.g. "src", "sink_0")
+    pub name: String,
+
+    /// "src", "sink" or "unknown"
+    pub direction: String,
+
+    /// Linked pad as "element.pad", None when unlinked
+    pub peer: Option<String>,
+
+    /// Caps negotiated on the pad, None before negotiation
+    pub caps: Option<String>,
+}
+
+/// A link between two pads of a [`PipelineTopology`].
+#[derive(Debug, Serialize)]
+pub struct TopologyLink {
+    // ---
+    /// Source pad as "element.pad"
+    pub from: String,
+
+    /// Sink pad as "element.pad"
+    pub to: String,
+
+    /// Caps negotiated on the link, None before negotiation
+    pub caps: Option<String>,
+}
+
+/// Response for `PATCH /pipelines/{id}/elements/{name}`: the values each
+/// property had before and after the update.
+///
+/// # Example Response
+/// ```json
+/// {
+///   "pipeline_id": "550e8400-e29b-41d4-a716-446655440000",
+///   "element": "encoder",
+///   "factory": "x264enc",
+///   "properties": [{ "name": "bitrate", "old_value": 2048, "new_value": 2500 }]
+/// }
+/// ```
+#[derive(Debug, Serialize)]
+pub struct ElementUpdateResponse {
+    // ---
+    /// ID of the running pipeline
+    pub pipeline_id: String,
+
+    /// Name of the updated element
+    pub element: String,
+
+    /// Factory the element was created from, if any
+    pub factory: Option<String>,
+
+    /// One entry per updated property, in name order
+    pub properties: Vec<PropertyChange>,
+}
+
+/// Old and new value of one property in an [`ElementUpdateResponse`].
+///
+/// Values use the same JSON representation as the topology's properties.
+#[derive(Debug, Serialize)]
+pub struct PropertyChange {
+    // ---
+    /// Property name
+    pub name: String,
+
+    /// Value before the update
+    pub old_value: serde_json::Value,
+
+    /// Value read back after the update
+    pub new_value: serde_json::Value,
+}
+
+/// Playback position of a running pipeline, returned by
+/// `GET /pipelines/{id}/position` and `POST /pipelines/{id}/seek`.
+///
+/// # Example Response
+/// ```json
+/// {
+///   "pipeline_id": "550e8400-e29b-41d4-a716-446655440000",
+///   "position": 30.0,
+///   "duration": 125.4,
+///   "rate": 1.0,
+///   "seekable": true
+/// }
+/// ```
+#[derive(Debug, Serialize)]
+pub struct PipelinePosition {
+    // ---
+    /// ID of the running pipeline
+    pub pipeline_id: String,
+
+    /// Current position in seconds, None until the pipeline knows it
+    pub position: Option<f64>,
+
+    /// Total duration in seconds, None for live or unknown-length media
+    pub duration: Option<f64>,
+
+    /// Current playback rate; negative when playing backwards
+    pub rate: f64,
+
+    /// Whether the pipeline accepts time seeks
+    pub seekable: bool,
+}
+
+/// What a [`PipelineDiagnostic`] is about.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DiagnosticKind {
+    // ---
+    /// The pipeline could not be parsed or built
+    Syntax,
+
+    /// A pad that must be linked has no peer
+    NotLinked,
+
+    /// Elements could not agree on a format
+    CapsNegotiation,
+
+    /// An element posted an error or warning (missing file, unreachable host, ...)
+    Element,
+
+    /// The pipeline did not preroll in time
+    Timeout,
+}
+
+/// A problem found by deep pipeline validation.
+///
+/// # Example JSON Representation
+/// ```json
+/// {
+///   "severity": "error",
+///   "kind": "caps_negotiation",
+///   "element": "fakesrc0",
+///   "pad": null,
+///   "message": "Internal data stream error.",
+///   "debug": "streaming stopped, reason not-negotiated (-4)"
+/// }
+/// ```
+#[derive(Debug, Clone, Serialize)]
+pub struct PipelineDiagnostic {
+    // ---
+    /// `error` fails validation; `warning` is reported alongside errors
+    pub severity: LogLevel,
+
+    /// Category of the problem
+    pub kind: DiagnosticKind,
+
+    /// Element the problem was found at, if known
+    pub element: Option<String>,
+
+    /// Pad the problem was found at, if known
+    pub pad: Option<String>,
+
+    /// Human-readable description
+    pub message: String,
+
+    /// GStreamer debug details, if any
+    pub debug: Option<String>,
+}
+
+/// Registry metadata of an element factory, as listed by `GET /elements`.
+///
+/// # Example JSON Representation
+/// ```json
+/// {
+///   "name": "fakesink",
+///   "long_name": "Fake Sink",
+///   "klass": "Sink",
+///   "description": "Black hole for data",
+///   "rank": 0,
+///   "plugin": "coreelements"
+/// }
+/// ```
+#[derive(Debug, Serialize)]
+pub struct ElementSummary {
+    // ---
+    /// Factory name used in pipeline strings (e.g. "x264enc")
+    pub name: String,
+
+    /// Human-readable name (e.g. "x264 H.264 Encoder")
+    pub long_name: String,
+
+    /// Slash-separated classification (e.g. "Codec/Encoder/Video")
+    pub klass: String,
+
+    /// Short description of what the element does
+    pub description: String,
+
+    /// Autoplugging rank: 0 none, 64 marginal, 128 secondary, 256 primary
+    pub rank: i32,
+
+    /// Plugin providing the factory, if known
+    pub plugin: Option<String>,
+}
+
+/// Response for `GET /elements`: matching element factories, by name.
+#[derive(Debug, Serialize)]
+pub struct ElementListResponse {
+    // ---
+    /// Factories in name order
+    pub elements: Vec<ElementSummary>,
+}
+
+/// Full description of an element factory, returned by `GET /elements/{factory}`.
+#[derive(Debug, Serialize)]
+pub struct ElementDetails {
+    // ---
+    /// Registry metadata, as in the list response
+    #[serde(flatten)]
+    pub summary: ElementSummary,
+
+    /// Element author(s)
+    pub author: String,
+
+    /// Pad templates in registration order
+    pub pad_templates: Vec<PadTemplateInfo>,
+
+    /// Properties in name order, excluding "name" and "parent"
+    pub properties: Vec<PropertyInfo>,
+}
+
+/// A pad template of an [`ElementDetails`].
+///
+/// # Example JSON Representation
+/// ```json
+/// {
+///   "name": "src_%u",
+///   "direction": "src",
+///   "presence": "request",
+///   "caps": "ANY"
+/// }
+/// ```
+#[derive(Debug, Serialize)]
+pub struct PadTemplateInfo {
+    // ---
+    /// Template name; `%u`, `%d` and `%s` mark request or sometimes pad names
+    pub name: String,
+
+    /// "src", "sink" or "unknown"
+    pub direction: String,
+
+    /// "always", "sometimes" or "request"
+    pub presence: String,
+
+    /// Caps the pads accept or produce
+    pub caps: String,
+}
+
+/// A property of an [`ElementDetails`].
+///
+/// # Example JSON Representation
+/// ```json
+/// {
+///   "name": "num-buffers",
+///   "description": "Number of buffers to output before sending EOS (-1 = unlimited)",
+///   "type_name": "gint",
+///   "readable": true,
+///   "writable": true,
+///   "controllable_while_playing": false,
+///   "default": -1,
+///   "minimum": -1,
+///   "maximum": 2147483647
+/// }
+/// ```
+#[derive(Debug, Serialize)]
+pub struct PropertyInfo {
+    // ---
+    /// Property name as used in pipeline strings
+    pub name: String,
+
+    /// Short description of the property
+    pub description: String,
+
+    /// GLib type name (e.g. "gint", "gchararray", "GstFakeSrcDataType")
+    pub type_name: String,
+
+    /// Whether the property can be read
+    pub readable: bool,
+
+    /// Whether the property can be set
+    pub writable: bool,
+
+    /// Whether the property may be changed on a playing pipeline
+    /// (`PATCH /pipelines/{id}/elements/{name}`)
+    pub controllable_while_playing: bool,
+
+    /// Default value, in the same JSON form as topology properties
+    pub default: serde_json::Value,
+
+    /// Smallest accepted value of numeric properties
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub minimum: Option<serde_json::Value>,
+
+    /// Largest accepted value of numeric properties
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub maximum: Option<serde_json::Value>,
+
+    /// Accepted nicks of enum and flags properties
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub values: Option<Vec<String>>,
+}
+
 /// Standardized error response structure for all API endpoints.
 ///
 /// Provides consistent error reporting across the API with optional additional
@@ -175,6 +674,9 @@ pub struct SampleMedia {
 ///   "details": "Pipeline must contain at least one element connection (!)"
 /// }
 /// ```
+///
+/// Deep validation failures (`POST /pipelines?validate=deep`) also carry a
+/// `diagnostics` list of [`PipelineDiagnostic`]s.
 #[derive(Debug, Serialize)]
 pub struct ApiError {
     // ---
@@ -183,6 +685,10 @@ pub struct ApiError {
 
     /// Optional additional technical details about the error
     pub details: Option<String>,
+
+    /// Structured findings of deep pipeline validation, omitted when empty
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub diagnostics: Vec<PipelineDiagnostic>,
 }
 
 impl ApiError {
@@ -202,6 +708,7 @@ impl ApiError {
         Self {
             error: error.to_string(),
             details: None,
+            diagnostics: Vec::new(),
         }
     }
 
@@ -226,6 +733,28 @@ impl ApiError {
         Self {
             error: error.to_string(),
             details: Some(details.to_string()),
+            diagnostics: Vec::new(),
+        }
+    }
+
+    /// Creates a new ApiError listing deep validation diagnostics.
+    ///
+    /// # Arguments
+    /// * `error`       - The main error message to display
+    /// * `diagnostics` - Problems found; the first error also becomes `details`
+    pub fn with_diagnostics(error: &str, diagnostics: Vec<PipelineDiagnostic>) -> Self {
+        // ---
+        let details = diagnostics
+            .iter()
+            .find(|diagnostic| diagnostic.severity == LogLevel::Error)
+            .map(|diagnostic| match &diagnostic.element {
+                Some(element) => format!("{element}: {}", diagnostic.message),
+                None => diagnostic.message.clone(),
+            });
+        Self {
+            error: error.to_string(),
+            details,
+            diagnostics,
         }
     }
 }
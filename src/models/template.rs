@@ -0,0 +1,100 @@
+//! Parameterized pipeline template records.
+//!
+//! A template is a `parse_launch` string with typed placeholders such as
+//! `{{source_url:url}}` or `{{bitrate:int:100..20000}}`. Callers create
+//! pipelines from it by supplying values, which are checked against the
+//! placeholder types and escaped, instead of writing pipeline strings.
+
+use serde::{Deserialize, Serialize};
+
+/// Type of a template placeholder and the values it accepts.
+///
+/// Serialized with a `type` tag next to the parameter name, e.g.
+/// `{"name": "bitrate", "type": "int", "min": 100, "max": 20000}`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum ParameterKind {
+    // ---
+    /// Any text without control characters (`{{name}}` or `{{name:string}}`)
+    String,
+
+    /// Absolute URL with a streaming scheme (`{{name:url}}`)
+    Url,
+
+    /// `true` or `false` (`{{name:bool}}`)
+    Bool,
+
+    /// Integer, optionally bounded (`{{name:int:MIN..MAX}}`)
+    Int {
+        /// Smallest accepted value
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        min: Option<i64>,
+
+        /// Largest accepted value
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        max: Option<i64>,
+    },
+
+    /// Number, optionally bounded (`{{name:float:MIN..MAX}}`)
+    Float {
+        /// Smallest accepted value
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        min: Option<f64>,
+
+        /// Largest accepted value
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        max: Option<f64>,
+    },
+
+    /// One of a fixed list of words (`{{name:choice:fast|medium|slow}}`)
+    Choice {
+        /// Accepted values
+        options: Vec<String>,
+    },
+}
+
+/// A named placeholder of a template.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TemplateParameter {
+    // ---
+    /// Placeholder name, the key of its value when instantiating
+    pub name: String,
+
+    /// Accepted values
+    #[serde(flatten)]
+    pub kind: ParameterKind,
+}
+
+/// A stored pipeline template.
+///
+/// # Example JSON Representation
+/// ```json
+/// {
+///   "name": "hls-restream",
+///   "description": "Restream a source as HLS",
+///   "template": "souphttpsrc location={{source_url:url}} ! decodebin ! videoconvert ! x264enc bitrate={{bitrate:int:100..20000}} ! mpegtsmux ! hlssink",
+///   "parameters": [
+///     { "name": "source_url", "type": "url" },
+///     { "name": "bitrate", "type": "int", "min": 100, "max": 20000 }
+///   ],
+///   "created_at": "2024-09-21T10:30:00Z"
+/// }
+/// ```
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PipelineTemplate {
+    // ---
+    /// Unique name used in `/templates/{name}` URLs
+    pub name: String,
+
+    /// Human-readable description, used for pipelines created from it
+    pub description: Option<String>,
+
+    /// `parse_launch` string with placeholders
+    pub template: String,
+
+    /// Placeholders in order of first appearance
+    pub parameters: Vec<TemplateParameter>,
+
+    /// ISO 8601 timestamp when the template was saved
+    pub created_at: String,
+}
@@ -0,0 +1,47 @@
+//! Uploaded source media records.
+//!
+//! Clients without a public HTTP URL for their media upload it to the service
+//! first. Each completed upload is described by an [`UploadInfo`] and can be
+//! referenced as a job source with `upload://<id>`.
+
+use serde::{Deserialize, Serialize};
+
+/// Metadata of a completed upload.
+///
+/// # Example JSON Representation
+/// ```json
+/// {
+///   "id": "7c9e6679-7425-40de-944b-e07fc1f90ae7",
+///   "source_url": "upload://7c9e6679-7425-40de-944b-e07fc1f90ae7",
+///   "filename": "interview.mp4",
+///   "size": 10485760,
+///   "content_type": "video/quicktime",
+///   "owner": "key:team-a",
+///   "created_at": "2024-09-21T10:30:00Z"
+/// }
+/// ```
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UploadInfo {
+    // ---
+    /// Unique identifier for this upload (UUID v4 format)
+    pub id: String,
+
+    /// Value to pass as `source_url` in job requests
+    pub source_url: String,
+
+    /// File name supplied by the client, if any
+    pub filename: Option<String>,
+
+    /// Size of the stored file in bytes
+    pub size: u64,
+
+    /// Media type detected by GStreamer's typefind (None when undetermined)
+    pub content_type: Option<String>,
+
+    /// Owner of the upload (`key:<id>` or `tenant:<tenant>`); None when anonymous
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub owner: Option<String>,
+
+    /// ISO 8601 timestamp when the upload completed
+    pub created_at: String,
+}
@@ -0,0 +1,325 @@
+//! Output artifact lookup and HTTP download helpers.
+//!
+//! Resolves the files a pipeline wrote (its `outputs`) for download, and
+//! provides the pieces of HTTP semantics the download endpoints need: content
+//! type detection, entity tags and `Range` header parsing.
+//!
+//! # Output Layout
+//!
+//! Single-file jobs (conversions, thumbnails) record the file itself as their
+//! output. Multi-file jobs (HLS streams) record a directory; individual files
+//! inside it are addressed by name.
+//!
+//! # Security
+//!
+//! Artifact names supplied by clients must be a single path component, so a
+//! request can never escape the pipeline's recorded outputs. Outputs are
+//! only served when they resolve, with symlinks followed, to a path inside
+//! the pipeline's job directory.
+
+use std::fs::Metadata;
+use std::path::{Path, PathBuf};
+use std::time::UNIX_EPOCH;
+
+// ---
+
+// Import through gateway
+use crate::models::PipelineInfo;
+
+// ---
+
+// Import from sibling module
+use super::storage::is_inside_job_dir;
+
+/// Reasons an artifact cannot be served.
+#[derive(Debug, PartialEq, Eq)]
+pub enum ArtifactError {
+    // ---
+    /// No artifact with that name exists (yet) for the pipeline
+    NotFound(String),
+
+    /// The primary output is a directory; files must be requested by name
+    IsDirectory,
+
+    /// The requested name is not a plain file name
+    InvalidName(String),
+}
+
+/// Byte range selected by a `Range` request header.
+#[derive(Debug, PartialEq, Eq)]
+pub enum ByteRange {
+    // ---
+    /// Serve the whole file (no header, unsupported unit or multiple ranges)
+    Full,
+
+    /// Serve bytes `start..=end` with 206 Partial Content
+    Partial { start: u64, end: u64 },
+
+    /// The range lies outside the file; respond 416
+    Unsatisfiable,
+}
+
+/// Resolves a pipeline artifact to a file on disk.
+///
+/// # Arguments
+/// * `pipeline` - Pipeline whose outputs are searched
+/// * `job_dir`  - The pipeline's job directory; files outside it are never served
+/// * `name`     - File name within the outputs, or None for the primary output
+///
+/// # Returns
+/// * `Ok(PathBuf)` - Path to an existing regular file
+/// * `Err(ArtifactError)` - No such file, directory output without a name, or a bad name
+pub fn resolve_artifact(
+    pipeline: &PipelineInfo,
+    job_dir: &Path,
+    name: Option<&str>,
+) -> Result<PathBuf, ArtifactError> {
+    // ---
+    let Some(name) = name else {
+        let primary = pipeline
+            .outputs
+            .first()
+            .map(PathBuf::from)
+            .ok_or_else(|| ArtifactError::NotFound("Pipeline has no outputs".to_string()))?;
+
+        // Missing outputs and outputs outside the job directory look the same
+        if !is_inside_job_dir(job_dir, &primary) {
+            return Err(ArtifactError::NotFound(
+                "Output has not been written yet".to_string(),
+            ));
+        }
+        if primary.is_dir() {
+            return Err(ArtifactError::IsDirectory);
+        }
+        if !primary.is_file() {
+            return Err(ArtifactError::NotFound(
+                "Output has not been written yet".to_string(),
+            ));
+        }
+        return Ok(primary);
+    };
+
+    if name.is_empty() || name == "." || name == ".." || name.contains(['/', '\\']) {
+        return Err(ArtifactError::InvalidName(format!(
+            "'{name}' is not a plain file name"
+        )));
+    }
+
+    for output in pipeline.outputs.iter().map(Path::new) {
+        // ---
+        let candidate = if output.is_dir() {
+            output.join(name)
+        } else if output.file_name().is_some_and(|file| file == name) {
+            output.to_path_buf()
+        } else {
+            continue;
+        };
+
+        if candidate.is_file() && is_inside_job_dir(job_dir, &candidate) {
+            return Ok(candidate);
+        }
+    }
+
+    Err(ArtifactError::NotFound(format!(
+        "No output named '{name}' for this pipeline"
+    )))
+}
+
+/// Returns the MIME type for an artifact based on its extension.
+pub fn content_type_for(path: &Path) -> &'static str {
+    // ---
+    let extension = path
+        .extension()
+        .and_then(|extension| extension.to_str())
+        .map(str::to_ascii_lowercase);
+
+    match extension.as_deref() {
+        Some("mp4") => "video/mp4",
+        Some("webm") => "video/webm",
+        Some("avi") => "video/x-msvideo",
+        Some("ts") => "video/mp2t",
+        Some("m3u8") => "application/vnd.apple.mpegurl",
+        Some("png") => "image/png",
+        Some("jpg" | "jpeg") => "image/jpeg",
+        Some("ogg") => "audio/ogg",
+        _ => "application/octet-stream",
+    }
+}
+
+/// Computes a strong entity tag from file size and modification time.
+///
+/// Artifacts are only ever rewritten as a whole, so size plus mtime changes
+/// whenever the content does.
+pub fn etag_for(metadata: &Metadata) -> String {
+    // ---
+    let modified = metadata
+        .modified()
+        .ok()
+        .and_then(|time| time.duration_since(UNIX_EPOCH).ok())
+        .map(|duration| duration.as_nanos())
+        .unwrap_or_default();
+
+    format!("\"{:x}-{:x}\"", metadata.len(), modified)
+}
+
+/// Interprets a `Range` header against a file of `len` bytes.
+///
+/// Only single `bytes` ranges are honored; anything else yields the full file,
+/// which RFC 9110 permits.
+pub fn parse_range(header: Option<&str>, len: u64) -> ByteRange {
+    // ---
+    let Some(spec) = header.and_then(|header| header.trim().strip_prefix("bytes=")) else {
+        return ByteRange::Full;
+    };
+    if spec.contains(',') {
+        return ByteRange::Full;
+    }
+    let Some((start, end)) = spec.trim().split_once('-') else {
+        return ByteRange::Full;
+    };
+
+    let (start, end) = match (start.parse::<u64>(), end.parse::<u64>()) {
+        // bytes=start-end
+        (Ok(start), Ok(end)) if start <= end => (start, end.min(len.saturating_sub(1))),
+        // bytes=start-
+        (Ok(start), Err(_)) if end.is_empty() => (start, len.saturating_sub(1)),
+        // bytes=-suffix
+        (Err(_), Ok(suffix)) if start.is_empty() => {
+            if suffix == 0 {
+                return ByteRange::Unsatisfiable;
+            }
+            (len.saturating_sub(suffix), len.saturating_sub(1))
+        }
+        _ => return ByteRange::Full,
+    };
+
+    if start >= len {
+        return ByteRange::Unsatisfiable;
+    }
+    ByteRange::Partial { start, end }
+}
+
+#[cfg(test)]
+mod tests {
+    // ---
+
+    use super::*;
+    use crate::models::PipelineState;
+
+    fn pipeline_with_outputs(outputs: Vec<String>) -> PipelineInfo {
+        // ---
+        PipelineInfo {
+            outputs,
+            ..PipelineInfo::test("abc", None, PipelineState::Stopped)
+        }
+    }
+
+    #[test]
+    fn test_parse_range() {
+        // ---
+        assert_eq!(parse_range(None, 100), ByteRange::Full);
+        assert_eq!(
+            parse_range(Some("bytes=0-9"), 100),
+            ByteRange::Partial { start: 0, end: 9 }
+        );
+        assert_eq!(
+            parse_range(Some("bytes=90-"), 100),
+            ByteRange::Partial { start: 90, end: 99 }
+        );
+        assert_eq!(
+            parse_range(Some("bytes=-10"), 100),
+            ByteRange::Partial { start: 90, end: 99 }
+        );
+        assert_eq!(
+            parse_range(Some("bytes=50-500"), 100),
+            ByteRange::Partial { start: 50, end: 99 }
+        );
+        assert_eq!(
+            parse_range(Some("bytes=100-"), 100),
+            ByteRange::Unsatisfiable
+        );
+        assert_eq!(parse_range(Some("bytes=0-1,5-6"), 100), ByteRange::Full);
+        assert_eq!(parse_range(Some("items=0-1"), 100), ByteRange::Full);
+    }
+
+    #[test]
+    fn test_content_type_for() {
+        // ---
+        assert_eq!(content_type_for(Path::new("output_1.webm")), "video/webm");
+        assert_eq!(content_type_for(Path::new("thumb_1.PNG")), "image/png");
+        assert_eq!(
+            content_type_for(Path::new("stream_1/playlist.m3u8")),
+            "application/vnd.apple.mpegurl"
+        );
+        assert_eq!(
+            content_type_for(Path::new("unknown")),
+            "application/octet-stream"
+        );
+    }
+
+    #[test]
+    fn test_resolve_artifact() {
+        // ---
+        let dir = std::env::temp_dir().join(format!("artifacts-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("segment_00000.ts"), b"data").unwrap();
+        let file = dir.join("output.webm");
+        std::fs::write(&file, b"data").unwrap();
+
+        let single = pipeline_with_outputs(vec![file.display().to_string()]);
+        assert_eq!(resolve_artifact(&single, &dir, None), Ok(file.clone()));
+        assert_eq!(
+            resolve_artifact(&single, &dir, Some("output.webm")),
+            Ok(file)
+        );
+
+        let stream = pipeline_with_outputs(vec![dir.display().to_string()]);
+        assert_eq!(
+            resolve_artifact(&stream, &dir, None),
+            Err(ArtifactError::IsDirectory)
+        );
+        assert_eq!(
+            resolve_artifact(&stream, &dir, Some("segment_00000.ts")),
+            Ok(dir.join("segment_00000.ts"))
+        );
+        assert!(matches!(
+            resolve_artifact(&stream, &dir, Some("..")),
+            Err(ArtifactError::InvalidName(_))
+        ));
+        assert!(matches!(
+            resolve_artifact(&stream, &dir, Some("missing.ts")),
+            Err(ArtifactError::NotFound(_))
+        ));
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_resolve_artifact_stays_in_job_dir() {
+        // ---
+        let dir = std::env::temp_dir().join(format!("artifacts-{}", uuid::Uuid::new_v4()));
+        let job_dir = dir.join("job");
+        std::fs::create_dir_all(&job_dir).unwrap();
+        let outside = dir.join("secret.txt");
+        std::fs::write(&outside, b"secret").unwrap();
+        std::os::unix::fs::symlink(&outside, job_dir.join("link.txt")).unwrap();
+
+        let direct = pipeline_with_outputs(vec![outside.display().to_string()]);
+        assert!(matches!(
+            resolve_artifact(&direct, &job_dir, None),
+            Err(ArtifactError::NotFound(_))
+        ));
+        assert!(matches!(
+            resolve_artifact(&direct, &job_dir, Some("secret.txt")),
+            Err(ArtifactError::NotFound(_))
+        ));
+
+        let linked = pipeline_with_outputs(vec![job_dir.display().to_string()]);
+        assert!(matches!(
+            resolve_artifact(&linked, &job_dir, Some("link.txt")),
+            Err(ArtifactError::NotFound(_))
+        ));
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
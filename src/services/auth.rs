@@ -0,0 +1,231 @@
+//! API key verification.
+//!
+//! Keys are configured in a JSON file passed with `--api-keys`. The file
+//! never contains the keys themselves, only their SHA-256 digests, so a
+//! leaked configuration does not grant access. Digests are produced with
+//! `--hash-api-key <KEY>` (or [`hash_api_key`]).
+//!
+//! # Key File Format
+//!
+//! ```json
+//! {
+//!   "keys": [
+//!     {
+//!       "id": "ingest-service",
+//!       "key_hash": "sha256:9f86d081884c7d659a2feaa0c55ad015a3bf4f1b2b0b822cd15d6c15b0f00a08",
+//!       "scopes": ["pipelines:read", "pipelines:write"]
+//!     }
+//!   ]
+//! }
+//! ```
+
+use anyhow::Context;
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
+
+// ---
+
+// Import through gateway
+use crate::models::{Caller, Scope};
+
+/// Prefix identifying the digest algorithm of a `key_hash`
+const HASH_PREFIX: &str = "sha256:";
+
+/// On-disk representation of the key file.
+#[derive(Deserialize)]
+struct KeyFile {
+    // ---
+    keys: Vec<KeyEntry>,
+}
+
+/// One configured API key.
+#[derive(Deserialize)]
+struct KeyEntry {
+    // ---
+    id: String,
+    key_hash: String,
+    #[serde(default)]
+    scopes: Vec<Scope>,
+}
+
+/// Configured API keys, indexed by digest.
+#[derive(Debug)]
+pub struct ApiKeys {
+    // ---
+    /// Hex SHA-256 digest → (key ID, granted scopes)
+    by_hash: HashMap<String, (String, Vec<Scope>)>,
+}
+
+impl ApiKeys {
+    // ---
+
+    /// Loads and validates a key file.
+    ///
+    /// # Errors
+    /// Fails if the file cannot be read or parsed, an ID is not a valid
+    /// owner name, a `key_hash` is not a `sha256:` digest, or two entries
+    /// share an ID or a digest.
+    pub fn load(path: &Path) -> anyhow::Result<Self> {
+        // ---
+        let contents = std::fs::read(path)
+            .with_context(|| format!("Failed to read API key file {}", path.display()))?;
+        Self::parse(&contents).with_context(|| format!("Invalid API key file {}", path.display()))
+    }
+
+    /// Parses the JSON contents of a key file.
+    fn parse(contents: &[u8]) -> anyhow::Result<Self> {
+        // ---
+        let file: KeyFile = serde_json::from_slice(contents)?;
+
+        let mut ids = HashSet::new();
+        let mut by_hash = HashMap::new();
+        for entry in file.keys {
+            let digest = entry
+                .key_hash
+                .strip_prefix(HASH_PREFIX)
+                .filter(|hex| hex.len() == 64 && hex.bytes().all(|b| b.is_ascii_hexdigit()))
+                .with_context(|| {
+                    format!(
+                        "Key '{}' must have a key_hash of the form sha256:<64 hex digits>",
+                        entry.id
+                    )
+                })?
+                .to_ascii_lowercase();
+
+            anyhow::ensure!(
+                is_valid_owner(&entry.id),
+                "Key ID '{}' may only contain letters, digits, '-', '_', '.' and '@'",
+                entry.id
+            );
+            anyhow::ensure!(
+                ids.insert(entry.id.clone()),
+                "Duplicate key ID '{}'",
+                entry.id
+            );
+            anyhow::ensure!(
+                !by_hash.contains_key(&digest),
+                "Key '{}' reuses the hash of another key",
+                entry.id
+            );
+            by_hash.insert(digest, (entry.id, entry.scopes));
+        }
+
+        Ok(Self { by_hash })
+    }
+
+    /// Returns the number of configured keys.
+    pub fn len(&self) -> usize {
+        // ---
+        self.by_hash.len()
+    }
+
+    /// Returns true if no keys are configured, so no API key is accepted.
+    pub fn is_empty(&self) -> bool {
+        // ---
+        self.by_hash.is_empty()
+    }
+
+    /// Looks up the caller presenting `key`, or None if the key is unknown.
+    pub fn authenticate(&self, key: &str) -> Option<Caller> {
+        // ---
+        let digest = hex::encode(Sha256::digest(key.as_bytes()));
+        self.by_hash
+            .get(&digest)
+            .map(|(id, scopes)| Caller::ApiKey {
+                id: id.clone(),
+                scopes: scopes.clone(),
+            })
+    }
+}
+
+/// Returns true if `name` can own pipelines.
+///
+/// Owners name a directory in output storage, so they must be a single,
+/// non-hidden path segment of a conservative character set.
+pub(super) fn is_valid_owner(name: &str) -> bool {
+    // ---
+    !name.is_empty()
+        && name.len() <= 128
+        && !name.starts_with('.')
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.' | '@'))
+}
+
+/// Returns the `key_hash` value to store in the key file for `key`.
+pub fn hash_api_key(key: &str) -> String {
+    // ---
+    format!(
+        "{HASH_PREFIX}{}",
+        hex::encode(Sha256::digest(key.as_bytes()))
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    // ---
+
+    use super::*;
+
+    fn key_file(entries: &[(&str, &str, &[&str])]) -> Vec<u8> {
+        // ---
+        let keys: Vec<_> = entries
+            .iter()
+            .map(|(id, key, scopes)| {
+                serde_json::json!({
+                    "id": id,
+                    "key_hash": hash_api_key(key),
+                    "scopes": scopes,
+                })
+            })
+            .collect();
+        serde_json::to_vec(&serde_json::json!({ "keys": keys })).unwrap()
+    }
+
+    #[test]
+    fn test_authenticate_by_hashed_key() {
+        // ---
+        let keys = ApiKeys::parse(&key_file(&[
+            (
+                "ingest",
+                "secret-one",
+                &["pipelines:read", "pipelines:write"],
+            ),
+            ("viewer", "secret-two", &["pipelines:read"]),
+        ]))
+        .unwrap();
+        assert_eq!(keys.len(), 2);
+
+        let caller = keys.authenticate("secret-one").unwrap();
+        assert_eq!(caller.owner().as_deref(), Some("key:ingest"));
+        assert!(caller.has_scope(Scope::PipelinesWrite));
+        assert!(!caller.has_scope(Scope::CustomPipelines));
+
+        let viewer = keys.authenticate("secret-two").unwrap();
+        assert!(!viewer.has_scope(Scope::PipelinesWrite));
+
+        assert!(keys.authenticate("secret-three").is_none());
+        assert!(keys.authenticate("").is_none());
+    }
+
+    #[test]
+    fn test_parse_rejects_invalid_key_files() {
+        // ---
+        let duplicate = key_file(&[("a", "one", &[]), ("a", "two", &[])]);
+        assert!(ApiKeys::parse(&duplicate).is_err());
+
+        let reused = key_file(&[("a", "one", &[]), ("b", "one", &[])]);
+        assert!(ApiKeys::parse(&reused).is_err());
+
+        let unsafe_id = key_file(&[("../a", "one", &[])]);
+        assert!(ApiKeys::parse(&unsafe_id).is_err());
+
+        let plaintext = br#"{"keys":[{"id":"a","key_hash":"hunter2","scopes":[]}]}"#;
+        assert!(ApiKeys::parse(plaintext).is_err());
+
+        let unknown_scope = br#"{"keys":[{"id":"a","key_hash":"sha256:00","scopes":["admin"]}]}"#;
+        assert!(ApiKeys::parse(unknown_scope).is_err());
+    }
+}
@@ -0,0 +1,648 @@
+//! Building GStreamer pipelines from pipeline records.
+//!
+//! Pipelines are described either by a `parse_launch` string or by a
+//! structured [`PipelineGraph`]. Graphs are built element by element with
+//! `ElementFactory` and linked programmatically, so element names and
+//! property values are never parsed as pipeline syntax.
+//!
+//! # Links
+//!
+//! Each link names its endpoints as `element` (any compatible pads) or
+//! `element.pad`, where `pad` may also be a request pad template such as
+//! `mux.video_%u`. A link with `caps` gets a `capsfilter` in between, like
+//! a caps string between two elements of a pipeline string. Links from
+//! elements whose source pads only appear at runtime (e.g. `decodebin`) are
+//! made when the pad is added, as `parse_launch` does.
+//!
+//! # Output Locations
+//!
+//! File paths of the sinks in [`FILE_SINKS`] (`location`,
+//! `playlist-location`, ...) are relative to a custom pipeline's job
+//! directory, so custom pipelines write their results next to those of every
+//! other job. [`confine_outputs`] refuses paths outside it, and any other
+//! sink given a file path, when the pipeline is created.
+
+use gstreamer::glib;
+use gstreamer::prelude::*;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+// ---
+
+// Import through gateway
+use crate::models::{GraphElement, PipelineGraph, PipelineInfo, PipelineKind};
+
+// ---
+
+// Import from sibling modules
+use super::control::{is_path_property, json_to_value};
+use super::storage::resolve_output_location;
+
+/// Sinks whose file paths custom pipelines may set, confined to the job
+/// directory; all but `filesink` write numbered files from a pattern
+pub(super) const FILE_SINKS: [&str; 5] = [
+    "filesink",
+    "multifilesink",
+    "splitmuxsink",
+    "hlssink",
+    "hlssink2",
+];
+
+/// Longest accepted element name
+const MAX_NAME_LEN: usize = 64;
+
+/// Creates a fresh GStreamer pipeline for a pipeline record.
+///
+/// Builds the record's `graph` when it has one, and parses its pipeline
+/// string otherwise. File sinks of custom pipelines write into `job_dir`.
+pub(super) fn build_pipeline(
+    pipeline: &PipelineInfo,
+    job_dir: &Path,
+) -> anyhow::Result<gstreamer::Pipeline> {
+    // ---
+    let instance = build_from(&pipeline.pipeline_string, pipeline.graph.as_ref())?;
+    if pipeline.kind == PipelineKind::Custom {
+        place_file_sinks(&instance, job_dir);
+    }
+    Ok(instance)
+}
+
+/// Resolves the file paths of every file sink against `job_dir`, the way
+/// the pipeline's outputs were recorded.
+fn place_file_sinks(pipeline: &gstreamer::Pipeline, job_dir: &Path) {
+    // ---
+    for element in pipeline
+        .iterate_recurse()
+        .into_iter()
+        .filter_map(Result::ok)
+    {
+        for (property, location) in file_paths(&element) {
+            let location = job_dir.join(location);
+            element.set_property(&property, location.to_string_lossy().as_ref());
+        }
+    }
+}
+
+/// Lists the file path properties set on `element`, by name and value.
+pub(super) fn file_paths(element: &gstreamer::Element) -> Vec<(String, String)> {
+    // ---
+    let is_sink = element.factory().is_some_and(|factory| {
+        factory
+            .metadata(gstreamer::ELEMENT_METADATA_KLASS)
+            .is_some_and(|klass| klass.contains("Sink"))
+    });
+    if !is_sink {
+        return Vec::new();
+    }
+
+    element
+        .list_properties()
+        .iter()
+        .filter(|pspec| {
+            is_path_property(pspec) && pspec.flags().contains(glib::ParamFlags::READABLE)
+        })
+        .filter_map(|pspec| {
+            let value = element.property::<Option<String>>(pspec.name())?;
+            (!value.is_empty()).then(|| (pspec.name().to_string(), value))
+        })
+        .collect()
+}
+
+/// Resolves the files a custom pipeline writes against its job directory,
+/// for recording as its outputs.
+///
+/// Builds the pipeline from `graph`, or from `pipeline_string` when there is
+/// no graph, and confines the file paths of every sink.
+///
+/// # Returns
+/// * `Ok(Vec<String>)` - The file of each `filesink`, and the directory each
+///   sink writing numbered files (`multifilesink`, `hlssink`, ...) fills,
+///   in element order
+/// * `Err(String)` - A path lies outside `job_dir`, or a sink not in
+///   [`FILE_SINKS`] was given a file path
+pub fn confine_outputs(
+    pipeline_string: &str,
+    graph: Option<&PipelineGraph>,
+    job_dir: &Path,
+) -> Result<Vec<String>, String> {
+    // ---
+    let pipeline = build_from(pipeline_string, graph).map_err(|e| e.to_string())?;
+    let mut elements: Vec<_> = pipeline
+        .iterate_recurse()
+        .into_iter()
+        .filter_map(Result::ok)
+        .collect();
+
+    // Bin iteration yields children in reverse order of addition
+    elements.reverse();
+
+    let mut outputs = Vec::new();
+    for element in elements {
+        let factory = element
+            .factory()
+            .map(|factory| factory.name().to_string())
+            .unwrap_or_default();
+        for (property, location) in file_paths(&element) {
+            let output = confine_sink_path(&factory, &property, &location, job_dir)?;
+            if !outputs.contains(&output) {
+                outputs.push(output);
+            }
+        }
+    }
+    Ok(outputs
+        .into_iter()
+        .map(|output| output.display().to_string())
+        .collect())
+}
+
+/// Resolves one file path of a sink, returning the output it produces.
+fn confine_sink_path(
+    factory: &str,
+    property: &str,
+    location: &str,
+    job_dir: &Path,
+) -> Result<PathBuf, String> {
+    // ---
+    if !FILE_SINKS.contains(&factory) {
+        return Err(format!(
+            "'{factory}' writes to '{location}' ({property}), which cannot be confined to the \
+             job directory; use one of {}",
+            FILE_SINKS.join(", ")
+        ));
+    }
+
+    let path = resolve_output_location(job_dir, location)?;
+    match factory {
+        "filesink" => Ok(path),
+        _ => Ok(path.parent().unwrap_or(job_dir).to_path_buf()),
+    }
+}
+
+/// Creates a fresh GStreamer pipeline from `graph`, or from
+/// `pipeline_string` when there is no graph.
+pub(super) fn build_from(
+    pipeline_string: &str,
+    graph: Option<&PipelineGraph>,
+) -> anyhow::Result<gstreamer::Pipeline> {
+    // ---
+    match graph {
+        Some(graph) => build_graph(graph).map_err(anyhow::Error::msg),
+        None => gstreamer::parse_launch(pipeline_string)?
+            .downcast::<gstreamer::Pipeline>()
+            .map_err(|_| anyhow::anyhow!("Pipeline string did not produce a pipeline")),
+    }
+}
+
+/// Validates a structured pipeline by building it.
+///
+/// # Arguments
+/// * `graph` - The pipeline graph to validate
+///
+/// # Returns
+/// * `Ok(())` - Every element exists, every property value fits its type
+///   and every link could be made (or is made once its pad appears)
+/// * `Err(String)` - The first problem found
+///
+/// # Example
+/// ```rust
+/// let graph: PipelineGraph = serde_json::from_value(json!({
+///     "elements": [
+///         { "factory": "fakesrc", "name": "src", "properties": { "num-buffers": 10 } },
+///         { "factory": "fakesink", "name": "sink" }
+///     ],
+///     "links": [{ "from": "src", "to": "sink" }]
+/// }))?;
+/// assert!(validate_pipeline_graph(&graph).is_ok());
+/// ```
+#[tracing::instrument(name = "validate_pipeline_graph", skip_all)]
+pub fn validate_pipeline_graph(graph: &PipelineGraph) -> Result<(), String> {
+    // ---
+    build_graph(graph).map(|_| ())
+}
+
+/// Renders a graph as an equivalent pipeline string.
+///
+/// Stored as the `pipeline_string` of graph pipelines so they can be read
+/// and listed like any other; the graph itself is what gets built.
+pub fn describe_graph(graph: &PipelineGraph) -> String {
+    // ---
+    let elements = graph.elements.iter().map(|element| {
+        let properties = element
+            .properties
+            .iter()
+            .map(|(name, value)| format!(" {name}={}", launch_value(value)));
+        format!(
+            "{} name={}{}",
+            element.factory,
+            element.name,
+            properties.collect::<String>()
+        )
+    });
+    let links = graph.links.iter().map(|link| {
+        let endpoint = |end: &str| match end.split_once('.') {
+            Some(_) => end.to_string(),
+            None => format!("{end}."),
+        };
+        match &link.caps {
+            Some(caps) => format!(
+                "{} ! capsfilter caps={} ! {}",
+                endpoint(&link.from),
+                quote(caps),
+                endpoint(&link.to)
+            ),
+            None => format!("{} ! {}", endpoint(&link.from), endpoint(&link.to)),
+        }
+    });
+
+    elements.chain(links).collect::<Vec<_>>().join(" ")
+}
+
+/// Creates, configures and links the elements of a graph.
+fn build_graph(graph: &PipelineGraph) -> Result<gstreamer::Pipeline, String> {
+    // ---
+    if graph.elements.is_empty() {
+        return Err("Graph must contain at least one element".to_string());
+    }
+    if graph.links.is_empty() {
+        return Err("Graph must contain at least one link".to_string());
+    }
+
+    let pipeline = gstreamer::Pipeline::new();
+    let mut elements = BTreeMap::new();
+    for element in &graph.elements {
+        if elements.contains_key(element.name.as_str()) {
+            return Err(format!("Duplicate element name '{}'", element.name));
+        }
+        let built = build_element(element)?;
+        pipeline
+            .add(&built)
+            .map_err(|e| format!("Cannot add element '{}': {e}", element.name))?;
+        elements.insert(element.name.as_str(), built);
+    }
+
+    for (index, link) in graph.links.iter().enumerate() {
+        let describe = || format!("link {} ('{}' to '{}')", index + 1, link.from, link.to);
+        let (from, from_pad) =
+            endpoint(&elements, &link.from).map_err(|e| format!("Invalid {}: {e}", describe()))?;
+        let (to, to_pad) =
+            endpoint(&elements, &link.to).map_err(|e| format!("Invalid {}: {e}", describe()))?;
+
+        match &link.caps {
+            Some(caps) => {
+                let caps = gstreamer::Caps::from_str(caps)
+                    .map_err(|_| format!("Invalid caps '{caps}' in {}", describe()))?;
+                let filter = gstreamer::ElementFactory::make("capsfilter")
+                    .property("caps", &caps)
+                    .build()
+                    .map_err(|e| format!("Cannot create capsfilter: {e}"))?;
+                pipeline
+                    .add(&filter)
+                    .map_err(|e| format!("Cannot add capsfilter: {e}"))?;
+                link_elements(from, from_pad, &filter, None)
+                    .and_then(|_| link_elements(&filter, None, to, to_pad))
+                    .map_err(|e| format!("Cannot make {}: {e}", describe()))?;
+            }
+            None => link_elements(from, from_pad, to, to_pad)
+                .map_err(|e| format!("Cannot make {}: {e}", describe()))?,
+        }
+    }
+
+    Ok(pipeline)
+}
+
+/// Creates one element and sets its properties.
+fn build_element(element: &GraphElement) -> Result<gstreamer::Element, String> {
+    // ---
+    let name = &element.name;
+    let valid_name = !name.is_empty()
+        && name.len() <= MAX_NAME_LEN
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+    if !valid_name {
+        return Err(format!(
+            "Invalid element name '{name}': use 1 to {MAX_NAME_LEN} letters, digits, '-' or '_'"
+        ));
+    }
+    if gstreamer::ElementFactory::find(&element.factory).is_none() {
+        return Err(format!(
+            "Unknown element factory '{}' for element '{name}'",
+            element.factory
+        ));
+    }
+    let built = gstreamer::ElementFactory::make(&element.factory)
+        .name(name.as_str())
+        .build()
+        .map_err(|e| format!("Cannot create element '{name}': {e}"))?;
+
+    for (property, json) in &element.properties {
+        if property == "name" {
+            return Err(format!(
+                "Element '{name}': set the name with the element's 'name' field"
+            ));
+        }
+        let pspec = built.find_property(property).ok_or_else(|| {
+            format!(
+                "Element '{name}' ({}) has no property '{property}'",
+                element.factory
+            )
+        })?;
+        if !pspec.flags().contains(glib::ParamFlags::WRITABLE) {
+            return Err(format!(
+                "Property '{property}' of element '{name}' is read-only"
+            ));
+        }
+        let value = json_to_value(&pspec, json).map_err(|e| format!("Element '{name}': {e}"))?;
+        built.set_property_from_value(property, &value);
+    }
+
+    Ok(built)
+}
+
+/// Resolves a link endpoint (`element` or `element.pad`).
+fn endpoint<'a>(
+    elements: &'a BTreeMap<&str, gstreamer::Element>,
+    end: &'a str,
+) -> Result<(&'a gstreamer::Element, Option<&'a str>), String> {
+    // ---
+    let (name, pad) = match end.split_once('.') {
+        Some((name, pad)) if !pad.is_empty() => (name, Some(pad)),
+        Some(_) => return Err(format!("missing pad name in '{end}'")),
+        None => (end, None),
+    };
+    let element = elements
+        .get(name)
+        .ok_or_else(|| format!("unknown element '{name}'"))?;
+    Ok((element, pad))
+}
+
+/// Links two elements now, or once `from` adds a matching source pad.
+fn link_elements(
+    from: &gstreamer::Element,
+    from_pad: Option<&str>,
+    to: &gstreamer::Element,
+    to_pad: Option<&str>,
+) -> Result<(), String> {
+    // ---
+    if from.link_pads(from_pad, to, to_pad).is_ok() {
+        return Ok(());
+    }
+
+    let has_sometimes_pads = from.pad_template_list().iter().any(|template| {
+        template.direction() == gstreamer::PadDirection::Src
+            && template.presence() == gstreamer::PadPresence::Sometimes
+    });
+    let pad_exists = from_pad.is_some_and(|pad| from.static_pad(pad).is_some());
+    if !has_sometimes_pads || pad_exists {
+        return Err("no compatible pads".to_string());
+    }
+
+    let from_pad = from_pad.map(str::to_string);
+    let to_pad = to_pad.map(str::to_string);
+    let to = to.downgrade();
+    from.connect_pad_added(move |_, pad| {
+        // ---
+        let Some(to) = to.upgrade() else {
+            return;
+        };
+        if pad.direction() != gstreamer::PadDirection::Src
+            || from_pad.as_deref().is_some_and(|name| pad.name() != name)
+        {
+            return;
+        }
+        let sink_pad = match &to_pad {
+            Some(name) => to.static_pad(name).or_else(|| to.request_pad_simple(name)),
+            None => to.compatible_pad(pad, None),
+        };
+        if let Some(sink_pad) = sink_pad.filter(|sink_pad| !sink_pad.is_linked()) {
+            let _ = pad.link(&sink_pad);
+        }
+    });
+    Ok(())
+}
+
+/// Formats a property value for display in a pipeline string.
+fn launch_value(value: &serde_json::Value) -> String {
+    // ---
+    match value {
+        serde_json::Value::String(text) => quote(text),
+        other => other.to_string(),
+    }
+}
+
+/// Quotes text as a single pipeline string value.
+pub(super) fn quote(text: &str) -> String {
+    // ---
+    format!("\"{}\"", text.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+#[cfg(test)]
+mod tests {
+    // ---
+
+    use super::*;
+    use serde_json::json;
+
+    fn graph(value: serde_json::Value) -> PipelineGraph {
+        // ---
+        serde_json::from_value(value).unwrap()
+    }
+
+    /// Plays a pipeline until it ends and returns whether it ended cleanly.
+    fn runs_to_eos(pipeline: &gstreamer::Pipeline) -> bool {
+        // ---
+        pipeline.set_state(gstreamer::State::Playing).unwrap();
+        let message = pipeline.bus().unwrap().timed_pop_filtered(
+            gstreamer::ClockTime::from_seconds(5),
+            &[gstreamer::MessageType::Eos, gstreamer::MessageType::Error],
+        );
+        pipeline.set_state(gstreamer::State::Null).unwrap();
+        message.is_some_and(|message| message.type_() == gstreamer::MessageType::Eos)
+    }
+
+    #[test]
+    fn test_build_graph_links_pads_and_caps() {
+        // ---
+        gstreamer::init().unwrap();
+        let graph = graph(json!({
+            "elements": [
+                { "factory": "fakesrc", "name": "src", "properties": { "num-buffers": 3 } },
+                { "factory": "tee", "name": "split" },
+                { "factory": "queue", "name": "q1" },
+                { "factory": "queue", "name": "q2" },
+                { "factory": "fakesink", "name": "a" },
+                { "factory": "fakesink", "name": "b", "properties": { "sync": false } }
+            ],
+            "links": [
+                { "from": "src", "to": "split", "caps": "audio/x-raw,rate=8000" },
+                { "from": "split.src_%u", "to": "q1" },
+                { "from": "split", "to": "q2.sink" },
+                { "from": "q1", "to": "a" },
+                { "from": "q2.src", "to": "b.sink" }
+            ]
+        }));
+
+        let pipeline = build_graph(&graph).unwrap();
+        let src = pipeline.by_name("src").unwrap();
+        assert_eq!(src.property::<i32>("num-buffers"), 3);
+        let filter = src.static_pad("src").unwrap().peer().unwrap();
+        let filter = filter.parent_element().unwrap();
+        assert_eq!(filter.factory().unwrap().name(), "capsfilter");
+        assert!(runs_to_eos(&pipeline));
+
+        // The display string describes the same pipeline
+        let described = describe_graph(&graph);
+        assert!(described.starts_with("fakesrc name=src num-buffers=3 tee name=split"));
+        assert!(described.contains("src. ! capsfilter caps=\"audio/x-raw,rate=8000\" ! split."));
+        gstreamer::parse_launch(&described).unwrap();
+    }
+
+    #[test]
+    fn test_build_graph_rejects_invalid_graphs() {
+        // ---
+        gstreamer::init().unwrap();
+        let elements = json!([
+            { "factory": "fakesrc", "name": "src" },
+            { "factory": "fakesink", "name": "sink" }
+        ]);
+        let cases = [
+            (json!({ "elements": elements }), "at least one link"),
+            (
+                json!({ "elements": [
+                    { "factory": "fakesrc", "name": "src" },
+                    { "factory": "nosuchelement", "name": "x" }
+                ], "links": [{ "from": "src", "to": "x" }] }),
+                "Unknown element factory",
+            ),
+            (
+                json!({ "elements": [
+                    { "factory": "fakesrc", "name": "src" },
+                    { "factory": "fakesink", "name": "src" }
+                ], "links": [{ "from": "src", "to": "src" }] }),
+                "Duplicate element name",
+            ),
+            (
+                json!({ "elements": [
+                    { "factory": "fakesrc", "name": "src ! fakesink name=x" },
+                    { "factory": "fakesink", "name": "sink" }
+                ], "links": [{ "from": "src", "to": "sink" }] }),
+                "Invalid element name",
+            ),
+            (
+                json!({ "elements": [
+                    { "factory": "fakesrc", "name": "src", "properties": { "bitrate": 1 } },
+                    { "factory": "fakesink", "name": "sink" }
+                ], "links": [{ "from": "src", "to": "sink" }] }),
+                "has no property 'bitrate'",
+            ),
+            (
+                json!({ "elements": [
+                    { "factory": "fakesrc", "name": "src", "properties": { "num-buffers": -5 } },
+                    { "factory": "fakesink", "name": "sink" }
+                ], "links": [{ "from": "src", "to": "sink" }] }),
+                "out of range",
+            ),
+            (
+                json!({ "elements": elements, "links": [{ "from": "src", "to": "other" }] }),
+                "unknown element 'other'",
+            ),
+            (
+                json!({ "elements": elements, "links": [{ "from": "sink", "to": "src" }] }),
+                "no compatible pads",
+            ),
+            (
+                json!({ "elements": elements, "links": [{ "from": "src.nope", "to": "sink" }] }),
+                "no compatible pads",
+            ),
+            (
+                json!({ "elements": elements,
+                        "links": [{ "from": "src", "to": "sink", "caps": "not caps," }] }),
+                "Invalid caps",
+            ),
+        ];
+
+        for (value, expected) in cases {
+            let error = validate_pipeline_graph(&graph(value.clone())).unwrap_err();
+            assert!(error.contains(expected), "{value}: {error}");
+        }
+    }
+
+    #[test]
+    fn test_build_graph_defers_links_from_sometimes_pads() {
+        // ---
+        gstreamer::init().unwrap();
+        let graph = graph(json!({
+            "elements": [
+                { "factory": "fakesrc", "name": "src", "properties": { "num-buffers": 3 } },
+                { "factory": "streamiddemux", "name": "demux" },
+                { "factory": "fakesink", "name": "sink" }
+            ],
+            "links": [{ "from": "src", "to": "demux" }, { "from": "demux", "to": "sink" }]
+        }));
+
+        let pipeline = build_graph(&graph).unwrap();
+        let sink_pad = pipeline
+            .by_name("sink")
+            .unwrap()
+            .static_pad("sink")
+            .unwrap();
+        assert!(!sink_pad.is_linked());
+
+        // Data only reaches the sink, without a not-linked error, once the
+        // pad added for the stream was linked
+        assert!(runs_to_eos(&pipeline));
+    }
+
+    #[test]
+    fn test_confine_outputs() {
+        // ---
+        gstreamer::init().unwrap();
+        let job_dir = Path::new("outputs/abc");
+        let graph = graph(json!({
+            "elements": [
+                { "factory": "fakesrc", "name": "src" },
+                { "factory": "filesink", "name": "out", "properties": { "location": "a.bin" } }
+            ],
+            "links": [{ "from": "src", "to": "out" }]
+        }));
+
+        assert_eq!(
+            confine_outputs("", Some(&graph), job_dir).unwrap(),
+            ["outputs/abc/a.bin"]
+        );
+        assert_eq!(
+            confine_outputs(
+                "fakesrc ! tee name=t ! queue ! filesink location=b.bin \
+                 t. ! queue ! filesink location=c/d.bin",
+                None,
+                job_dir
+            )
+            .unwrap(),
+            ["outputs/abc/b.bin", "outputs/abc/c/d.bin"]
+        );
+        assert!(confine_outputs("fakesrc ! filesink location=/tmp/x", None, job_dir).is_err());
+    }
+
+    #[test]
+    fn test_confine_sink_path_covers_numbered_file_sinks() {
+        // ---
+        let job_dir = Path::new("outputs/abc");
+
+        assert_eq!(
+            confine_sink_path("multifilesink", "location", "frames/x%d", job_dir),
+            Ok(job_dir.join("frames"))
+        );
+        assert_eq!(
+            confine_sink_path("hlssink", "playlist-location", "playlist.m3u8", job_dir),
+            Ok(job_dir.to_path_buf())
+        );
+        assert!(confine_sink_path("multifilesink", "location", "/tmp/x%d", job_dir).is_err());
+        assert!(confine_sink_path("hlssink", "location", "../x%05d.ts", job_dir).is_err());
+        assert!(
+            confine_sink_path("someothersink", "location", "x.bin", job_dir)
+                .unwrap_err()
+                .contains("cannot be confined")
+        );
+    }
+}
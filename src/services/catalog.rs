@@ -0,0 +1,252 @@
+//! Element factory catalog read from the GStreamer registry.
+//!
+//! Mirrors what `gst-inspect-1.0` prints: factory metadata, pad templates
+//! and properties with their types, defaults and ranges. Properties are read
+//! from the element class, so describing an element never instantiates it.
+
+use gstreamer::glib;
+use gstreamer::glib::translate::IntoGlib;
+use gstreamer::prelude::*;
+
+// ---
+
+// Import through gateways
+use crate::models::{ElementDetails, ElementSummary, PadTemplateInfo, PropertyInfo};
+
+// ---
+
+use super::control::check_mutable;
+use super::introspection::value_to_json;
+
+/// Lists registered element factories in name order.
+///
+/// `klass` keeps factories whose classification contains every
+/// `/`-separated component of the filter, case-insensitively, so
+/// "Encoder/Video" matches "Codec/Encoder/Video".
+pub fn list_elements(klass: Option<&str>) -> Vec<ElementSummary> {
+    // ---
+    let filter: Vec<String> = klass
+        .unwrap_or_default()
+        .split('/')
+        .map(|component| component.trim().to_lowercase())
+        .filter(|component| !component.is_empty())
+        .collect();
+
+    let mut elements: Vec<ElementSummary> = gstreamer::ElementFactory::factories_with_type(
+        gstreamer::ElementFactoryType::ANY,
+        gstreamer::Rank::None,
+    )
+    .iter()
+    .filter(|factory| klass_matches(factory.klass(), &filter))
+    .map(summarize)
+    .collect();
+
+    elements.sort_by(|a, b| a.name.cmp(&b.name));
+    elements
+}
+
+/// Describes one element factory, or None when it is not registered.
+pub fn describe_element(name: &str) -> Option<ElementDetails> {
+    // ---
+    let factory = gstreamer::ElementFactory::find(name)?;
+    let factory = factory.load().ok()?;
+
+    let pad_templates = factory
+        .static_pad_templates()
+        .iter()
+        .map(|template| PadTemplateInfo {
+            name: template.name_template().to_string(),
+            direction: match template.direction() {
+                gstreamer::PadDirection::Src => "src",
+                gstreamer::PadDirection::Sink => "sink",
+                _ => "unknown",
+            }
+            .to_string(),
+            presence: match template.presence() {
+                gstreamer::PadPresence::Always => "always",
+                gstreamer::PadPresence::Sometimes => "sometimes",
+                _ => "request",
+            }
+            .to_string(),
+            caps: template.caps().to_string(),
+        })
+        .collect();
+
+    let mut properties: Vec<PropertyInfo> =
+        glib::object::Class::<gstreamer::Element>::from_type(factory.element_type())
+            .map(|class| {
+                class
+                    .list_properties()
+                    .iter()
+                    .filter(|pspec| !matches!(pspec.name(), "name" | "parent"))
+                    .map(describe_property)
+                    .collect()
+            })
+            .unwrap_or_default();
+    properties.sort_by(|a, b| a.name.cmp(&b.name));
+
+    Some(ElementDetails {
+        author: factory.author().to_string(),
+        summary: summarize(&factory),
+        pad_templates,
+        properties,
+    })
+}
+
+/// Checks that every filter component appears in a factory's klass.
+fn klass_matches(klass: &str, filter: &[String]) -> bool {
+    // ---
+    let components: Vec<String> = klass
+        .split('/')
+        .map(|component| component.trim().to_lowercase())
+        .collect();
+    filter
+        .iter()
+        .all(|wanted| components.iter().any(|component| component == wanted))
+}
+
+/// Collects a factory's registry metadata.
+fn summarize(factory: &gstreamer::ElementFactory) -> ElementSummary {
+    // ---
+    ElementSummary {
+        name: factory.name().to_string(),
+        long_name: factory.longname().to_string(),
+        klass: factory.klass().to_string(),
+        description: factory.description().to_string(),
+        rank: factory.rank().into_glib(),
+        plugin: factory.plugin_name().map(|name| name.to_string()),
+    }
+}
+
+/// Describes one property with its type, default and accepted values.
+fn describe_property(pspec: &glib::ParamSpec) -> PropertyInfo {
+    // ---
+    let flags = pspec.flags();
+    let type_ = pspec.value_type();
+    let (minimum, maximum) = numeric_range(pspec);
+
+    let values = if let Some(class) = glib::EnumClass::with_type(type_) {
+        Some(
+            class
+                .values()
+                .iter()
+                .map(|v| v.nick().to_string())
+                .collect(),
+        )
+    } else {
+        glib::FlagsClass::with_type(type_).map(|class| {
+            class
+                .values()
+                .iter()
+                .map(|v| v.nick().to_string())
+                .collect()
+        })
+    };
+
+    PropertyInfo {
+        name: pspec.name().to_string(),
+        description: pspec.blurb().unwrap_or_default().to_string(),
+        type_name: type_.name().to_string(),
+        readable: flags.contains(glib::ParamFlags::READABLE),
+        writable: flags.contains(glib::ParamFlags::WRITABLE)
+            && !flags.contains(glib::ParamFlags::CONSTRUCT_ONLY),
+        controllable_while_playing: check_mutable(pspec).is_ok(),
+        default: value_to_json(pspec.default_value()),
+        minimum,
+        maximum,
+        values,
+    }
+}
+
+/// Returns the declared range of numeric properties.
+fn numeric_range(
+    pspec: &glib::ParamSpec,
+) -> (Option<serde_json::Value>, Option<serde_json::Value>) {
+    // ---
+    macro_rules! range {
+        ($spec:ty) => {
+            if let Some(spec) = pspec.downcast_ref::<$spec>() {
+                return (Some(spec.minimum().into()), Some(spec.maximum().into()));
+            }
+        };
+    }
+
+    range!(glib::ParamSpecInt);
+    range!(glib::ParamSpecUInt);
+    range!(glib::ParamSpecInt64);
+    range!(glib::ParamSpecUInt64);
+    range!(glib::ParamSpecDouble);
+    if let Some(spec) = pspec.downcast_ref::<glib::ParamSpecFloat>() {
+        return (
+            Some(f64::from(spec.minimum()).into()),
+            Some(f64::from(spec.maximum()).into()),
+        );
+    }
+    (None, None)
+}
+
+#[cfg(test)]
+mod tests {
+    // ---
+
+    use super::*;
+
+    #[test]
+    fn test_list_elements_filters_by_klass() {
+        // ---
+        gstreamer::init().unwrap();
+
+        let sinks = list_elements(Some("sink"));
+        assert!(sinks.iter().any(|element| element.name == "fakesink"));
+        assert!(sinks.iter().all(|element| element.klass.contains("Sink")));
+        assert!(!sinks.iter().any(|element| element.name == "fakesrc"));
+
+        let all = list_elements(None);
+        assert!(all.len() > sinks.len());
+        assert!(all.windows(2).all(|pair| pair[0].name <= pair[1].name));
+
+        assert!(list_elements(Some("Codec/NoSuchKlass")).is_empty());
+    }
+
+    #[test]
+    fn test_describe_element() {
+        // ---
+        gstreamer::init().unwrap();
+
+        let details = describe_element("fakesrc").unwrap();
+        assert_eq!(details.summary.name, "fakesrc");
+        assert_eq!(details.summary.plugin.as_deref(), Some("coreelements"));
+        assert_eq!(details.pad_templates.len(), 1);
+        assert_eq!(details.pad_templates[0].direction, "src");
+        assert_eq!(details.pad_templates[0].presence, "always");
+
+        let num_buffers = details
+            .properties
+            .iter()
+            .find(|property| property.name == "num-buffers")
+            .unwrap();
+        assert_eq!(num_buffers.type_name, "gint");
+        assert_eq!(num_buffers.default, serde_json::json!(-1));
+        assert_eq!(num_buffers.minimum, Some(serde_json::json!(-1)));
+
+        let data = details
+            .properties
+            .iter()
+            .find(|property| property.name == "data")
+            .unwrap();
+        assert!(data
+            .values
+            .as_ref()
+            .unwrap()
+            .contains(&"allocate".to_string()));
+        assert!(!details.properties.iter().any(|p| p.name == "name"));
+
+        let tee = describe_element("tee").unwrap();
+        assert!(tee
+            .pad_templates
+            .iter()
+            .any(|template| template.name == "src_%u" && template.presence == "request"));
+
+        assert!(describe_element("no-such-element").is_none());
+    }
+}
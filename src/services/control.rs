@@ -0,0 +1,466 @@
+//! Live control of running pipelines.
+//!
+//! Operates on the GStreamer pipeline a worker is executing, so changes take
+//! effect immediately (e.g. lowering an encoder's bitrate or a volume).
+//!
+//! # Property Updates
+//!
+//! Each requested property is checked against the element's `ParamSpec`
+//! before anything is applied: it must exist, be writable after
+//! construction and changeable in `PLAYING`, and the JSON value must convert
+//! to the property's type within its declared range. Only when every
+//! property passes are the values set, so an update never half-applies.
+//!
+//! Properties naming files or directories (`location`, `playlist-location`,
+//! ...) are never changed on a running pipeline: many sinks such as
+//! `hlssink` and `multifilesink` reopen them on the fly, which would move
+//! their output out of the job directory and past its size limits.
+//!
+//! # Seeking
+//!
+//! Seeks and position queries go to the pipeline as a whole, which forwards
+//! them to its sinks. Negative rates play backwards from the target position
+//! to the start, where the elements support it. Whether a seek is possible
+//! depends on the pipeline: live sources and raw byte streams without a
+//! demuxer usually refuse time seeks.
+
+use gstreamer::glib;
+use gstreamer::prelude::*;
+use std::collections::BTreeMap;
+use std::fmt::Display;
+
+// ---
+
+// Import through gateway
+use crate::models::{ElementUpdateResponse, PipelinePosition, PropertyChange, SeekRequest};
+
+// ---
+
+// Import from sibling modules
+use super::introspection::value_to_json;
+use super::runtime::PipelineRuntime;
+
+/// Reasons a running pipeline could not be controlled.
+#[derive(Debug, PartialEq, Eq)]
+pub enum ControlError {
+    // ---
+    /// The pipeline is not executing on a worker
+    NotRunning,
+
+    /// No element with that name exists in the pipeline
+    ElementNotFound(String),
+
+    /// The request cannot be applied; the message explains why
+    Invalid(String),
+
+    /// The pipeline refused a valid request, e.g. a seek on a live source
+    Rejected(String),
+}
+
+/// How long a flushing seek may take to preroll at its new position
+const SEEK_TIMEOUT: gstreamer::ClockTime = gstreamer::ClockTime::from_seconds(5);
+
+/// Sets properties of an element in a running pipeline.
+///
+/// # Arguments
+/// * `runtime`     - Runtime holding the live pipeline
+/// * `pipeline_id` - Pipeline to update
+/// * `element`     - Element name, searched recursively through bins
+/// * `properties`  - New values keyed by property name
+///
+/// # Returns
+/// The old and new value of every property, in name order.
+///
+/// # Errors
+/// [`ControlError::Invalid`] names the first property that does not exist,
+/// cannot be changed while playing, or whose value has the wrong type or is
+/// out of range; nothing is changed in that case.
+pub fn update_element(
+    runtime: &PipelineRuntime,
+    pipeline_id: &str,
+    element: &str,
+    properties: &BTreeMap<String, serde_json::Value>,
+) -> Result<ElementUpdateResponse, ControlError> {
+    // ---
+    let pipeline = runtime
+        .live_pipeline(pipeline_id)
+        .ok_or(ControlError::NotRunning)?;
+    let target = pipeline
+        .by_name(element)
+        .ok_or_else(|| ControlError::ElementNotFound(element.to_string()))?;
+
+    if properties.is_empty() {
+        return Err(ControlError::Invalid("No properties given".to_string()));
+    }
+
+    let values = properties
+        .iter()
+        .map(|(name, json)| {
+            let pspec = target.find_property(name).ok_or_else(|| {
+                ControlError::Invalid(format!("Element '{element}' has no property '{name}'"))
+            })?;
+            check_mutable(&pspec).map_err(ControlError::Invalid)?;
+            let value = json_to_value(&pspec, json).map_err(ControlError::Invalid)?;
+            Ok((name, value))
+        })
+        .collect::<Result<Vec<_>, ControlError>>()?;
+
+    let changes = values
+        .into_iter()
+        .map(|(name, value)| {
+            let old_value = value_to_json(&target.property_value(name));
+            target.set_property_from_value(name, &value);
+            PropertyChange {
+                name: name.clone(),
+                old_value,
+                new_value: value_to_json(&target.property_value(name)),
+            }
+        })
+        .collect();
+
+    Ok(ElementUpdateResponse {
+        pipeline_id: pipeline_id.to_string(),
+        element: element.to_string(),
+        factory: target.factory().map(|factory| factory.name().to_string()),
+        properties: changes,
+    })
+}
+
+/// Reports the playback position of a running pipeline.
+///
+/// # Returns
+/// Position and duration in seconds when the pipeline knows them, the
+/// current playback rate and whether time seeks are possible.
+pub fn pipeline_position(
+    runtime: &PipelineRuntime,
+    pipeline_id: &str,
+) -> Result<PipelinePosition, ControlError> {
+    // ---
+    let pipeline = runtime
+        .live_pipeline(pipeline_id)
+        .ok_or(ControlError::NotRunning)?;
+    Ok(position_of(&pipeline, pipeline_id))
+}
+
+/// Seeks a running pipeline and/or changes its playback rate.
+///
+/// Without a `position` the pipeline seeks to where it currently is, which
+/// changes only the rate. Flushing seeks wait (up to five seconds) for the
+/// pipeline to preroll at the new position, so the returned position
+/// reflects the seek.
+///
+/// # Errors
+/// [`ControlError::Invalid`] for a zero or non-finite rate or a negative
+/// position; [`ControlError::Rejected`] when the pipeline refuses the seek.
+pub fn seek_pipeline(
+    runtime: &PipelineRuntime,
+    pipeline_id: &str,
+    request: &SeekRequest,
+) -> Result<PipelinePosition, ControlError> {
+    // ---
+    let pipeline = runtime
+        .live_pipeline(pipeline_id)
+        .ok_or(ControlError::NotRunning)?;
+
+    let rate = request.rate.unwrap_or(1.0);
+    if rate == 0.0 || !rate.is_finite() {
+        return Err(ControlError::Invalid(format!(
+            "Rate must be a non-zero number, got {rate}"
+        )));
+    }
+    let target = match request.position {
+        Some(seconds) => gstreamer::ClockTime::try_from_seconds_f64(seconds).map_err(|_| {
+            ControlError::Invalid(format!(
+                "Position must be a non-negative number of seconds, got {seconds}"
+            ))
+        })?,
+        None => pipeline
+            .query_position::<gstreamer::ClockTime>()
+            .ok_or_else(|| {
+                ControlError::Rejected("The pipeline does not know its position yet".to_string())
+            })?,
+    };
+
+    let mut flags = gstreamer::SeekFlags::empty();
+    if request.flush.unwrap_or(true) {
+        flags |= gstreamer::SeekFlags::FLUSH;
+    }
+    if request.accurate {
+        flags |= gstreamer::SeekFlags::ACCURATE;
+    }
+    if request.key_unit {
+        flags |= gstreamer::SeekFlags::KEY_UNIT;
+    }
+
+    // Reverse playback runs from the target back to the start
+    let seeked = if rate > 0.0 {
+        pipeline.seek(
+            rate,
+            flags,
+            gstreamer::SeekType::Set,
+            Some(target),
+            gstreamer::SeekType::None,
+            gstreamer::ClockTime::NONE,
+        )
+    } else {
+        pipeline.seek(
+            rate,
+            flags,
+            gstreamer::SeekType::Set,
+            Some(gstreamer::ClockTime::ZERO),
+            gstreamer::SeekType::Set,
+            Some(target),
+        )
+    };
+    seeked.map_err(|_| {
+        ControlError::Rejected(format!(
+            "The pipeline refused to seek to {target} at rate {rate}"
+        ))
+    })?;
+
+    if flags.contains(gstreamer::SeekFlags::FLUSH) {
+        let _ = pipeline.state(SEEK_TIMEOUT);
+    }
+    Ok(position_of(&pipeline, pipeline_id))
+}
+
+/// Queries position, duration, rate and seekability of a pipeline.
+fn position_of(pipeline: &gstreamer::Pipeline, pipeline_id: &str) -> PipelinePosition {
+    // ---
+    let seconds = |time: gstreamer::ClockTime| time.nseconds() as f64 / 1e9;
+
+    let mut segment = gstreamer::query::Segment::new(gstreamer::Format::Time);
+    let rate = if pipeline.query(&mut segment) {
+        segment.result().0
+    } else {
+        1.0
+    };
+
+    let mut seeking = gstreamer::query::Seeking::new(gstreamer::Format::Time);
+    let seekable = pipeline.query(&mut seeking) && seeking.result().0;
+
+    PipelinePosition {
+        pipeline_id: pipeline_id.to_string(),
+        position: pipeline
+            .query_position::<gstreamer::ClockTime>()
+            .map(seconds),
+        duration: pipeline
+            .query_duration::<gstreamer::ClockTime>()
+            .map(seconds),
+        rate,
+        seekable,
+    }
+}
+
+/// Checks that a property may be set on a playing element.
+pub(super) fn check_mutable(pspec: &glib::ParamSpec) -> Result<(), String> {
+    // ---
+    let flags = pspec.flags();
+    if !flags.contains(glib::ParamFlags::WRITABLE)
+        || flags.contains(glib::ParamFlags::CONSTRUCT_ONLY)
+    {
+        return Err(format!("Property '{}' is not writable", pspec.name()));
+    }
+
+    if is_path_property(pspec) {
+        return Err(format!(
+            "Property '{}' names a file and cannot be changed on a running pipeline",
+            pspec.name()
+        ));
+    }
+
+    // Properties without any mutability flag may be changed in any state
+    let restricted = gstreamer::PARAM_FLAG_MUTABLE_READY | gstreamer::PARAM_FLAG_MUTABLE_PAUSED;
+    if flags.intersects(restricted) && !flags.contains(gstreamer::PARAM_FLAG_MUTABLE_PLAYING) {
+        return Err(format!(
+            "Property '{}' cannot be changed while the pipeline is playing",
+            pspec.name()
+        ));
+    }
+
+    Ok(())
+}
+
+/// Returns true for string properties holding a file or directory path,
+/// judged by their name (`location`, `playlist-location`, `filename`,
+/// `output-path`, `tmp-dir`, ...).
+pub(super) fn is_path_property(pspec: &glib::ParamSpec) -> bool {
+    // ---
+    let name = pspec.name();
+    pspec.value_type() == glib::Type::STRING
+        && (name.ends_with("location")
+            || name.contains("file")
+            || name.contains("path")
+            || name.ends_with("dir")
+            || name.contains("directory"))
+}
+
+/// Converts a JSON value to the type of a property, enforcing the range
+/// declared by numeric `ParamSpec`s.
+pub(super) fn json_to_value(
+    pspec: &glib::ParamSpec,
+    json: &serde_json::Value,
+) -> Result<glib::Value, String> {
+    // ---
+    let name = pspec.name();
+    let type_ = pspec.value_type();
+    let expected = |kind: &str| format!("Property '{name}' expects {kind}, got {json}");
+
+    match type_ {
+        glib::Type::BOOL => json
+            .as_bool()
+            .map(|v| v.to_value())
+            .ok_or_else(|| expected("a boolean")),
+        glib::Type::I32 => {
+            let spec = pspec.downcast_ref::<glib::ParamSpecInt>().unwrap();
+            let v = json.as_i64().ok_or_else(|| expected("an integer"))?;
+            in_range(name, v, spec.minimum().into(), spec.maximum().into())?;
+            Ok((v as i32).to_value())
+        }
+        glib::Type::U32 => {
+            let spec = pspec.downcast_ref::<glib::ParamSpecUInt>().unwrap();
+            let v = json
+                .as_u64()
+                .ok_or_else(|| expected("an unsigned integer"))?;
+            in_range(name, v, spec.minimum().into(), spec.maximum().into())?;
+            Ok((v as u32).to_value())
+        }
+        glib::Type::I64 => {
+            let spec = pspec.downcast_ref::<glib::ParamSpecInt64>().unwrap();
+            let v = json.as_i64().ok_or_else(|| expected("an integer"))?;
+            in_range(name, v, spec.minimum(), spec.maximum())?;
+            Ok(v.to_value())
+        }
+        glib::Type::U64 => {
+            let spec = pspec.downcast_ref::<glib::ParamSpecUInt64>().unwrap();
+            let v = json
+                .as_u64()
+                .ok_or_else(|| expected("an unsigned integer"))?;
+            in_range(name, v, spec.minimum(), spec.maximum())?;
+            Ok(v.to_value())
+        }
+        glib::Type::F32 => {
+            let spec = pspec.downcast_ref::<glib::ParamSpecFloat>().unwrap();
+            let v = json.as_f64().ok_or_else(|| expected("a number"))?;
+            in_range(name, v, spec.minimum().into(), spec.maximum().into())?;
+            Ok((v as f32).to_value())
+        }
+        glib::Type::F64 => {
+            let spec = pspec.downcast_ref::<glib::ParamSpecDouble>().unwrap();
+            let v = json.as_f64().ok_or_else(|| expected("a number"))?;
+            in_range(name, v, spec.minimum(), spec.maximum())?;
+            Ok(v.to_value())
+        }
+        glib::Type::STRING => match json {
+            serde_json::Value::String(v) => Ok(v.to_value()),
+            serde_json::Value::Null => Ok(None::<String>.to_value()),
+            _ => Err(expected("a string")),
+        },
+        _ => {
+            // Enums, flags, caps and other types in their serialized form
+            let text = match json {
+                serde_json::Value::String(v) => v.clone(),
+                serde_json::Value::Number(v) => v.to_string(),
+                _ => return Err(expected(type_.name())),
+            };
+            glib::Value::deserialize(&text, type_).map_err(|_| {
+                format!(
+                    "Invalid {} value '{text}' for property '{name}'",
+                    type_.name()
+                )
+            })
+        }
+    }
+}
+
+/// Checks that `value` lies within a property's declared range.
+fn in_range<T: PartialOrd + Display>(name: &str, value: T, min: T, max: T) -> Result<(), String> {
+    // ---
+    if value < min || value > max {
+        return Err(format!(
+            "Value {value} is out of range for property '{name}' ({min} to {max})"
+        ));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    // ---
+
+    use super::*;
+
+    fn pspec(factory: &str, property: &str) -> glib::ParamSpec {
+        // ---
+        gstreamer::init().unwrap();
+        gstreamer::ElementFactory::make(factory)
+            .build()
+            .unwrap()
+            .find_property(property)
+            .unwrap()
+    }
+
+    #[test]
+    fn test_json_to_value_checks_type_and_range() {
+        // ---
+        let probability = pspec("identity", "drop-probability");
+        let value = json_to_value(&probability, &serde_json::json!(0.25)).unwrap();
+        assert_eq!(value.get::<f32>().unwrap(), 0.25);
+        assert!(json_to_value(&probability, &serde_json::json!(1.5))
+            .unwrap_err()
+            .contains("out of range"));
+        assert!(json_to_value(&probability, &serde_json::json!("high"))
+            .unwrap_err()
+            .contains("expects a number"));
+
+        let num_buffers = pspec("fakesrc", "num-buffers");
+        assert!(json_to_value(&num_buffers, &serde_json::json!(-2)).is_err());
+        assert_eq!(
+            json_to_value(&num_buffers, &serde_json::json!(10))
+                .unwrap()
+                .get::<i32>()
+                .unwrap(),
+            10
+        );
+
+        let silent = pspec("identity", "silent");
+        assert!(json_to_value(&silent, &serde_json::json!(1)).is_err());
+        assert!(json_to_value(&silent, &serde_json::json!(true)).is_ok());
+    }
+
+    #[test]
+    fn test_json_to_value_parses_enums_by_nick() {
+        // ---
+        let filltype = pspec("fakesrc", "filltype");
+        let value = json_to_value(&filltype, &serde_json::json!("zero")).unwrap();
+        assert_eq!(value_to_json(&value), serde_json::json!("zero"));
+        assert!(json_to_value(&filltype, &serde_json::json!("bogus")).is_err());
+    }
+
+    #[test]
+    fn test_check_mutable_rejects_construct_only() {
+        // ---
+        assert!(check_mutable(&pspec("identity", "drop-probability")).is_ok());
+        assert!(check_mutable(&pspec("fakesrc", "last-message"))
+            .unwrap_err()
+            .contains("not writable"));
+    }
+
+    #[test]
+    fn test_check_mutable_rejects_paths() {
+        // ---
+        assert!(check_mutable(&pspec("filesink", "location"))
+            .unwrap_err()
+            .contains("names a file"));
+
+        // hlssink and multifilesink declare their paths without mutability flags
+        for name in ["location", "playlist-location"] {
+            let spec = glib::ParamSpecString::builder(name).build();
+            assert!(check_mutable(&spec).is_err(), "accepted {name}");
+        }
+        let playlist_root = glib::ParamSpecString::builder("playlist-root").build();
+        assert!(check_mutable(&playlist_root).is_ok());
+        let max_file_size = glib::ParamSpecUInt64::builder("max-file-size").build();
+        assert!(check_mutable(&max_file_size).is_ok());
+    }
+}
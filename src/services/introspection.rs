@@ -0,0 +1,435 @@
+//! Inspection of pipeline graphs for debugging custom pipelines.
+//!
+//! A pipeline record only holds its launch string; to see which elements
+//! were created and which caps they negotiated, the pipeline has to be
+//! instantiated. Depending on the record's state, the graph comes from:
+//!
+//! - **live**     : The running pipeline, with the caps it negotiated
+//! - **prerolled**: A fresh instance of a pipeline that has not run yet,
+//!   brought to `PAUSED` so caps are negotiated, then discarded
+//! - **parsed**   : A fresh instance left in `NULL`, for finished pipelines;
+//!   prerolling those would reopen their sources and truncate their outputs
+//!
+//! # Graph Export
+//!
+//! Graphs are exported with GStreamer's `debug_bin_to_dot_data` as Graphviz
+//! DOT, and rendered to SVG by a local `dot` binary when one is installed.
+//!
+//! # Topology
+//!
+//! [`pipeline_topology`] describes the same instance as JSON (elements with
+//! their factory and properties, pads with their peer and current caps, and
+//! links) for clients that draw the graph themselves.
+
+use gstreamer::glib;
+use gstreamer::prelude::*;
+use gstreamer::DebugGraphDetails;
+use std::collections::BTreeMap;
+use std::process::Stdio;
+use std::time::Duration;
+use tokio::io::AsyncWriteExt;
+
+// ---
+
+// Import through gateway
+use crate::models::{
+    PipelineInfo, PipelineState, PipelineTopology, TopologyElement, TopologyLink, TopologyPad,
+};
+
+// ---
+
+// Import from sibling modules
+use super::builder::build_pipeline;
+use super::runtime::PipelineRuntime;
+
+/// How long a pipeline may take to preroll before its graph is taken anyway
+const PREROLL_TIMEOUT: gstreamer::ClockTime = gstreamer::ClockTime::from_seconds(5);
+
+/// How long the `dot` renderer may run
+const RENDER_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Detail level names accepted by [`parse_graph_details`].
+pub const GRAPH_DETAIL_NAMES: [&str; 7] = [
+    "media-type",
+    "caps-details",
+    "non-default-params",
+    "states",
+    "full-params",
+    "all",
+    "verbose",
+];
+
+/// Where an inspected pipeline instance came from.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum GraphSource {
+    // ---
+    /// The pipeline currently executing on a worker
+    Live,
+
+    /// A fresh instance prerolled to `PAUSED`
+    Prerolled,
+
+    /// A fresh instance that was never started
+    Parsed,
+}
+
+impl GraphSource {
+    // ---
+
+    /// Returns the lowercase name reported to clients (e.g. "live").
+    pub fn name(&self) -> &'static str {
+        // ---
+        match self {
+            Self::Live => "live",
+            Self::Prerolled => "prerolled",
+            Self::Parsed => "parsed",
+        }
+    }
+}
+
+/// Output format of a pipeline graph.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum GraphFormat {
+    // ---
+    /// Graphviz DOT source
+    Dot,
+
+    /// SVG rendered by the local Graphviz `dot` binary
+    Svg,
+}
+
+impl GraphFormat {
+    // ---
+
+    /// Parses a `format` query value ("dot" or "svg").
+    pub fn parse(value: &str) -> Option<Self> {
+        // ---
+        match value.to_ascii_lowercase().as_str() {
+            "dot" => Some(Self::Dot),
+            "svg" => Some(Self::Svg),
+            _ => None,
+        }
+    }
+
+    /// Returns the `Content-Type` of graphs in this format.
+    pub fn content_type(&self) -> &'static str {
+        // ---
+        match self {
+            Self::Dot => "text/vnd.graphviz; charset=utf-8",
+            Self::Svg => "image/svg+xml",
+        }
+    }
+}
+
+/// Reasons a DOT graph could not be rendered to SVG.
+#[derive(Debug)]
+pub enum RenderError {
+    // ---
+    /// No Graphviz `dot` binary is installed
+    Unavailable,
+
+    /// `dot` failed or timed out; the message explains why
+    Failed(String),
+}
+
+/// Parses a comma-separated list of detail level names into graph flags.
+///
+/// # Errors
+/// Returns the first unknown name.
+///
+/// # Example
+/// ```rust
+/// let details = parse_graph_details("media-type,states")?;
+/// ```
+pub fn parse_graph_details(value: &str) -> Result<DebugGraphDetails, String> {
+    // ---
+    value
+        .split(',')
+        .map(str::trim)
+        .filter(|name| !name.is_empty())
+        .try_fold(DebugGraphDetails::empty(), |details, name| {
+            let flag = match name.to_ascii_lowercase().as_str() {
+                "media-type" => DebugGraphDetails::MEDIA_TYPE,
+                "caps-details" => DebugGraphDetails::CAPS_DETAILS,
+                "non-default-params" => DebugGraphDetails::NON_DEFAULT_PARAMS,
+                "states" => DebugGraphDetails::STATES,
+                "full-params" => DebugGraphDetails::FULL_PARAMS,
+                "all" => DebugGraphDetails::ALL,
+                "verbose" => DebugGraphDetails::VERBOSE,
+                _ => return Err(name.to_string()),
+            };
+            Ok(details | flag)
+        })
+}
+
+/// Exports a pipeline's graph as Graphviz DOT.
+///
+/// Blocks while a fresh instance prerolls; call from a blocking thread.
+///
+/// # Returns
+/// The DOT source and where the inspected instance came from.
+pub fn pipeline_graph(
+    runtime: &PipelineRuntime,
+    pipeline: &PipelineInfo,
+    details: DebugGraphDetails,
+) -> anyhow::Result<(String, GraphSource)> {
+    // ---
+    inspect_pipeline(runtime, pipeline, |instance| {
+        gstreamer::debug_bin_to_dot_data(instance, details).to_string()
+    })
+}
+
+/// Describes a pipeline's elements, pads and links as JSON.
+///
+/// Blocks while a fresh instance prerolls; call from a blocking thread.
+pub fn pipeline_topology(
+    runtime: &PipelineRuntime,
+    pipeline: &PipelineInfo,
+) -> anyhow::Result<PipelineTopology> {
+    // ---
+    let ((elements, links), source) = inspect_pipeline(runtime, pipeline, |instance| {
+        // Bins keep their most recently added child first
+        let children: Vec<_> = instance.children().into_iter().rev().collect();
+
+        let elements = children.iter().map(describe_element).collect();
+        let links = children
+            .iter()
+            .flat_map(|element| element.src_pads())
+            .filter_map(|pad| {
+                let peer = pad.peer()?;
+                Some(TopologyLink {
+                    from: pad_path(&pad),
+                    to: pad_path(&peer),
+                    caps: pad.current_caps().map(|caps| caps.to_string()),
+                })
+            })
+            .collect();
+
+        (elements, links)
+    })?;
+
+    Ok(PipelineTopology {
+        pipeline_id: pipeline.id.clone(),
+        source: source.name().to_string(),
+        elements,
+        links,
+    })
+}
+
+/// Renders DOT source to SVG with the local Graphviz `dot` binary.
+pub async fn render_svg(dot: &str) -> Result<Vec<u8>, RenderError> {
+    // ---
+    let mut child = tokio::process::Command::new("dot")
+        .arg("-Tsvg")
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true)
+        .spawn()
+        .map_err(|e| match e.kind() {
+            std::io::ErrorKind::NotFound => RenderError::Unavailable,
+            _ => RenderError::Failed(format!("Failed to run dot: {e}")),
+        })?;
+
+    let mut stdin = child.stdin.take().expect("stdin is piped");
+    let source = dot.as_bytes().to_vec();
+    tokio::spawn(async move {
+        let _ = stdin.write_all(&source).await;
+    });
+
+    let output = tokio::time::timeout(RENDER_TIMEOUT, child.wait_with_output())
+        .await
+        .map_err(|_| RenderError::Failed("dot timed out".to_string()))?
+        .map_err(|e| RenderError::Failed(format!("Failed to run dot: {e}")))?;
+
+    if !output.status.success() {
+        return Err(RenderError::Failed(
+            String::from_utf8_lossy(&output.stderr).trim().to_string(),
+        ));
+    }
+    Ok(output.stdout)
+}
+
+/// Runs `inspect` on the live pipeline, or on a fresh instance prepared
+/// according to the record's state.
+///
+/// Blocks while a fresh instance prerolls.
+pub(super) fn inspect_pipeline<T>(
+    runtime: &PipelineRuntime,
+    pipeline: &PipelineInfo,
+    inspect: impl FnOnce(&gstreamer::Pipeline) -> T,
+) -> anyhow::Result<(T, GraphSource)> {
+    // ---
+    if let Some(live) = runtime.live_pipeline(&pipeline.id) {
+        return Ok((inspect(&live), GraphSource::Live));
+    }
+
+    let instance = build_pipeline(pipeline, &runtime.job_dir(pipeline))?;
+
+    if !matches!(pipeline.state, PipelineState::Created) {
+        return Ok((inspect(&instance), GraphSource::Parsed));
+    }
+
+    // Caps are only negotiated once data flows; a failed preroll still
+    // shows the elements and whatever was negotiated before the failure
+    let _ = instance.set_state(gstreamer::State::Paused);
+    let _ = instance.state(PREROLL_TIMEOUT);
+    let result = inspect(&instance);
+    let _ = instance.set_state(gstreamer::State::Null);
+
+    Ok((result, GraphSource::Prerolled))
+}
+
+/// Describes one element with its readable properties and pads.
+fn describe_element(element: &gstreamer::Element) -> TopologyElement {
+    // ---
+    let properties = element
+        .list_properties()
+        .iter()
+        .filter(|pspec| pspec.flags().contains(glib::ParamFlags::READABLE))
+        .filter(|pspec| !matches!(pspec.name(), "name" | "parent"))
+        .map(|pspec| {
+            let value = element.property_value(pspec.name());
+            (pspec.name().to_string(), value_to_json(&value))
+        })
+        .collect::<BTreeMap<_, _>>();
+
+    let pads = element
+        .pads()
+        .iter()
+        .map(|pad| TopologyPad {
+            name: pad.name().to_string(),
+            direction: match pad.direction() {
+                gstreamer::PadDirection::Src => "src",
+                gstreamer::PadDirection::Sink => "sink",
+                _ => "unknown",
+            }
+            .to_string(),
+            peer: pad.peer().map(|peer| pad_path(&peer)),
+            caps: pad.current_caps().map(|caps| caps.to_string()),
+        })
+        .collect();
+
+    TopologyElement {
+        name: element.name().to_string(),
+        factory: element.factory().map(|factory| factory.name().to_string()),
+        properties,
+        pads,
+    }
+}
+
+/// Returns a pad's "element.pad" path.
+fn pad_path(pad: &gstreamer::Pad) -> String {
+    // ---
+    let parent = pad
+        .parent()
+        .map(|parent| parent.name().to_string())
+        .unwrap_or_default();
+    format!("{}.{}", parent, pad.name())
+}
+
+/// Converts a property value to JSON: booleans, numbers and strings as
+/// themselves, enums and flags by nick, caps and structures in their string
+/// form, and anything else (objects, buffers, samples) as null.
+pub(super) fn value_to_json(value: &glib::Value) -> serde_json::Value {
+    // ---
+    if let Ok(v) = value.get::<bool>() {
+        return v.into();
+    }
+    if let Ok(v) = value.get::<i32>() {
+        return v.into();
+    }
+    if let Ok(v) = value.get::<u32>() {
+        return v.into();
+    }
+    if let Ok(v) = value.get::<i64>() {
+        return v.into();
+    }
+    if let Ok(v) = value.get::<u64>() {
+        return v.into();
+    }
+    if let Ok(v) = value.get::<f32>() {
+        return f64::from(v).into();
+    }
+    if let Ok(v) = value.get::<f64>() {
+        return v.into();
+    }
+    if let Ok(v) = value.get::<Option<String>>() {
+        return v.into();
+    }
+    if let Ok(v) = value.get::<Option<gstreamer::Caps>>() {
+        return v.map(|caps| caps.to_string()).into();
+    }
+    if let Ok(v) = value.get::<Option<gstreamer::Structure>>() {
+        return v.map(|structure| structure.to_string()).into();
+    }
+
+    let type_ = value.type_();
+    if type_.is_a(glib::Type::ENUM) || type_.is_a(glib::Type::FLAGS) {
+        if let Ok(text) = value.serialize() {
+            return text.to_string().into();
+        }
+    }
+    serde_json::Value::Null
+}
+
+#[cfg(test)]
+mod tests {
+    // ---
+
+    use super::*;
+
+    #[test]
+    fn test_parse_graph_details() {
+        // ---
+        assert_eq!(parse_graph_details("all"), Ok(DebugGraphDetails::ALL));
+        assert_eq!(
+            parse_graph_details("media-type, states"),
+            Ok(DebugGraphDetails::MEDIA_TYPE | DebugGraphDetails::STATES)
+        );
+        assert_eq!(parse_graph_details(""), Ok(DebugGraphDetails::empty()));
+        assert_eq!(
+            parse_graph_details("states,bogus"),
+            Err("bogus".to_string())
+        );
+    }
+
+    #[test]
+    fn test_value_to_json() {
+        // ---
+        gstreamer::init().unwrap();
+
+        assert_eq!(value_to_json(&true.to_value()), serde_json::json!(true));
+        assert_eq!(value_to_json(&42u64.to_value()), serde_json::json!(42));
+        assert_eq!(value_to_json(&0.5f64.to_value()), serde_json::json!(0.5));
+        assert_eq!(value_to_json(&"abc".to_value()), serde_json::json!("abc"));
+        assert_eq!(
+            value_to_json(&None::<String>.to_value()),
+            serde_json::Value::Null
+        );
+
+        let caps = gstreamer::Caps::builder("audio/x-raw")
+            .field("rate", 8000i32)
+            .build();
+        assert_eq!(
+            value_to_json(&caps.to_value()),
+            serde_json::json!("audio/x-raw, rate=(int)8000")
+        );
+        assert_eq!(
+            value_to_json(&gstreamer::State::Paused.to_value()),
+            serde_json::json!("paused")
+        );
+        assert_eq!(
+            value_to_json(&gstreamer::Buffer::new().to_value()),
+            serde_json::Value::Null
+        );
+    }
+
+    #[test]
+    fn test_graph_format_parse() {
+        // ---
+        assert_eq!(GraphFormat::parse("DOT"), Some(GraphFormat::Dot));
+        assert_eq!(GraphFormat::parse("svg"), Some(GraphFormat::Svg));
+        assert_eq!(GraphFormat::parse("png"), None);
+    }
+}
@@ -0,0 +1,313 @@
+//! JWT bearer token verification.
+//!
+//! Multi-team deployments usually already issue JWTs from an identity
+//! provider. Tokens are accepted in the `Authorization: Bearer` header and
+//! verified locally, without calling the provider:
+//!
+//! - **HS256**: Signed with a shared secret (`--jwt-secret`)
+//! - **RS256**: Signed with a private key whose public half is listed in a
+//!   local JWKS file (`--jwks`), selected by the token's `kid`
+//!
+//! # Claims
+//!
+//! - `sub` (required): The authenticated subject
+//! - `tenant` (optional): Owner of the pipelines the subject creates;
+//!   defaults to `sub`, so tokens without a tenant are isolated per subject
+//! - `scope` (optional): Space-separated scopes, e.g. `"pipelines:read analyze"`;
+//!   names the service does not know are ignored
+//! - `exp` (required), plus `iss` / `aud` when `--jwt-issuer` / `--jwt-audience` are set
+
+use anyhow::Context;
+use jsonwebtoken::jwk::JwkSet;
+use jsonwebtoken::{Algorithm, DecodingKey, Validation};
+use serde::Deserialize;
+use std::path::PathBuf;
+
+// ---
+
+// Import through gateway
+use crate::models::{Caller, Scope};
+
+// ---
+
+// Import from sibling module
+use super::auth::is_valid_owner;
+
+/// Settings for [`JwtVerifier::new`].
+#[derive(Debug, Default)]
+pub struct JwtConfig {
+    // ---
+    /// Shared secret accepting HS256 tokens
+    pub secret: Option<String>,
+
+    /// JWKS file of RSA public keys accepting RS256 tokens
+    pub jwks: Option<PathBuf>,
+
+    /// Required `iss` claim
+    pub issuer: Option<String>,
+
+    /// Required `aud` claim
+    pub audience: Option<String>,
+}
+
+/// Claims read from verified tokens.
+#[derive(Deserialize)]
+struct Claims {
+    // ---
+    sub: String,
+    tenant: Option<String>,
+    #[serde(default)]
+    scope: String,
+}
+
+/// Verifies bearer tokens against the configured keys.
+pub struct JwtVerifier {
+    // ---
+    /// Key for HS256 tokens
+    secret: Option<DecodingKey>,
+
+    /// RS256 keys from the JWKS file, with their key IDs
+    rsa_keys: Vec<(Option<String>, DecodingKey)>,
+
+    issuer: Option<String>,
+    audience: Option<String>,
+}
+
+impl JwtVerifier {
+    // ---
+
+    /// Builds a verifier, reading the JWKS file if one is configured.
+    ///
+    /// # Returns
+    /// * `Ok(None)` - Neither a secret nor a JWKS file is configured
+    /// * `Ok(Some(JwtVerifier))` - Verifier accepting the configured algorithms
+    ///
+    /// # Errors
+    /// Fails if the JWKS file cannot be read, or holds no usable RSA key.
+    pub fn new(config: JwtConfig) -> anyhow::Result<Option<Self>> {
+        // ---
+        if config.secret.is_none() && config.jwks.is_none() {
+            return Ok(None);
+        }
+
+        let mut rsa_keys = Vec::new();
+        if let Some(path) = &config.jwks {
+            let contents = std::fs::read(path)
+                .with_context(|| format!("Failed to read JWKS file {}", path.display()))?;
+            let set: JwkSet = serde_json::from_slice(&contents)
+                .with_context(|| format!("Invalid JWKS file {}", path.display()))?;
+
+            for jwk in &set.keys {
+                let key = DecodingKey::from_jwk(jwk)
+                    .with_context(|| format!("Unsupported key in JWKS file {}", path.display()))?;
+                rsa_keys.push((jwk.common.key_id.clone(), key));
+            }
+            anyhow::ensure!(
+                !rsa_keys.is_empty(),
+                "JWKS file {} contains no keys",
+                path.display()
+            );
+        }
+
+        Ok(Some(Self {
+            secret: config
+                .secret
+                .map(|secret| DecodingKey::from_secret(secret.as_bytes())),
+            rsa_keys,
+            issuer: config.issuer,
+            audience: config.audience,
+        }))
+    }
+
+    /// Verifies `token` and returns the caller it identifies.
+    ///
+    /// # Errors
+    /// Returns a client-facing reason when the signature, algorithm, expiry,
+    /// issuer or audience is wrong, or the tenant is not a valid owner name.
+    pub fn verify(&self, token: &str) -> Result<Caller, String> {
+        // ---
+        let header =
+            jsonwebtoken::decode_header(token).map_err(|e| format!("Malformed token: {e}"))?;
+
+        let key = match header.alg {
+            Algorithm::HS256 => self
+                .secret
+                .as_ref()
+                .ok_or("HS256 tokens are not accepted")?,
+            Algorithm::RS256 => self.rsa_key(header.kid.as_deref())?,
+            other => return Err(format!("{other:?} tokens are not accepted")),
+        };
+
+        let mut validation = Validation::new(header.alg);
+        validation.set_required_spec_claims(&["exp", "sub"]);
+        if let Some(issuer) = &self.issuer {
+            validation.set_issuer(&[issuer]);
+        }
+        match &self.audience {
+            Some(audience) => validation.set_audience(&[audience]),
+            None => validation.validate_aud = false,
+        }
+
+        let claims = jsonwebtoken::decode::<Claims>(token, key, &validation)
+            .map_err(|e| format!("Token rejected: {e}"))?
+            .claims;
+
+        let tenant = claims.tenant.unwrap_or_else(|| claims.sub.clone());
+        if !is_valid_owner(&tenant) {
+            return Err(format!("Tenant '{tenant}' is not a valid owner name"));
+        }
+
+        Ok(Caller::Token {
+            subject: claims.sub,
+            tenant,
+            scopes: claims
+                .scope
+                .split_whitespace()
+                .filter_map(Scope::from_name)
+                .collect(),
+        })
+    }
+
+    /// Selects the RS256 key named by a token's `kid`.
+    ///
+    /// Tokens without a `kid` are accepted only when the JWKS holds one key.
+    fn rsa_key(&self, kid: Option<&str>) -> Result<&DecodingKey, String> {
+        // ---
+        match (kid, self.rsa_keys.as_slice()) {
+            (_, []) => Err("RS256 tokens are not accepted".to_string()),
+            (None, [(_, key)]) => Ok(key),
+            (None, _) => Err("Token has no kid to select a key".to_string()),
+            (Some(kid), keys) => keys
+                .iter()
+                .find(|(id, _)| id.as_deref() == Some(kid))
+                .map(|(_, key)| key)
+                .ok_or_else(|| format!("No key with kid '{kid}'")),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    // ---
+
+    use super::*;
+    use jsonwebtoken::{EncodingKey, Header};
+
+    const PRIVATE_KEY: &[u8] = include_bytes!("../../tests/fixtures/jwt_rs256_private.pem");
+    const JWKS: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures/jwks.json");
+
+    fn token(header: Header, key: &EncodingKey, claims: serde_json::Value) -> String {
+        // ---
+        jsonwebtoken::encode(&header, &claims, key).unwrap()
+    }
+
+    fn expires() -> i64 {
+        // ---
+        chrono::Utc::now().timestamp() + 300
+    }
+
+    #[test]
+    fn test_hs256_tokens_map_tenant_and_scopes() {
+        // ---
+        let verifier = JwtVerifier::new(JwtConfig {
+            secret: Some("shared".to_string()),
+            audience: Some("media".to_string()),
+            ..Default::default()
+        })
+        .unwrap()
+        .unwrap();
+        let key = EncodingKey::from_secret(b"shared");
+
+        let valid = token(
+            Header::default(),
+            &key,
+            serde_json::json!({
+                "sub": "alice", "tenant": "acme", "aud": "media", "exp": expires(),
+                "scope": "pipelines:read openid analyze"
+            }),
+        );
+        let caller = verifier.verify(&valid).unwrap();
+        assert_eq!(caller.owner().as_deref(), Some("tenant:acme"));
+        assert!(caller.has_scope(Scope::PipelinesRead));
+        assert!(caller.has_scope(Scope::Analyze));
+        assert!(!caller.has_scope(Scope::PipelinesWrite));
+
+        let no_tenant = token(
+            Header::default(),
+            &key,
+            serde_json::json!({ "sub": "bob", "aud": "media", "exp": expires() }),
+        );
+        let caller = verifier.verify(&no_tenant).unwrap();
+        assert_eq!(caller.owner().as_deref(), Some("tenant:bob"));
+
+        let wrong_audience = token(
+            Header::default(),
+            &key,
+            serde_json::json!({ "sub": "bob", "aud": "other", "exp": expires() }),
+        );
+        assert!(verifier.verify(&wrong_audience).is_err());
+
+        let expired = token(
+            Header::default(),
+            &key,
+            serde_json::json!({ "sub": "bob", "aud": "media", "exp": 1000 }),
+        );
+        assert!(verifier.verify(&expired).is_err());
+
+        let traversal = token(
+            Header::default(),
+            &key,
+            serde_json::json!({ "sub": "x", "tenant": "../etc", "aud": "media", "exp": expires() }),
+        );
+        assert!(verifier.verify(&traversal).is_err());
+
+        let forged = token(
+            Header::default(),
+            &EncodingKey::from_secret(b"guess"),
+            serde_json::json!({ "sub": "alice", "aud": "media", "exp": expires() }),
+        );
+        assert!(verifier.verify(&forged).is_err());
+    }
+
+    #[test]
+    fn test_rs256_tokens_verified_against_jwks() {
+        // ---
+        let verifier = JwtVerifier::new(JwtConfig {
+            jwks: Some(PathBuf::from(JWKS)),
+            issuer: Some("https://idp.example.com".to_string()),
+            ..Default::default()
+        })
+        .unwrap()
+        .unwrap();
+        let key = EncodingKey::from_rsa_pem(PRIVATE_KEY).unwrap();
+        let claims = serde_json::json!({
+            "sub": "alice", "tenant": "acme", "iss": "https://idp.example.com",
+            "exp": expires(), "scope": "pipelines:write"
+        });
+
+        let mut header = Header::new(Algorithm::RS256);
+        header.kid = Some("test-rs256".to_string());
+        let caller = verifier
+            .verify(&token(header, &key, claims.clone()))
+            .unwrap();
+        assert!(caller.has_scope(Scope::PipelinesWrite));
+
+        // A single-key JWKS also accepts tokens without a kid
+        let header = Header::new(Algorithm::RS256);
+        assert!(verifier
+            .verify(&token(header, &key, claims.clone()))
+            .is_ok());
+
+        let mut header = Header::new(Algorithm::RS256);
+        header.kid = Some("rotated".to_string());
+        assert!(verifier.verify(&token(header, &key, claims)).is_err());
+
+        // HS256 is refused without a configured secret
+        let hs256 = token(
+            Header::default(),
+            &EncodingKey::from_secret(b"anything"),
+            serde_json::json!({ "sub": "alice", "exp": expires() }),
+        );
+        assert!(verifier.verify(&hs256).is_err());
+    }
+}
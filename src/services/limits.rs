@@ -0,0 +1,698 @@
+//! Per-client rate limiting and job quotas.
+//!
+//! Protects the service from clients that flood expensive endpoints (each
+//! `/analyze` call opens a remote connection, each job runs a pipeline) or
+//! monopolize workers and disk.
+//!
+//! # Rate Limits
+//!
+//! Rules are given as `GROUP=COUNT/DURATION`, e.g. `analyze=10/1m`: each
+//! client may make `COUNT` requests to the route group in a burst, and the
+//! allowance refills evenly over `DURATION` (token bucket). Groups are:
+//!
+//! - **analyze**: `GET /analyze/{url}`
+//! - **jobs**   : `POST /convert`, `/thumbnail`, `/stream`, `/pipelines` and
+//!   `/pipelines/{id}/retry`
+//! - **uploads**: `/uploads` and `/uploads/tus`
+//! - **api**    : Every other authenticated route (listing, status, downloads, stop)
+//!
+//! Clients are identified by their owner (API key ID or JWT tenant) when
+//! authenticated, and by IP address otherwise.
+//!
+//! # Quotas
+//!
+//! Authenticated owners can additionally be limited in how many pipelines
+//! they have queued or running at once, and in the total bytes their job
+//! directories hold. Quotas are checked when a job is submitted.
+//!
+//! # Execution Limits
+//!
+//! Every job may ask for a `max_runtime_seconds` and `max_output_bytes`.
+//! [`ExecutionLimitPolicy`] fills in server-wide defaults for omitted limits
+//! and refuses requests above the server-wide caps; the runtime enforces the
+//! resulting limits while the pipeline plays.
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+// ---
+
+// Import through gateway
+use crate::models::ExecutionLimits;
+
+// ---
+
+// Import from sibling modules
+use super::retention::parse_duration;
+use super::store::{PipelineQuery, SharedPipelineStore};
+
+/// Number of tracked buckets above which idle, full buckets are dropped
+const PRUNE_THRESHOLD: usize = 10_000;
+
+/// Routes sharing one rate limit.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum RouteGroup {
+    // ---
+    /// Remote media analysis
+    Analyze,
+
+    /// Job submission
+    Jobs,
+
+    /// Media uploads, direct and resumable
+    Uploads,
+
+    /// Everything else behind authentication
+    Api,
+}
+
+impl RouteGroup {
+    // ---
+
+    /// Every group, for parsing and documentation.
+    pub const ALL: [RouteGroup; 4] = [Self::Analyze, Self::Jobs, Self::Uploads, Self::Api];
+
+    /// Returns the lowercase name used on the command line (e.g. "analyze").
+    pub fn name(&self) -> &'static str {
+        // ---
+        match self {
+            Self::Analyze => "analyze",
+            Self::Jobs => "jobs",
+            Self::Uploads => "uploads",
+            Self::Api => "api",
+        }
+    }
+}
+
+/// One `GROUP=COUNT/DURATION` rate limit rule.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RateLimitRule {
+    // ---
+    /// Routes the rule applies to
+    group: RouteGroup,
+
+    /// Bucket size: requests allowed in a burst
+    count: u32,
+
+    /// Time over which a full bucket refills
+    period: Duration,
+}
+
+impl FromStr for RateLimitRule {
+    // ---
+    type Err = String;
+
+    fn from_str(rule: &str) -> Result<Self, Self::Err> {
+        // ---
+        let invalid = || format!("Rate limit '{rule}' must look like GROUP=COUNT/DURATION");
+        let (group, limit) = rule.split_once('=').ok_or_else(invalid)?;
+        let (count, period) = limit.split_once('/').ok_or_else(invalid)?;
+
+        let group = group.trim().to_ascii_lowercase();
+        let group = RouteGroup::ALL
+            .into_iter()
+            .find(|candidate| candidate.name() == group)
+            .ok_or_else(|| {
+                let groups: Vec<&str> = RouteGroup::ALL.iter().map(|group| group.name()).collect();
+                format!(
+                    "Unknown route group '{group}', expected one of: {}",
+                    groups.join(", ")
+                )
+            })?;
+
+        let count: u32 = count
+            .trim()
+            .parse()
+            .ok()
+            .filter(|count| *count > 0)
+            .ok_or_else(|| format!("Rate limit count in '{rule}' must be a positive integer"))?;
+        let period = parse_duration(period.trim())?;
+        if period.is_zero() {
+            return Err(format!("Rate limit period in '{rule}' must not be zero"));
+        }
+
+        Ok(Self {
+            group,
+            count,
+            period,
+        })
+    }
+}
+
+/// Outcome of a rate limit check.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RateDecision {
+    // ---
+    /// Bucket size of the applicable rule
+    pub limit: u32,
+
+    /// Whole requests left in the bucket after this one
+    pub remaining: u32,
+
+    /// When refused, how long until a request would be allowed
+    pub retry_after: Option<Duration>,
+}
+
+/// Token bucket state of one client in one group.
+struct Bucket {
+    // ---
+    tokens: f64,
+    updated: Instant,
+}
+
+/// Token bucket rate limiter keyed by client and route group.
+///
+/// Cheap to clone; all clones share the same buckets.
+#[derive(Clone, Default)]
+pub struct RateLimiter {
+    // ---
+    inner: Arc<RateLimiterInner>,
+}
+
+#[derive(Default)]
+struct RateLimiterInner {
+    // ---
+    rules: HashMap<RouteGroup, RateLimitRule>,
+    buckets: Mutex<HashMap<(String, RouteGroup), Bucket>>,
+}
+
+impl RateLimiter {
+    // ---
+
+    /// Creates a limiter; later rules for the same group replace earlier ones.
+    pub fn new(rules: Vec<RateLimitRule>) -> Self {
+        // ---
+        let rules = rules.into_iter().map(|rule| (rule.group, rule)).collect();
+        Self {
+            inner: Arc::new(RateLimiterInner {
+                rules,
+                buckets: Mutex::new(HashMap::new()),
+            }),
+        }
+    }
+
+    /// Takes one request from `client`'s bucket for `group`.
+    ///
+    /// # Returns
+    /// None when the group is not rate limited.
+    pub fn check(&self, client: &str, group: RouteGroup, now: Instant) -> Option<RateDecision> {
+        // ---
+        let rule = self.inner.rules.get(&group)?;
+        let capacity = f64::from(rule.count);
+        let refill_per_second = capacity / rule.period.as_secs_f64();
+
+        let mut buckets = self.inner.buckets.lock().unwrap();
+        if buckets.len() > PRUNE_THRESHOLD {
+            buckets.retain(|(_, group), bucket| {
+                let Some(rule) = self.inner.rules.get(group) else {
+                    return false;
+                };
+                now.duration_since(bucket.updated) < rule.period
+            });
+        }
+
+        let bucket = buckets
+            .entry((client.to_string(), group))
+            .or_insert(Bucket {
+                tokens: capacity,
+                updated: now,
+            });
+        let elapsed = now.duration_since(bucket.updated).as_secs_f64();
+        bucket.tokens = (bucket.tokens + elapsed * refill_per_second).min(capacity);
+        bucket.updated = now;
+
+        if bucket.tokens >= 1.0 {
+            bucket.tokens -= 1.0;
+            Some(RateDecision {
+                limit: rule.count,
+                remaining: bucket.tokens as u32,
+                retry_after: None,
+            })
+        } else {
+            let wait = (1.0 - bucket.tokens) / refill_per_second;
+            Some(RateDecision {
+                limit: rule.count,
+                remaining: 0,
+                retry_after: Some(Duration::from_secs_f64(wait)),
+            })
+        }
+    }
+}
+
+/// Per-owner job quota limits.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct QuotaLimits {
+    // ---
+    /// Most pipelines an owner may have queued or running
+    pub max_running: Option<usize>,
+
+    /// Most bytes an owner's job directories and uploads may hold
+    pub max_output_bytes: Option<u64>,
+}
+
+/// An owner's usage, reported alongside the configured limits.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct QuotaUsage {
+    // ---
+    /// Pipelines currently queued or running
+    pub running: usize,
+
+    /// Bytes held by the owner's job directories and uploads
+    pub output_bytes: u64,
+}
+
+/// Why a job was refused by [`Quotas::reserve`].
+#[derive(Debug)]
+pub enum QuotaError {
+    // ---
+    /// The owner already has this many pipelines queued or running
+    Running(usize),
+
+    /// The owner's outputs already take this many bytes
+    OutputBytes(u64),
+
+    /// Usage could not be determined
+    Io(anyhow::Error),
+}
+
+/// Enforces [`QuotaLimits`] against the store and output directory.
+///
+/// Cheap to clone.
+#[derive(Clone)]
+pub struct Quotas {
+    // ---
+    inner: Arc<QuotasInner>,
+}
+
+struct QuotasInner {
+    // ---
+    store: SharedPipelineStore,
+    output_dir: PathBuf,
+    upload_dir: PathBuf,
+    limits: QuotaLimits,
+
+    /// Admitted job submissions per owner whose reservation is still held
+    admitting: Mutex<HashMap<String, usize>>,
+}
+
+impl Quotas {
+    // ---
+
+    /// Creates the quota checker.
+    ///
+    /// # Arguments
+    /// * `store`      - Store counting an owner's active pipelines
+    /// * `output_dir` - Root holding per-owner job directories
+    /// * `upload_dir` - Root holding per-owner upload directories
+    /// * `limits`     - Configured limits; unset limits are not enforced
+    pub fn new(
+        store: SharedPipelineStore,
+        output_dir: PathBuf,
+        upload_dir: PathBuf,
+        limits: QuotaLimits,
+    ) -> Self {
+        // ---
+        Self {
+            inner: Arc::new(QuotasInner {
+                store,
+                output_dir,
+                upload_dir,
+                limits,
+                admitting: Mutex::new(HashMap::new()),
+            }),
+        }
+    }
+
+    /// Returns the configured limits.
+    pub fn limits(&self) -> QuotaLimits {
+        // ---
+        self.inner.limits
+    }
+
+    /// Reserves a running slot for another job of `owner`.
+    ///
+    /// Counting the owner's active pipelines and taking the slot happen under
+    /// one lock, and held reservations count as running, so concurrent
+    /// submissions cannot all pass the check before any of them is stored.
+    /// Hold the reservation until the job's record is stored or refused.
+    ///
+    /// Blocks on filesystem I/O when an output byte limit is configured.
+    ///
+    /// # Returns
+    /// The reservation, carrying the owner's usage of every configured limit.
+    pub fn reserve(&self, owner: &str) -> Result<QuotaReservation, QuotaError> {
+        // ---
+        let limits = self.inner.limits;
+        let mut usage = QuotaUsage::default();
+
+        let mut admitting = self.inner.admitting.lock().unwrap();
+        if let Some(max_running) = limits.max_running {
+            usage.running = admitting.get(owner).copied().unwrap_or_default();
+            for state in ["Created", "Playing", "Paused"] {
+                let query = PipelineQuery {
+                    state: Some(state),
+                    owner: Some(owner.to_string()),
+                    limit: max_running + 1,
+                    ..Default::default()
+                };
+                usage.running += self
+                    .inner
+                    .store
+                    .query(&query)
+                    .map_err(QuotaError::Io)?
+                    .len();
+            }
+            if usage.running >= max_running {
+                return Err(QuotaError::Running(usage.running));
+            }
+        }
+        *admitting.entry(owner.to_string()).or_default() += 1;
+        drop(admitting);
+
+        let mut reservation = QuotaReservation {
+            quotas: self.clone(),
+            owner: owner.to_string(),
+            usage,
+        };
+
+        if let Some(max_output_bytes) = limits.max_output_bytes {
+            let output_bytes = directory_size(&self.inner.output_dir.join(owner))
+                .and_then(|outputs| {
+                    Ok(outputs + directory_size(&self.inner.upload_dir.join(owner))?)
+                })
+                .map_err(|e| QuotaError::Io(e.into()))?;
+            if output_bytes >= max_output_bytes {
+                return Err(QuotaError::OutputBytes(output_bytes));
+            }
+            reservation.usage.output_bytes = output_bytes;
+        }
+
+        Ok(reservation)
+    }
+}
+
+/// A running slot taken by [`Quotas::reserve`]; released on drop.
+pub struct QuotaReservation {
+    // ---
+    quotas: Quotas,
+    owner: String,
+    usage: QuotaUsage,
+}
+
+impl QuotaReservation {
+    // ---
+
+    /// Returns the owner's usage when the slot was reserved.
+    pub fn usage(&self) -> QuotaUsage {
+        // ---
+        self.usage
+    }
+}
+
+impl Drop for QuotaReservation {
+    // ---
+
+    fn drop(&mut self) {
+        // ---
+        let mut admitting = self.quotas.inner.admitting.lock().unwrap();
+        if let Some(count) = admitting.get_mut(&self.owner) {
+            *count -= 1;
+            if *count == 0 {
+                admitting.remove(&self.owner);
+            }
+        }
+    }
+}
+
+/// Server-wide defaults and caps of per-job [`ExecutionLimits`].
+#[derive(Clone, Copy, Debug, Default)]
+pub struct ExecutionLimitPolicy {
+    // ---
+    /// Limits applied to jobs that do not set their own
+    pub defaults: ExecutionLimits,
+
+    /// Largest limits a job may ask for; also applied when neither the job
+    /// nor the defaults set a limit
+    pub caps: ExecutionLimits,
+}
+
+impl ExecutionLimitPolicy {
+    // ---
+
+    /// Returns the limits to enforce for a job requesting `requested`.
+    ///
+    /// # Errors
+    /// Describes the offending limit when it is zero or above its cap.
+    pub fn resolve(&self, requested: ExecutionLimits) -> Result<ExecutionLimits, String> {
+        // ---
+        let resolve = |name: &str,
+                       requested: Option<u64>,
+                       default: Option<u64>,
+                       cap: Option<u64>|
+         -> Result<Option<u64>, String> {
+            match (requested, cap) {
+                (Some(0), _) => Err(format!("{name} must be greater than 0")),
+                (Some(value), Some(cap)) if value > cap => Err(format!(
+                    "{name} of {value} exceeds the server maximum of {cap}"
+                )),
+                (Some(value), _) => Ok(Some(value)),
+                (None, _) => Ok(default.or(cap)),
+            }
+        };
+
+        Ok(ExecutionLimits {
+            max_runtime_seconds: resolve(
+                "max_runtime_seconds",
+                requested.max_runtime_seconds,
+                self.defaults.max_runtime_seconds,
+                self.caps.max_runtime_seconds,
+            )?,
+            max_output_bytes: resolve(
+                "max_output_bytes",
+                requested.max_output_bytes,
+                self.defaults.max_output_bytes,
+                self.caps.max_output_bytes,
+            )?,
+        })
+    }
+}
+
+/// Returns the total size of the files below `path` (0 if it does not exist).
+fn directory_size(path: &Path) -> std::io::Result<u64> {
+    // ---
+    let entries = match std::fs::read_dir(path) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(0),
+        Err(e) => return Err(e),
+    };
+
+    let mut total = 0;
+    for entry in entries {
+        let entry = entry?;
+        let file_type = entry.file_type()?;
+        if file_type.is_dir() {
+            total += directory_size(&entry.path())?;
+        } else if file_type.is_file() {
+            total += entry.metadata()?.len();
+        }
+    }
+    Ok(total)
+}
+
+/// Returns the size of an output file, or of everything below an output
+/// directory; 0 when it does not exist (yet).
+pub(super) fn output_size(path: &Path) -> u64 {
+    // ---
+    match std::fs::metadata(path) {
+        Ok(metadata) if metadata.is_dir() => directory_size(path).unwrap_or(0),
+        Ok(metadata) => metadata.len(),
+        Err(_) => 0,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    // ---
+
+    use super::*;
+    use crate::models::{PipelineInfo, PipelineState};
+    use crate::services::store::MemoryPipelineStore;
+
+    #[test]
+    fn test_parse_rate_limit_rules() {
+        // ---
+        let rule: RateLimitRule = "analyze=10/1m".parse().unwrap();
+        assert_eq!(rule.group, RouteGroup::Analyze);
+        assert_eq!(rule.count, 10);
+        assert_eq!(rule.period, Duration::from_secs(60));
+
+        assert!("analyze=10".parse::<RateLimitRule>().is_err());
+        assert!("everything=1/1s".parse::<RateLimitRule>().is_err());
+        assert!("jobs=0/1s".parse::<RateLimitRule>().is_err());
+        assert!("jobs=5/0s".parse::<RateLimitRule>().is_err());
+    }
+
+    #[test]
+    fn test_execution_limit_policy_applies_defaults_and_caps() {
+        // ---
+        let policy = ExecutionLimitPolicy {
+            defaults: ExecutionLimits {
+                max_runtime_seconds: Some(60),
+                max_output_bytes: None,
+            },
+            caps: ExecutionLimits {
+                max_runtime_seconds: Some(600),
+                max_output_bytes: Some(1000),
+            },
+        };
+
+        let resolved = policy.resolve(ExecutionLimits::default()).unwrap();
+        assert_eq!(resolved.max_runtime_seconds, Some(60));
+        assert_eq!(resolved.max_output_bytes, Some(1000));
+
+        let requested = ExecutionLimits {
+            max_runtime_seconds: Some(600),
+            max_output_bytes: Some(10),
+        };
+        assert_eq!(policy.resolve(requested).unwrap(), requested);
+
+        for requested in [
+            ExecutionLimits {
+                max_runtime_seconds: Some(601),
+                max_output_bytes: None,
+            },
+            ExecutionLimits {
+                max_runtime_seconds: None,
+                max_output_bytes: Some(0),
+            },
+        ] {
+            assert!(policy.resolve(requested).is_err());
+        }
+
+        // Without a policy, jobs run unlimited unless they ask otherwise
+        let unlimited = ExecutionLimitPolicy::default();
+        assert_eq!(
+            unlimited.resolve(ExecutionLimits::default()).unwrap(),
+            ExecutionLimits::default()
+        );
+    }
+
+    #[test]
+    fn test_token_bucket_refills_over_period() {
+        // ---
+        let limiter = RateLimiter::new(vec!["jobs=2/10s".parse().unwrap()]);
+        let start = Instant::now();
+
+        assert!(limiter.check("a", RouteGroup::Analyze, start).is_none());
+
+        let first = limiter.check("a", RouteGroup::Jobs, start).unwrap();
+        assert_eq!((first.limit, first.remaining), (2, 1));
+        assert!(limiter
+            .check("a", RouteGroup::Jobs, start)
+            .unwrap()
+            .retry_after
+            .is_none());
+
+        let refused = limiter.check("a", RouteGroup::Jobs, start).unwrap();
+        assert_eq!(refused.retry_after, Some(Duration::from_secs(5)));
+
+        // Other clients have their own bucket
+        assert!(limiter
+            .check("b", RouteGroup::Jobs, start)
+            .unwrap()
+            .retry_after
+            .is_none());
+
+        let later = limiter
+            .check("a", RouteGroup::Jobs, start + Duration::from_secs(5))
+            .unwrap();
+        assert!(later.retry_after.is_none());
+    }
+
+    #[test]
+    fn test_quotas_count_active_pipelines_and_bytes() {
+        // ---
+        let store: SharedPipelineStore = Arc::new(MemoryPipelineStore::new());
+        for (id, owner, state) in [
+            ("a", "acme", PipelineState::Playing),
+            ("b", "acme", PipelineState::Stopped),
+            ("c", "globex", PipelineState::Created),
+        ] {
+            store
+                .insert(PipelineInfo::test(id, Some(owner), state))
+                .unwrap();
+        }
+
+        let output_dir = std::env::temp_dir().join(format!("quotas-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(output_dir.join("acme").join("a")).unwrap();
+        std::fs::write(output_dir.join("acme").join("a").join("out.bin"), [0; 100]).unwrap();
+        let upload_dir = output_dir.join("uploads");
+        std::fs::create_dir_all(upload_dir.join("acme")).unwrap();
+        std::fs::write(upload_dir.join("acme").join("clip"), [0; 50]).unwrap();
+
+        let quotas = Quotas::new(
+            store.clone(),
+            output_dir.clone(),
+            upload_dir.clone(),
+            QuotaLimits {
+                max_running: Some(2),
+                max_output_bytes: Some(1000),
+            },
+        );
+        let usage = quotas.reserve("acme").unwrap().usage();
+        assert_eq!(usage.running, 1);
+        assert_eq!(usage.output_bytes, 150);
+
+        // Held reservations count as running until they are dropped
+        let reservation = quotas.reserve("acme").unwrap();
+        assert_eq!(reservation.usage().running, 1);
+        assert!(matches!(
+            quotas.reserve("acme"),
+            Err(QuotaError::Running(2))
+        ));
+        drop(reservation);
+        assert_eq!(quotas.reserve("acme").unwrap().usage().running, 1);
+
+        let strict = Quotas::new(
+            store,
+            output_dir.clone(),
+            upload_dir,
+            QuotaLimits {
+                max_running: Some(1),
+                max_output_bytes: Some(100),
+            },
+        );
+        assert!(matches!(
+            strict.reserve("acme"),
+            Err(QuotaError::Running(1))
+        ));
+        assert!(strict.reserve("globex").is_err());
+        assert_eq!(
+            strict.reserve("initech").unwrap().usage(),
+            QuotaUsage::default()
+        );
+
+        // A reservation refused on bytes releases its running slot
+        let bytes_only = Quotas::new(
+            Arc::new(MemoryPipelineStore::new()),
+            output_dir.clone(),
+            output_dir.join("uploads"),
+            QuotaLimits {
+                max_running: Some(1),
+                max_output_bytes: Some(100),
+            },
+        );
+        assert!(matches!(
+            bytes_only.reserve("acme"),
+            Err(QuotaError::OutputBytes(150))
+        ));
+        assert!(bytes_only.reserve("initech").is_ok());
+        assert!(bytes_only.reserve("acme").is_err());
+        assert!(bytes_only.inner.admitting.lock().unwrap().is_empty());
+
+        let _ = std::fs::remove_dir_all(&output_dir);
+    }
+}
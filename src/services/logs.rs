@@ -0,0 +1,167 @@
+//! Per-pipeline capture of GStreamer bus messages.
+//!
+//! Server logs are not visible to API clients, so the runtime also records
+//! each pipeline's bus errors, warnings and info messages, plus its own
+//! lifecycle events, into a bounded ring buffer served by
+//! `GET /pipelines/{id}/logs`.
+//!
+//! # Bounds
+//!
+//! Each pipeline keeps its most recent `--log-buffer-size` entries; older
+//! entries are discarded and counted as `dropped`. Buffers live in memory,
+//! are lost on restart, and are removed with the pipeline's record by
+//! retention.
+
+use chrono::Utc;
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Arc, Mutex};
+
+// ---
+
+// Import through gateway
+use crate::models::{LogEntry, LogLevel, PipelineLogsResponse};
+
+/// Captured messages of one pipeline.
+#[derive(Default)]
+struct LogBuffer {
+    // ---
+    entries: VecDeque<LogEntry>,
+    dropped: u64,
+}
+
+/// Ring buffers of captured messages, keyed by pipeline ID.
+///
+/// Cheap to clone; all clones share the same buffers.
+#[derive(Clone)]
+pub struct PipelineLogs {
+    // ---
+    buffers: Arc<Mutex<HashMap<String, LogBuffer>>>,
+    capacity: usize,
+}
+
+impl PipelineLogs {
+    // ---
+
+    /// Creates an empty set of buffers holding at most `capacity` entries
+    /// per pipeline. A capacity of zero disables capture.
+    pub fn new(capacity: usize) -> Self {
+        // ---
+        Self {
+            buffers: Arc::new(Mutex::new(HashMap::new())),
+            capacity,
+        }
+    }
+
+    /// Appends a message to a pipeline's buffer, discarding its oldest entry
+    /// when the buffer is full.
+    ///
+    /// # Arguments
+    /// * `pipeline_id` - Pipeline the message belongs to
+    /// * `level`       - Message severity
+    /// * `source`      - Element that posted the message, None for runtime events
+    /// * `message`     - Human-readable message
+    /// * `debug`       - GStreamer debug details, if any
+    pub fn record(
+        &self,
+        pipeline_id: &str,
+        level: LogLevel,
+        source: Option<String>,
+        message: impl Into<String>,
+        debug: Option<String>,
+    ) {
+        // ---
+        if self.capacity == 0 {
+            return;
+        }
+
+        let entry = LogEntry {
+            timestamp: Utc::now().to_rfc3339(),
+            level,
+            source,
+            message: message.into(),
+            debug,
+        };
+
+        let mut buffers = self.buffers.lock().unwrap();
+        let buffer = buffers.entry(pipeline_id.to_string()).or_default();
+        if buffer.entries.len() == self.capacity {
+            buffer.entries.pop_front();
+            buffer.dropped += 1;
+        }
+        buffer.entries.push_back(entry);
+    }
+
+    /// Returns a pipeline's captured messages, oldest first.
+    ///
+    /// Pipelines that never logged anything have an empty response.
+    pub fn get(&self, pipeline_id: &str) -> PipelineLogsResponse {
+        // ---
+        let buffers = self.buffers.lock().unwrap();
+        let (entries, dropped) = buffers
+            .get(pipeline_id)
+            .map(|buffer| (buffer.entries.iter().cloned().collect(), buffer.dropped))
+            .unwrap_or_default();
+
+        PipelineLogsResponse {
+            pipeline_id: pipeline_id.to_string(),
+            entries,
+            dropped,
+        }
+    }
+
+    /// Discards a pipeline's buffer.
+    pub fn remove(&self, pipeline_id: &str) {
+        // ---
+        self.buffers.lock().unwrap().remove(pipeline_id);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    // ---
+
+    use super::*;
+
+    #[test]
+    fn test_buffer_keeps_newest_entries() {
+        // ---
+        let logs = PipelineLogs::new(2);
+        for message in ["first", "second", "third"] {
+            logs.record("p1", LogLevel::Info, None, message, None);
+        }
+        logs.record(
+            "p2",
+            LogLevel::Error,
+            Some("filesrc0".into()),
+            "failed",
+            None,
+        );
+
+        let response = logs.get("p1");
+        let messages: Vec<_> = response
+            .entries
+            .iter()
+            .map(|e| e.message.as_str())
+            .collect();
+        assert_eq!(messages, ["second", "third"]);
+        assert_eq!(response.dropped, 1);
+
+        let other = logs.get("p2");
+        assert_eq!(other.entries.len(), 1);
+        assert_eq!(other.entries[0].source.as_deref(), Some("filesrc0"));
+        assert_eq!(other.dropped, 0);
+    }
+
+    #[test]
+    fn test_removed_and_disabled_buffers_are_empty() {
+        // ---
+        let logs = PipelineLogs::new(10);
+        logs.record("p1", LogLevel::Warning, None, "slow", None);
+        logs.remove("p1");
+        assert!(logs.get("p1").entries.is_empty());
+
+        let disabled = PipelineLogs::new(0);
+        disabled.record("p1", LogLevel::Error, None, "failed", None);
+        assert!(disabled.get("p1").entries.is_empty());
+    }
+}
@@ -0,0 +1,384 @@
+//! Prometheus metrics for the HTTP API and the pipeline runtime.
+//!
+//! All metrics live in a private [`Registry`] and are exported in the
+//! Prometheus text format by `GET /metrics`. Labels are kept to bounded sets
+//! (route patterns rather than paths, element factories rather than element
+//! names) so scraping a busy service does not explode series cardinality.
+//!
+//! # Metrics
+//!
+//! - `media_http_requests_total{method,route,status}`: Requests served
+//! - `media_http_request_duration_seconds{method,route}`: Request latency
+//! - `media_pipelines{kind,state}`: Pipeline records, computed at scrape time
+//! - `media_pipeline_queue_depth`: Pipelines waiting for a worker
+//! - `media_pipeline_duration_seconds{kind,outcome}`: Execution time of finished jobs
+//! - `media_pipeline_encoding_speed{kind}`: Media time processed per second of wall time
+//! - `media_analysis_duration_seconds{outcome}`: Latency of `/analyze` probes
+//! - `media_output_bytes_total{kind}`: Bytes written by successful jobs
+//! - `media_gstreamer_errors_total{element}`: Bus errors by element factory
+
+use prometheus::{
+    Encoder, HistogramOpts, HistogramVec, IntCounterVec, IntGauge, IntGaugeVec, Opts, Registry,
+    TextEncoder,
+};
+use std::path::Path;
+use std::sync::Arc;
+use std::time::Duration;
+
+// ---
+
+// Import through gateway
+use crate::models::{PipelineInfo, PipelineKind, PipelineState};
+
+// ---
+
+// Import from sibling modules
+use super::limits::output_size;
+use super::store::SharedPipelineStore;
+
+/// Content type of the Prometheus text exposition format
+pub const METRICS_CONTENT_TYPE: &str = "text/plain; version=0.0.4; charset=utf-8";
+
+/// Every state label of `media_pipelines`, so idle states export as 0
+const STATE_NAMES: [&str; 5] = ["Created", "Playing", "Paused", "Stopped", "Error"];
+
+/// Buckets for media jobs, from a quick thumbnail to a long transcode
+const JOB_BUCKETS: [f64; 10] = [
+    0.5, 1.0, 5.0, 15.0, 30.0, 60.0, 300.0, 900.0, 1800.0, 3600.0,
+];
+
+/// Buckets for realtime factors: below 1.0 is slower than playback
+const SPEED_BUCKETS: [f64; 9] = [0.25, 0.5, 1.0, 2.0, 4.0, 8.0, 16.0, 32.0, 64.0];
+
+/// Service metrics, shared by the HTTP layer and the runtime.
+///
+/// Cheap to clone; all clones record into the same registry.
+#[derive(Clone)]
+pub struct Metrics {
+    // ---
+    inner: Arc<MetricsInner>,
+}
+
+struct MetricsInner {
+    // ---
+    registry: Registry,
+    http_requests: IntCounterVec,
+    http_duration: HistogramVec,
+    pipelines: IntGaugeVec,
+    queue_depth: IntGauge,
+    job_duration: HistogramVec,
+    encoding_speed: HistogramVec,
+    analysis_duration: HistogramVec,
+    output_bytes: IntCounterVec,
+    gstreamer_errors: IntCounterVec,
+}
+
+impl Metrics {
+    // ---
+
+    /// Creates and registers every metric.
+    pub fn new() -> Self {
+        // ---
+        let registry = Registry::new();
+
+        let http_requests = IntCounterVec::new(
+            Opts::new("media_http_requests_total", "HTTP requests served"),
+            &["method", "route", "status"],
+        )
+        .unwrap();
+        let http_duration = HistogramVec::new(
+            HistogramOpts::new(
+                "media_http_request_duration_seconds",
+                "HTTP request latency in seconds",
+            ),
+            &["method", "route"],
+        )
+        .unwrap();
+        let pipelines = IntGaugeVec::new(
+            Opts::new("media_pipelines", "Pipeline records by kind and state"),
+            &["kind", "state"],
+        )
+        .unwrap();
+        let queue_depth = IntGauge::new(
+            "media_pipeline_queue_depth",
+            "Pipelines submitted but not yet running",
+        )
+        .unwrap();
+        let job_duration = HistogramVec::new(
+            HistogramOpts::new(
+                "media_pipeline_duration_seconds",
+                "Execution time of finished pipelines in seconds",
+            )
+            .buckets(JOB_BUCKETS.to_vec()),
+            &["kind", "outcome"],
+        )
+        .unwrap();
+        let encoding_speed = HistogramVec::new(
+            HistogramOpts::new(
+                "media_pipeline_encoding_speed",
+                "Seconds of media processed per second of execution",
+            )
+            .buckets(SPEED_BUCKETS.to_vec()),
+            &["kind"],
+        )
+        .unwrap();
+        let analysis_duration = HistogramVec::new(
+            HistogramOpts::new(
+                "media_analysis_duration_seconds",
+                "Latency of media analysis in seconds",
+            ),
+            &["outcome"],
+        )
+        .unwrap();
+        let output_bytes = IntCounterVec::new(
+            Opts::new(
+                "media_output_bytes_total",
+                "Bytes of output written by successful pipelines",
+            ),
+            &["kind"],
+        )
+        .unwrap();
+        let gstreamer_errors = IntCounterVec::new(
+            Opts::new(
+                "media_gstreamer_errors_total",
+                "GStreamer errors by element factory",
+            ),
+            &["element"],
+        )
+        .unwrap();
+
+        registry.register(Box::new(http_requests.clone())).unwrap();
+        registry.register(Box::new(http_duration.clone())).unwrap();
+        registry.register(Box::new(pipelines.clone())).unwrap();
+        registry.register(Box::new(queue_depth.clone())).unwrap();
+        registry.register(Box::new(job_duration.clone())).unwrap();
+        registry.register(Box::new(encoding_speed.clone())).unwrap();
+        registry
+            .register(Box::new(analysis_duration.clone()))
+            .unwrap();
+        registry.register(Box::new(output_bytes.clone())).unwrap();
+        registry
+            .register(Box::new(gstreamer_errors.clone()))
+            .unwrap();
+
+        Self {
+            inner: Arc::new(MetricsInner {
+                registry,
+                http_requests,
+                http_duration,
+                pipelines,
+                queue_depth,
+                job_duration,
+                encoding_speed,
+                analysis_duration,
+                output_bytes,
+                gstreamer_errors,
+            }),
+        }
+    }
+
+    /// Records a served request on the route pattern `route`.
+    pub fn observe_request(&self, method: &str, route: &str, status: u16, elapsed: Duration) {
+        // ---
+        self.inner
+            .http_requests
+            .with_label_values(&[method, route, &status.to_string()])
+            .inc();
+        self.inner
+            .http_duration
+            .with_label_values(&[method, route])
+            .observe(elapsed.as_secs_f64());
+    }
+
+    /// Records one `/analyze` probe.
+    pub fn observe_analysis(&self, succeeded: bool, elapsed: Duration) {
+        // ---
+        let outcome = if succeeded { "success" } else { "failure" };
+        self.inner
+            .analysis_duration
+            .with_label_values(&[outcome])
+            .observe(elapsed.as_secs_f64());
+    }
+
+    /// Counts a pipeline entering the execution queue.
+    pub(super) fn job_queued(&self) {
+        // ---
+        self.inner.queue_depth.inc();
+    }
+
+    /// Counts a pipeline leaving the execution queue for a worker.
+    pub(super) fn job_dequeued(&self) {
+        // ---
+        self.inner.queue_depth.dec();
+    }
+
+    /// Records a pipeline that finished executing.
+    ///
+    /// # Arguments
+    /// * `pipeline`       - The record in its terminal state
+    /// * `elapsed`        - Wall time from start to finish
+    /// * `media_position` - Media time the pipeline reached, when known
+    pub(super) fn observe_job(
+        &self,
+        pipeline: &PipelineInfo,
+        elapsed: Duration,
+        media_position: Option<Duration>,
+    ) {
+        // ---
+        let kind = pipeline.kind.name();
+        let succeeded = matches!(pipeline.state, PipelineState::Stopped);
+        let outcome = if succeeded { "success" } else { "failure" };
+
+        self.inner
+            .job_duration
+            .with_label_values(&[kind, outcome])
+            .observe(elapsed.as_secs_f64());
+
+        if !succeeded {
+            return;
+        }
+
+        if let Some(position) = media_position.filter(|position| !position.is_zero()) {
+            if !elapsed.is_zero() {
+                self.inner
+                    .encoding_speed
+                    .with_label_values(&[kind])
+                    .observe(position.as_secs_f64() / elapsed.as_secs_f64());
+            }
+        }
+
+        let bytes: u64 = pipeline
+            .outputs
+            .iter()
+            .map(|output| output_size(Path::new(output)))
+            .sum();
+        self.inner
+            .output_bytes
+            .with_label_values(&[kind])
+            .inc_by(bytes);
+    }
+
+    /// Counts a GStreamer error posted by an element of `factory`.
+    pub(super) fn record_gstreamer_error(&self, factory: &str) {
+        // ---
+        self.inner
+            .gstreamer_errors
+            .with_label_values(&[factory])
+            .inc();
+    }
+
+    /// Renders every metric in the Prometheus text format.
+    ///
+    /// Blocks while counting pipeline records in `store`.
+    pub fn render(&self, store: &SharedPipelineStore) -> anyhow::Result<String> {
+        // ---
+        let pipelines = &self.inner.pipelines;
+        for kind in PipelineKind::ALL {
+            for state in STATE_NAMES {
+                pipelines.with_label_values(&[kind.name(), state]).set(0);
+            }
+        }
+        for pipeline in store.list()? {
+            pipelines
+                .with_label_values(&[pipeline.kind.name(), pipeline.state.name()])
+                .inc();
+        }
+
+        let mut buffer = Vec::new();
+        TextEncoder::new().encode(&self.inner.registry.gather(), &mut buffer)?;
+        Ok(String::from_utf8(buffer)?)
+    }
+}
+
+impl Default for Metrics {
+    // ---
+    fn default() -> Self {
+        // ---
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    // ---
+
+    use super::*;
+    use crate::services::store::MemoryPipelineStore;
+
+    fn pipeline(id: &str, kind: PipelineKind, state: PipelineState) -> PipelineInfo {
+        // ---
+        PipelineInfo {
+            kind,
+            ..PipelineInfo::test(id, None, state)
+        }
+    }
+
+    #[test]
+    fn test_render_counts_pipelines_by_kind_and_state() {
+        // ---
+        let store: SharedPipelineStore = Arc::new(MemoryPipelineStore::new());
+        store
+            .insert(pipeline("a", PipelineKind::Convert, PipelineState::Playing))
+            .unwrap();
+        store
+            .insert(pipeline("b", PipelineKind::Convert, PipelineState::Playing))
+            .unwrap();
+        store
+            .insert(pipeline(
+                "c",
+                PipelineKind::Custom,
+                PipelineState::Error("boom".to_string()),
+            ))
+            .unwrap();
+
+        let metrics = Metrics::new();
+        metrics.observe_request("GET", "/pipelines/:id", 200, Duration::from_millis(3));
+        let text = metrics.render(&store).unwrap();
+
+        assert!(text.contains(r#"media_pipelines{kind="convert",state="Playing"} 2"#));
+        assert!(text.contains(r#"media_pipelines{kind="custom",state="Error"} 1"#));
+        assert!(text.contains(r#"media_pipelines{kind="stream",state="Created"} 0"#));
+        assert!(text.contains(
+            r#"media_http_requests_total{method="GET",route="/pipelines/:id",status="200"} 1"#
+        ));
+
+        // Gauges are recomputed, not accumulated, on every scrape
+        store.delete("a").unwrap();
+        let text = metrics.render(&store).unwrap();
+        assert!(text.contains(r#"media_pipelines{kind="convert",state="Playing"} 1"#));
+    }
+
+    #[test]
+    fn test_observe_job_records_speed_and_output_bytes() {
+        // ---
+        let dir = std::env::temp_dir().join(format!("metrics-test-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("segment0.ts"), vec![0u8; 1500]).unwrap();
+        let file = dir.join("output.mp4");
+        std::fs::write(&file, vec![0u8; 500]).unwrap();
+
+        let mut finished = pipeline("a", PipelineKind::Stream, PipelineState::Stopped);
+        finished.outputs = vec![dir.display().to_string()];
+        let metrics = Metrics::new();
+        metrics.observe_job(
+            &finished,
+            Duration::from_secs(5),
+            Some(Duration::from_secs(20)),
+        );
+
+        let failed = pipeline("b", PipelineKind::Stream, PipelineState::Error("x".into()));
+        metrics.observe_job(&failed, Duration::from_secs(1), None);
+        metrics.record_gstreamer_error("souphttpsrc");
+
+        let store: SharedPipelineStore = Arc::new(MemoryPipelineStore::new());
+        let text = metrics.render(&store).unwrap();
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        assert!(text.contains(r#"media_output_bytes_total{kind="stream"} 2000"#));
+        assert!(text.contains(r#"media_pipeline_encoding_speed_sum{kind="stream"} 4"#));
+        assert!(text.contains(
+            r#"media_pipeline_duration_seconds_count{kind="stream",outcome="failure"} 1"#
+        ));
+        assert!(text.contains(r#"media_gstreamer_errors_total{element="souphttpsrc"} 1"#));
+    }
+}
@@ -15,9 +15,29 @@
 //! # Core Responsibilities
 //!
 //! - **Pipeline Validation**  : Ensuring GStreamer pipeline strings are syntactically correct
+//! - **Pipeline Building**    : Creating pipelines from strings or structured element graphs
 //! - **Media Analysis**       : Extracting metadata and technical information from media files  
 //! - **Pipeline Construction**: Generating optimized pipelines for common operations
 //! - **Error Translation**    : Converting GStreamer errors into application-level errors
+//! - **Pipeline Execution**   : Running queued pipelines and driving their state transitions
+//! - **Retries**              : Retry policies naming GStreamer errors, with exponential backoff
+//! - **Pipeline Persistence** : In-memory and SQLite storage of pipeline records
+//! - **Completion Webhooks**  : Signed, retried notifications when pipelines finish
+//! - **Artifact Downloads**   : Locating pipeline outputs and parsing HTTP range requests
+//! - **Output Storage**       : Per-job output directories, optionally uploaded to S3
+//! - **Retention**            : Expiring finished pipelines and guarding disk capacity
+//! - **Uploads**              : Streaming uploaded source media to disk for use by jobs
+//! - **Resumable Uploads**    : tus 1.0 chunked uploads surviving dropped connections
+//! - **Authentication**       : Verifying hashed API keys and JWT bearer tokens
+//! - **Rate Limits & Quotas** : Per-client token buckets and per-owner job quotas
+//! - **Metrics**              : Prometheus counters and histograms for requests and jobs
+//! - **Telemetry**            : Log output and optional OpenTelemetry trace export
+//! - **Pipeline Logs**        : Bounded capture of each pipeline's GStreamer messages
+//! - **Introspection**        : DOT/SVG graphs and JSON topology of live, prerolled or parsed pipelines
+//! - **Live Control**         : Property updates, seeking and position queries on running pipelines
+//! - **Snapshots**            : JPEG/PNG encoding of the latest video frame of running pipelines
+//! - **Templates**            : Stored pipeline strings with typed, escaped placeholders
+//! - **Element Catalog**      : Factory metadata, pad templates and properties from the registry
 //!
 //! # EMBP Implementation
 //!
@@ -28,7 +48,30 @@
 // ---
 
 // EMBP Services Gateway: Controls public API for all service functionality
+mod artifacts;
+mod auth;
+mod builder;
+mod catalog;
+mod control;
+mod introspection;
+mod jwt;
+mod limits;
+mod logs;
+mod metrics;
+mod retention;
+mod retry;
+mod runtime;
+mod s3_storage;
+mod snapshot;
+mod sqlite_store;
+mod storage;
+mod store;
+mod telemetry;
+mod templates;
+mod tus;
+mod uploads;
 mod validation;
+mod webhooks;
 
 /// Media file metadata and technical information.
 ///
@@ -68,7 +111,38 @@ pub struct MediaInfo {
 // ---
 
 // Public exports - this defines the entire public services API
+pub use artifacts::{
+    content_type_for, etag_for, parse_range, resolve_artifact, ArtifactError, ByteRange,
+};
+pub use auth::{hash_api_key, ApiKeys};
+pub use builder::{confine_outputs, describe_graph, validate_pipeline_graph};
+pub use catalog::{describe_element, list_elements};
+pub use control::{pipeline_position, seek_pipeline, update_element, ControlError};
+pub use introspection::{
+    parse_graph_details, pipeline_graph, pipeline_topology, render_svg, GraphFormat, RenderError,
+    GRAPH_DETAIL_NAMES,
+};
+pub use jwt::{JwtConfig, JwtVerifier};
+pub use limits::{
+    ExecutionLimitPolicy, QuotaError, QuotaLimits, QuotaUsage, Quotas, RateLimitRule, RateLimiter,
+    RouteGroup,
+};
+pub use logs::PipelineLogs;
+pub use metrics::{Metrics, METRICS_CONTENT_TYPE};
+pub use retention::{DiskWatermarks, RetentionManager, RetentionPolicy, RetentionRule};
+pub use retry::resolve_retry_policy;
+pub use runtime::{PipelineRuntime, RestartPolicy};
+pub use s3_storage::{S3Config, S3OutputStorage};
+pub use snapshot::{take_snapshot, SnapshotError, SnapshotFormat};
+pub use sqlite_store::SqlitePipelineStore;
+pub use storage::{public_link, LocalOutputStorage, SharedOutputStorage};
+pub use store::{Cursor, MemoryPipelineStore, PipelineQuery, SharedPipelineStore, SortOrder};
+pub use telemetry::{continue_trace, LogFormat, Telemetry, TelemetryConfig};
+pub use templates::{render_template, TemplateError, TemplateStore};
+pub use tus::{TusError, TusUploads, TUS_EXTENSIONS, TUS_VERSION};
+pub use uploads::{UploadError, UploadStore};
 pub use validation::{
     create_conversion_pipeline, create_hls_stream_pipeline, create_thumbnail_pipeline,
-    get_media_info, validate_pipeline_string,
+    dry_run_pipeline, get_media_info, validate_pipeline_string, validate_source_url,
 };
+pub use webhooks::{validate_callback_url, WebhookNotifier};
@@ -0,0 +1,522 @@
+//! Retention policy, background reaper and disk-usage watermarks.
+//!
+//! Finished pipelines and their artifacts are kept until a retention rule
+//! says they have expired. A background reaper periodically deletes expired
+//! pipelines: artifacts first (local and published), then the record and
+//! its captured logs.
+//!
+//! # Retention Rules
+//!
+//! Rules are given as `KIND:STATE=DURATION`, where `KIND` is one of
+//! `convert`, `thumbnail`, `stream`, `custom` or `*`, `STATE` is `stopped`,
+//! `error` or `*`, and `DURATION` is a number with an `s`, `m`, `h` or `d`
+//! suffix. Either selector may be omitted (`stream=6h`, `error=1d`). When
+//! several rules match a pipeline, the most specific wins; among equally
+//! specific rules the last one given wins. Pipelines matching no rule are
+//! kept forever. Ages are measured from `finished_at`.
+//!
+//! # Watermarks
+//!
+//! With a high watermark configured, new jobs are refused once the
+//! filesystem holding the output directory is at least that full, and
+//! accepted again only after usage drops below the low watermark. The gap
+//! prevents flapping while the reaper frees space.
+
+use chrono::{DateTime, Utc};
+use std::path::PathBuf;
+use std::str::FromStr;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use tracing::{info, warn};
+
+// ---
+
+// Import through gateway
+use crate::models::{PipelineInfo, PipelineKind};
+
+// ---
+
+// Import from sibling modules
+use super::logs::PipelineLogs;
+use super::storage::SharedOutputStorage;
+use super::store::SharedPipelineStore;
+
+/// One `KIND:STATE=DURATION` retention rule.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RetentionRule {
+    // ---
+    /// Job kind the rule applies to, None for any
+    kind: Option<PipelineKind>,
+
+    /// Terminal state name ("Stopped" or "Error") the rule applies to, None for any
+    state: Option<&'static str>,
+
+    /// How long matching pipelines are kept after finishing
+    ttl: Duration,
+}
+
+impl RetentionRule {
+    // ---
+
+    /// Returns true if the rule selects this pipeline.
+    fn matches(&self, pipeline: &PipelineInfo) -> bool {
+        // ---
+        self.kind.is_none_or(|kind| kind == pipeline.kind)
+            && self
+                .state
+                .is_none_or(|state| state == pipeline.state.name())
+    }
+
+    /// Number of non-wildcard selectors, used to pick the most specific rule.
+    fn specificity(&self) -> usize {
+        // ---
+        usize::from(self.kind.is_some()) + usize::from(self.state.is_some())
+    }
+}
+
+impl FromStr for RetentionRule {
+    // ---
+    type Err = String;
+
+    fn from_str(rule: &str) -> Result<Self, Self::Err> {
+        // ---
+        let (selectors, duration) = rule
+            .split_once('=')
+            .ok_or_else(|| format!("Retention rule '{rule}' must look like KIND:STATE=DURATION"))?;
+
+        let mut parsed = Self {
+            kind: None,
+            state: None,
+            ttl: parse_duration(duration.trim())?,
+        };
+
+        for selector in selectors.split(':').map(str::trim) {
+            // ---
+            let selector = selector.to_ascii_lowercase();
+            if selector == "*" {
+                continue;
+            }
+
+            if let Some(kind) = PipelineKind::ALL
+                .into_iter()
+                .find(|kind| kind.name() == selector)
+            {
+                parsed.kind = Some(kind);
+            } else if selector == "stopped" {
+                parsed.state = Some("Stopped");
+            } else if selector == "error" {
+                parsed.state = Some("Error");
+            } else {
+                let kinds: Vec<&str> = PipelineKind::ALL.iter().map(|kind| kind.name()).collect();
+                return Err(format!(
+                    "Unknown retention selector '{selector}', expected one of: {}, stopped, error, *",
+                    kinds.join(", ")
+                ));
+            }
+        }
+
+        Ok(parsed)
+    }
+}
+
+/// Parses durations such as `90s`, `15m`, `12h` or `7d`.
+pub(super) fn parse_duration(value: &str) -> Result<Duration, String> {
+    // ---
+    let split = value
+        .find(|c: char| !c.is_ascii_digit())
+        .ok_or_else(|| format!("Duration '{value}' needs a unit (s, m, h or d)"))?;
+    let (amount, unit) = value.split_at(split);
+
+    let amount: u64 = amount
+        .parse()
+        .map_err(|_| format!("Invalid duration '{value}'"))?;
+    let seconds = match unit {
+        "s" => 1,
+        "m" => 60,
+        "h" => 60 * 60,
+        "d" => 24 * 60 * 60,
+        _ => {
+            return Err(format!(
+                "Unknown duration unit in '{value}' (use s, m, h or d)"
+            ))
+        }
+    };
+
+    amount
+        .checked_mul(seconds)
+        .map(Duration::from_secs)
+        .ok_or_else(|| format!("Duration '{value}' is too long"))
+}
+
+/// Ordered set of retention rules.
+#[derive(Clone, Debug, Default)]
+pub struct RetentionPolicy {
+    // ---
+    rules: Vec<RetentionRule>,
+}
+
+impl RetentionPolicy {
+    // ---
+
+    /// Creates a policy from rules in command-line order.
+    pub fn new(rules: Vec<RetentionRule>) -> Self {
+        // ---
+        Self { rules }
+    }
+
+    /// Returns true when no rule is configured and nothing ever expires.
+    pub fn is_empty(&self) -> bool {
+        // ---
+        self.rules.is_empty()
+    }
+
+    /// Returns how long a finished pipeline is kept, None for forever.
+    ///
+    /// Pipelines that have not finished never expire.
+    pub fn ttl_for(&self, pipeline: &PipelineInfo) -> Option<Duration> {
+        // ---
+        if !pipeline.state.is_terminal() {
+            return None;
+        }
+
+        self.rules
+            .iter()
+            .enumerate()
+            .filter(|(_, rule)| rule.matches(pipeline))
+            .max_by_key(|(position, rule)| (rule.specificity(), *position))
+            .map(|(_, rule)| rule.ttl)
+    }
+}
+
+/// Disk usage thresholds, in percent of the output filesystem.
+#[derive(Clone, Copy, Debug)]
+pub struct DiskWatermarks {
+    // ---
+    /// Usage at which new jobs start being refused
+    pub high: f64,
+
+    /// Usage below which new jobs are accepted again
+    pub low: f64,
+}
+
+/// Applies the retention policy and guards disk capacity.
+///
+/// Cheap to clone; all clones share the same configuration and watermark
+/// state.
+#[derive(Clone)]
+pub struct RetentionManager {
+    // ---
+    inner: Arc<RetentionInner>,
+}
+
+struct RetentionInner {
+    // ---
+    store: SharedPipelineStore,
+    storage: SharedOutputStorage,
+    logs: PipelineLogs,
+    policy: RetentionPolicy,
+    watermarks: Option<DiskWatermarks>,
+    output_dir: PathBuf,
+    refusing: AtomicBool,
+}
+
+impl RetentionManager {
+    // ---
+
+    /// Creates the manager.
+    ///
+    /// # Arguments
+    /// * `store`      - Store whose finished records expire
+    /// * `storage`    - Backend deleting the artifacts of expired pipelines
+    /// * `logs`       - Captured pipeline logs, discarded with their records
+    /// * `policy`     - Retention rules
+    /// * `watermarks` - Disk usage thresholds, None to never refuse jobs
+    /// * `output_dir` - Directory whose filesystem usage is watched
+    pub fn new(
+        store: SharedPipelineStore,
+        storage: SharedOutputStorage,
+        logs: PipelineLogs,
+        policy: RetentionPolicy,
+        watermarks: Option<DiskWatermarks>,
+        output_dir: PathBuf,
+    ) -> Self {
+        // ---
+        Self {
+            inner: Arc::new(RetentionInner {
+                store,
+                storage,
+                logs,
+                policy,
+                watermarks,
+                output_dir,
+                refusing: AtomicBool::new(false),
+            }),
+        }
+    }
+
+    /// Starts the background reaper, running every `interval`.
+    ///
+    /// Does nothing when the policy has no rules. Must be called from within
+    /// a Tokio runtime.
+    pub fn start_reaper(&self, interval: Duration) {
+        // ---
+        if self.inner.policy.is_empty() {
+            return;
+        }
+
+        let manager = self.clone();
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(interval);
+            loop {
+                ticker.tick().await;
+                let pass = manager.clone();
+                match tokio::task::spawn_blocking(move || pass.reap(Utc::now())).await {
+                    Ok(Ok(0)) => {}
+                    Ok(Ok(reaped)) => info!("Reaped {} expired pipeline(s)", reaped),
+                    Ok(Err(e)) => warn!("Retention pass failed: {:#}", e),
+                    Err(e) => warn!("Retention pass panicked: {}", e),
+                }
+            }
+        });
+        info!("Retention reaper running every {:?}", interval);
+    }
+
+    /// Deletes every pipeline that expired before `now`.
+    ///
+    /// Blocks on storage I/O; call from a blocking thread.
+    ///
+    /// # Returns
+    /// The number of pipelines deleted.
+    pub fn reap(&self, now: DateTime<Utc>) -> anyhow::Result<usize> {
+        // ---
+        let mut reaped = 0;
+
+        for pipeline in self.inner.store.list()? {
+            // ---
+            let Some(ttl) = self.inner.policy.ttl_for(&pipeline) else {
+                continue;
+            };
+            let finished = pipeline
+                .finished_at
+                .as_deref()
+                .unwrap_or(&pipeline.created_at);
+            let Ok(finished) = DateTime::parse_from_rfc3339(finished) else {
+                continue;
+            };
+
+            let age = now
+                .signed_duration_since(finished)
+                .to_std()
+                .unwrap_or_default();
+            if age < ttl {
+                continue;
+            }
+
+            match self.remove(&pipeline) {
+                Ok(()) => reaped += 1,
+                Err(e) => warn!("Failed to reap pipeline {}: {:#}", pipeline.id, e),
+            }
+        }
+
+        Ok(reaped)
+    }
+
+    /// Deletes a pipeline's artifacts and record immediately.
+    ///
+    /// Blocks on storage I/O; call from a blocking thread.
+    ///
+    /// # Returns
+    /// False if no such pipeline exists.
+    pub fn purge(&self, pipeline_id: &str) -> anyhow::Result<bool> {
+        // ---
+        let Some(pipeline) = self.inner.store.get(pipeline_id)? else {
+            return Ok(false);
+        };
+
+        self.remove(&pipeline)?;
+        info!("Purged pipeline {}", pipeline_id);
+        Ok(true)
+    }
+
+    /// Deletes artifacts first so a failure leaves the record for a retry.
+    fn remove(&self, pipeline: &PipelineInfo) -> anyhow::Result<()> {
+        // ---
+        self.inner.storage.delete(pipeline)?;
+        self.inner.store.delete(&pipeline.id)?;
+        self.inner.logs.remove(&pipeline.id);
+        Ok(())
+    }
+
+    /// Checks whether a new job may be accepted under the disk watermarks.
+    ///
+    /// # Returns
+    /// * `Ok(())` - No watermarks, usage unknown, or enough free space
+    /// * `Err(String)` - Storage is too full; the message reports usage
+    pub fn check_capacity(&self) -> Result<(), String> {
+        // ---
+        let Some(watermarks) = self.inner.watermarks else {
+            return Ok(());
+        };
+
+        let usage = match disk_usage_percent(&self.inner.output_dir) {
+            Ok(usage) => usage,
+            Err(e) => {
+                warn!(
+                    "Failed to read disk usage of {}: {}",
+                    self.inner.output_dir.display(),
+                    e
+                );
+                return Ok(());
+            }
+        };
+
+        let refusing = if self.inner.refusing.load(Ordering::SeqCst) {
+            usage >= watermarks.low
+        } else {
+            usage >= watermarks.high
+        };
+        if refusing != self.inner.refusing.swap(refusing, Ordering::SeqCst) {
+            info!(
+                "Output storage at {:.1}% - {} new jobs",
+                usage,
+                if refusing { "refusing" } else { "accepting" }
+            );
+        }
+
+        if refusing {
+            Err(format!(
+                "Output storage is {usage:.1}% full (high watermark {}%, accepting again below {}%)",
+                watermarks.high, watermarks.low
+            ))
+        } else {
+            Ok(())
+        }
+    }
+}
+
+/// Returns how full the filesystem containing `path` is, in percent.
+fn disk_usage_percent(path: &std::path::Path) -> std::io::Result<f64> {
+    // ---
+    let total = fs2::total_space(path)?;
+    let available = fs2::available_space(path)?;
+    if total == 0 {
+        return Ok(0.0);
+    }
+    Ok(100.0 * (1.0 - available as f64 / total as f64))
+}
+
+#[cfg(test)]
+mod tests {
+    // ---
+
+    use super::*;
+    use crate::models::PipelineState;
+    use crate::services::storage::LocalOutputStorage;
+    use crate::services::store::MemoryPipelineStore;
+
+    fn finished_pipeline(id: &str, kind: PipelineKind, state: PipelineState) -> PipelineInfo {
+        // ---
+        PipelineInfo {
+            kind,
+            created_at: "2024-01-01T00:00:00+00:00".to_string(),
+            finished_at: Some("2024-01-01T00:00:00+00:00".to_string()),
+            ..PipelineInfo::test(id, None, state)
+        }
+    }
+
+    fn policy(rules: &[&str]) -> RetentionPolicy {
+        // ---
+        RetentionPolicy::new(rules.iter().map(|rule| rule.parse().unwrap()).collect())
+    }
+
+    #[test]
+    fn test_parse_rules() {
+        // ---
+        let rule: RetentionRule = "convert:error=2h".parse().unwrap();
+        assert_eq!(rule.kind, Some(PipelineKind::Convert));
+        assert_eq!(rule.state, Some("Error"));
+        assert_eq!(rule.ttl, Duration::from_secs(2 * 3600));
+
+        let rule: RetentionRule = "*=7d".parse().unwrap();
+        assert_eq!(rule.specificity(), 0);
+        assert_eq!(rule.ttl, Duration::from_secs(7 * 86400));
+
+        assert!("convert=7".parse::<RetentionRule>().is_err());
+        assert!("bogus=1d".parse::<RetentionRule>().is_err());
+        assert!("convert".parse::<RetentionRule>().is_err());
+
+        assert_eq!(
+            parse_duration("213503982334602d"),
+            Err("Duration '213503982334602d' is too long".to_string())
+        );
+        assert_eq!(
+            parse_duration("18446744073709551615s"),
+            Ok(Duration::from_secs(u64::MAX))
+        );
+    }
+
+    #[test]
+    fn test_most_specific_rule_wins() {
+        // ---
+        let policy = policy(&["*=30d", "error=1d", "stream=6h", "stream:error=1h"]);
+
+        let convert = finished_pipeline("a", PipelineKind::Convert, PipelineState::Stopped);
+        assert_eq!(
+            policy.ttl_for(&convert),
+            Some(Duration::from_secs(30 * 86400))
+        );
+
+        let failed =
+            finished_pipeline("b", PipelineKind::Convert, PipelineState::Error("x".into()));
+        assert_eq!(policy.ttl_for(&failed), Some(Duration::from_secs(86400)));
+
+        let stream = finished_pipeline("c", PipelineKind::Stream, PipelineState::Error("x".into()));
+        assert_eq!(policy.ttl_for(&stream), Some(Duration::from_secs(3600)));
+
+        let running = finished_pipeline("d", PipelineKind::Stream, PipelineState::Playing);
+        assert_eq!(policy.ttl_for(&running), None);
+    }
+
+    #[test]
+    fn test_reap_deletes_only_expired_pipelines() {
+        // ---
+        let store: SharedPipelineStore = Arc::new(MemoryPipelineStore::new());
+        let storage: SharedOutputStorage = Arc::new(LocalOutputStorage::new(
+            std::env::temp_dir().join(format!("reap-{}", uuid::Uuid::new_v4())),
+        ));
+        store
+            .insert(finished_pipeline(
+                "failed",
+                PipelineKind::Custom,
+                PipelineState::Error("x".into()),
+            ))
+            .unwrap();
+        store
+            .insert(finished_pipeline(
+                "done",
+                PipelineKind::Custom,
+                PipelineState::Stopped,
+            ))
+            .unwrap();
+
+        let manager = RetentionManager::new(
+            store.clone(),
+            storage,
+            PipelineLogs::new(10),
+            policy(&["error=1h", "stopped=1d"]),
+            None,
+            std::env::temp_dir(),
+        );
+
+        let two_hours_later = DateTime::parse_from_rfc3339("2024-01-01T02:00:00+00:00")
+            .unwrap()
+            .with_timezone(&Utc);
+        assert_eq!(manager.reap(two_hours_later).unwrap(), 1);
+        assert!(store.get("failed").unwrap().is_none());
+        assert!(store.get("done").unwrap().is_some());
+
+        assert!(manager.purge("done").unwrap());
+        assert!(!manager.purge("done").unwrap());
+    }
+}
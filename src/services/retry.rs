@@ -0,0 +1,271 @@
+//! Automatic retry policies for failed pipelines.
+//!
+//! GStreamer reports failures as errors of four domains (`CoreError`,
+//! `LibraryError`, `ResourceError` and `StreamError`), each with a set of
+//! codes. Retry policies name the failures worth retrying as `Domain::Code`,
+//! e.g. `ResourceError::Read` for a connection dropped mid-stream, or as a
+//! whole `Domain`. Codes are the CamelCase form of the GLib enum nicks
+//! (`open-read` becomes `OpenRead`).
+//!
+//! # Backoff
+//!
+//! The first retry waits `backoff_seconds`; every further retry multiplies
+//! the delay by `backoff_multiplier`, up to one hour.
+
+use gstreamer::glib;
+use gstreamer::glib::translate::IntoGlib;
+use gstreamer::prelude::*;
+use std::time::Duration;
+
+// ---
+
+// Import through gateway
+use crate::models::RetryPolicy;
+
+/// Most attempts a retry policy may allow, including the first
+const MAX_ATTEMPTS: u32 = 10;
+
+/// Delay before the first retry unless the policy sets `backoff_seconds`
+const DEFAULT_BACKOFF_SECONDS: f64 = 1.0;
+
+/// Delay growth per retry unless the policy sets `backoff_multiplier`
+const DEFAULT_BACKOFF_MULTIPLIER: f64 = 2.0;
+
+/// Failures retried unless the policy sets `retry_on`
+const DEFAULT_RETRY_ON: [&str; 2] = ["ResourceError::Read", "ResourceError::OpenRead"];
+
+/// Longest delay between two attempts
+const MAX_BACKOFF: Duration = Duration::from_secs(3600);
+
+/// Validates a requested retry policy and fills in its defaults.
+///
+/// # Errors
+/// Describes the first invalid setting: attempts outside 1 to 10, a negative
+/// or excessive backoff, a multiplier below 1, or an unknown error domain or
+/// code.
+pub fn resolve_retry_policy(policy: RetryPolicy) -> Result<RetryPolicy, String> {
+    // ---
+    if !(1..=MAX_ATTEMPTS).contains(&policy.max_attempts) {
+        return Err(format!("max_attempts must be between 1 and {MAX_ATTEMPTS}"));
+    }
+
+    let backoff_seconds = policy.backoff_seconds.unwrap_or(DEFAULT_BACKOFF_SECONDS);
+    if !(0.0..=MAX_BACKOFF.as_secs_f64()).contains(&backoff_seconds) {
+        return Err(format!(
+            "backoff_seconds must be between 0 and {}",
+            MAX_BACKOFF.as_secs()
+        ));
+    }
+
+    let backoff_multiplier = policy
+        .backoff_multiplier
+        .unwrap_or(DEFAULT_BACKOFF_MULTIPLIER);
+    if !(1.0..=10.0).contains(&backoff_multiplier) {
+        return Err("backoff_multiplier must be between 1 and 10".to_string());
+    }
+
+    let retry_on = policy
+        .retry_on
+        .unwrap_or_else(|| DEFAULT_RETRY_ON.map(String::from).to_vec());
+    for entry in &retry_on {
+        check_error_name(entry)?;
+    }
+
+    Ok(RetryPolicy {
+        max_attempts: policy.max_attempts,
+        backoff_seconds: Some(backoff_seconds),
+        backoff_multiplier: Some(backoff_multiplier),
+        retry_on: Some(retry_on),
+    })
+}
+
+/// Returns the `Domain::Code` name of a GStreamer error, or None for errors
+/// outside the four GStreamer domains.
+pub(super) fn error_code(error: &glib::Error) -> Option<String> {
+    // ---
+    let (domain, type_, code) = if let Some(kind) = error.kind::<gstreamer::CoreError>() {
+        (
+            "CoreError",
+            gstreamer::CoreError::static_type(),
+            kind.into_glib(),
+        )
+    } else if let Some(kind) = error.kind::<gstreamer::LibraryError>() {
+        (
+            "LibraryError",
+            gstreamer::LibraryError::static_type(),
+            kind.into_glib(),
+        )
+    } else if let Some(kind) = error.kind::<gstreamer::ResourceError>() {
+        (
+            "ResourceError",
+            gstreamer::ResourceError::static_type(),
+            kind.into_glib(),
+        )
+    } else if let Some(kind) = error.kind::<gstreamer::StreamError>() {
+        (
+            "StreamError",
+            gstreamer::StreamError::static_type(),
+            kind.into_glib(),
+        )
+    } else {
+        return None;
+    };
+
+    let class = glib::EnumClass::with_type(type_)?;
+    let value = class.value(code)?;
+    Some(format!("{domain}::{}", camel_case(value.nick())))
+}
+
+/// Returns true if the policy retries failures with this error code.
+pub(super) fn retries(policy: &RetryPolicy, error_code: &str) -> bool {
+    // ---
+    let domain = error_code.split("::").next().unwrap_or_default();
+    policy
+        .retry_on
+        .iter()
+        .flatten()
+        .any(|entry| entry == error_code || entry == domain)
+}
+
+/// Returns the delay before retry number `retry` (starting at 1).
+pub(super) fn retry_delay(policy: &RetryPolicy, retry: u32) -> Duration {
+    // ---
+    let backoff = policy.backoff_seconds.unwrap_or(DEFAULT_BACKOFF_SECONDS);
+    let multiplier = policy
+        .backoff_multiplier
+        .unwrap_or(DEFAULT_BACKOFF_MULTIPLIER);
+    let seconds = backoff * multiplier.powi(retry.saturating_sub(1) as i32);
+    Duration::from_secs_f64(seconds.min(MAX_BACKOFF.as_secs_f64()))
+}
+
+/// Checks that `name` is a known `Domain` or `Domain::Code`.
+fn check_error_name(name: &str) -> Result<(), String> {
+    // ---
+    let (domain, code) = match name.split_once("::") {
+        Some((domain, code)) => (domain, Some(code)),
+        None => (name, None),
+    };
+    let type_ = match domain {
+        "CoreError" => gstreamer::CoreError::static_type(),
+        "LibraryError" => gstreamer::LibraryError::static_type(),
+        "ResourceError" => gstreamer::ResourceError::static_type(),
+        "StreamError" => gstreamer::StreamError::static_type(),
+        _ => {
+            return Err(format!(
+                "Unknown error domain in '{name}'; use CoreError, LibraryError, ResourceError or StreamError"
+            ))
+        }
+    };
+
+    let Some(code) = code else {
+        return Ok(());
+    };
+    let known = glib::EnumClass::with_type(type_).is_some_and(|class| {
+        class
+            .values()
+            .iter()
+            .any(|value| value.nick() != "num-errors" && camel_case(value.nick()) == code)
+    });
+    if !known {
+        return Err(format!("Unknown error code '{name}'"));
+    }
+    Ok(())
+}
+
+/// Converts a GLib enum nick such as "open-read" to "OpenRead".
+fn camel_case(nick: &str) -> String {
+    // ---
+    nick.split('-')
+        .map(|word| {
+            let mut chars = word.chars();
+            match chars.next() {
+                Some(first) => first.to_uppercase().chain(chars).collect(),
+                None => String::new(),
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    // ---
+
+    use super::*;
+
+    fn policy(retry_on: Option<Vec<&str>>) -> RetryPolicy {
+        // ---
+        RetryPolicy {
+            max_attempts: 3,
+            backoff_seconds: None,
+            backoff_multiplier: None,
+            retry_on: retry_on.map(|entries| entries.into_iter().map(String::from).collect()),
+        }
+    }
+
+    #[test]
+    fn test_resolve_retry_policy() {
+        // ---
+        gstreamer::init().unwrap();
+
+        let resolved = resolve_retry_policy(policy(None)).unwrap();
+        assert_eq!(resolved.backoff_seconds, Some(1.0));
+        assert_eq!(resolved.backoff_multiplier, Some(2.0));
+        assert_eq!(
+            resolved.retry_on,
+            Some(vec![
+                "ResourceError::Read".to_string(),
+                "ResourceError::OpenRead".to_string()
+            ])
+        );
+
+        assert!(resolve_retry_policy(policy(Some(vec!["StreamError"]))).is_ok());
+        for entries in [
+            vec!["ResourceError::Bogus"],
+            vec!["NetworkError::Read"],
+            vec!["ResourceError::NumErrors"],
+        ] {
+            assert!(resolve_retry_policy(policy(Some(entries.clone()))).is_err());
+        }
+
+        let mut too_many = policy(None);
+        too_many.max_attempts = 11;
+        assert!(resolve_retry_policy(too_many).is_err());
+        let mut shrinking = policy(None);
+        shrinking.backoff_multiplier = Some(0.5);
+        assert!(resolve_retry_policy(shrinking).is_err());
+    }
+
+    #[test]
+    fn test_error_code_names_gstreamer_errors() {
+        // ---
+        gstreamer::init().unwrap();
+
+        let error = glib::Error::new(gstreamer::ResourceError::OpenRead, "gone");
+        assert_eq!(
+            error_code(&error).as_deref(),
+            Some("ResourceError::OpenRead")
+        );
+        let error = glib::Error::new(gstreamer::StreamError::Decode, "corrupt");
+        assert_eq!(error_code(&error).as_deref(), Some("StreamError::Decode"));
+        let error = glib::Error::new(glib::FileError::Noent, "missing");
+        assert_eq!(error_code(&error), None);
+    }
+
+    #[test]
+    fn test_retries_and_backoff() {
+        // ---
+        gstreamer::init().unwrap();
+
+        let defaults = resolve_retry_policy(policy(None)).unwrap();
+        assert!(retries(&defaults, "ResourceError::Read"));
+        assert!(!retries(&defaults, "ResourceError::NotFound"));
+        assert!(!retries(&defaults, "StreamError::Decode"));
+
+        let domain = resolve_retry_policy(policy(Some(vec!["StreamError"]))).unwrap();
+        assert!(retries(&domain, "StreamError::Decode"));
+
+        assert_eq!(retry_delay(&defaults, 1), Duration::from_secs(1));
+        assert_eq!(retry_delay(&defaults, 3), Duration::from_secs(4));
+        assert_eq!(retry_delay(&defaults, 30), MAX_BACKOFF);
+    }
+}
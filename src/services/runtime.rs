@@ -0,0 +1,1102 @@
+//! Pipeline execution runtime and lifecycle state transitions.
+//!
+//! The runtime owns every state change a pipeline goes through after
+//! creation. Handlers insert a pipeline into the [`PipelineStore`] and submit
+//! its ID; a dispatcher then runs up to `--workers` pipelines concurrently,
+//! each on a blocking thread that watches the GStreamer bus until EOS, error
+//! or a stop request.
+//!
+//! # Lifecycle
+//!
+//! ```text
+//! submit → queue → Playing → Stopped (EOS or stop request)
+//!            ↑        ↓
+//!            └──── Error (bus error, start failure or exceeded limit)
+//!         retry
+//! ```
+//!
+//! # Terminal Transitions
+//!
+//! All transitions go through a single guarded path: once a pipeline is
+//! `Stopped` or `Error`, later transitions are ignored, and the first terminal
+//! transition hands the record to the [`WebhookNotifier`].
+//!
+//! # Publishing Outputs
+//!
+//! When a pipeline drains to EOS, its outputs are handed to the configured
+//! [`OutputStorage`] before the `Stopped` transition is recorded, so webhook
+//! receivers see the published locations. A failed upload turns the pipeline
+//! into `Error` instead.
+//!
+//! # Execution Limits
+//!
+//! A pipeline's `max_runtime_seconds` counts from the moment it starts
+//! playing, and its `max_output_bytes` is checked against its outputs on
+//! disk every half second. A pipeline exceeding either receives EOS like a
+//! stop request, so muxers can finalize what was written, and then fails
+//! with `Error` naming the limit. Its partial outputs are not published.
+//!
+//! # Retries
+//!
+//! Every attempt is recorded in the pipeline's `attempts`. A pipeline whose
+//! [`RetryPolicy`] covers the GStreamer error it failed with goes back to
+//! `Created` and is queued again after the policy's backoff, until it has
+//! made `max_attempts` attempts in a row. Stopped pipelines and failures
+//! outside GStreamer (exceeded limits, failed uploads) are not retried.
+//! [`PipelineRuntime::retry`] queues a finished pipeline again on request,
+//! which also starts a new series of automatic attempts.
+//!
+//! # Captured Logs
+//!
+//! Bus errors, warnings and info messages, lifecycle events (playing, end of
+//! stream) and failures outside GStreamer (parse errors, failed uploads) are
+//! recorded in the pipeline's [`PipelineLogs`] buffer.
+//!
+//! # Metrics
+//!
+//! The runtime reports queue depth, execution time, encoding speed (media
+//! time reached per second of execution), output bytes and GStreamer errors
+//! to [`Metrics`].
+//!
+//! # Tracing
+//!
+//! A pipeline's `queue_wait` and `execute_pipeline` spans are children of the
+//! span that submitted it (normally the `http_request` span), so exported
+//! traces follow a job from request to completion. Execution is split into
+//! `parse_pipeline`, `start_pipeline`, `run_pipeline` and `publish_outputs`.
+//!
+//! # Registry-Only Mode
+//!
+//! With zero workers the runtime never executes anything; pipelines stay in
+//! `Created` until stopped, matching the service's original behavior.
+//!
+//! # Restart Recovery
+//!
+//! With a persistent store, pipelines that were `Playing` or `Paused` when the
+//! previous process died are handled by [`RestartPolicy`] at startup: either
+//! failed with `Error("interrupted by restart")` or reset to `Created` and run
+//! again. Pipelines still waiting in the queue are always resubmitted.
+
+use chrono::Utc;
+use clap::ValueEnum;
+use gstreamer::prelude::*;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::sync::{mpsc, Semaphore};
+use tracing::{info, info_span, warn, Span};
+
+// ---
+
+// Import through gateway
+use crate::models::{LogLevel, PipelineAttempt, PipelineInfo, PipelineState, RetryPolicy};
+
+// ---
+
+// Import from sibling modules
+use super::builder::build_pipeline;
+use super::limits::output_size;
+use super::logs::PipelineLogs;
+use super::metrics::Metrics;
+use super::retry::{self, retries, retry_delay};
+use super::storage::SharedOutputStorage;
+use super::store::SharedPipelineStore;
+use super::webhooks::WebhookNotifier;
+
+/// How long a stopping pipeline may take to drain after EOS is sent
+const STOP_GRACE_PERIOD: Duration = Duration::from_secs(5);
+
+/// How often the size of a running pipeline's outputs is checked
+const OUTPUT_CHECK_INTERVAL: Duration = Duration::from_millis(500);
+
+/// Error recorded on pipelines failed by [`RestartPolicy::Fail`]
+const INTERRUPTED_BY_RESTART: &str = "interrupted by restart";
+
+/// What to do at startup with pipelines that were running when the previous
+/// process exited.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum RestartPolicy {
+    /// Mark interrupted pipelines as `Error("interrupted by restart")`
+    Fail,
+    /// Reset interrupted pipelines to `Created` and run them again
+    Requeue,
+}
+
+/// Executes pipelines and drives their state transitions.
+///
+/// Cheap to clone; all clones share the same queue and set of live pipelines.
+#[derive(Clone)]
+pub struct PipelineRuntime {
+    // ---
+    inner: Arc<RuntimeInner>,
+}
+
+struct RuntimeInner {
+    // ---
+    store: SharedPipelineStore,
+    webhooks: WebhookNotifier,
+    storage: SharedOutputStorage,
+    metrics: Metrics,
+    logs: PipelineLogs,
+    queue: Option<mpsc::UnboundedSender<Queued>>,
+    running: Mutex<HashMap<String, RunningPipeline>>,
+}
+
+/// A submitted pipeline waiting for a worker.
+struct Queued {
+    // ---
+    pipeline_id: String,
+
+    /// Span that submitted the pipeline, parent of its execution
+    parent: Span,
+
+    /// Open while the pipeline waits; closed when a worker picks it up
+    wait: Span,
+}
+
+/// How a pipeline that started playing came to an end.
+struct Execution {
+    // ---
+    /// Final state reported by the bus
+    state: PipelineState,
+
+    /// `Domain::Code` of the GStreamer error the pipeline failed with
+    error_code: Option<String>,
+
+    /// Media time the pipeline reached before it was shut down
+    media_position: Option<Duration>,
+}
+
+/// A pipeline that refused to start, with the GStreamer error it posted.
+#[derive(Debug)]
+struct StartFailure {
+    // ---
+    message: String,
+    error_code: Option<String>,
+}
+
+impl std::fmt::Display for StartFailure {
+    // ---
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        // ---
+        f.write_str(&self.message)
+    }
+}
+
+impl std::error::Error for StartFailure {}
+
+/// Handle to a pipeline currently executing on a worker thread.
+struct RunningPipeline {
+    // ---
+    pipeline: gstreamer::Pipeline,
+    stop_requested: Arc<AtomicBool>,
+}
+
+impl PipelineRuntime {
+    // ---
+
+    /// Creates the runtime and, when `workers > 0`, starts its dispatcher.
+    ///
+    /// Must be called from within a Tokio runtime.
+    ///
+    /// # Arguments
+    /// * `store`    - Store holding the pipeline records to execute
+    /// * `webhooks` - Notifier invoked on every terminal transition
+    /// * `storage`  - Backend publishing the outputs of finished pipelines
+    /// * `metrics`  - Metrics recording queue depth and job outcomes
+    /// * `logs`     - Buffers receiving each pipeline's captured messages
+    /// * `workers`  - Maximum number of concurrently executing pipelines
+    pub fn new(
+        store: SharedPipelineStore,
+        webhooks: WebhookNotifier,
+        storage: SharedOutputStorage,
+        metrics: Metrics,
+        logs: PipelineLogs,
+        workers: usize,
+    ) -> Self {
+        // ---
+        let (queue, receiver) = if workers > 0 {
+            let (sender, receiver) = mpsc::unbounded_channel();
+            (Some(sender), Some(receiver))
+        } else {
+            (None, None)
+        };
+
+        let runtime = Self {
+            inner: Arc::new(RuntimeInner {
+                store,
+                webhooks,
+                storage,
+                metrics,
+                logs,
+                queue,
+                running: Mutex::new(HashMap::new()),
+            }),
+        };
+
+        if let Some(receiver) = receiver {
+            tokio::spawn(runtime.clone().dispatch(receiver, workers));
+            info!("Pipeline runtime started with {} worker(s)", workers);
+        } else {
+            info!("Pipeline runtime disabled (--workers 0): pipelines are recorded only");
+        }
+
+        runtime
+    }
+
+    /// Queues a registered pipeline for execution.
+    ///
+    /// Records `pipeline.id` on the current span if it declares that field.
+    /// A no-op in registry-only mode.
+    pub fn submit(&self, pipeline_id: &str) {
+        // ---
+        let parent = Span::current();
+        parent.record("pipeline.id", pipeline_id);
+
+        if let Some(queue) = &self.inner.queue {
+            let queued = Queued {
+                pipeline_id: pipeline_id.to_string(),
+                wait: info_span!(parent: &parent, "queue_wait", pipeline.id = %pipeline_id),
+                parent,
+            };
+            if queue.send(queued).is_ok() {
+                self.inner.metrics.job_queued();
+            }
+        }
+    }
+
+    /// Re-establishes runtime state from the store after a restart.
+    ///
+    /// Pipelines found `Playing` or `Paused` were interrupted when the previous
+    /// process exited and are handled according to `policy`. Pipelines still in
+    /// `Created` (and any requeued ones) are resubmitted for execution.
+    ///
+    /// # Returns
+    /// The number of interrupted pipelines that were found.
+    pub fn recover(&self, policy: RestartPolicy) -> anyhow::Result<usize> {
+        // ---
+        let mut interrupted = 0;
+
+        for pipeline in self.inner.store.list()? {
+            match pipeline.state {
+                PipelineState::Playing | PipelineState::Paused => {
+                    interrupted += 1;
+                    match policy {
+                        RestartPolicy::Fail => {
+                            self.transition(
+                                &pipeline.id,
+                                PipelineState::Error(INTERRUPTED_BY_RESTART.to_string()),
+                            );
+                        }
+                        RestartPolicy::Requeue => {
+                            self.inner.store.update(&pipeline.id, &mut |pipeline| {
+                                pipeline.state = PipelineState::Created
+                            })?;
+                            self.submit(&pipeline.id);
+                        }
+                    }
+                }
+                PipelineState::Created => self.submit(&pipeline.id),
+                PipelineState::Stopped | PipelineState::Error(_) => {}
+            }
+        }
+
+        if interrupted > 0 {
+            info!(
+                "Recovered {} interrupted pipeline(s) with policy {:?}",
+                interrupted, policy
+            );
+        }
+
+        Ok(interrupted)
+    }
+
+    /// Stops a pipeline, returning false if no such pipeline exists.
+    ///
+    /// Running pipelines receive EOS so muxers can finalize their output and
+    /// transition to `Stopped` once drained (or after a grace period). Queued
+    /// and idle pipelines are marked `Stopped` immediately. Stopping a pipeline
+    /// that already finished overwrites its state with `Stopped` without
+    /// sending another webhook.
+    pub fn stop(&self, pipeline_id: &str) -> anyhow::Result<bool> {
+        // ---
+        // Hold the running lock across the record update so a worker cannot
+        // start this pipeline between the check and the state change.
+        let running = self.inner.running.lock().unwrap();
+
+        if let Some(live) = running.get(pipeline_id) {
+            live.stop_requested.store(true, Ordering::SeqCst);
+            live.pipeline.send_event(gstreamer::event::Eos::new());
+            info!("Sent EOS to pipeline: {}", pipeline_id);
+            return Ok(true);
+        }
+
+        let mut was_terminal = false;
+        let updated = self.inner.store.update(pipeline_id, &mut |pipeline| {
+            was_terminal = pipeline.state.is_terminal();
+            pipeline.state = PipelineState::Stopped;
+            if !was_terminal {
+                pipeline.finished_at = Some(Utc::now().to_rfc3339());
+            }
+        })?;
+        drop(running);
+
+        let Some(pipeline) = updated else {
+            return Ok(false);
+        };
+        if !was_terminal {
+            self.inner.webhooks.notify(pipeline);
+        }
+
+        Ok(true)
+    }
+
+    /// Queues a finished pipeline for another attempt.
+    ///
+    /// The pipeline goes back to `Created` with its published outputs
+    /// cleared; its attempt history is kept.
+    ///
+    /// # Returns
+    /// The updated record, or None if the pipeline does not exist or has not
+    /// finished.
+    pub fn retry(&self, pipeline_id: &str) -> anyhow::Result<Option<PipelineInfo>> {
+        // ---
+        let mut requeued = false;
+        let updated = self.inner.store.update(pipeline_id, &mut |pipeline| {
+            requeued = pipeline.state.is_terminal();
+            if requeued {
+                pipeline.state = PipelineState::Created;
+                pipeline.finished_at = None;
+                pipeline.published_outputs.clear();
+            }
+        })?;
+
+        let Some(pipeline) = updated.filter(|_| requeued) else {
+            return Ok(None);
+        };
+        info!("Retrying pipeline {} on request", pipeline_id);
+        self.inner
+            .logs
+            .record(pipeline_id, LogLevel::Info, None, "Retry requested", None);
+        self.submit(pipeline_id);
+        Ok(Some(pipeline))
+    }
+
+    /// Returns the directory the pipeline's outputs are written to.
+    pub(super) fn job_dir(&self, pipeline: &PipelineInfo) -> PathBuf {
+        // ---
+        self.inner
+            .storage
+            .job_dir(pipeline.owner.as_deref(), &pipeline.id)
+    }
+
+    /// Returns the executing GStreamer pipeline, None if it is not running.
+    pub fn live_pipeline(&self, pipeline_id: &str) -> Option<gstreamer::Pipeline> {
+        // ---
+        let running = self.inner.running.lock().unwrap();
+        running.get(pipeline_id).map(|live| live.pipeline.clone())
+    }
+
+    /// Receives queued pipeline IDs and runs each on a blocking thread,
+    /// never exceeding `workers` concurrent executions.
+    async fn dispatch(self, mut receiver: mpsc::UnboundedReceiver<Queued>, workers: usize) {
+        // ---
+        let permits = Arc::new(Semaphore::new(workers));
+
+        while let Some(queued) = receiver.recv().await {
+            let Ok(permit) = permits.clone().acquire_owned().await else {
+                break;
+            };
+            self.inner.metrics.job_dequeued();
+            drop(queued.wait);
+
+            let span = info_span!(
+                parent: &queued.parent,
+                "execute_pipeline",
+                pipeline.id = %queued.pipeline_id
+            );
+            let runtime = self.clone();
+            tokio::task::spawn_blocking(move || {
+                span.in_scope(|| runtime.execute(&queued.pipeline_id));
+                drop(permit);
+            });
+        }
+    }
+
+    /// Runs one pipeline to completion and records its final state, or
+    /// schedules its next attempt.
+    fn execute(&self, pipeline_id: &str) {
+        // ---
+        let pipeline = match self.inner.store.get(pipeline_id) {
+            Ok(Some(pipeline)) if !pipeline.state.is_terminal() => pipeline,
+            // Deleted or stopped while waiting in the queue
+            Ok(_) => return,
+            Err(e) => {
+                warn!("Failed to load pipeline {}: {}", pipeline_id, e);
+                return;
+            }
+        };
+
+        let started = Instant::now();
+        let started_at = Utc::now().to_rfc3339();
+        let outcome = self.run(pipeline_id, &pipeline);
+        self.inner.running.lock().unwrap().remove(pipeline_id);
+
+        let (state, error_code, media_position) = match outcome {
+            Ok(Some(execution)) => {
+                let state = match execution.state {
+                    PipelineState::Stopped => {
+                        info_span!("publish_outputs", pipeline.id = %pipeline_id)
+                            .in_scope(|| self.publish(pipeline_id))
+                    }
+                    state => state,
+                };
+                (state, execution.error_code, execution.media_position)
+            }
+            Ok(None) => return,
+            Err(e) => {
+                warn!("Pipeline {} failed to start: {}", pipeline_id, e);
+                self.inner
+                    .logs
+                    .record(pipeline_id, LogLevel::Error, None, e.to_string(), None);
+                let error_code = e
+                    .downcast_ref::<StartFailure>()
+                    .and_then(|failure| failure.error_code.clone());
+                (PipelineState::Error(e.to_string()), error_code, None)
+            }
+        };
+
+        if self.record_attempt(pipeline_id, &started_at, &state, error_code) {
+            return;
+        }
+        if let Some(finished) = self.transition(pipeline_id, state) {
+            self.inner
+                .metrics
+                .observe_job(&finished, started.elapsed(), media_position);
+        }
+    }
+
+    /// Builds and plays the pipeline, returning how it ended, or None if it
+    /// was stopped before it could start.
+    fn run(&self, pipeline_id: &str, record: &PipelineInfo) -> anyhow::Result<Option<Execution>> {
+        // ---
+        let pipeline = info_span!("parse_pipeline", pipeline.id = %pipeline_id)
+            .in_scope(|| build_pipeline(record, &self.job_dir(record)))?;
+        let bus = pipeline
+            .bus()
+            .ok_or_else(|| anyhow::anyhow!("Pipeline without bus"))?;
+
+        let stop_requested = Arc::new(AtomicBool::new(false));
+        self.inner.running.lock().unwrap().insert(
+            pipeline_id.to_string(),
+            RunningPipeline {
+                pipeline: pipeline.clone(),
+                stop_requested: stop_requested.clone(),
+            },
+        );
+
+        if self.is_terminal(pipeline_id) {
+            return Ok(None);
+        }
+
+        let started = info_span!("start_pipeline", pipeline.id = %pipeline_id)
+            .in_scope(|| pipeline.set_state(gstreamer::State::Playing));
+        if let Err(e) = started {
+            // The element that refused to start posted its error on the bus
+            let mut error_code = None;
+            if let Some(msg) = bus.pop_filtered(&[gstreamer::MessageType::Error]) {
+                if let gstreamer::MessageView::Error(err) = msg.view() {
+                    self.inner
+                        .metrics
+                        .record_gstreamer_error(&source_factory(err));
+                    record_message(&self.inner.logs, pipeline_id, &msg);
+                    error_code = retry::error_code(&err.error());
+                }
+            }
+            let _ = pipeline.set_state(gstreamer::State::Null);
+            return Err(StartFailure {
+                message: format!("Failed to start pipeline: {e}"),
+                error_code,
+            }
+            .into());
+        }
+
+        info!("Pipeline {} is playing", pipeline_id);
+        self.inner.logs.record(
+            pipeline_id,
+            LogLevel::Info,
+            None,
+            "Pipeline is playing",
+            None,
+        );
+        self.transition(pipeline_id, PipelineState::Playing);
+
+        let (state, error_code) =
+            info_span!("run_pipeline", pipeline.id = %pipeline_id).in_scope(|| {
+                watch_bus(
+                    &pipeline,
+                    record,
+                    &stop_requested,
+                    &self.inner.metrics,
+                    &self.inner.logs,
+                )
+            });
+        let media_position = pipeline
+            .query_position::<gstreamer::ClockTime>()
+            .map(|position| Duration::from_nanos(position.nseconds()));
+        let _ = pipeline.set_state(gstreamer::State::Null);
+
+        // Failures after a stop request are not worth retrying
+        let error_code = error_code.filter(|_| !stop_requested.load(Ordering::SeqCst));
+
+        Ok(Some(Execution {
+            state,
+            error_code,
+            media_position,
+        }))
+    }
+
+    /// Records a finished attempt and, when the pipeline failed with an error
+    /// its retry policy covers and attempts remain, schedules the next one.
+    ///
+    /// The decision is made on the stored record, so a pipeline stopped after
+    /// its execution ended (and is already terminal) is never rescheduled.
+    ///
+    /// # Returns
+    /// True if a retry was scheduled; the pipeline is then back in `Created`.
+    fn record_attempt(
+        &self,
+        pipeline_id: &str,
+        started_at: &str,
+        state: &PipelineState,
+        error_code: Option<String>,
+    ) -> bool {
+        // ---
+        let error = match state {
+            PipelineState::Error(error) => Some(error.clone()),
+            _ => None,
+        };
+
+        let mut scheduled = None;
+        let result = self.inner.store.update(pipeline_id, &mut |pipeline| {
+            // Attempts made since the series was last started or retried by hand
+            let series = pipeline
+                .attempts
+                .iter()
+                .rev()
+                .take_while(|attempt| attempt.retry_at.is_some())
+                .count() as u32
+                + 1;
+            let stopped = pipeline.state.is_terminal();
+            scheduled = match (&pipeline.retry, &error_code, &error) {
+                (Some(policy), Some(code), Some(_))
+                    if !stopped && series < policy.max_attempts && retries(policy, code) =>
+                {
+                    Some((retry_delay(policy, series), series, policy.clone()))
+                }
+                _ => None,
+            };
+
+            let now = Utc::now();
+            pipeline.attempts.push(PipelineAttempt {
+                attempt: pipeline.attempts.len() as u32 + 1,
+                started_at: started_at.to_string(),
+                finished_at: now.to_rfc3339(),
+                error: error.clone(),
+                error_code: error_code.clone(),
+                retry_at: scheduled.as_ref().map(|(delay, _, _)| {
+                    (now + chrono::Duration::from_std(*delay).unwrap_or_default()).to_rfc3339()
+                }),
+            });
+            if scheduled.is_some() {
+                pipeline.state = PipelineState::Created;
+            }
+        });
+
+        if let Err(e) = result {
+            warn!(
+                "Failed to record attempt of pipeline {}: {}",
+                pipeline_id, e
+            );
+            return false;
+        }
+        let Some((delay, series, policy)) = scheduled else {
+            return false;
+        };
+
+        let message = retry_message(&policy, series, delay, error_code.as_deref());
+        info!("Pipeline {}: {}", pipeline_id, message);
+        self.inner
+            .logs
+            .record(pipeline_id, LogLevel::Info, None, &message, None);
+
+        let runtime = self.clone();
+        let pipeline_id = pipeline_id.to_string();
+        let span = Span::current();
+        tokio::runtime::Handle::current().spawn(async move {
+            tokio::time::sleep(delay).await;
+            span.in_scope(|| runtime.submit(&pipeline_id));
+        });
+        true
+    }
+
+    /// Publishes the outputs of a drained pipeline and records their
+    /// locations, returning the state the pipeline should finish in.
+    fn publish(&self, pipeline_id: &str) -> PipelineState {
+        // ---
+        let pipeline = match self.inner.store.get(pipeline_id) {
+            Ok(Some(pipeline)) => pipeline,
+            _ => return PipelineState::Stopped,
+        };
+
+        let locations = match self.inner.storage.publish(&pipeline) {
+            Ok(locations) => locations,
+            Err(e) => {
+                warn!("Failed to publish outputs of {}: {:#}", pipeline_id, e);
+                let message = format!("Failed to publish outputs: {e:#}");
+                self.inner
+                    .logs
+                    .record(pipeline_id, LogLevel::Error, None, &message, None);
+                return PipelineState::Error(message);
+            }
+        };
+
+        if !locations.is_empty() {
+            let result = self.inner.store.update(pipeline_id, &mut |pipeline| {
+                pipeline.published_outputs = locations.clone()
+            });
+            if let Err(e) = result {
+                warn!(
+                    "Failed to record published outputs of {}: {}",
+                    pipeline_id, e
+                );
+            }
+        }
+
+        PipelineState::Stopped
+    }
+
+    /// Returns true if the pipeline record is missing, unreadable or already
+    /// terminal.
+    fn is_terminal(&self, pipeline_id: &str) -> bool {
+        // ---
+        match self.inner.store.get(pipeline_id) {
+            Ok(Some(pipeline)) => pipeline.state.is_terminal(),
+            _ => true,
+        }
+    }
+
+    /// Applies a state change unless the pipeline already reached a terminal
+    /// state, notifying webhooks on the first terminal transition.
+    ///
+    /// # Returns
+    /// The finished record when this call made the pipeline terminal.
+    fn transition(&self, pipeline_id: &str, state: PipelineState) -> Option<PipelineInfo> {
+        // ---
+        let mut finished = None;
+        let result = self.inner.store.update(pipeline_id, &mut |pipeline| {
+            if pipeline.state.is_terminal() {
+                return;
+            }
+            pipeline.state = state.clone();
+            if pipeline.state.is_terminal() {
+                pipeline.finished_at = Some(Utc::now().to_rfc3339());
+                finished = Some(pipeline.clone());
+            }
+        });
+
+        if let Err(e) = result {
+            warn!("Failed to record state of pipeline {}: {}", pipeline_id, e);
+            return None;
+        }
+
+        let pipeline = finished?;
+        info!("Pipeline {} finished: {:?}", pipeline_id, pipeline.state);
+        self.inner.webhooks.notify(pipeline.clone());
+        Some(pipeline)
+    }
+}
+
+/// Blocks on the pipeline bus until EOS, an error, or an expired stop request.
+///
+/// Sends EOS once the pipeline exceeds one of its execution limits, and then
+/// reports it as failed however it ends. Errors are counted in `metrics` by
+/// the factory of the posting element. Errors, warnings, info messages and
+/// EOS are captured in `logs`.
+///
+/// # Returns
+/// The final state, and the `Domain::Code` of the GStreamer error that ended
+/// the pipeline, if any.
+fn watch_bus(
+    pipeline: &gstreamer::Pipeline,
+    record: &PipelineInfo,
+    stop_requested: &AtomicBool,
+    metrics: &Metrics,
+    logs: &PipelineLogs,
+) -> (PipelineState, Option<String>) {
+    // ---
+    use gstreamer::MessageView;
+
+    let pipeline_id = record.id.as_str();
+    let Some(bus) = pipeline.bus() else {
+        return (
+            PipelineState::Error("Pipeline without bus".to_string()),
+            None,
+        );
+    };
+
+    let started = Instant::now();
+    let mut next_output_check = started;
+    let mut stop_deadline: Option<Instant> = None;
+    let mut exceeded: Option<String> = None;
+
+    let finish =
+        |state: PipelineState, error_code: Option<String>, exceeded: Option<String>| match exceeded
+        {
+            Some(reason) => (PipelineState::Error(reason), None),
+            None => (state, error_code),
+        };
+
+    loop {
+        if exceeded.is_none() {
+            let now = Instant::now();
+            let check_outputs = now >= next_output_check;
+            if check_outputs {
+                next_output_check = now + OUTPUT_CHECK_INTERVAL;
+            }
+            exceeded = exceeded_limit(record, started.elapsed(), check_outputs);
+
+            if let Some(reason) = &exceeded {
+                warn!("Pipeline {}: {}", pipeline_id, reason);
+                logs.record(pipeline_id, LogLevel::Error, None, reason, None);
+                stop_deadline = Some(now + STOP_GRACE_PERIOD);
+                pipeline.send_event(gstreamer::event::Eos::new());
+            }
+        }
+
+        if stop_requested.load(Ordering::SeqCst) {
+            stop_deadline.get_or_insert_with(|| Instant::now() + STOP_GRACE_PERIOD);
+        }
+        if stop_deadline.is_some_and(|deadline| Instant::now() >= deadline) {
+            return finish(PipelineState::Stopped, None, exceeded);
+        }
+
+        let Some(msg) = bus.timed_pop(gstreamer::ClockTime::from_mseconds(100)) else {
+            continue;
+        };
+
+        match msg.view() {
+            MessageView::Eos(_) => {
+                logs.record(
+                    pipeline_id,
+                    LogLevel::Info,
+                    None,
+                    "End of stream reached",
+                    None,
+                );
+                return finish(PipelineState::Stopped, None, exceeded);
+            }
+            MessageView::Error(err) => {
+                let source = err
+                    .src()
+                    .map(|src| src.name().to_string())
+                    .unwrap_or_else(|| "unknown element".to_string());
+                metrics.record_gstreamer_error(&source_factory(err));
+                record_message(logs, pipeline_id, &msg);
+                return finish(
+                    PipelineState::Error(format!("GStreamer error from {source}: {}", err.error())),
+                    retry::error_code(&err.error()),
+                    exceeded,
+                );
+            }
+            MessageView::Warning(_) | MessageView::Info(_) => {
+                record_message(logs, pipeline_id, &msg);
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Describes the execution limit a pipeline has exceeded, if any.
+///
+/// Output sizes are only measured when `check_outputs` is set.
+fn exceeded_limit(record: &PipelineInfo, elapsed: Duration, check_outputs: bool) -> Option<String> {
+    // ---
+    if let Some(max) = record.limits.max_runtime_seconds {
+        if elapsed >= Duration::from_secs(max) {
+            return Some(format!("Exceeded max_runtime_seconds limit of {max} s"));
+        }
+    }
+
+    if let Some(max) = record.limits.max_output_bytes.filter(|_| check_outputs) {
+        let written: u64 = record
+            .outputs
+            .iter()
+            .map(|output| output_size(Path::new(output)))
+            .sum();
+        if written > max {
+            return Some(format!(
+                "Exceeded max_output_bytes limit of {max} bytes ({written} bytes written)"
+            ));
+        }
+    }
+
+    None
+}
+
+/// Describes a scheduled automatic retry for the pipeline's logs.
+fn retry_message(
+    policy: &RetryPolicy,
+    series: u32,
+    delay: Duration,
+    error_code: Option<&str>,
+) -> String {
+    // ---
+    format!(
+        "Retrying after {} in {:.1} s (attempt {} of {})",
+        error_code.unwrap_or("error"),
+        delay.as_secs_f64(),
+        series + 1,
+        policy.max_attempts
+    )
+}
+
+/// Captures an error, warning or info bus message in `logs`; other message
+/// types are ignored.
+fn record_message(logs: &PipelineLogs, pipeline_id: &str, msg: &gstreamer::Message) {
+    // ---
+    use gstreamer::MessageView;
+
+    let (level, message, debug) = match msg.view() {
+        MessageView::Error(m) => (LogLevel::Error, m.error(), m.debug()),
+        MessageView::Warning(m) => (LogLevel::Warning, m.error(), m.debug()),
+        MessageView::Info(m) => (LogLevel::Info, m.error(), m.debug()),
+        _ => return,
+    };
+
+    logs.record(
+        pipeline_id,
+        level,
+        msg.src().map(|src| src.name().to_string()),
+        message.to_string(),
+        debug.map(|debug| debug.to_string()),
+    );
+}
+
+/// Returns the factory name (e.g. "souphttpsrc") of the element that posted
+/// an error, or "unknown" for errors not posted by an element.
+fn source_factory(err: &gstreamer::message::Error) -> String {
+    // ---
+    err.src()
+        .and_then(|src| src.downcast_ref::<gstreamer::Element>())
+        .and_then(|element| element.factory())
+        .map(|factory| factory.name().to_string())
+        .unwrap_or_else(|| "unknown".to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    // ---
+
+    use super::*;
+    use crate::models::ExecutionLimits;
+    use crate::services::retry::resolve_retry_policy;
+    use crate::services::storage::LocalOutputStorage;
+    use crate::services::store::MemoryPipelineStore;
+
+    fn pipeline_in(id: &str, state: PipelineState) -> PipelineInfo {
+        // ---
+        PipelineInfo::test(id, None, state)
+    }
+
+    fn runtime_with(pipelines: Vec<PipelineInfo>) -> (SharedPipelineStore, PipelineRuntime) {
+        // ---
+        let store: SharedPipelineStore = Arc::new(MemoryPipelineStore::new());
+        for pipeline in pipelines {
+            store.insert(pipeline).unwrap();
+        }
+        let webhooks = WebhookNotifier::new(store.clone(), None, 1, None);
+        let storage = Arc::new(LocalOutputStorage::new(std::env::temp_dir()));
+        let runtime = PipelineRuntime::new(
+            store.clone(),
+            webhooks,
+            storage,
+            Metrics::new(),
+            PipelineLogs::new(10),
+            0,
+        );
+        (store, runtime)
+    }
+
+    fn state_of(store: &SharedPipelineStore, id: &str) -> PipelineState {
+        // ---
+        store.get(id).unwrap().unwrap().state
+    }
+
+    #[tokio::test]
+    async fn test_recover_fail_policy_marks_interrupted_pipelines() {
+        // ---
+        let (store, runtime) = runtime_with(vec![
+            pipeline_in("playing", PipelineState::Playing),
+            pipeline_in("created", PipelineState::Created),
+            pipeline_in("stopped", PipelineState::Stopped),
+        ]);
+
+        assert_eq!(runtime.recover(RestartPolicy::Fail).unwrap(), 1);
+
+        assert!(matches!(
+            state_of(&store, "playing"),
+            PipelineState::Error(ref e) if e == INTERRUPTED_BY_RESTART
+        ));
+        assert!(matches!(
+            state_of(&store, "created"),
+            PipelineState::Created
+        ));
+        assert!(matches!(
+            state_of(&store, "stopped"),
+            PipelineState::Stopped
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_recover_requeue_policy_resets_interrupted_pipelines() {
+        // ---
+        let (store, runtime) = runtime_with(vec![
+            pipeline_in("playing", PipelineState::Playing),
+            pipeline_in("paused", PipelineState::Paused),
+        ]);
+
+        assert_eq!(runtime.recover(RestartPolicy::Requeue).unwrap(), 2);
+
+        assert!(matches!(
+            state_of(&store, "playing"),
+            PipelineState::Created
+        ));
+        assert!(matches!(state_of(&store, "paused"), PipelineState::Created));
+    }
+
+    #[test]
+    fn test_exceeded_limit_checks_runtime_and_output_size() {
+        // ---
+        let output = std::env::temp_dir().join(format!("limit-test-{}.bin", std::process::id()));
+        std::fs::write(&output, [0u8; 100]).unwrap();
+
+        let mut pipeline = pipeline_in("limited", PipelineState::Playing);
+        pipeline.outputs = vec![output.display().to_string()];
+        assert_eq!(
+            exceeded_limit(&pipeline, Duration::from_secs(3600), true),
+            None
+        );
+
+        pipeline.limits = ExecutionLimits {
+            max_runtime_seconds: Some(10),
+            max_output_bytes: Some(50),
+        };
+        let runtime = exceeded_limit(&pipeline, Duration::from_secs(10), false).unwrap();
+        assert!(runtime.contains("max_runtime_seconds"), "{runtime}");
+        assert_eq!(
+            exceeded_limit(&pipeline, Duration::from_secs(9), false),
+            None
+        );
+
+        let output_bytes = exceeded_limit(&pipeline, Duration::ZERO, true).unwrap();
+        assert!(output_bytes.contains("100 bytes written"), "{output_bytes}");
+
+        pipeline.limits.max_output_bytes = Some(100);
+        assert_eq!(exceeded_limit(&pipeline, Duration::ZERO, true), None);
+
+        std::fs::remove_file(&output).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_record_attempt_retries_covered_errors_up_to_max_attempts() {
+        // ---
+        gstreamer::init().unwrap();
+
+        let mut pipeline = pipeline_in("flaky", PipelineState::Playing);
+        pipeline.retry = Some(
+            resolve_retry_policy(RetryPolicy {
+                max_attempts: 2,
+                backoff_seconds: Some(0.0),
+                backoff_multiplier: None,
+                retry_on: Some(vec!["ResourceError".to_string()]),
+            })
+            .unwrap(),
+        );
+        let (store, runtime) = runtime_with(vec![pipeline]);
+        let failed = PipelineState::Error("connection reset".to_string());
+        let code = Some("ResourceError::Read".to_string());
+        let started_at = "2024-09-21T10:30:00+00:00";
+
+        // Errors outside the policy are final
+        let other = Some("StreamError::Decode".to_string());
+        assert!(!runtime.record_attempt("flaky", started_at, &failed, other));
+        assert!(runtime.record_attempt("flaky", started_at, &failed, code.clone()));
+        assert!(matches!(state_of(&store, "flaky"), PipelineState::Created));
+        assert!(!runtime.record_attempt("flaky", started_at, &failed, code.clone()));
+
+        let attempts = store.get("flaky").unwrap().unwrap().attempts;
+        assert_eq!(attempts.len(), 3);
+        assert_eq!(attempts[2].attempt, 3);
+        assert_eq!(attempts[1].error_code, code);
+        assert!(attempts[1].retry_at.is_some());
+        assert!(attempts[2].retry_at.is_none());
+
+        // Successful runs are recorded but never retried
+        assert!(!runtime.record_attempt("flaky", started_at, &PipelineState::Stopped, None));
+    }
+
+    #[tokio::test]
+    async fn test_record_attempt_skips_retry_of_stopped_pipeline() {
+        // ---
+        gstreamer::init().unwrap();
+
+        let mut pipeline = pipeline_in("flaky", PipelineState::Playing);
+        pipeline.retry = Some(
+            resolve_retry_policy(RetryPolicy {
+                max_attempts: 3,
+                backoff_seconds: Some(0.0),
+                backoff_multiplier: None,
+                retry_on: None,
+            })
+            .unwrap(),
+        );
+        let (store, runtime) = runtime_with(vec![pipeline]);
+
+        // Stopped after the execution ended, before its attempt was recorded
+        assert!(runtime.stop("flaky").unwrap());
+        let failed = PipelineState::Error("connection reset".to_string());
+        let code = Some("ResourceError::Read".to_string());
+        assert!(!runtime.record_attempt("flaky", "2024-09-21T10:30:00+00:00", &failed, code));
+
+        let record = store.get("flaky").unwrap().unwrap();
+        assert!(matches!(record.state, PipelineState::Stopped));
+        assert_eq!(record.attempts.len(), 1);
+        assert!(record.attempts[0].retry_at.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_retry_requeues_only_finished_pipelines() {
+        // ---
+        let mut failed = pipeline_in("failed", PipelineState::Error("gone".to_string()));
+        failed.finished_at = Some("2024-09-21T10:31:00+00:00".to_string());
+        failed.published_outputs = vec!["s3://bucket/old.mp4".to_string()];
+        let (store, runtime) =
+            runtime_with(vec![failed, pipeline_in("playing", PipelineState::Playing)]);
+
+        let retried = runtime.retry("failed").unwrap().unwrap();
+        assert!(matches!(retried.state, PipelineState::Created));
+        assert!(retried.finished_at.is_none());
+        assert!(retried.published_outputs.is_empty());
+        assert!(matches!(state_of(&store, "failed"), PipelineState::Created));
+
+        assert!(runtime.retry("playing").unwrap().is_none());
+        assert!(matches!(
+            state_of(&store, "playing"),
+            PipelineState::Playing
+        ));
+        assert!(runtime.retry("missing").unwrap().is_none());
+    }
+}
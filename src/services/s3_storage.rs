@@ -0,0 +1,412 @@
+//! S3-compatible output storage backend.
+//!
+//! Uploads the artifacts of successfully finished pipelines to a bucket on
+//! AWS S3 or any S3-compatible service (MinIO, Ceph, R2, ...). Jobs still
+//! write into a local staging directory first; publishing copies every file
+//! to `{prefix}{pipeline_id}/{path}`, keyed by its path relative to the job
+//! directory, and records the `s3://` URIs.
+//!
+//! # Protocol
+//!
+//! Objects are written with single `PUT` requests using path-style URLs
+//! (`{endpoint}/{bucket}/{key}`), which every S3-compatible service accepts.
+//! Requests are authenticated with AWS Signature Version 4; the body is
+//! streamed from disk and sent as `UNSIGNED-PAYLOAD` so large artifacts never
+//! have to be hashed or buffered in memory. Deleting a pipeline's artifacts
+//! removes the published objects with signed `DELETE` requests.
+//!
+//! Every request is bounded by a connect and an overall timeout, so an
+//! unresponsive endpoint fails the publish instead of hanging a worker.
+
+use anyhow::{bail, Context};
+use chrono::Utc;
+use hmac::{Hmac, Mac};
+use sha2::{Digest, Sha256};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use tokio::runtime::Handle;
+use tokio_util::io::ReaderStream;
+use tracing::{info, warn};
+
+// ---
+
+// Import through gateway
+use crate::models::PipelineInfo;
+
+// ---
+
+// Import from sibling modules
+use super::artifacts::content_type_for;
+use super::storage::{
+    is_inside_job_dir, partition, remove_local_outputs, LocalOutputStorage, OutputStorage,
+};
+
+/// Payload hash placeholder for streamed, unsigned request bodies
+const UNSIGNED_PAYLOAD: &str = "UNSIGNED-PAYLOAD";
+
+/// Headers covered by the request signature, in canonical order
+const SIGNED_HEADERS: &str = "host;x-amz-content-sha256;x-amz-date";
+
+/// Time allowed to establish a connection to the endpoint
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Time allowed for one request, including streaming a large artifact
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(30 * 60);
+
+/// Connection settings for an S3-compatible bucket.
+#[derive(Clone, Debug)]
+pub struct S3Config {
+    // ---
+    /// Service endpoint, e.g. `https://s3.us-east-1.amazonaws.com` or `http://localhost:9000`
+    pub endpoint: String,
+
+    /// Bucket receiving the artifacts
+    pub bucket: String,
+
+    /// Region used in request signatures
+    pub region: String,
+
+    /// Key prefix prepended to every object, e.g. `media/`
+    pub prefix: String,
+
+    /// Access key ID
+    pub access_key: String,
+
+    /// Secret access key
+    pub secret_key: String,
+}
+
+/// Output storage that stages artifacts locally and uploads them to S3.
+pub struct S3OutputStorage {
+    // ---
+    staging: LocalOutputStorage,
+    config: S3Config,
+    client: reqwest::Client,
+    handle: Handle,
+}
+
+impl S3OutputStorage {
+    // ---
+
+    /// Creates the backend with `staging_dir` as the local job directory root.
+    ///
+    /// Must be called from within a Tokio runtime; uploads are driven on it
+    /// from the runtime's blocking worker threads.
+    pub fn new(staging_dir: impl Into<PathBuf>, config: S3Config) -> Self {
+        // ---
+        let client = reqwest::Client::builder()
+            .connect_timeout(CONNECT_TIMEOUT)
+            .timeout(REQUEST_TIMEOUT)
+            .build()
+            .expect("Failed to build S3 HTTP client");
+
+        Self {
+            staging: LocalOutputStorage::new(staging_dir),
+            config,
+            client,
+            handle: Handle::current(),
+        }
+    }
+
+    /// Returns the object key for a file belonging to a pipeline.
+    ///
+    /// Keys mirror the local layout, so owned pipelines share an owner prefix.
+    fn object_key(&self, pipeline: &PipelineInfo, relative: &str) -> String {
+        // ---
+        format!(
+            "{}{}/{}",
+            self.config.prefix,
+            partition(pipeline.owner.as_deref(), &pipeline.id),
+            relative
+        )
+    }
+
+    /// Returns the path-style URL of an object.
+    fn object_url(&self, key: &str) -> anyhow::Result<reqwest::Url> {
+        // ---
+        let encoded_key: Vec<String> = key
+            .split('/')
+            .map(|segment| urlencoding::encode(segment).into_owned())
+            .collect();
+
+        Ok(reqwest::Url::parse(&format!(
+            "{}/{}/{}",
+            self.config.endpoint.trim_end_matches('/'),
+            urlencoding::encode(&self.config.bucket),
+            encoded_key.join("/")
+        ))?)
+    }
+
+    /// Uploads one file with a signed PUT request.
+    async fn put_object(&self, key: &str, path: &Path) -> anyhow::Result<()> {
+        // ---
+        let file = tokio::fs::File::open(path)
+            .await
+            .with_context(|| format!("Failed to open {}", path.display()))?;
+        let len = file.metadata().await?.len();
+
+        let url = self.object_url(key)?;
+        let amz_date = Utc::now().format("%Y%m%dT%H%M%SZ").to_string();
+        let authorization = self.authorization("PUT", &url, &amz_date);
+
+        let response = self
+            .client
+            .put(url)
+            .header("x-amz-date", &amz_date)
+            .header("x-amz-content-sha256", UNSIGNED_PAYLOAD)
+            .header(reqwest::header::AUTHORIZATION, authorization)
+            .header(reqwest::header::CONTENT_LENGTH, len)
+            .header(reqwest::header::CONTENT_TYPE, content_type_for(path))
+            .body(reqwest::Body::wrap_stream(ReaderStream::new(file)))
+            .send()
+            .await
+            .with_context(|| format!("Failed to upload {key}"))?;
+
+        if !response.status().is_success() {
+            let status = response.status().as_u16();
+            let body = response.text().await.unwrap_or_default();
+            bail!("Upload of {key} failed with HTTP {status}: {body}");
+        }
+        Ok(())
+    }
+
+    /// Deletes one object with a signed DELETE request.
+    ///
+    /// S3 reports success for objects that do not exist, so this is idempotent.
+    async fn delete_object(&self, key: &str) -> anyhow::Result<()> {
+        // ---
+        let url = self.object_url(key)?;
+        let amz_date = Utc::now().format("%Y%m%dT%H%M%SZ").to_string();
+        let authorization = self.authorization("DELETE", &url, &amz_date);
+
+        let response = self
+            .client
+            .delete(url)
+            .header("x-amz-date", &amz_date)
+            .header("x-amz-content-sha256", UNSIGNED_PAYLOAD)
+            .header(reqwest::header::AUTHORIZATION, authorization)
+            .send()
+            .await
+            .with_context(|| format!("Failed to delete {key}"))?;
+
+        if !response.status().is_success() {
+            let status = response.status().as_u16();
+            let body = response.text().await.unwrap_or_default();
+            bail!("Deletion of {key} failed with HTTP {status}: {body}");
+        }
+        Ok(())
+    }
+
+    /// Builds the SigV4 `Authorization` header for a request without query
+    /// parameters.
+    fn authorization(&self, method: &str, url: &reqwest::Url, amz_date: &str) -> String {
+        // ---
+        let date = &amz_date[..8];
+        let host = match (url.host_str(), url.port()) {
+            (Some(host), Some(port)) => format!("{host}:{port}"),
+            (Some(host), None) => host.to_string(),
+            (None, _) => String::new(),
+        };
+
+        let canonical_request = format!(
+            "{method}\n{}\n\nhost:{host}\nx-amz-content-sha256:{UNSIGNED_PAYLOAD}\nx-amz-date:{amz_date}\n\n{SIGNED_HEADERS}\n{UNSIGNED_PAYLOAD}",
+            url.path()
+        );
+        let scope = format!("{date}/{}/s3/aws4_request", self.config.region);
+        let string_to_sign = format!(
+            "AWS4-HMAC-SHA256\n{amz_date}\n{scope}\n{}",
+            hex::encode(Sha256::digest(canonical_request.as_bytes()))
+        );
+
+        let key = signing_key(&self.config.secret_key, date, &self.config.region, "s3");
+        let signature = hex::encode(hmac_sha256(&key, string_to_sign.as_bytes()));
+
+        format!(
+            "AWS4-HMAC-SHA256 Credential={}/{scope}, SignedHeaders={SIGNED_HEADERS}, Signature={signature}",
+            self.config.access_key
+        )
+    }
+}
+
+impl OutputStorage for S3OutputStorage {
+    // ---
+
+    fn job_dir(&self, owner: Option<&str>, pipeline_id: &str) -> PathBuf {
+        // ---
+        self.staging.job_dir(owner, pipeline_id)
+    }
+
+    fn publish(&self, pipeline: &PipelineInfo) -> anyhow::Result<Vec<String>> {
+        // ---
+        let job_dir = self.job_dir(pipeline.owner.as_deref(), &pipeline.id);
+        let mut files = Vec::new();
+        for output in pipeline.outputs.iter().map(Path::new) {
+            collect_files(&job_dir, output, &mut files)?;
+        }
+
+        let mut locations = Vec::new();
+        for (relative, path) in files {
+            let key = self.object_key(pipeline, &relative);
+            self.handle.block_on(self.put_object(&key, &path))?;
+            locations.push(format!("s3://{}/{}", self.config.bucket, key));
+        }
+
+        info!(
+            "Uploaded {} artifact(s) of pipeline {} to bucket {}",
+            locations.len(),
+            pipeline.id,
+            self.config.bucket
+        );
+        Ok(locations)
+    }
+
+    fn delete(&self, pipeline: &PipelineInfo) -> anyhow::Result<()> {
+        // ---
+        let bucket_prefix = format!("s3://{}/", self.config.bucket);
+        for location in &pipeline.published_outputs {
+            if let Some(key) = location.strip_prefix(&bucket_prefix) {
+                self.handle.block_on(self.delete_object(key))?;
+            }
+        }
+
+        remove_local_outputs(
+            &self.job_dir(pipeline.owner.as_deref(), &pipeline.id),
+            pipeline,
+        )
+    }
+}
+
+/// Collects `(relative name, path)` for every file at or below `path`.
+///
+/// Files are named by their path relative to `job_dir`, so outputs in
+/// different subdirectories never share a name, and each file is collected
+/// once even when outputs overlap. Missing outputs are skipped, since sinks
+/// may legitimately write nothing. So are paths that resolve outside
+/// `job_dir`, so nothing but the job's own files is ever uploaded.
+fn collect_files(
+    job_dir: &Path,
+    path: &Path,
+    files: &mut Vec<(String, PathBuf)>,
+) -> anyhow::Result<()> {
+    // ---
+    if path.exists() && !is_inside_job_dir(job_dir, path) {
+        warn!(
+            "Not publishing {}: outside the job directory",
+            path.display()
+        );
+        return Ok(());
+    }
+
+    if path.is_file() {
+        let relative = path
+            .strip_prefix(job_dir)
+            .ok()
+            .map(|relative| relative.to_string_lossy().replace('\\', "/"));
+        if let Some(relative) = relative.filter(|relative| !collected(files, relative)) {
+            files.push((relative, path.to_path_buf()));
+        }
+    } else if path.is_dir() {
+        let mut entries: Vec<PathBuf> = std::fs::read_dir(path)?
+            .map(|entry| entry.map(|entry| entry.path()))
+            .collect::<Result<_, _>>()?;
+        entries.sort();
+        for entry in entries {
+            collect_files(job_dir, &entry, files)?;
+        }
+    }
+    Ok(())
+}
+
+/// Returns true if a file named `relative` was already collected.
+fn collected(files: &[(String, PathBuf)], relative: &str) -> bool {
+    // ---
+    files.iter().any(|(name, _)| name == relative)
+}
+
+/// Computes HMAC-SHA256 of `data` under `key`.
+fn hmac_sha256(key: &[u8], data: &[u8]) -> Vec<u8> {
+    // ---
+    let mut mac = Hmac::<Sha256>::new_from_slice(key).expect("HMAC accepts keys of any size");
+    mac.update(data);
+    mac.finalize().into_bytes().to_vec()
+}
+
+/// Derives the SigV4 signing key for a date, region and service.
+fn signing_key(secret_key: &str, date: &str, region: &str, service: &str) -> Vec<u8> {
+    // ---
+    let date_key = hmac_sha256(format!("AWS4{secret_key}").as_bytes(), date.as_bytes());
+    let region_key = hmac_sha256(&date_key, region.as_bytes());
+    let service_key = hmac_sha256(&region_key, service.as_bytes());
+    hmac_sha256(&service_key, b"aws4_request")
+}
+
+#[cfg(test)]
+mod tests {
+    // ---
+
+    use super::*;
+
+    #[test]
+    fn test_signing_key_matches_aws_example() {
+        // ---
+        // Example from the AWS "Deriving the signing key" documentation
+        let key = signing_key(
+            "wJalrXUtnFEMI/K7MDENG+bPxRfiCYEXAMPLEKEY",
+            "20120215",
+            "us-east-1",
+            "iam",
+        );
+        assert_eq!(
+            hex::encode(key),
+            "f4780e2d9f65fa895f9c67b32ce1baf0b0d8a43505a000a1a9e090d414db404d"
+        );
+    }
+
+    #[test]
+    fn test_collect_files_names_directory_contents_relatively() {
+        // ---
+        let root = std::env::temp_dir().join(format!("collect-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(root.join("stream")).unwrap();
+        let outside = std::env::temp_dir().join(format!("outside-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&outside).unwrap();
+        std::fs::write(outside.join("secret.txt"), b"secret").unwrap();
+        std::os::unix::fs::symlink(outside.join("secret.txt"), root.join("stream/link.txt"))
+            .unwrap();
+        std::fs::write(root.join("stream/playlist.m3u8"), b"#EXTM3U").unwrap();
+        std::fs::write(root.join("stream/segment_00000.ts"), b"ts").unwrap();
+        std::fs::write(root.join("output.webm"), b"webm").unwrap();
+        for dir in ["a", "b"] {
+            std::fs::create_dir_all(root.join(dir)).unwrap();
+            std::fs::write(root.join(dir).join("out.ts"), dir).unwrap();
+        }
+
+        let mut files = Vec::new();
+        for output in [
+            "output.webm",
+            "stream",
+            "stream/playlist.m3u8",
+            "a",
+            "b",
+            "missing",
+        ] {
+            collect_files(&root, &root.join(output), &mut files).unwrap();
+        }
+        // Outputs outside the job directory are never uploaded
+        collect_files(&root, &outside, &mut files).unwrap();
+
+        let names: Vec<&str> = files.iter().map(|(name, _)| name.as_str()).collect();
+        assert_eq!(
+            names,
+            vec![
+                "output.webm",
+                "stream/playlist.m3u8",
+                "stream/segment_00000.ts",
+                "a/out.ts",
+                "b/out.ts"
+            ]
+        );
+
+        let _ = std::fs::remove_dir_all(&root);
+        let _ = std::fs::remove_dir_all(&outside);
+    }
+}
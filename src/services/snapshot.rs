@@ -0,0 +1,265 @@
+//! Still frames grabbed from running pipelines.
+//!
+//! Sinks keep the last buffer they rendered in their `last-sample`
+//! property, so a snapshot never touches the running job: the most recent
+//! raw video frame is copied from a sink and encoded on demand by a
+//! separate, short-lived `appsrc ! videoconvert ! <encoder> ! appsink`
+//! pipeline.
+//!
+//! # Frame Sources
+//!
+//! Only sinks that receive raw video (`video/x-raw`) hold a usable frame.
+//! Pipelines that encode to a file or stream can add a branch for
+//! snapshots, e.g. `... ! tee name=t ! queue ! x264enc ! ... t. ! queue
+//! leaky=downstream ! fakesink name=snapshot`, and select it with
+//! `?element=snapshot`.
+//!
+//! # Encoding
+//!
+//! Encoding uses GStreamer's `videoconvert` and `jpegenc`/`pngenc`
+//! (gst-plugins-base and -good) plus the `app` elements; when one of them is
+//! not installed snapshots are reported as unavailable.
+
+use gstreamer::prelude::*;
+
+// ---
+
+// Import from sibling module
+use super::runtime::PipelineRuntime;
+
+/// How long encoding a single frame may take
+const ENCODE_TIMEOUT: gstreamer::ClockTime = gstreamer::ClockTime::from_seconds(5);
+
+/// Image formats snapshots can be encoded to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SnapshotFormat {
+    // ---
+    /// JPEG via `jpegenc`
+    Jpeg,
+
+    /// PNG via `pngenc`
+    Png,
+}
+
+impl SnapshotFormat {
+    // ---
+
+    /// Returns the `Content-Type` of snapshots in this format.
+    pub fn content_type(&self) -> &'static str {
+        // ---
+        match self {
+            Self::Jpeg => "image/jpeg",
+            Self::Png => "image/png",
+        }
+    }
+
+    /// Returns the GStreamer element that encodes this format.
+    fn encoder(&self) -> &'static str {
+        // ---
+        match self {
+            Self::Jpeg => "jpegenc",
+            Self::Png => "pngenc",
+        }
+    }
+}
+
+/// Reasons a snapshot could not be taken.
+#[derive(Debug, PartialEq, Eq)]
+pub enum SnapshotError {
+    // ---
+    /// The pipeline is not executing on a worker
+    NotRunning,
+
+    /// No element with the requested name exists in the pipeline
+    ElementNotFound(String),
+
+    /// No sink holds a raw video frame (yet)
+    NoFrame,
+
+    /// The named GStreamer element needed for encoding is not installed
+    Unavailable(String),
+
+    /// Encoding failed; the message explains why
+    Failed(String),
+}
+
+/// Encodes the most recent video frame of a running pipeline.
+///
+/// Blocks while the frame is encoded.
+///
+/// # Arguments
+/// * `runtime`     - Runtime holding the live pipeline
+/// * `pipeline_id` - Pipeline to take the frame from
+/// * `element`     - Sink to take the frame from; by default the first sink
+///   holding a raw video frame
+/// * `format`      - Image format to encode to
+pub fn take_snapshot(
+    runtime: &PipelineRuntime,
+    pipeline_id: &str,
+    element: Option<&str>,
+    format: SnapshotFormat,
+) -> Result<Vec<u8>, SnapshotError> {
+    // ---
+    let pipeline = runtime
+        .live_pipeline(pipeline_id)
+        .ok_or(SnapshotError::NotRunning)?;
+
+    let sample = match element {
+        Some(name) => {
+            let sink = pipeline
+                .by_name(name)
+                .ok_or_else(|| SnapshotError::ElementNotFound(name.to_string()))?;
+            video_sample(&sink)
+        }
+        None => pipeline
+            .iterate_sinks()
+            .into_iter()
+            .filter_map(Result::ok)
+            .find_map(|sink| video_sample(&sink)),
+    }
+    .ok_or(SnapshotError::NoFrame)?;
+
+    encode_sample(&sample, format)
+}
+
+/// Returns the last raw video frame a sink rendered, if any.
+fn video_sample(sink: &gstreamer::Element) -> Option<gstreamer::Sample> {
+    // ---
+    sink.find_property("last-sample")?;
+    let sample = sink.property::<Option<gstreamer::Sample>>("last-sample")?;
+    let is_video = sample
+        .caps()
+        .and_then(|caps| caps.structure(0))
+        .is_some_and(|structure| structure.name() == "video/x-raw");
+    (is_video && sample.buffer().is_some()).then_some(sample)
+}
+
+/// Encodes a raw video sample with a throwaway encoding pipeline.
+fn encode_sample(
+    sample: &gstreamer::Sample,
+    format: SnapshotFormat,
+) -> Result<Vec<u8>, SnapshotError> {
+    // ---
+    let make = |factory: &str| {
+        gstreamer::ElementFactory::make(factory)
+            .build()
+            .map_err(|_| SnapshotError::Unavailable(factory.to_string()))
+    };
+    let source = make("appsrc")?;
+    let convert = make("videoconvert")?;
+    let encoder = make(format.encoder())?;
+    let sink = make("appsink")?;
+
+    source.set_property("caps", sample.caps_owned());
+    source.set_property_from_str("format", "time");
+    sink.set_property("sync", false);
+
+    let pipeline = gstreamer::Pipeline::new();
+    pipeline
+        .add_many([&source, &convert, &encoder, &sink])
+        .map_err(|e| SnapshotError::Failed(e.to_string()))?;
+    gstreamer::Element::link_many([&source, &convert, &encoder, &sink])
+        .map_err(|e| SnapshotError::Failed(e.to_string()))?;
+
+    // The frame keeps its original timestamps, which mean nothing here
+    let mut buffer = sample
+        .buffer_owned()
+        .ok_or_else(|| SnapshotError::Failed("Sample has no buffer".to_string()))?;
+    {
+        let buffer = buffer.make_mut();
+        buffer.set_pts(gstreamer::ClockTime::ZERO);
+        buffer.set_dts(gstreamer::ClockTime::NONE);
+    }
+
+    pipeline
+        .set_state(gstreamer::State::Playing)
+        .map_err(|e| SnapshotError::Failed(e.to_string()))?;
+    let pushed = source.emit_by_name::<gstreamer::FlowReturn>("push-buffer", &[&buffer]);
+    if pushed == gstreamer::FlowReturn::Ok {
+        let _ = source.emit_by_name::<gstreamer::FlowReturn>("end-of-stream", &[]);
+    }
+
+    let encoded = sink.emit_by_name::<Option<gstreamer::Sample>>(
+        "try-pull-sample",
+        &[&ENCODE_TIMEOUT.nseconds()],
+    );
+    let error = pipeline.bus().and_then(|bus| {
+        bus.pop_filtered(&[gstreamer::MessageType::Error])
+            .and_then(|message| match message.view() {
+                gstreamer::MessageView::Error(err) => Some(err.error().to_string()),
+                _ => None,
+            })
+    });
+    let _ = pipeline.set_state(gstreamer::State::Null);
+
+    let encoded = encoded.ok_or_else(|| {
+        SnapshotError::Failed(error.unwrap_or_else(|| "Encoder produced no image".to_string()))
+    })?;
+    let map = encoded
+        .buffer()
+        .ok_or_else(|| SnapshotError::Failed("Encoder produced no image".to_string()))?
+        .map_readable()
+        .map_err(|e| SnapshotError::Failed(e.to_string()))?;
+    Ok(map.as_slice().to_vec())
+}
+
+#[cfg(test)]
+mod tests {
+    // ---
+
+    use super::*;
+
+    /// Runs `launch` to completion and returns the frame held by its sink
+    /// named "out", before shutting down clears it.
+    fn last_video_sample(launch: &str) -> Option<gstreamer::Sample> {
+        // ---
+        gstreamer::init().unwrap();
+        let pipeline = gstreamer::parse_launch(launch)
+            .unwrap()
+            .downcast::<gstreamer::Pipeline>()
+            .unwrap();
+        pipeline.set_state(gstreamer::State::Playing).unwrap();
+        let bus = pipeline.bus().unwrap();
+        bus.timed_pop_filtered(
+            gstreamer::ClockTime::from_seconds(5),
+            &[gstreamer::MessageType::Eos, gstreamer::MessageType::Error],
+        )
+        .unwrap();
+        let sample = video_sample(&pipeline.by_name("out").unwrap());
+        pipeline.set_state(gstreamer::State::Null).unwrap();
+        sample
+    }
+
+    const RGB_FRAME: &str = "fakesrc num-buffers=1 sizetype=fixed sizemax=48 \
+        ! video/x-raw,format=RGB,width=4,height=4,framerate=1/1 ! fakesink name=out";
+
+    #[test]
+    fn test_video_sample_requires_raw_video() {
+        // ---
+        let sample = last_video_sample(RGB_FRAME).unwrap();
+        assert_eq!(sample.buffer().unwrap().size(), 48);
+
+        assert!(last_video_sample(
+            "fakesrc num-buffers=1 ! audio/x-raw,rate=8000 ! fakesink name=out"
+        )
+        .is_none());
+        assert!(last_video_sample(
+            "fakesrc num-buffers=1 ! video/x-raw ! fakesink name=out enable-last-sample=false"
+        )
+        .is_none());
+    }
+
+    #[test]
+    fn test_encode_sample_reports_missing_elements() {
+        // ---
+        let sample = last_video_sample(RGB_FRAME).unwrap();
+
+        match encode_sample(&sample, SnapshotFormat::Png) {
+            Ok(image) => assert!(image.starts_with(b"\x89PNG")),
+            Err(SnapshotError::Unavailable(element)) => {
+                assert!(["appsrc", "videoconvert", "pngenc", "appsink"].contains(&element.as_str()))
+            }
+            Err(e) => panic!("unexpected error: {e:?}"),
+        }
+    }
+}
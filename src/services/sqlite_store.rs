@@ -0,0 +1,372 @@
+//! SQLite-backed persistent pipeline store.
+//!
+//! Keeps pipeline records in a single SQLite database file so job history,
+//! webhook delivery attempts and final states survive service restarts.
+//!
+//! # Schema
+//!
+//! ```text
+//! pipelines(id TEXT PRIMARY KEY, state TEXT, created_at TEXT, record TEXT)
+//! ```
+//!
+//! `record` holds the full `PipelineInfo` as JSON, so new record fields need
+//! no migration. `state` and `created_at` are denormalized copies kept for
+//! querying without decoding every row; an index on `(created_at, id)` keeps
+//! filtered, paginated listings fast with large job histories.
+
+use anyhow::Context;
+use rusqlite::{params, params_from_iter, Connection, OptionalExtension};
+use std::path::Path;
+use std::sync::Mutex;
+
+// ---
+
+// Import through gateway
+use crate::models::PipelineInfo;
+
+// ---
+
+// Import from sibling module
+use super::store::{PipelineQuery, PipelineStore, SortOrder};
+
+/// Pipeline store persisting records to a SQLite database file.
+///
+/// A single connection is shared behind a mutex; SQLite serializes writers
+/// anyway and pipeline record traffic is small.
+pub struct SqlitePipelineStore {
+    // ---
+    connection: Mutex<Connection>,
+}
+
+impl SqlitePipelineStore {
+    // ---
+
+    /// Opens (creating if needed) the database at `path` and ensures the
+    /// schema exists.
+    ///
+    /// # Errors
+    /// Fails if the file cannot be opened or is not a SQLite database.
+    pub fn open(path: &Path) -> anyhow::Result<Self> {
+        // ---
+        let connection = Connection::open(path)
+            .with_context(|| format!("Failed to open pipeline database {}", path.display()))?;
+
+        connection.execute_batch(
+            "PRAGMA journal_mode = WAL;
+             CREATE TABLE IF NOT EXISTS pipelines (
+                 id         TEXT PRIMARY KEY,
+                 state      TEXT NOT NULL,
+                 created_at TEXT NOT NULL,
+                 record     TEXT NOT NULL
+             );
+             CREATE INDEX IF NOT EXISTS pipelines_created_at ON pipelines (created_at, id);",
+        )?;
+
+        Ok(Self {
+            connection: Mutex::new(connection),
+        })
+    }
+}
+
+/// Writes a record, replacing any existing row with the same ID.
+fn write_record(connection: &Connection, pipeline: &PipelineInfo) -> anyhow::Result<()> {
+    // ---
+    connection.execute(
+        "INSERT OR REPLACE INTO pipelines (id, state, created_at, record) VALUES (?1, ?2, ?3, ?4)",
+        params![
+            pipeline.id,
+            pipeline.state.name(),
+            pipeline.created_at,
+            serde_json::to_string(pipeline)?
+        ],
+    )?;
+    Ok(())
+}
+
+/// Reads and decodes the record with the given ID.
+fn read_record(connection: &Connection, id: &str) -> anyhow::Result<Option<PipelineInfo>> {
+    // ---
+    let record: Option<String> = connection
+        .query_row(
+            "SELECT record FROM pipelines WHERE id = ?1",
+            params![id],
+            |row| row.get(0),
+        )
+        .optional()?;
+
+    record
+        .map(|record| {
+            serde_json::from_str(&record)
+                .with_context(|| format!("Corrupt pipeline record for {id}"))
+        })
+        .transpose()
+}
+
+/// Builds the WHERE clause and bound values for a listing query.
+///
+/// Every user-supplied value is bound as a parameter, never interpolated.
+fn where_clause(query: &PipelineQuery) -> (String, Vec<String>) {
+    // ---
+    let mut conditions = Vec::new();
+    let mut values = Vec::new();
+
+    let mut bind = |condition: &str, value: String| {
+        values.push(value);
+        conditions.push(condition.replace('?', &format!("?{}", values.len())));
+    };
+
+    if let Some(state) = query.state {
+        bind("state = ?", state.to_string());
+    }
+    if let Some(text) = &query.description {
+        bind(
+            "instr(lower(json_extract(record, '$.description')), lower(?)) > 0",
+            text.clone(),
+        );
+    }
+    if let Some(source_url) = &query.source_url {
+        bind(
+            "json_extract(record, '$.source_url') = ?",
+            source_url.clone(),
+        );
+    }
+    if let Some(owner) = &query.owner {
+        bind("json_extract(record, '$.owner') = ?", owner.clone());
+    }
+    if let Some(after) = &query.created_after {
+        bind("created_at > ?", after.clone());
+    }
+    if let Some(before) = &query.created_before {
+        bind("created_at < ?", before.clone());
+    }
+
+    if let Some(cursor) = &query.after {
+        let op = match query.order {
+            SortOrder::Ascending => ">",
+            SortOrder::Descending => "<",
+        };
+        values.push(cursor.created_at.clone());
+        let created_at = values.len();
+        values.push(cursor.id.clone());
+        let id = values.len();
+        conditions.push(format!(
+            "(created_at {op} ?{created_at} OR (created_at = ?{created_at} AND id {op} ?{id}))"
+        ));
+    }
+
+    if conditions.is_empty() {
+        (String::new(), values)
+    } else {
+        (format!("WHERE {}", conditions.join(" AND ")), values)
+    }
+}
+
+impl PipelineStore for SqlitePipelineStore {
+    // ---
+
+    fn insert(&self, pipeline: PipelineInfo) -> anyhow::Result<()> {
+        // ---
+        let connection = self.connection.lock().unwrap();
+        write_record(&connection, &pipeline)
+    }
+
+    fn get(&self, id: &str) -> anyhow::Result<Option<PipelineInfo>> {
+        // ---
+        let connection = self.connection.lock().unwrap();
+        read_record(&connection, id)
+    }
+
+    fn list(&self) -> anyhow::Result<Vec<PipelineInfo>> {
+        // ---
+        let connection = self.connection.lock().unwrap();
+        let mut statement = connection.prepare("SELECT record FROM pipelines")?;
+        let records = statement.query_map([], |row| row.get::<_, String>(0))?;
+
+        records
+            .map(|record| Ok(serde_json::from_str(&record?)?))
+            .collect()
+    }
+
+    fn query(&self, query: &PipelineQuery) -> anyhow::Result<Vec<PipelineInfo>> {
+        // ---
+        let (filter, values) = where_clause(query);
+        let direction = match query.order {
+            SortOrder::Ascending => "ASC",
+            SortOrder::Descending => "DESC",
+        };
+        let sql = format!(
+            "SELECT record FROM pipelines {filter} ORDER BY created_at {direction}, id {direction} LIMIT {}",
+            query.limit
+        );
+
+        let connection = self.connection.lock().unwrap();
+        let mut statement = connection.prepare(&sql)?;
+        let records = statement.query_map(params_from_iter(values.iter()), |row| {
+            row.get::<_, String>(0)
+        })?;
+
+        records
+            .map(|record| Ok(serde_json::from_str(&record?)?))
+            .collect()
+    }
+
+    fn delete(&self, id: &str) -> anyhow::Result<bool> {
+        // ---
+        let connection = self.connection.lock().unwrap();
+        let deleted = connection.execute("DELETE FROM pipelines WHERE id = ?1", params![id])?;
+        Ok(deleted > 0)
+    }
+
+    fn update(
+        &self,
+        id: &str,
+        apply: &mut dyn FnMut(&mut PipelineInfo),
+    ) -> anyhow::Result<Option<PipelineInfo>> {
+        // ---
+        let mut connection = self.connection.lock().unwrap();
+        let transaction = connection.transaction()?;
+
+        let Some(mut pipeline) = read_record(&transaction, id)? else {
+            return Ok(None);
+        };
+        apply(&mut pipeline);
+        write_record(&transaction, &pipeline)?;
+
+        transaction.commit()?;
+        Ok(Some(pipeline))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    // ---
+
+    use super::*;
+    use crate::models::PipelineState;
+
+    use crate::services::store::Cursor;
+
+    fn sample_pipeline(id: &str) -> PipelineInfo {
+        // ---
+        PipelineInfo {
+            outputs: vec!["output.webm".to_string()],
+            ..PipelineInfo::test(id, None, PipelineState::Created)
+        }
+    }
+
+    fn temp_database(name: &str) -> std::path::PathBuf {
+        // ---
+        let path = std::env::temp_dir().join(format!(
+            "media-pipeline-{}-{}.db",
+            name,
+            uuid::Uuid::new_v4()
+        ));
+        let _ = std::fs::remove_file(&path);
+        path
+    }
+
+    #[test]
+    fn test_records_survive_reopen() {
+        // ---
+        let path = temp_database("reopen");
+
+        {
+            let store = SqlitePipelineStore::open(&path).unwrap();
+            store.insert(sample_pipeline("a")).unwrap();
+            store
+                .update("a", &mut |pipeline| {
+                    pipeline.state = PipelineState::Error("boom".to_string())
+                })
+                .unwrap();
+        }
+
+        let store = SqlitePipelineStore::open(&path).unwrap();
+        let pipeline = store.get("a").unwrap().expect("record should persist");
+        assert!(matches!(pipeline.state, PipelineState::Error(ref e) if e == "boom"));
+        assert_eq!(pipeline.outputs, vec!["output.webm".to_string()]);
+        assert_eq!(store.list().unwrap().len(), 1);
+
+        assert!(store.delete("a").unwrap());
+        assert!(!store.delete("a").unwrap());
+        assert!(store.get("a").unwrap().is_none());
+
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[test]
+    fn test_missing_records() {
+        // ---
+        let path = temp_database("missing");
+        let store = SqlitePipelineStore::open(&path).unwrap();
+
+        assert!(store.get("nope").unwrap().is_none());
+        let updated = store
+            .update("nope", &mut |_| panic!("must not be called"))
+            .unwrap();
+        assert!(updated.is_none());
+
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[test]
+    fn test_query_filters_and_pages() {
+        // ---
+        let path = temp_database("query");
+        let store = SqlitePipelineStore::open(&path).unwrap();
+
+        for (id, day, description) in [
+            ("a", 1, "Convert to webm"),
+            ("b", 2, "Generate thumbnail"),
+            ("c", 3, "Convert to mp4"),
+        ] {
+            let mut pipeline = sample_pipeline(id);
+            pipeline.created_at = format!("2024-01-0{day}T00:00:00+00:00");
+            pipeline.description = description.to_string();
+            pipeline.owner = (id == "b").then(|| "ingest".to_string());
+            store.insert(pipeline).unwrap();
+        }
+
+        let query = PipelineQuery {
+            description: Some("convert".to_string()),
+            limit: 1,
+            ..Default::default()
+        };
+        let first = store.page(&query).unwrap();
+        assert_eq!(first.pipelines[0].id, "c");
+        assert_eq!(first.next_cursor, Some(Cursor::after(&first.pipelines[0])));
+
+        let query = PipelineQuery {
+            after: first.next_cursor,
+            ..query
+        };
+        let second = store.page(&query).unwrap();
+        assert_eq!(second.pipelines[0].id, "a");
+        assert!(second.next_cursor.is_none());
+
+        let query = PipelineQuery {
+            state: Some("Created"),
+            created_after: Some("2024-01-01T00:00:00+00:00".to_string()),
+            order: SortOrder::Ascending,
+            limit: 10,
+            ..Default::default()
+        };
+        let ids: Vec<String> = store
+            .query(&query)
+            .unwrap()
+            .into_iter()
+            .map(|p| p.id)
+            .collect();
+        assert_eq!(ids, vec!["b", "c"]);
+
+        let query = PipelineQuery {
+            owner: Some("ingest".to_string()),
+            limit: 10,
+            ..Default::default()
+        };
+        let owned = store.query(&query).unwrap();
+        assert_eq!(owned.len(), 1);
+        assert_eq!(owned[0].id, "b");
+
+        let _ = std::fs::remove_file(&path);
+    }
+}
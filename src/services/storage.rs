@@ -0,0 +1,294 @@
+//! Output storage abstraction and local-directory implementation.
+//!
+//! Every job writes its artifacts into its own directory below the configured
+//! `--output-dir`, named after the pipeline ID. Jobs created by an
+//! authenticated caller are partitioned by owner (`key:<id>` or
+//! `tenant:<tenant>`), as `{output-dir}/{owner}/{id}/`. The [`OutputStorage`] trait
+//! decides where those directories live and what happens to their contents
+//! once a pipeline finishes successfully.
+//!
+//! # Backends
+//!
+//! - [`LocalOutputStorage`]: Artifacts stay in the job directory
+//! - `S3OutputStorage`: Artifacts are additionally uploaded to an
+//!   S3-compatible bucket, and the object URIs are recorded on the pipeline
+//!
+//! Local copies are kept in both cases so the download endpoints can serve
+//! them directly.
+
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use tracing::warn;
+
+// ---
+
+// Import through gateway
+use crate::models::PipelineInfo;
+
+/// Shared handle to the configured output storage backend.
+pub type SharedOutputStorage = Arc<dyn OutputStorage>;
+
+/// Storage backend for pipeline output artifacts.
+///
+/// `publish` runs on the runtime's blocking worker threads, so
+/// implementations may block on I/O.
+pub trait OutputStorage: Send + Sync {
+    // ---
+
+    /// Returns the local directory a job writes its artifacts to.
+    ///
+    /// `owner` is the pipeline's owner, if any; owned jobs live in a
+    /// per-owner subdirectory. The directory is not created; see
+    /// [`OutputStorage::prepare_job_dir`].
+    fn job_dir(&self, owner: Option<&str>, pipeline_id: &str) -> PathBuf;
+
+    /// Creates the job directory so pipeline sinks can write into it.
+    fn prepare_job_dir(&self, owner: Option<&str>, pipeline_id: &str) -> anyhow::Result<PathBuf> {
+        // ---
+        let dir = self.job_dir(owner, pipeline_id);
+        std::fs::create_dir_all(&dir)?;
+        Ok(dir)
+    }
+
+    /// Makes the outputs of a successfully finished pipeline durable.
+    ///
+    /// # Returns
+    /// Locations of the published artifacts (e.g. `s3://bucket/key`), recorded
+    /// as the pipeline's `published_outputs`; empty when nothing is copied.
+    ///
+    /// # Errors
+    /// Fails if any artifact cannot be published; the pipeline is then marked
+    /// as failed.
+    fn publish(&self, pipeline: &PipelineInfo) -> anyhow::Result<Vec<String>>;
+
+    /// Deletes every artifact of a pipeline, local and published.
+    ///
+    /// Missing artifacts are not an error, so deletion can be retried.
+    fn delete(&self, pipeline: &PipelineInfo) -> anyhow::Result<()>;
+}
+
+/// Keeps artifacts in per-job subdirectories of a local directory.
+pub struct LocalOutputStorage {
+    // ---
+    root: PathBuf,
+}
+
+impl LocalOutputStorage {
+    // ---
+
+    /// Creates a storage rooted at `root`, which is created on demand.
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        // ---
+        Self { root: root.into() }
+    }
+}
+
+impl OutputStorage for LocalOutputStorage {
+    // ---
+
+    fn job_dir(&self, owner: Option<&str>, pipeline_id: &str) -> PathBuf {
+        // ---
+        self.root.join(partition(owner, pipeline_id))
+    }
+
+    fn publish(&self, _pipeline: &PipelineInfo) -> anyhow::Result<Vec<String>> {
+        // ---
+        Ok(Vec::new())
+    }
+
+    fn delete(&self, pipeline: &PipelineInfo) -> anyhow::Result<()> {
+        // ---
+        remove_local_outputs(
+            &self.job_dir(pipeline.owner.as_deref(), &pipeline.id),
+            pipeline,
+        )
+    }
+}
+
+/// Returns the relative location of a job's artifacts: `{owner}/{id}` or `{id}`.
+///
+/// Owners are validated as single path segments when callers authenticate.
+pub(super) fn partition(owner: Option<&str>, pipeline_id: &str) -> String {
+    // ---
+    match owner {
+        Some(owner) => format!("{owner}/{pipeline_id}"),
+        None => pipeline_id.to_string(),
+    }
+}
+
+/// Builds the link a client should use to reach `path` on this service.
+///
+/// Relative to the service root unless a `--public-url` base is configured.
+/// Used for every link handed out, in responses and webhooks alike.
+pub fn public_link(public_url: Option<&str>, path: &str) -> String {
+    // ---
+    format!("{}{path}", public_url.unwrap_or_default())
+}
+
+/// Resolves a file sink location of a custom pipeline against its job
+/// directory.
+///
+/// Relative locations are placed inside `job_dir`; absolute locations must
+/// already point into it. Locations climbing out with `..` are refused, so
+/// custom pipelines can only record outputs in their own directory.
+///
+/// # Errors
+/// Describes the location that escapes the job directory.
+pub fn resolve_output_location(job_dir: &Path, location: &str) -> Result<PathBuf, String> {
+    // ---
+    let relative = Path::new(location);
+    let path = job_dir.join(relative);
+    let escapes = relative
+        .components()
+        .any(|component| matches!(component, std::path::Component::ParentDir));
+
+    if escapes || !path.starts_with(job_dir) || path == job_dir {
+        return Err(format!(
+            "Output location '{location}' is outside the job directory; use a relative file name"
+        ));
+    }
+    Ok(path)
+}
+
+/// Returns true if `path` exists and, with symlinks resolved, lies inside
+/// `job_dir`.
+///
+/// Guards every operation on recorded outputs, which records written before
+/// locations were confined may point anywhere.
+pub(super) fn is_inside_job_dir(job_dir: &Path, path: &Path) -> bool {
+    // ---
+    match (job_dir.canonicalize(), path.canonicalize()) {
+        (Ok(job_dir), Ok(path)) => path.starts_with(job_dir),
+        _ => false,
+    }
+}
+
+/// Removes a pipeline's recorded outputs and its job directory from disk.
+///
+/// Shared by every backend, since all of them stage artifacts locally.
+/// Recorded outputs that do not resolve to inside the job directory are
+/// logged and left alone.
+pub(super) fn remove_local_outputs(job_dir: &Path, pipeline: &PipelineInfo) -> anyhow::Result<()> {
+    // ---
+    let outputs = pipeline.outputs.iter().map(PathBuf::from).filter(|path| {
+        if path.exists() && !is_inside_job_dir(job_dir, path) {
+            warn!("Not deleting {}: outside the job directory", path.display());
+            return false;
+        }
+        true
+    });
+    let paths = outputs.chain(std::iter::once(job_dir.to_path_buf()));
+
+    for path in paths {
+        let result = if path.is_dir() {
+            std::fs::remove_dir_all(&path)
+        } else {
+            std::fs::remove_file(&path)
+        };
+
+        match result {
+            Ok(()) => {}
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+            Err(e) => {
+                return Err(anyhow::Error::new(e)
+                    .context(format!("Failed to delete output {}", path.display())))
+            }
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    // ---
+
+    use super::*;
+    use crate::models::{PipelineKind, PipelineState};
+
+    #[test]
+    fn test_local_storage_uses_per_job_directories() {
+        // ---
+        let root = std::env::temp_dir().join(format!("outputs-{}", uuid::Uuid::new_v4()));
+        let storage = LocalOutputStorage::new(&root);
+
+        assert_eq!(storage.job_dir(None, "abc"), root.join("abc"));
+        assert_eq!(
+            storage.job_dir(Some("team-a"), "abc"),
+            root.join("team-a").join("abc")
+        );
+        let dir = storage.prepare_job_dir(None, "abc").unwrap();
+        assert!(dir.is_dir());
+
+        let output = dir.join("output.webm");
+        std::fs::write(&output, b"data").unwrap();
+        let pipeline = PipelineInfo {
+            kind: PipelineKind::Convert,
+            outputs: vec![output.display().to_string()],
+            ..PipelineInfo::test("abc", None, PipelineState::Stopped)
+        };
+
+        storage.delete(&pipeline).unwrap();
+        assert!(!dir.exists());
+        storage.delete(&pipeline).unwrap();
+
+        let _ = std::fs::remove_dir_all(&root);
+    }
+
+    #[test]
+    fn test_delete_skips_outputs_outside_job_dir() {
+        // ---
+        let root = std::env::temp_dir().join(format!("outputs-{}", uuid::Uuid::new_v4()));
+        let storage = LocalOutputStorage::new(root.join("jobs"));
+        let dir = storage.prepare_job_dir(None, "abc").unwrap();
+
+        let outside_dir = root.join("outside");
+        std::fs::create_dir_all(&outside_dir).unwrap();
+        let outside_file = root.join("keep.txt");
+        std::fs::write(&outside_file, b"keep").unwrap();
+        let inside = dir.join("out.bin");
+        std::fs::write(&inside, b"data").unwrap();
+
+        let pipeline = PipelineInfo {
+            outputs: vec![
+                outside_dir.display().to_string(),
+                outside_file.display().to_string(),
+                dir.join("../../keep.txt").display().to_string(),
+                inside.display().to_string(),
+            ],
+            ..PipelineInfo::test("abc", None, PipelineState::Stopped)
+        };
+
+        storage.delete(&pipeline).unwrap();
+        assert!(!dir.exists());
+        assert!(outside_dir.is_dir());
+        assert!(outside_file.is_file());
+
+        let _ = std::fs::remove_dir_all(&root);
+    }
+
+    #[test]
+    fn test_resolve_output_location_confines_to_job_dir() {
+        // ---
+        let job_dir = Path::new("outputs/abc");
+
+        assert_eq!(
+            resolve_output_location(job_dir, "out.mp4"),
+            Ok(job_dir.join("out.mp4"))
+        );
+        assert_eq!(
+            resolve_output_location(job_dir, "hls/playlist.m3u8"),
+            Ok(job_dir.join("hls/playlist.m3u8"))
+        );
+        assert_eq!(
+            resolve_output_location(job_dir, "outputs/abc/out.mp4"),
+            Ok(job_dir.join("outputs/abc/out.mp4"))
+        );
+
+        for location in ["/etc/passwd", "../other/out.mp4", "hls/../../x", ""] {
+            assert!(
+                resolve_output_location(job_dir, location).is_err(),
+                "accepted {location}"
+            );
+        }
+    }
+}
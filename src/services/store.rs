@@ -0,0 +1,427 @@
+//! Pipeline record storage abstraction and in-memory implementation.
+//!
+//! Every component that reads or changes pipeline records goes through the
+//! [`PipelineStore`] trait, so the service can keep records in memory (the
+//! default) or persist them across restarts with the SQLite backend.
+//!
+//! # Concurrency
+//!
+//! Implementations are shared between async handlers and the blocking worker
+//! threads of the runtime, so all methods are synchronous and must be cheap
+//! enough to call while holding no other locks. Read-modify-write sequences use
+//! [`PipelineStore::update`], which applies a closure atomically.
+//!
+//! # Listing
+//!
+//! Listings are filtered, ordered by `(created_at, id)` and paginated with an
+//! opaque [`Cursor`] naming the last record of the previous page, so pages stay
+//! stable while new pipelines are created.
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+// ---
+
+// Import through gateway
+use crate::models::PipelineInfo;
+
+/// Shared handle to the configured pipeline store.
+pub type SharedPipelineStore = Arc<dyn PipelineStore>;
+
+/// Direction of a pipeline listing, by creation time then ID.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum SortOrder {
+    // ---
+    /// Oldest pipelines first
+    Ascending,
+
+    /// Newest pipelines first
+    #[default]
+    Descending,
+}
+
+/// Position in a sorted listing: the sort key of the last record returned.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Cursor {
+    // ---
+    /// `created_at` of the last record on the previous page
+    pub created_at: String,
+
+    /// ID of the last record on the previous page
+    pub id: String,
+}
+
+impl Cursor {
+    // ---
+
+    /// Returns the cursor positioned after `pipeline`.
+    pub fn after(pipeline: &PipelineInfo) -> Self {
+        // ---
+        Self {
+            created_at: pipeline.created_at.clone(),
+            id: pipeline.id.clone(),
+        }
+    }
+
+    /// Encodes the cursor as an opaque string for API clients.
+    pub fn encode(&self) -> String {
+        // ---
+        hex::encode(format!("{}\n{}", self.created_at, self.id))
+    }
+
+    /// Decodes a cursor produced by [`Cursor::encode`], None if malformed.
+    pub fn decode(encoded: &str) -> Option<Self> {
+        // ---
+        let text = String::from_utf8(hex::decode(encoded).ok()?).ok()?;
+        let (created_at, id) = text.split_once('\n')?;
+        Some(Self {
+            created_at: created_at.to_string(),
+            id: id.to_string(),
+        })
+    }
+}
+
+/// Filters, ordering and page position for a pipeline listing.
+///
+/// Timestamp bounds must be normalized RFC 3339 UTC strings (as produced by
+/// `chrono::Utc::now().to_rfc3339()`) so they compare correctly with stored
+/// `created_at` values.
+#[derive(Clone, Debug, Default)]
+pub struct PipelineQuery {
+    // ---
+    /// Only pipelines whose state has this name (see `PipelineState::name`)
+    pub state: Option<&'static str>,
+
+    /// Only pipelines whose description contains this text (ASCII case-insensitive)
+    pub description: Option<String>,
+
+    /// Only pipelines processing exactly this source URL
+    pub source_url: Option<String>,
+
+    /// Only pipelines of this owner (`key:<id>` or `tenant:<tenant>`)
+    pub owner: Option<String>,
+
+    /// Only pipelines created strictly after this timestamp
+    pub created_after: Option<String>,
+
+    /// Only pipelines created strictly before this timestamp
+    pub created_before: Option<String>,
+
+    /// Listing direction
+    pub order: SortOrder,
+
+    /// Only pipelines sorting strictly after this cursor
+    pub after: Option<Cursor>,
+
+    /// Maximum number of records to return
+    pub limit: usize,
+}
+
+impl PipelineQuery {
+    // ---
+
+    /// Returns true if `pipeline` passes every filter and lies after the cursor.
+    pub fn matches(&self, pipeline: &PipelineInfo) -> bool {
+        // ---
+        if self
+            .state
+            .is_some_and(|state| state != pipeline.state.name())
+        {
+            return false;
+        }
+        if let Some(text) = &self.description {
+            let description = pipeline.description.to_ascii_lowercase();
+            if !description.contains(&text.to_ascii_lowercase()) {
+                return false;
+            }
+        }
+        if let Some(source_url) = &self.source_url {
+            if pipeline.source_url.as_ref() != Some(source_url) {
+                return false;
+            }
+        }
+        if let Some(owner) = &self.owner {
+            if pipeline.owner.as_ref() != Some(owner) {
+                return false;
+            }
+        }
+        if let Some(after) = &self.created_after {
+            if pipeline.created_at.as_str() <= after.as_str() {
+                return false;
+            }
+        }
+        if let Some(before) = &self.created_before {
+            if pipeline.created_at.as_str() >= before.as_str() {
+                return false;
+            }
+        }
+        if let Some(cursor) = &self.after {
+            let key = (pipeline.created_at.as_str(), pipeline.id.as_str());
+            let position = (cursor.created_at.as_str(), cursor.id.as_str());
+            let past_cursor = match self.order {
+                SortOrder::Ascending => key > position,
+                SortOrder::Descending => key < position,
+            };
+            if !past_cursor {
+                return false;
+            }
+        }
+        true
+    }
+}
+
+/// One page of a pipeline listing.
+#[derive(Debug)]
+pub struct PipelinePage {
+    // ---
+    /// Matching pipelines in listing order
+    pub pipelines: Vec<PipelineInfo>,
+
+    /// Cursor for the following page, None when this is the last page
+    pub next_cursor: Option<Cursor>,
+}
+
+/// Storage backend for pipeline records.
+///
+/// # Errors
+/// All methods return `anyhow::Error` for backend failures (I/O, corrupt
+/// rows). A missing record is not an error: lookups return `Ok(None)`.
+pub trait PipelineStore: Send + Sync {
+    // ---
+
+    /// Inserts a new record, replacing any existing record with the same ID.
+    fn insert(&self, pipeline: PipelineInfo) -> anyhow::Result<()>;
+
+    /// Returns the record with the given ID, if any.
+    fn get(&self, id: &str) -> anyhow::Result<Option<PipelineInfo>>;
+
+    /// Returns every stored record in unspecified order.
+    fn list(&self) -> anyhow::Result<Vec<PipelineInfo>>;
+
+    /// Returns up to `query.limit` records matching `query`, in `query.order`.
+    fn query(&self, query: &PipelineQuery) -> anyhow::Result<Vec<PipelineInfo>>;
+
+    /// Returns one page of records plus the cursor for the next page.
+    ///
+    /// Fetches one record beyond the limit to learn whether another page
+    /// exists, so `next_cursor` is None exactly on the last page.
+    fn page(&self, query: &PipelineQuery) -> anyhow::Result<PipelinePage> {
+        // ---
+        let probe = PipelineQuery {
+            limit: query.limit + 1,
+            ..query.clone()
+        };
+        let mut pipelines = self.query(&probe)?;
+
+        let next_cursor = if pipelines.len() > query.limit {
+            pipelines.truncate(query.limit);
+            pipelines.last().map(Cursor::after)
+        } else {
+            None
+        };
+
+        Ok(PipelinePage {
+            pipelines,
+            next_cursor,
+        })
+    }
+
+    /// Removes the record with the given ID, returning false if none existed.
+    fn delete(&self, id: &str) -> anyhow::Result<bool>;
+
+    /// Atomically applies `apply` to the record with the given ID.
+    ///
+    /// # Returns
+    /// * `Ok(Some(record))` - The record after `apply` ran
+    /// * `Ok(None)` - No record with that ID exists; `apply` was not called
+    fn update(
+        &self,
+        id: &str,
+        apply: &mut dyn FnMut(&mut PipelineInfo),
+    ) -> anyhow::Result<Option<PipelineInfo>>;
+}
+
+/// Volatile store keeping all records in a `HashMap`.
+///
+/// Records are lost when the process exits; use the SQLite store when job
+/// history must survive restarts.
+#[derive(Default)]
+pub struct MemoryPipelineStore {
+    // ---
+    pipelines: Mutex<HashMap<String, PipelineInfo>>,
+}
+
+impl MemoryPipelineStore {
+    // ---
+
+    /// Creates an empty in-memory store.
+    pub fn new() -> Self {
+        // ---
+        Self::default()
+    }
+}
+
+impl PipelineStore for MemoryPipelineStore {
+    // ---
+
+    fn insert(&self, pipeline: PipelineInfo) -> anyhow::Result<()> {
+        // ---
+        let mut pipelines = self.pipelines.lock().unwrap();
+        pipelines.insert(pipeline.id.clone(), pipeline);
+        Ok(())
+    }
+
+    fn get(&self, id: &str) -> anyhow::Result<Option<PipelineInfo>> {
+        // ---
+        let pipelines = self.pipelines.lock().unwrap();
+        Ok(pipelines.get(id).cloned())
+    }
+
+    fn list(&self) -> anyhow::Result<Vec<PipelineInfo>> {
+        // ---
+        let pipelines = self.pipelines.lock().unwrap();
+        Ok(pipelines.values().cloned().collect())
+    }
+
+    fn query(&self, query: &PipelineQuery) -> anyhow::Result<Vec<PipelineInfo>> {
+        // ---
+        let pipelines = self.pipelines.lock().unwrap();
+        let mut matching: Vec<PipelineInfo> = pipelines
+            .values()
+            .filter(|pipeline| query.matches(pipeline))
+            .cloned()
+            .collect();
+
+        matching.sort_by(|a, b| {
+            (a.created_at.as_str(), a.id.as_str()).cmp(&(b.created_at.as_str(), b.id.as_str()))
+        });
+        if query.order == SortOrder::Descending {
+            matching.reverse();
+        }
+        matching.truncate(query.limit);
+
+        Ok(matching)
+    }
+
+    fn delete(&self, id: &str) -> anyhow::Result<bool> {
+        // ---
+        let mut pipelines = self.pipelines.lock().unwrap();
+        Ok(pipelines.remove(id).is_some())
+    }
+
+    fn update(
+        &self,
+        id: &str,
+        apply: &mut dyn FnMut(&mut PipelineInfo),
+    ) -> anyhow::Result<Option<PipelineInfo>> {
+        // ---
+        let mut pipelines = self.pipelines.lock().unwrap();
+        Ok(pipelines.get_mut(id).map(|pipeline| {
+            apply(pipeline);
+            pipeline.clone()
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    // ---
+
+    use super::*;
+    use crate::models::PipelineState;
+
+    fn pipeline(id: &str, created_at: &str, description: &str) -> PipelineInfo {
+        // ---
+        PipelineInfo {
+            description: description.to_string(),
+            created_at: created_at.to_string(),
+            ..PipelineInfo::test(id, None, PipelineState::Created)
+        }
+    }
+
+    fn seeded_store() -> MemoryPipelineStore {
+        // ---
+        let store = MemoryPipelineStore::new();
+        store
+            .insert(pipeline(
+                "a",
+                "2024-01-01T00:00:00+00:00",
+                "Convert to webm",
+            ))
+            .unwrap();
+        store
+            .insert(pipeline(
+                "b",
+                "2024-01-02T00:00:00+00:00",
+                "Generate thumbnail",
+            ))
+            .unwrap();
+        store
+            .insert(pipeline("c", "2024-01-03T00:00:00+00:00", "Convert to mp4"))
+            .unwrap();
+        store
+    }
+
+    fn ids(pipelines: &[PipelineInfo]) -> Vec<&str> {
+        // ---
+        pipelines.iter().map(|p| p.id.as_str()).collect()
+    }
+
+    #[test]
+    fn test_cursor_round_trip() {
+        // ---
+        let cursor = Cursor {
+            created_at: "2024-01-01T00:00:00+00:00".to_string(),
+            id: "abc".to_string(),
+        };
+        assert_eq!(Cursor::decode(&cursor.encode()), Some(cursor));
+        assert_eq!(Cursor::decode("not-hex"), None);
+    }
+
+    #[test]
+    fn test_page_walks_all_records_newest_first() {
+        // ---
+        let store = seeded_store();
+        let mut query = PipelineQuery {
+            limit: 2,
+            ..Default::default()
+        };
+
+        let first = store.page(&query).unwrap();
+        assert_eq!(ids(&first.pipelines), vec!["c", "b"]);
+
+        query.after = first.next_cursor;
+        let second = store.page(&query).unwrap();
+        assert_eq!(ids(&second.pipelines), vec!["a"]);
+        assert!(second.next_cursor.is_none());
+    }
+
+    #[test]
+    fn test_query_filters() {
+        // ---
+        let store = seeded_store();
+
+        let query = PipelineQuery {
+            description: Some("CONVERT".to_string()),
+            order: SortOrder::Ascending,
+            limit: 10,
+            ..Default::default()
+        };
+        assert_eq!(ids(&store.query(&query).unwrap()), vec!["a", "c"]);
+
+        let query = PipelineQuery {
+            created_after: Some("2024-01-01T12:00:00+00:00".to_string()),
+            created_before: Some("2024-01-03T00:00:00+00:00".to_string()),
+            limit: 10,
+            ..Default::default()
+        };
+        assert_eq!(ids(&store.query(&query).unwrap()), vec!["b"]);
+
+        let query = PipelineQuery {
+            state: Some("Stopped"),
+            limit: 10,
+            ..Default::default()
+        };
+        assert!(store.query(&query).unwrap().is_empty());
+    }
+}
@@ -0,0 +1,146 @@
+//! Log output and optional OpenTelemetry trace export.
+//!
+//! Logs always go to stdout through `tracing_subscriber::fmt`, as human
+//! readable text or, with `--log-format json`, as one JSON object per line
+//! carrying the event's fields and enclosing spans (e.g. `pipeline.id`).
+//!
+//! When an OTLP endpoint is configured (`--otlp-endpoint` or
+//! `OTEL_EXPORTER_OTLP_ENDPOINT`), the same `tracing` spans are also exported
+//! as OpenTelemetry traces over OTLP/HTTP (protobuf) to
+//! `{endpoint}/v1/traces`.
+//!
+//! # Spans
+//!
+//! - `http_request`: Every routed request, exported under its method and
+//!   route (e.g. `POST /pipelines`), continuing an incoming W3C `traceparent`
+//!   when present
+//! - `analyze_media`, `build_pipeline`, `validate_pipeline`: Work done while
+//!   handling a request
+//! - `queue_wait`: Time a submitted pipeline waits for a worker
+//! - `execute_pipeline` with `parse_pipeline`, `start_pipeline`,
+//!   `run_pipeline` and `publish_outputs` phases
+//! - `deliver_webhook`: Completion webhook delivery, whose requests carry the
+//!   trace context in a `traceparent` header
+//!
+//! Spans concerning one pipeline carry its ID as the `pipeline.id` attribute.
+
+use clap::ValueEnum;
+use opentelemetry::propagation::TextMapPropagator;
+use opentelemetry::KeyValue;
+use opentelemetry_otlp::WithExportConfig;
+use opentelemetry_sdk::propagation::TraceContextPropagator;
+use opentelemetry_sdk::{trace, Resource};
+use std::collections::HashMap;
+use tracing_opentelemetry::OpenTelemetrySpanExt;
+use tracing_subscriber::layer::SubscriberExt;
+use tracing_subscriber::util::SubscriberInitExt;
+use tracing_subscriber::{Layer, Registry};
+
+/// Format of log lines written to stdout.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum LogFormat {
+    /// Human-readable text, colored on terminals
+    Text,
+    /// One JSON object per line, for log collectors
+    Json,
+}
+
+/// Settings for [`Telemetry::init`].
+#[derive(Debug)]
+pub struct TelemetryConfig {
+    // ---
+    /// Emit ANSI colors in log output
+    pub use_color: bool,
+
+    /// Text or JSON log lines
+    pub log_format: LogFormat,
+
+    /// OTLP/HTTP collector base URL (e.g. `http://localhost:4318`); traces
+    /// are not exported when None
+    pub otlp_endpoint: Option<String>,
+
+    /// `service.name` resource attribute of exported spans
+    pub service_name: String,
+}
+
+/// Handle to the installed tracing setup, used to flush spans on shutdown.
+pub struct Telemetry {
+    // ---
+    exporting: bool,
+}
+
+impl Telemetry {
+    // ---
+
+    /// Installs the global `tracing` subscriber and, when configured, the
+    /// OTLP exporter and W3C trace context propagator.
+    ///
+    /// Must be called once, from within a Tokio runtime.
+    ///
+    /// # Errors
+    /// Fails if the OTLP exporter cannot be built from the endpoint.
+    pub fn init(config: TelemetryConfig) -> anyhow::Result<Self> {
+        // ---
+        let fmt: Box<dyn Layer<Registry> + Send + Sync> = match config.log_format {
+            LogFormat::Text => tracing_subscriber::fmt::layer()
+                .with_ansi(config.use_color)
+                .boxed(),
+            LogFormat::Json => tracing_subscriber::fmt::layer().json().boxed(),
+        };
+
+        let Some(endpoint) = config.otlp_endpoint else {
+            tracing_subscriber::registry().with(fmt).init();
+            return Ok(Self { exporting: false });
+        };
+
+        opentelemetry::global::set_text_map_propagator(TraceContextPropagator::new());
+        let tracer =
+            opentelemetry_otlp::new_pipeline()
+                .tracing()
+                .with_exporter(
+                    opentelemetry_otlp::new_exporter()
+                        .http()
+                        .with_endpoint(endpoint.trim_end_matches('/')),
+                )
+                .with_trace_config(trace::config().with_resource(Resource::new(vec![
+                    KeyValue::new("service.name", config.service_name),
+                ])))
+                .install_batch(opentelemetry_sdk::runtime::Tokio)?;
+
+        tracing_subscriber::registry()
+            .with(fmt)
+            .with(tracing_opentelemetry::layer().with_tracer(tracer))
+            .init();
+        tracing::info!("Exporting traces to {}/v1/traces", endpoint);
+
+        Ok(Self { exporting: true })
+    }
+
+    /// Flushes spans still waiting to be exported.
+    pub async fn shutdown(self) {
+        // ---
+        if self.exporting {
+            let _ =
+                tokio::task::spawn_blocking(opentelemetry::global::shutdown_tracer_provider).await;
+        }
+    }
+}
+
+/// Returns the W3C trace context headers (`traceparent`, `tracestate`) that
+/// continue `span`'s trace in an outgoing request.
+///
+/// Empty when traces are not exported.
+pub(super) fn trace_headers(span: &tracing::Span) -> HashMap<String, String> {
+    // ---
+    let mut headers = HashMap::new();
+    TraceContextPropagator::new().inject_context(&span.context(), &mut headers);
+    headers
+}
+
+/// Makes `span` a child of the remote span described by the W3C trace
+/// context `headers` (lowercase names), if they hold a valid one.
+pub fn continue_trace(span: &tracing::Span, headers: &HashMap<String, String>) {
+    // ---
+    let parent = TraceContextPropagator::new().extract(headers);
+    span.set_parent(parent);
+}
@@ -0,0 +1,701 @@
+//! Named, parameterized pipeline templates.
+//!
+//! Custom pipelines are one-off strings. A template stores a pipeline string
+//! once, with typed placeholders where per-job values go, so clients only
+//! supply those values. Placeholders look like `{{name:type:constraint}}`;
+//! see [`parse_template`] for the accepted types.
+//!
+//! # Safety
+//!
+//! Every value is checked against its placeholder type and substituted as a
+//! quoted, escaped `parse_launch` string (numbers and booleans as literals),
+//! so values cannot add elements or properties to the pipeline. Placeholders
+//! must therefore stand alone as a property value, outside any quoted string.
+//!
+//! # On-Disk Layout
+//!
+//! Each template is stored as `<name>.json` below `--template-dir`, so
+//! templates survive restarts and can be provisioned as files.
+
+use chrono::Utc;
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+
+// ---
+
+// Import through gateway
+use crate::models::{ParameterKind, PipelineTemplate, TemplateParameter};
+
+// ---
+
+// Import from sibling module
+use super::validation::validate_pipeline_string;
+
+/// URL schemes accepted by `url` placeholders
+const URL_SCHEMES: [&str; 7] = ["http", "https", "rtsp", "rtsps", "rtmp", "rtmps", "srt"];
+
+/// Longest accepted template name
+const MAX_NAME_LEN: usize = 64;
+
+/// Reasons a template cannot be saved.
+#[derive(Debug)]
+pub enum TemplateError {
+    // ---
+    /// The template name is not allowed; the message explains why
+    InvalidName(String),
+
+    /// The template or its placeholders are malformed, or the pipeline
+    /// fails validation with sample values
+    Invalid(String),
+
+    /// A template with this name already exists
+    Exists(String),
+
+    /// The template could not be written to disk
+    Io(anyhow::Error),
+}
+
+/// Directory of stored pipeline templates.
+///
+/// Cheap to clone; all clones share the same directory.
+#[derive(Clone)]
+pub struct TemplateStore {
+    // ---
+    dir: PathBuf,
+}
+
+impl TemplateStore {
+    // ---
+
+    /// Opens (creating if needed) the template directory.
+    pub fn open(dir: impl Into<PathBuf>) -> anyhow::Result<Self> {
+        // ---
+        let dir = dir.into();
+        std::fs::create_dir_all(&dir)?;
+        Ok(Self { dir })
+    }
+
+    /// Returns the path of a template's file.
+    ///
+    /// Returns None for invalid names, so client input can never address
+    /// files outside the template directory.
+    fn path(&self, name: &str) -> Option<PathBuf> {
+        // ---
+        check_name(name).ok()?;
+        Some(self.dir.join(format!("{name}.json")))
+    }
+
+    /// Validates and stores a new template.
+    ///
+    /// # Arguments
+    /// * `name`        - Unique template name
+    /// * `description` - Optional description for pipelines created from it
+    /// * `template`    - Pipeline string with placeholders
+    ///
+    /// # Errors
+    /// [`TemplateError::Invalid`] when a placeholder is malformed or the
+    /// pipeline does not validate with sample values substituted.
+    pub fn save(
+        &self,
+        name: &str,
+        description: Option<String>,
+        template: &str,
+    ) -> Result<PipelineTemplate, TemplateError> {
+        // ---
+        check_name(name).map_err(TemplateError::InvalidName)?;
+        let path = self.dir.join(format!("{name}.json"));
+        if path.exists() {
+            return Err(TemplateError::Exists(name.to_string()));
+        }
+
+        let parameters = parse_template(template).map_err(TemplateError::Invalid)?;
+        let stored = PipelineTemplate {
+            name: name.to_string(),
+            description,
+            template: template.to_string(),
+            parameters,
+            created_at: Utc::now().to_rfc3339(),
+        };
+
+        let samples = stored
+            .parameters
+            .iter()
+            .map(|parameter| (parameter.name.clone(), sample_value(&parameter.kind)))
+            .collect();
+        let pipeline = render_template(&stored, &samples).map_err(TemplateError::Invalid)?;
+        validate_pipeline_string(&pipeline).map_err(|e| {
+            TemplateError::Invalid(format!("Template fails validation with sample values: {e}"))
+        })?;
+
+        let contents =
+            serde_json::to_vec_pretty(&stored).map_err(|e| TemplateError::Io(e.into()))?;
+        std::fs::write(&path, contents).map_err(|e| TemplateError::Io(e.into()))?;
+        Ok(stored)
+    }
+
+    /// Returns a stored template.
+    pub fn get(&self, name: &str) -> anyhow::Result<Option<PipelineTemplate>> {
+        // ---
+        let Some(path) = self.path(name) else {
+            return Ok(None);
+        };
+        match std::fs::read(path) {
+            Ok(contents) => Ok(Some(serde_json::from_slice(&contents)?)),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// Returns every stored template in name order.
+    pub fn list(&self) -> anyhow::Result<Vec<PipelineTemplate>> {
+        // ---
+        let mut templates = Vec::new();
+        for entry in std::fs::read_dir(&self.dir)? {
+            let file_name = entry?.file_name();
+            let Some(name) = file_name.to_str().and_then(|f| f.strip_suffix(".json")) else {
+                continue;
+            };
+            if let Some(template) = self.get(name)? {
+                templates.push(template);
+            }
+        }
+        templates.sort_by(|a, b| a.name.cmp(&b.name));
+        Ok(templates)
+    }
+
+    /// Deletes a template.
+    ///
+    /// # Returns
+    /// `Ok(false)` when no template has that name.
+    pub fn remove(&self, name: &str) -> anyhow::Result<bool> {
+        // ---
+        let Some(path) = self.path(name) else {
+            return Ok(false);
+        };
+        match std::fs::remove_file(path) {
+            Ok(()) => Ok(true),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(false),
+            Err(e) => Err(e.into()),
+        }
+    }
+}
+
+/// Checks that a template name is safe to use in URLs and file names.
+fn check_name(name: &str) -> Result<(), String> {
+    // ---
+    let valid = !name.is_empty()
+        && name.len() <= MAX_NAME_LEN
+        && name
+            .bytes()
+            .all(|b| b.is_ascii_lowercase() || b.is_ascii_digit() || b == b'-' || b == b'_')
+        && name.as_bytes()[0].is_ascii_alphanumeric();
+    if !valid {
+        return Err(format!(
+            "Template names are 1-{MAX_NAME_LEN} lowercase letters, digits, '-' or '_', \
+             starting with a letter or digit; got '{name}'"
+        ));
+    }
+    Ok(())
+}
+
+/// A placeholder occurrence: its byte range in the template and its parameter.
+struct Placeholder {
+    // ---
+    start: usize,
+    end: usize,
+    parameter: TemplateParameter,
+}
+
+/// Extracts the typed placeholders of a template.
+///
+/// # Placeholder Syntax
+/// - `{{name}}` or `{{name:string}}`: Text
+/// - `{{name:url}}`: Absolute URL with an http(s), rtsp(s), rtmp(s) or srt scheme
+/// - `{{name:bool}}`: `true` or `false`
+/// - `{{name:int}}`, `{{name:int:MIN..MAX}}`: Integer; either bound may be omitted
+/// - `{{name:float}}`, `{{name:float:MIN..MAX}}`: Number; either bound may be omitted
+/// - `{{name:choice:a|b|c}}`: One of the listed words
+///
+/// A name may appear several times, always with the same type. Each
+/// placeholder must follow `=` directly and lie outside quoted strings.
+///
+/// # Returns
+/// The parameters in order of first appearance.
+pub fn parse_template(template: &str) -> Result<Vec<TemplateParameter>, String> {
+    // ---
+    let mut parameters: Vec<TemplateParameter> = Vec::new();
+    for placeholder in placeholders(template)? {
+        match parameters
+            .iter()
+            .find(|p| p.name == placeholder.parameter.name)
+        {
+            Some(existing) if existing.kind != placeholder.parameter.kind => {
+                return Err(format!(
+                    "Placeholder '{}' is used with different types",
+                    existing.name
+                ));
+            }
+            Some(_) => {}
+            None => parameters.push(placeholder.parameter),
+        }
+    }
+    Ok(parameters)
+}
+
+/// Substitutes checked, escaped values for a template's placeholders.
+///
+/// # Errors
+/// Names a missing, unknown or invalid value.
+pub fn render_template(
+    template: &PipelineTemplate,
+    values: &BTreeMap<String, serde_json::Value>,
+) -> Result<String, String> {
+    // ---
+    if let Some(unknown) = values
+        .keys()
+        .find(|name| !template.parameters.iter().any(|p| &p.name == *name))
+    {
+        return Err(format!(
+            "Template '{}' has no parameter '{unknown}'",
+            template.name
+        ));
+    }
+
+    let mut rendered = String::with_capacity(template.template.len());
+    let mut copied = 0;
+    for placeholder in placeholders(&template.template)? {
+        let name = &placeholder.parameter.name;
+        let value = values
+            .get(name)
+            .ok_or_else(|| format!("Missing value for parameter '{name}'"))?;
+        rendered.push_str(&template.template[copied..placeholder.start]);
+        rendered.push_str(&format_value(&placeholder.parameter, value)?);
+        copied = placeholder.end;
+    }
+    rendered.push_str(&template.template[copied..]);
+    Ok(rendered)
+}
+
+/// Finds every placeholder of a template.
+///
+/// Tracks `parse_launch` quoting while scanning, so a placeholder anywhere
+/// inside a quoted string is refused, not only one right after the opening
+/// quote. Placeholders must also stand alone as a value: directly after `=`
+/// and followed by whitespace, `!` or the end of the template.
+fn placeholders(template: &str) -> Result<Vec<Placeholder>, String> {
+    // ---
+    let mut found = Vec::new();
+    let mut quote = None;
+    let mut offset = 0;
+    while let Some(c) = template[offset..].chars().next() {
+        let start = offset;
+        offset += c.len_utf8();
+
+        if let Some(open) = quote {
+            if template[start..].starts_with("{{") {
+                return Err(format!(
+                    "Placeholder at byte {start} must not be quoted; values are quoted when substituted"
+                ));
+            }
+            if c == '\\' {
+                offset += template[offset..].chars().next().map_or(0, char::len_utf8);
+            } else if c == open {
+                quote = None;
+            }
+            continue;
+        }
+        if c == '"' || c == '\'' {
+            quote = Some(c);
+            continue;
+        }
+        if !template[start..].starts_with("{{") {
+            continue;
+        }
+
+        let close = template[start..]
+            .find("}}")
+            .ok_or_else(|| format!("Unclosed placeholder at byte {start}"))?;
+        let end = start + close + 2;
+        let next = template[end..].chars().next();
+        if !template[..start].ends_with('=') || next.is_some_and(|c| !c.is_whitespace() && c != '!')
+        {
+            return Err(format!(
+                "Placeholder '{}' must stand alone as a property value",
+                &template[start..end]
+            ));
+        }
+        found.push(Placeholder {
+            start,
+            end,
+            parameter: parse_placeholder(&template[start + 2..end - 2])?,
+        });
+        offset = end;
+    }
+    Ok(found)
+}
+
+/// Parses the inside of a `{{...}}` placeholder.
+fn parse_placeholder(spec: &str) -> Result<TemplateParameter, String> {
+    // ---
+    let mut parts = spec.trim().splitn(3, ':');
+    let name = parts.next().unwrap_or_default().trim();
+    let kind = parts.next().map(str::trim).unwrap_or("string");
+    let constraint = parts.next().map(str::trim);
+
+    let valid_name = name
+        .chars()
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_');
+    if !valid_name {
+        return Err(format!("Invalid placeholder name in '{{{{{spec}}}}}'"));
+    }
+
+    let invalid = |reason: &str| format!("Invalid placeholder '{{{{{spec}}}}}': {reason}");
+    let kind = match (kind, constraint) {
+        ("string", None) => ParameterKind::String,
+        ("url", None) => ParameterKind::Url,
+        ("bool", None) => ParameterKind::Bool,
+        ("int", range) => {
+            let (min, max) = parse_range::<i64>(range).map_err(|e| invalid(&e))?;
+            ParameterKind::Int { min, max }
+        }
+        ("float", range) => {
+            let (min, max) = parse_range::<f64>(range).map_err(|e| invalid(&e))?;
+            ParameterKind::Float { min, max }
+        }
+        ("choice", Some(options)) => {
+            let options: Vec<String> = options.split('|').map(|o| o.trim().to_string()).collect();
+            let words = options.iter().all(|o| {
+                !o.is_empty()
+                    && o.chars()
+                        .all(|c| c.is_ascii_alphanumeric() || "-_.+".contains(c))
+            });
+            if !words {
+                return Err(invalid("choices are words separated by '|'"));
+            }
+            ParameterKind::Choice { options }
+        }
+        ("choice", None) => return Err(invalid("choice needs options, e.g. choice:a|b")),
+        ("string" | "url" | "bool", Some(_)) => {
+            return Err(invalid(&format!("type '{kind}' takes no constraint")))
+        }
+        (other, _) => return Err(invalid(&format!("unknown type '{other}'"))),
+    };
+
+    Ok(TemplateParameter {
+        name: name.to_string(),
+        kind,
+    })
+}
+
+/// Parses an optional `MIN..MAX` range where either bound may be empty.
+fn parse_range<T>(range: Option<&str>) -> Result<(Option<T>, Option<T>), String>
+where
+    T: std::str::FromStr + PartialOrd + Copy,
+{
+    // ---
+    let Some(range) = range else {
+        return Ok((None, None));
+    };
+    let (min, max) = range
+        .split_once("..")
+        .ok_or_else(|| format!("range '{range}' is not MIN..MAX"))?;
+    let bound = |text: &str| -> Result<Option<T>, String> {
+        match text.trim() {
+            "" => Ok(None),
+            text => text
+                .parse()
+                .map(Some)
+                .map_err(|_| format!("invalid bound '{text}'")),
+        }
+    };
+    let (min, max) = (bound(min)?, bound(max)?);
+    if let (Some(low), Some(high)) = (min, max) {
+        if low > high {
+            return Err(format!("empty range '{range}'"));
+        }
+    }
+    Ok((min, max))
+}
+
+/// Returns a value accepted by a placeholder, for validating templates.
+fn sample_value(kind: &ParameterKind) -> serde_json::Value {
+    // ---
+    match kind {
+        ParameterKind::String => "sample".into(),
+        ParameterKind::Url => "http://example.com/sample".into(),
+        ParameterKind::Bool => true.into(),
+        ParameterKind::Int { min, max } => min.or(*max).unwrap_or(0).into(),
+        ParameterKind::Float { min, max } => min.or(*max).unwrap_or(0.0).into(),
+        ParameterKind::Choice { options } => options[0].clone().into(),
+    }
+}
+
+/// Checks a value against its parameter and formats it for `parse_launch`.
+fn format_value(
+    parameter: &TemplateParameter,
+    value: &serde_json::Value,
+) -> Result<String, String> {
+    // ---
+    let name = &parameter.name;
+    let expected = |kind: &str| format!("Parameter '{name}' expects {kind}, got {value}");
+
+    match &parameter.kind {
+        ParameterKind::String => {
+            let text = value.as_str().ok_or_else(|| expected("a string"))?;
+            quote(name, text)
+        }
+        ParameterKind::Url => {
+            let text = value.as_str().ok_or_else(|| expected("a URL"))?;
+            let url = reqwest::Url::parse(text)
+                .map_err(|e| format!("Parameter '{name}' is not a valid URL: {e}"))?;
+            if !URL_SCHEMES.contains(&url.scheme()) {
+                return Err(format!(
+                    "Parameter '{name}' must use one of the schemes {}",
+                    URL_SCHEMES.join(", ")
+                ));
+            }
+            quote(name, text)
+        }
+        ParameterKind::Bool => value
+            .as_bool()
+            .map(|v| v.to_string())
+            .ok_or_else(|| expected("a boolean")),
+        ParameterKind::Int { min, max } => {
+            let v = value.as_i64().ok_or_else(|| expected("an integer"))?;
+            check_bounds(name, v, *min, *max)?;
+            Ok(v.to_string())
+        }
+        ParameterKind::Float { min, max } => {
+            let v = value.as_f64().ok_or_else(|| expected("a number"))?;
+            check_bounds(name, v, *min, *max)?;
+            Ok(v.to_string())
+        }
+        ParameterKind::Choice { options } => {
+            let text = value.as_str().ok_or_else(|| expected("a string"))?;
+            if !options.iter().any(|option| option == text) {
+                return Err(format!(
+                    "Parameter '{name}' must be one of {}",
+                    options.join(", ")
+                ));
+            }
+            Ok(text.to_string())
+        }
+    }
+}
+
+/// Checks that `value` lies within optional bounds.
+fn check_bounds<T: PartialOrd + std::fmt::Display>(
+    name: &str,
+    value: T,
+    min: Option<T>,
+    max: Option<T>,
+) -> Result<(), String> {
+    // ---
+    if min.as_ref().is_some_and(|min| value < *min) || max.as_ref().is_some_and(|max| value > *max)
+    {
+        let bound = |b: Option<T>| b.map(|b| b.to_string()).unwrap_or_default();
+        return Err(format!(
+            "Value {value} is out of range for parameter '{name}' ({}..{})",
+            bound(min),
+            bound(max)
+        ));
+    }
+    Ok(())
+}
+
+/// Quotes text as a single `parse_launch` value.
+fn quote(name: &str, text: &str) -> Result<String, String> {
+    // ---
+    if text.chars().any(char::is_control) {
+        return Err(format!(
+            "Parameter '{name}' must not contain control characters"
+        ));
+    }
+    Ok(format!(
+        "\"{}\"",
+        text.replace('\\', "\\\\").replace('"', "\\\"")
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    // ---
+
+    use super::*;
+    use serde_json::json;
+
+    fn template(text: &str) -> PipelineTemplate {
+        // ---
+        PipelineTemplate {
+            name: "test".to_string(),
+            description: None,
+            template: text.to_string(),
+            parameters: parse_template(text).unwrap(),
+            created_at: String::new(),
+        }
+    }
+
+    #[test]
+    fn test_parse_template_reads_typed_placeholders() {
+        // ---
+        let parameters = parse_template(
+            "souphttpsrc location={{src:url}} ! x264enc bitrate={{bitrate:int:100..20000}} \
+             speed-preset={{preset:choice:fast|slow}} ! identity name={{label}} \
+             drop-probability={{p:float:..1.0}} silent={{quiet:bool}} sync={{quiet:bool}}",
+        )
+        .unwrap();
+
+        let kinds: Vec<_> = parameters
+            .iter()
+            .map(|p| (p.name.as_str(), &p.kind))
+            .collect();
+        assert_eq!(
+            kinds,
+            [
+                ("src", &ParameterKind::Url),
+                (
+                    "bitrate",
+                    &ParameterKind::Int {
+                        min: Some(100),
+                        max: Some(20000)
+                    }
+                ),
+                (
+                    "preset",
+                    &ParameterKind::Choice {
+                        options: vec!["fast".into(), "slow".into()]
+                    }
+                ),
+                ("label", &ParameterKind::String),
+                (
+                    "p",
+                    &ParameterKind::Float {
+                        min: None,
+                        max: Some(1.0)
+                    }
+                ),
+                ("quiet", &ParameterKind::Bool),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_template_rejects_malformed_placeholders() {
+        // ---
+        for text in [
+            "fakesrc num-buffers={{n:int:5..1}} ! fakesink",
+            "fakesrc num-buffers={{n:int:a..b}} ! fakesink",
+            "fakesrc num-buffers={{n:number}} ! fakesink",
+            "fakesrc num-buffers={{n:int}} ! fakesink silent={{n:bool}}",
+            "fakesrc ! fakesink name={{1name}}",
+            "fakesrc ! fakesink name=\"{{label}}\"",
+            "fakesrc ! filesink location=\"x {{path}}\"",
+            "fakesrc ! filesink location='out/{{path}}.bin'",
+            "fakesrc ! filesink location=out/{{path}}",
+            "fakesrc ! filesink location={{path}}.bin",
+            "fakesrc ! fakesink name={{label",
+            "fakesrc ! fakesink name={{label:choice:a b}}",
+        ] {
+            assert!(parse_template(text).is_err(), "accepted {text}");
+        }
+    }
+
+    #[test]
+    fn test_parse_template_tracks_quoted_strings() {
+        // ---
+        let parameters =
+            parse_template("fakesrc ! identity name=\"a \\\" '{\" ! fakesink name={{label}}")
+                .unwrap();
+        assert_eq!(parameters.len(), 1);
+        assert_eq!(parameters[0].name, "label");
+
+        assert!(parse_template("fakesrc ! identity name='it\\'s {{label}}' ! fakesink").is_err());
+    }
+
+    #[test]
+    fn test_render_template_checks_and_escapes_values() {
+        // ---
+        let template = template(
+            "fakesrc num-buffers={{n:int:1..100}} ! identity name={{label}} ! fakesink sync={{sync:bool}}",
+        );
+        let values = |n: serde_json::Value, label: &str| {
+            BTreeMap::from([
+                ("n".to_string(), n),
+                ("label".to_string(), json!(label)),
+                ("sync".to_string(), json!(false)),
+            ])
+        };
+
+        assert_eq!(
+            render_template(&template, &values(json!(5), "a \"b\" ! filesink")).unwrap(),
+            "fakesrc num-buffers=5 ! identity name=\"a \\\"b\\\" ! filesink\" ! fakesink sync=false"
+        );
+        assert!(render_template(&template, &values(json!(500), "a")).is_err());
+        assert!(render_template(&template, &values(json!("5"), "a")).is_err());
+        assert!(render_template(&template, &values(json!(5), "line\nbreak")).is_err());
+
+        let mut extra = values(json!(5), "a");
+        extra.insert("other".to_string(), json!(1));
+        assert!(render_template(&template, &extra).is_err());
+        let mut missing = values(json!(5), "a");
+        missing.remove("sync");
+        assert!(render_template(&template, &missing).is_err());
+    }
+
+    #[test]
+    fn test_url_parameters_require_streaming_schemes() {
+        // ---
+        let template = template("souphttpsrc location={{src:url}} ! fakesink");
+        let render = |url: &str| {
+            render_template(
+                &template,
+                &BTreeMap::from([("src".to_string(), json!(url))]),
+            )
+        };
+        assert!(render("https://example.com/live.m3u8").is_ok());
+        assert!(render("file:///etc/passwd").is_err());
+        assert!(render("not a url").is_err());
+    }
+
+    #[test]
+    fn test_store_saves_validated_templates() {
+        // ---
+        gstreamer::init().unwrap();
+        let dir = std::env::temp_dir().join(format!("templates-{}", uuid::Uuid::new_v4()));
+        let store = TemplateStore::open(&dir).unwrap();
+
+        let saved = store
+            .save(
+                "burst",
+                None,
+                "fakesrc num-buffers={{n:int:1..10}} ! fakesink",
+            )
+            .unwrap();
+        assert_eq!(saved.parameters.len(), 1);
+        assert!(matches!(
+            store.save("burst", None, "fakesrc ! fakesink"),
+            Err(TemplateError::Exists(_))
+        ));
+        assert!(matches!(
+            store.save("../escape", None, "fakesrc ! fakesink"),
+            Err(TemplateError::InvalidName(_))
+        ));
+        assert!(matches!(
+            store.save("broken", None, "nosuchelement name={{n}} ! fakesink"),
+            Err(TemplateError::Invalid(_))
+        ));
+
+        assert_eq!(
+            store.get("burst").unwrap().unwrap().template,
+            saved.template
+        );
+        assert_eq!(store.list().unwrap().len(), 1);
+        assert!(store.remove("burst").unwrap());
+        assert!(store.get("burst").unwrap().is_none());
+        assert!(!store.remove("burst").unwrap());
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
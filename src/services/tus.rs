@@ -0,0 +1,443 @@
+//! Resumable uploads following the tus 1.0 protocol.
+//!
+//! Implements the server side of tus core plus the `creation` and
+//! `termination` extensions on top of the [`UploadStore`] directory. A client
+//! declares the total length up front, then sends the file in one or more
+//! `PATCH` requests. Bytes are appended to `<id>.part` as they arrive, so when
+//! a connection drops mid-request everything received so far is kept and the
+//! client resumes from the offset reported by `HEAD`.
+//!
+//! # Completion
+//!
+//! Once the offset reaches the declared length the file is sniffed and moved
+//! into place exactly like a direct upload, and becomes available to jobs as
+//! `upload://<id>` under the same ID.
+//!
+//! # Concurrency
+//!
+//! Only one `PATCH` may write to an upload at a time; a second concurrent
+//! request is rejected instead of interleaving bytes.
+//!
+//! # Ownership
+//!
+//! Every operation takes the caller's owner. Upload state lives in that
+//! owner's directory of the [`UploadStore`], so IDs created by another owner
+//! are reported as unknown.
+
+use base64::Engine;
+use bytes::Bytes;
+use chrono::Utc;
+use futures_util::{Stream, StreamExt};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use tokio::io::AsyncWriteExt;
+use tracing::{info, warn};
+use uuid::Uuid;
+
+// ---
+
+// Import through gateway
+use crate::models::UploadInfo;
+
+// ---
+
+// Import from sibling module
+use super::uploads::{UploadError, UploadStore};
+
+/// Protocol version implemented and required in `Tus-Resumable` headers
+pub const TUS_VERSION: &str = "1.0.0";
+
+/// Protocol extensions advertised in `Tus-Extension`
+pub const TUS_EXTENSIONS: &str = "creation,termination";
+
+/// Reasons a tus request cannot be served.
+#[derive(Debug)]
+pub enum TusError {
+    // ---
+    /// No resumable upload with that ID exists
+    NotFound,
+
+    /// The client's `Upload-Offset` differs from the stored offset
+    OffsetMismatch(u64),
+
+    /// The declared or written length exceeds the size limit (in bytes)
+    TooLarge(u64),
+
+    /// Another request is currently writing to the upload
+    Busy,
+
+    /// Finishing the upload failed (rejected type or storage error)
+    Upload(UploadError),
+}
+
+impl From<std::io::Error> for TusError {
+    // ---
+    fn from(error: std::io::Error) -> Self {
+        // ---
+        Self::Upload(error.into())
+    }
+}
+
+/// Outcome of a `PATCH` request.
+#[derive(Debug)]
+pub struct TusProgress {
+    // ---
+    /// Offset after the bytes of this request were stored
+    pub offset: u64,
+
+    /// The completed upload once `offset` reached the declared length
+    pub upload: Option<UploadInfo>,
+}
+
+/// Persisted state of an unfinished resumable upload.
+#[derive(Debug, Serialize, Deserialize)]
+struct TusState {
+    // ---
+    length: u64,
+    filename: Option<String>,
+    created_at: String,
+}
+
+/// Resumable uploads stored alongside direct uploads.
+///
+/// Cheap to clone; all clones share the same store and write locks.
+#[derive(Clone)]
+pub struct TusUploads {
+    // ---
+    uploads: UploadStore,
+    writing: Arc<Mutex<HashSet<String>>>,
+}
+
+/// Releases an upload's write lock when the request finishes or is dropped.
+struct WriteGuard<'a> {
+    // ---
+    writing: &'a Mutex<HashSet<String>>,
+    id: String,
+}
+
+impl Drop for WriteGuard<'_> {
+    // ---
+    fn drop(&mut self) {
+        // ---
+        self.writing.lock().unwrap().remove(&self.id);
+    }
+}
+
+impl TusUploads {
+    // ---
+
+    /// Creates the tus endpoint state on top of `uploads`.
+    pub fn new(uploads: UploadStore) -> Self {
+        // ---
+        Self {
+            uploads,
+            writing: Arc::new(Mutex::new(HashSet::new())),
+        }
+    }
+
+    /// Returns the largest accepted upload in bytes, advertised as `Tus-Max-Size`.
+    pub fn max_size(&self) -> u64 {
+        // ---
+        self.uploads.max_size()
+    }
+
+    /// Creates an empty resumable upload (tus `creation` extension).
+    ///
+    /// # Arguments
+    /// * `owner`    - Caller the upload belongs to (None when anonymous)
+    /// * `length`   - Total size the client will send, from `Upload-Length`
+    /// * `metadata` - Raw `Upload-Metadata` header; its `filename` is recorded
+    ///
+    /// # Returns
+    /// The new upload ID.
+    pub fn create(
+        &self,
+        owner: Option<&str>,
+        length: u64,
+        metadata: Option<&str>,
+    ) -> Result<String, TusError> {
+        // ---
+        if length > self.uploads.max_size() {
+            return Err(TusError::TooLarge(self.uploads.max_size()));
+        }
+
+        let id = Uuid::new_v4().to_string();
+        let state = TusState {
+            length,
+            filename: metadata.and_then(|metadata| metadata_value(metadata, "filename")),
+            created_at: Utc::now().to_rfc3339(),
+        };
+
+        let state_json = serde_json::to_vec_pretty(&state)
+            .map_err(|e| TusError::Upload(UploadError::Io(e.into())))?;
+        std::fs::create_dir_all(self.uploads.owner_dir(owner))?;
+        std::fs::write(self.state_path(owner, &id), state_json)?;
+        std::fs::File::create(self.partial_path(owner, &id))?;
+
+        info!("Created resumable upload {} ({} bytes)", id, length);
+        Ok(id)
+    }
+
+    /// Returns `(offset, length)` of an upload, finished or not.
+    pub fn status(&self, owner: Option<&str>, id: &str) -> Result<(u64, u64), TusError> {
+        // ---
+        if let Some(state) = self.load_state(owner, id)? {
+            let offset = std::fs::metadata(self.partial_path(owner, id))?.len();
+            return Ok((offset, state.length));
+        }
+
+        match self.uploads.get(owner, id) {
+            Ok(Some(upload)) => Ok((upload.size, upload.size)),
+            Ok(None) => Err(TusError::NotFound),
+            Err(e) => Err(TusError::Upload(UploadError::Io(e))),
+        }
+    }
+
+    /// Appends a `PATCH` body at `offset`, completing the upload when full.
+    ///
+    /// Every chunk is written as it arrives. If the body ends early (client
+    /// disconnect), the bytes received so far are kept and the new offset is
+    /// returned as a normal result.
+    pub async fn append<S, E>(
+        &self,
+        owner: Option<&str>,
+        id: &str,
+        offset: u64,
+        body: S,
+    ) -> Result<TusProgress, TusError>
+    where
+        S: Stream<Item = Result<Bytes, E>>,
+        E: std::fmt::Display,
+    {
+        // ---
+        let Some(state) = self.load_state(owner, id)? else {
+            // Completed uploads accept no further bytes
+            let (current, _) = self.status(owner, id)?;
+            if offset != current {
+                return Err(TusError::OffsetMismatch(current));
+            }
+            return Ok(TusProgress {
+                offset: current,
+                upload: None,
+            });
+        };
+        let _guard = self.lock(id)?;
+
+        let partial = self.partial_path(owner, id);
+        let current = tokio::fs::metadata(&partial).await?.len();
+        if offset != current {
+            return Err(TusError::OffsetMismatch(current));
+        }
+
+        let mut file = tokio::fs::OpenOptions::new()
+            .append(true)
+            .open(&partial)
+            .await?;
+        let mut written = current;
+        let mut body = std::pin::pin!(body);
+
+        while let Some(chunk) = body.next().await {
+            let chunk = match chunk {
+                Ok(chunk) => chunk,
+                Err(e) => {
+                    warn!("Upload {} interrupted at offset {}: {}", id, written, e);
+                    break;
+                }
+            };
+            if written + chunk.len() as u64 > state.length {
+                file.flush().await?;
+                return Err(TusError::TooLarge(state.length));
+            }
+            file.write_all(&chunk).await?;
+            written += chunk.len() as u64;
+        }
+
+        file.flush().await?;
+        file.sync_data().await?;
+        drop(file);
+
+        if written < state.length {
+            return Ok(TusProgress {
+                offset: written,
+                upload: None,
+            });
+        }
+
+        let upload = self
+            .uploads
+            .complete(owner, id, &partial, state.filename, written)
+            .await;
+        let _ = std::fs::remove_file(self.state_path(owner, id));
+
+        Ok(TusProgress {
+            offset: written,
+            upload: Some(upload.map_err(TusError::Upload)?),
+        })
+    }
+
+    /// Deletes an unfinished upload (tus `termination` extension).
+    pub fn terminate(&self, owner: Option<&str>, id: &str) -> Result<(), TusError> {
+        // ---
+        if self.load_state(owner, id)?.is_none() {
+            return Err(TusError::NotFound);
+        }
+        let _guard = self.lock(id)?;
+
+        std::fs::remove_file(self.partial_path(owner, id))?;
+        std::fs::remove_file(self.state_path(owner, id))?;
+        info!("Terminated resumable upload {}", id);
+        Ok(())
+    }
+
+    /// Takes the write lock of an upload.
+    fn lock(&self, id: &str) -> Result<WriteGuard<'_>, TusError> {
+        // ---
+        if !self.writing.lock().unwrap().insert(id.to_string()) {
+            return Err(TusError::Busy);
+        }
+        Ok(WriteGuard {
+            writing: &self.writing,
+            id: id.to_string(),
+        })
+    }
+
+    /// Loads the state of an unfinished upload, None if there is none.
+    fn load_state(&self, owner: Option<&str>, id: &str) -> Result<Option<TusState>, TusError> {
+        // ---
+        if Uuid::parse_str(id).is_err() {
+            return Ok(None);
+        }
+
+        match std::fs::read(self.state_path(owner, id)) {
+            Ok(state) => {
+                Ok(Some(serde_json::from_slice(&state).map_err(|e| {
+                    TusError::Upload(UploadError::Io(e.into()))
+                })?))
+            }
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    fn state_path(&self, owner: Option<&str>, id: &str) -> PathBuf {
+        // ---
+        self.uploads.owner_dir(owner).join(format!("{id}.tus.json"))
+    }
+
+    fn partial_path(&self, owner: Option<&str>, id: &str) -> PathBuf {
+        // ---
+        self.uploads
+            .partial_path(owner, id)
+            .expect("Upload IDs are validated before use")
+    }
+}
+
+/// Extracts one value from a tus `Upload-Metadata` header.
+///
+/// The header is a comma-separated list of `key base64value` pairs; keys may
+/// appear without a value.
+fn metadata_value(metadata: &str, key: &str) -> Option<String> {
+    // ---
+    metadata.split(',').find_map(|pair| {
+        let mut parts = pair.trim().splitn(2, ' ');
+        if parts.next()? != key {
+            return None;
+        }
+        let decoded = base64::engine::general_purpose::STANDARD
+            .decode(parts.next()?.trim())
+            .ok()?;
+        String::from_utf8(decoded).ok()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    // ---
+
+    use super::super::validation::test_wav;
+    use super::*;
+
+    fn body(data: &[u8]) -> impl Stream<Item = Result<Bytes, std::io::Error>> {
+        // ---
+        futures_util::stream::iter(vec![Ok(Bytes::copy_from_slice(data))])
+    }
+
+    #[test]
+    fn test_metadata_value() {
+        // ---
+        let metadata = "filename d29ybGQubXA0,is_confidential, type dmlkZW8vbXA0";
+        assert_eq!(
+            metadata_value(metadata, "filename").as_deref(),
+            Some("world.mp4")
+        );
+        assert_eq!(
+            metadata_value(metadata, "type").as_deref(),
+            Some("video/mp4")
+        );
+        assert_eq!(metadata_value(metadata, "is_confidential"), None);
+        assert_eq!(metadata_value(metadata, "missing"), None);
+    }
+
+    #[tokio::test]
+    async fn test_append_resumes_and_completes() {
+        // ---
+        let wav = test_wav();
+        let length = wav.len() as u64;
+        let (head, tail) = wav.split_at(3);
+        let dir = std::env::temp_dir().join(format!("tus-{}", Uuid::new_v4()));
+        let tus = TusUploads::new(UploadStore::open(&dir, 1024).unwrap());
+
+        assert!(matches!(
+            tus.create(None, 4096, None),
+            Err(TusError::TooLarge(1024))
+        ));
+
+        let id = tus
+            .create(None, length, Some("filename Y2xpcC5iaW4="))
+            .unwrap();
+        assert_eq!(tus.status(None, &id).unwrap(), (0, length));
+
+        let progress = tus.append(None, &id, 0, body(head)).await.unwrap();
+        assert_eq!(progress.offset, 3);
+        assert!(progress.upload.is_none());
+
+        assert!(matches!(
+            tus.append(None, &id, 0, body(head)).await,
+            Err(TusError::OffsetMismatch(3))
+        ));
+
+        let progress = tus.append(None, &id, 3, body(tail)).await.unwrap();
+        let upload = progress.upload.unwrap();
+        assert_eq!(upload.id, id);
+        assert_eq!(upload.filename.as_deref(), Some("clip.bin"));
+        assert_eq!(std::fs::read(dir.join(&id)).unwrap(), wav);
+        assert_eq!(tus.status(None, &id).unwrap(), (length, length));
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_terminate_removes_partial_upload() {
+        // ---
+        let dir = std::env::temp_dir().join(format!("tus-{}", Uuid::new_v4()));
+        let tus = TusUploads::new(UploadStore::open(&dir, 1024).unwrap());
+
+        let id = tus.create(None, 10, None).unwrap();
+        tus.terminate(None, &id).unwrap();
+        assert!(matches!(tus.status(None, &id), Err(TusError::NotFound)));
+        assert!(matches!(tus.terminate(None, &id), Err(TusError::NotFound)));
+
+        let id = tus.create(Some("tenant:acme"), 10, None).unwrap();
+        assert!(dir.join("tenant:acme").join(format!("{id}.part")).is_file());
+        assert!(matches!(
+            tus.status(Some("tenant:globex"), &id),
+            Err(TusError::NotFound)
+        ));
+        assert!(matches!(tus.terminate(None, &id), Err(TusError::NotFound)));
+        tus.terminate(Some("tenant:acme"), &id).unwrap();
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,407 @@
+//! Storage of uploaded source media.
+//!
+//! Clients that cannot expose their media at a public HTTP URL upload it with
+//! `POST /uploads`. The body is streamed straight to disk below
+//! `--upload-dir`, checked against the size limit as it arrives, and sniffed
+//! with GStreamer's typefind once complete. Only data typefind identifies as
+//! media is kept. Jobs then reference the file as
+//! `upload://<id>`.
+//!
+//! # On-Disk Layout
+//!
+//! Uploads of authenticated callers live in a subdirectory named after their
+//! owner (`key:<id>` or `tenant:<tenant>`), so one tenant can neither read nor
+//! reference another's uploads. Anonymous uploads stay at the top level.
+//!
+//! - `<id>.part`: Upload in progress; removed if the upload fails
+//! - `<id>`: Completed upload, read by job pipelines with `filesrc`
+//! - `<id>.json`: [`UploadInfo`] metadata, so uploads survive restarts
+//! - `<id>.tus.json`: Declared length and metadata of a resumable upload
+
+use bytes::Bytes;
+use chrono::Utc;
+use futures_util::{Stream, StreamExt};
+use std::path::PathBuf;
+use std::sync::Arc;
+use tokio::io::AsyncWriteExt;
+use tracing::info;
+use uuid::Uuid;
+
+// ---
+
+// Import through gateway
+use crate::models::UploadInfo;
+
+// ---
+
+// Import from sibling module
+use super::validation::{is_media_type, sniff_media_type};
+
+/// URL scheme referencing a completed upload in job requests
+pub const UPLOAD_SCHEME: &str = "upload://";
+
+/// Reasons an upload is rejected.
+#[derive(Debug)]
+pub enum UploadError {
+    // ---
+    /// The body exceeded the configured size limit (in bytes)
+    TooLarge(u64),
+
+    /// typefind identified the data as something other than media
+    UnsupportedType(String),
+
+    /// No installed typefinder recognised the data
+    UnknownType,
+
+    /// The client aborted or sent a malformed body
+    Body(String),
+
+    /// The upload could not be written to disk
+    Io(anyhow::Error),
+}
+
+impl From<std::io::Error> for UploadError {
+    // ---
+    fn from(error: std::io::Error) -> Self {
+        // ---
+        Self::Io(error.into())
+    }
+}
+
+/// Directory of uploaded source media.
+///
+/// Cheap to clone; all clones share the same directory and limits.
+#[derive(Clone)]
+pub struct UploadStore {
+    // ---
+    inner: Arc<UploadStoreInner>,
+}
+
+struct UploadStoreInner {
+    // ---
+    dir: PathBuf,
+    max_size: u64,
+}
+
+impl UploadStore {
+    // ---
+
+    /// Opens (creating if needed) the upload directory.
+    ///
+    /// # Arguments
+    /// * `dir`      - Directory holding uploaded files and their metadata
+    /// * `max_size` - Largest accepted upload in bytes
+    pub fn open(dir: impl Into<PathBuf>, max_size: u64) -> anyhow::Result<Self> {
+        // ---
+        let dir = dir.into();
+        std::fs::create_dir_all(&dir)?;
+
+        Ok(Self {
+            inner: Arc::new(UploadStoreInner { dir, max_size }),
+        })
+    }
+
+    /// Returns the path of a completed upload's data file.
+    ///
+    /// Returns None for IDs that are not UUIDs, so client input can never
+    /// address files outside the owner's upload directory.
+    fn data_path(&self, owner: Option<&str>, id: &str) -> Option<PathBuf> {
+        // ---
+        Uuid::parse_str(id).ok()?;
+        Some(self.owner_dir(owner).join(id))
+    }
+
+    /// Returns the path of an upload's in-progress data file.
+    ///
+    /// Like [`UploadStore::data_path`], only UUIDs are accepted.
+    pub(super) fn partial_path(&self, owner: Option<&str>, id: &str) -> Option<PathBuf> {
+        // ---
+        Uuid::parse_str(id).ok()?;
+        Some(self.owner_dir(owner).join(format!("{id}.part")))
+    }
+
+    /// Returns the directory holding the uploads of `owner`.
+    ///
+    /// Anonymous uploads (`None`) live directly in the upload directory.
+    pub(super) fn owner_dir(&self, owner: Option<&str>) -> PathBuf {
+        // ---
+        match owner {
+            Some(owner) => self.inner.dir.join(owner),
+            None => self.inner.dir.clone(),
+        }
+    }
+
+    /// Returns the largest accepted upload in bytes.
+    pub(super) fn max_size(&self) -> u64 {
+        // ---
+        self.inner.max_size
+    }
+
+    /// Streams a request body to disk and records it as a completed upload.
+    ///
+    /// # Arguments
+    /// * `owner`    - Caller the upload belongs to (None when anonymous)
+    /// * `body`     - Chunks of the uploaded file
+    /// * `filename` - File name supplied by the client, kept for reference
+    ///
+    /// # Errors
+    /// Partial files are removed whenever an upload is rejected.
+    pub async fn receive<S, E>(
+        &self,
+        owner: Option<&str>,
+        body: S,
+        filename: Option<String>,
+    ) -> Result<UploadInfo, UploadError>
+    where
+        S: Stream<Item = Result<Bytes, E>>,
+        E: std::fmt::Display,
+    {
+        // ---
+        let id = Uuid::new_v4().to_string();
+        let partial = self
+            .partial_path(owner, &id)
+            .expect("Generated upload IDs are UUIDs");
+        tokio::fs::create_dir_all(self.owner_dir(owner)).await?;
+
+        let result = self.write_body(&partial, body).await;
+        let size = match result {
+            Ok(size) => size,
+            Err(e) => {
+                let _ = tokio::fs::remove_file(&partial).await;
+                return Err(e);
+            }
+        };
+
+        self.complete(owner, &id, &partial, filename, size).await
+    }
+
+    /// Sniffs a fully written file and moves it into place as upload `id`.
+    ///
+    /// Shared by every upload protocol once all bytes have arrived.
+    pub async fn complete(
+        &self,
+        owner: Option<&str>,
+        id: &str,
+        partial: &std::path::Path,
+        filename: Option<String>,
+        size: u64,
+    ) -> Result<UploadInfo, UploadError> {
+        // ---
+        let probe = partial.to_path_buf();
+        let sniffed = tokio::task::spawn_blocking(move || sniff_media_type(&probe))
+            .await
+            .map_err(|e| UploadError::Io(e.into()))?
+            .map_err(UploadError::Io);
+
+        let content_type = match sniffed {
+            Ok(Some(media_type)) if !is_media_type(&media_type) => {
+                let _ = tokio::fs::remove_file(partial).await;
+                return Err(UploadError::UnsupportedType(media_type));
+            }
+            Ok(None) => {
+                let _ = tokio::fs::remove_file(partial).await;
+                return Err(UploadError::UnknownType);
+            }
+            Ok(content_type) => content_type,
+            Err(e) => {
+                let _ = tokio::fs::remove_file(partial).await;
+                return Err(e);
+            }
+        };
+
+        let upload = UploadInfo {
+            id: id.to_string(),
+            source_url: format!("{UPLOAD_SCHEME}{id}"),
+            filename,
+            size,
+            content_type,
+            owner: owner.map(str::to_string),
+            created_at: Utc::now().to_rfc3339(),
+        };
+
+        let dir = self.owner_dir(owner);
+        let metadata = serde_json::to_vec_pretty(&upload).map_err(|e| UploadError::Io(e.into()))?;
+        tokio::fs::write(dir.join(format!("{id}.json")), metadata).await?;
+        tokio::fs::rename(partial, dir.join(id)).await?;
+
+        info!(
+            "Stored upload {} ({} bytes, {})",
+            id,
+            size,
+            upload.content_type.as_deref().unwrap_or("unknown type")
+        );
+        Ok(upload)
+    }
+
+    /// Copies `body` into `path`, enforcing the size limit as data arrives.
+    async fn write_body<S, E>(&self, path: &std::path::Path, body: S) -> Result<u64, UploadError>
+    where
+        S: Stream<Item = Result<Bytes, E>>,
+        E: std::fmt::Display,
+    {
+        // ---
+        let mut body = std::pin::pin!(body);
+        let mut file = tokio::fs::File::create(path).await?;
+        let mut size: u64 = 0;
+
+        while let Some(chunk) = body.next().await {
+            let chunk = chunk.map_err(|e| UploadError::Body(e.to_string()))?;
+            size += chunk.len() as u64;
+            if size > self.inner.max_size {
+                return Err(UploadError::TooLarge(self.inner.max_size));
+            }
+            file.write_all(&chunk).await?;
+        }
+
+        file.flush().await?;
+        Ok(size)
+    }
+
+    /// Returns the metadata of a completed upload owned by `owner`.
+    ///
+    /// Uploads of other owners are reported as missing.
+    pub fn get(&self, owner: Option<&str>, id: &str) -> anyhow::Result<Option<UploadInfo>> {
+        // ---
+        let Some(data) = self.data_path(owner, id) else {
+            return Ok(None);
+        };
+        if !data.is_file() {
+            return Ok(None);
+        }
+
+        let upload: UploadInfo =
+            match std::fs::read(self.owner_dir(owner).join(format!("{id}.json"))) {
+                Ok(metadata) => serde_json::from_slice(&metadata)?,
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+                Err(e) => return Err(e.into()),
+            };
+        Ok(Some(upload).filter(|upload| upload.owner.as_deref() == owner))
+    }
+
+    /// Resolves a job's `source_url` to a completed upload's file.
+    ///
+    /// # Arguments
+    /// * `owner`      - Caller submitting the job; only their uploads resolve
+    /// * `source_url` - The job's source URL
+    ///
+    /// # Returns
+    /// * `Ok(None)` - The URL does not use the `upload://` scheme
+    /// * `Ok(Some(PathBuf))` - Path of the referenced upload
+    /// * `Err(String)` - The URL names no completed upload of `owner`
+    pub fn resolve(
+        &self,
+        owner: Option<&str>,
+        source_url: &str,
+    ) -> Result<Option<PathBuf>, String> {
+        // ---
+        let Some(id) = source_url.strip_prefix(UPLOAD_SCHEME) else {
+            return Ok(None);
+        };
+
+        match (self.get(owner, id), self.data_path(owner, id)) {
+            (Ok(Some(_)), Some(path)) => Ok(Some(path)),
+            _ => Err(format!("No completed upload with ID '{id}'")),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    // ---
+
+    use super::super::validation::test_wav;
+    use super::*;
+
+    fn chunks(data: &[&[u8]]) -> impl Stream<Item = Result<Bytes, std::io::Error>> {
+        // ---
+        let chunks: Vec<_> = data
+            .iter()
+            .map(|chunk| Ok(Bytes::copy_from_slice(chunk)))
+            .collect();
+        futures_util::stream::iter(chunks)
+    }
+
+    #[tokio::test]
+    async fn test_receive_stores_and_resolves_uploads() {
+        // ---
+        let wav = test_wav();
+        let dir = std::env::temp_dir().join(format!("uploads-{}", Uuid::new_v4()));
+        let uploads = UploadStore::open(&dir, 1024).unwrap();
+
+        let (head, tail) = wav.split_at(20);
+        let upload = uploads
+            .receive(None, chunks(&[head, tail]), Some("clip.wav".to_string()))
+            .await
+            .unwrap();
+        assert_eq!(upload.size, wav.len() as u64);
+        assert_eq!(upload.content_type.as_deref(), Some("audio/x-wav"));
+        assert_eq!(upload.source_url, format!("upload://{}", upload.id));
+
+        let path = uploads.resolve(None, &upload.source_url).unwrap().unwrap();
+        assert_eq!(std::fs::read(path).unwrap(), wav);
+        assert_eq!(
+            uploads
+                .get(None, &upload.id)
+                .unwrap()
+                .unwrap()
+                .filename
+                .as_deref(),
+            Some("clip.wav")
+        );
+
+        assert!(uploads
+            .resolve(None, "https://example.com/a.mp4")
+            .unwrap()
+            .is_none());
+        assert!(uploads.resolve(None, "upload://../../etc/passwd").is_err());
+        assert!(uploads.get(None, "../secrets").unwrap().is_none());
+
+        let owned = uploads
+            .receive(Some("key:team-a"), chunks(&[&wav]), None)
+            .await
+            .unwrap();
+        assert_eq!(owned.owner.as_deref(), Some("key:team-a"));
+        assert!(dir.join("key:team-a").join(&owned.id).is_file());
+        assert!(uploads
+            .get(Some("key:team-a"), &owned.id)
+            .unwrap()
+            .is_some());
+        assert!(uploads.get(None, &owned.id).unwrap().is_none());
+        assert!(uploads
+            .get(Some("key:team-b"), &owned.id)
+            .unwrap()
+            .is_none());
+        assert!(uploads
+            .resolve(Some("key:team-b"), &owned.source_url)
+            .is_err());
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[tokio::test]
+    async fn test_receive_rejects_unidentified_data() {
+        // ---
+        test_wav();
+        let dir = std::env::temp_dir().join(format!("uploads-{}", Uuid::new_v4()));
+        let uploads = UploadStore::open(&dir, 1024).unwrap();
+
+        let result = uploads.receive(None, chunks(&[b"abcdef"]), None).await;
+        assert!(matches!(result, Err(UploadError::UnknownType)));
+        assert_eq!(std::fs::read_dir(&dir).unwrap().count(), 0);
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[tokio::test]
+    async fn test_receive_enforces_size_limit() {
+        // ---
+        let dir = std::env::temp_dir().join(format!("uploads-{}", Uuid::new_v4()));
+        let uploads = UploadStore::open(&dir, 4).unwrap();
+
+        let result = uploads.receive(None, chunks(&[b"abc", b"def"]), None).await;
+        assert!(matches!(result, Err(UploadError::TooLarge(4))));
+        assert_eq!(std::fs::read_dir(&dir).unwrap().count(), 0);
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
@@ -8,7 +8,9 @@
 //! # Core Functionality
 //!
 //! - **Pipeline Validation**  : Syntax checking and element verification for custom pipelines
+//! - **Deep Validation**      : Preroll dry-runs reporting unlinked pads, caps and element errors
 //! - **Media Discovery**      : Analysis of remote media files to extract metadata
+//! - **Content Sniffing**     : Typefind-based detection of uploaded media types
 //! - **Pipeline Construction**: Programmatic generation of common pipeline patterns
 //! - **Error Handling**       : Comprehensive error reporting from GStreamer operations
 //!
@@ -23,9 +25,18 @@ use std::time::Duration;
 
 // ---
 
-// Import from parent module
+// Import through gateway
+use crate::models::{DiagnosticKind, LogLevel, PipelineDiagnostic, PipelineGraph};
+
+// ---
+
+// Import from parent and sibling modules
+use super::builder::{build_from, file_paths, quote, FILE_SINKS};
 use super::MediaInfo;
 
+/// How long a deep validation dry-run may take to preroll
+const DRY_RUN_TIMEOUT: gstreamer::ClockTime = gstreamer::ClockTime::from_seconds(5);
+
 /// Validates a GStreamer pipeline string for syntax and basic structural correctness.
 ///
 /// Performs comprehensive validation including syntax checking, element connectivity
@@ -56,6 +67,7 @@ use super::MediaInfo;
 /// // Invalid - empty string
 /// assert!(validate_pipeline_string("").is_err());
 /// ```
+#[tracing::instrument(name = "validate_pipeline", skip_all)]
 pub fn validate_pipeline_string(pipeline_string: &str) -> Result<(), String> {
     // ---
 
@@ -76,6 +88,252 @@ pub fn validate_pipeline_string(pipeline_string: &str) -> Result<(), String> {
     }
 }
 
+/// Validates a pipeline by prerolling it, reporting every problem found.
+///
+/// Builds the pipeline (from `graph` when given), checks that every
+/// always-present pad is linked, then sets it to Paused and waits up to
+/// five seconds for it to preroll. Unlike [`validate_pipeline_string`] this
+/// catches caps that cannot be negotiated, unlinked elements and element
+/// errors such as missing input files or unreachable hosts. Blocks for the
+/// duration of the dry-run.
+///
+/// # Arguments
+/// * `pipeline_string` - Pipeline string, used when there is no graph
+/// * `graph`           - Structured pipeline to validate instead
+///
+/// # Returns
+/// The problems found; the pipeline passed when none has `error` severity.
+///
+/// # Side Effects
+/// Sources really start during the dry-run (files are opened, network
+/// sources connect). File sinks write to a temporary directory that is
+/// removed afterwards. Live pipelines do not preroll, so for them only
+/// building and linking are checked.
+///
+/// # Example
+/// ```rust
+/// let diagnostics = dry_run_pipeline("fakesrc ! audio/x-raw ! video/x-raw ! fakesink", None);
+/// assert_eq!(diagnostics[0].kind, DiagnosticKind::CapsNegotiation);
+/// ```
+#[tracing::instrument(name = "dry_run_pipeline", skip_all)]
+pub fn dry_run_pipeline(
+    pipeline_string: &str,
+    graph: Option<&PipelineGraph>,
+) -> Vec<PipelineDiagnostic> {
+    // ---
+    let pipeline = match build_from(pipeline_string, graph) {
+        Ok(pipeline) => pipeline,
+        Err(e) => {
+            let unlinkable = e
+                .downcast_ref::<gstreamer::glib::Error>()
+                .is_some_and(|e| e.matches(gstreamer::ParseError::Link));
+            let kind = match unlinkable {
+                true => DiagnosticKind::NotLinked,
+                false => DiagnosticKind::Syntax,
+            };
+            return vec![diagnostic(LogLevel::Error, kind, None, e.to_string())];
+        }
+    };
+
+    let unlinked = unlinked_pads(&pipeline);
+    if !unlinked.is_empty() {
+        return unlinked;
+    }
+
+    let scratch = std::env::temp_dir().join(format!("dry-run-{}", uuid::Uuid::new_v4()));
+    redirect_file_sinks(&pipeline, &scratch);
+    let diagnostics = preroll(&pipeline);
+    let _ = pipeline.set_state(gstreamer::State::Null);
+    let _ = std::fs::remove_dir_all(&scratch);
+
+    diagnostics
+}
+
+/// Reports always-present pads without a peer.
+fn unlinked_pads(pipeline: &gstreamer::Pipeline) -> Vec<PipelineDiagnostic> {
+    // ---
+    let mut diagnostics: Vec<PipelineDiagnostic> = pipeline
+        .iterate_elements()
+        .into_iter()
+        .filter_map(Result::ok)
+        .flat_map(|element| {
+            element
+                .pads()
+                .into_iter()
+                .filter(|pad| {
+                    !pad.is_linked()
+                        && pad
+                            .pad_template()
+                            .is_some_and(|t| t.presence() == gstreamer::PadPresence::Always)
+                })
+                .map(move |pad| {
+                    let direction = match pad.direction() {
+                        gstreamer::PadDirection::Src => "output",
+                        _ => "input",
+                    };
+                    PipelineDiagnostic {
+                        pad: Some(pad.name().to_string()),
+                        ..diagnostic(
+                            LogLevel::Error,
+                            DiagnosticKind::NotLinked,
+                            Some(element.name().to_string()),
+                            format!("The {direction} pad '{}' is not linked", pad.name()),
+                        )
+                    }
+                })
+                .collect::<Vec<_>>()
+        })
+        .collect();
+
+    // Bin iteration yields children in reverse order of addition
+    diagnostics.reverse();
+    diagnostics
+}
+
+/// Points the file paths of sinks into `dir`, so validation never creates
+/// or truncates real output files.
+fn redirect_file_sinks(pipeline: &gstreamer::Pipeline, dir: &std::path::Path) {
+    // ---
+    for sink in pipeline
+        .iterate_recurse()
+        .into_iter()
+        .filter_map(Result::ok)
+    {
+        let factory = sink
+            .factory()
+            .map(|factory| factory.name().to_string())
+            .unwrap_or_default();
+        let mut properties: Vec<String> = file_paths(&sink)
+            .into_iter()
+            .map(|(property, _)| property)
+            .collect();
+        if FILE_SINKS.contains(&factory.as_str()) && !properties.iter().any(|p| p == "location") {
+            properties.push("location".to_string());
+        }
+
+        for property in properties {
+            let _ = std::fs::create_dir_all(dir);
+            let location = match factory.as_str() {
+                "filesink" => dir.join(sink.name().as_str()),
+                _ => dir.join(format!("{}-{property}-%05d", sink.name())),
+            };
+            sink.set_property(&property, location.to_string_lossy().as_ref());
+        }
+    }
+}
+
+/// Prerolls the pipeline and collects the errors and warnings it posts.
+fn preroll(pipeline: &gstreamer::Pipeline) -> Vec<PipelineDiagnostic> {
+    // ---
+    let Some(bus) = pipeline.bus() else {
+        return Vec::new();
+    };
+    let mut diagnostics = Vec::new();
+    let types = [
+        gstreamer::MessageType::Error,
+        gstreamer::MessageType::Warning,
+        gstreamer::MessageType::AsyncDone,
+    ];
+
+    match pipeline.set_state(gstreamer::State::Paused) {
+        Ok(gstreamer::StateChangeSuccess::NoPreroll) => return diagnostics,
+        Ok(_) => {}
+        // The element that failed to start posted an error on the bus
+        Err(_) => {
+            while let Some(message) = bus.pop_filtered(&types) {
+                diagnostics.extend(message_diagnostic(&message));
+            }
+            return diagnostics;
+        }
+    }
+
+    let deadline = std::time::Instant::now() + Duration::from(DRY_RUN_TIMEOUT);
+    loop {
+        let remaining = deadline.saturating_duration_since(std::time::Instant::now());
+        let timeout = gstreamer::ClockTime::from_nseconds(remaining.as_nanos() as u64);
+        let Some(message) = bus.timed_pop_filtered(timeout, &types) else {
+            diagnostics.push(diagnostic(
+                LogLevel::Error,
+                DiagnosticKind::Timeout,
+                None,
+                format!(
+                    "Pipeline did not preroll within {} seconds",
+                    DRY_RUN_TIMEOUT.seconds()
+                ),
+            ));
+            return diagnostics;
+        };
+
+        match message.view() {
+            gstreamer::MessageView::AsyncDone(_) => return diagnostics,
+            gstreamer::MessageView::Error(_) => {
+                diagnostics.extend(message_diagnostic(&message));
+                // Collect what else was posted along with the first error
+                while let Some(message) = bus.pop_filtered(&types) {
+                    diagnostics.extend(message_diagnostic(&message));
+                }
+                return diagnostics;
+            }
+            _ => diagnostics.extend(message_diagnostic(&message)),
+        }
+    }
+}
+
+/// Converts an error or warning message into a diagnostic.
+fn message_diagnostic(message: &gstreamer::Message) -> Option<PipelineDiagnostic> {
+    // ---
+    let (severity, error, debug) = match message.view() {
+        gstreamer::MessageView::Error(err) => (LogLevel::Error, err.error(), err.debug()),
+        gstreamer::MessageView::Warning(warning) => {
+            (LogLevel::Warning, warning.error(), warning.debug())
+        }
+        _ => return None,
+    };
+    let debug = debug.map(|debug| debug.to_string());
+
+    // Streaming threads report flow failures as generic stream errors
+    // with the flow return in the debug details
+    let flow = debug.as_deref().unwrap_or_default();
+    let kind = if error.matches(gstreamer::StreamError::Format)
+        || error.matches(gstreamer::CoreError::Negotiation)
+        || flow.contains("not-negotiated")
+    {
+        DiagnosticKind::CapsNegotiation
+    } else if flow.contains("not-linked") {
+        DiagnosticKind::NotLinked
+    } else {
+        DiagnosticKind::Element
+    };
+
+    Some(PipelineDiagnostic {
+        debug,
+        ..diagnostic(
+            severity,
+            kind,
+            message.src().map(|src| src.name().to_string()),
+            error.to_string(),
+        )
+    })
+}
+
+/// Creates a diagnostic without pad or debug details.
+fn diagnostic(
+    severity: LogLevel,
+    kind: DiagnosticKind,
+    element: Option<String>,
+    message: String,
+) -> PipelineDiagnostic {
+    // ---
+    PipelineDiagnostic {
+        severity,
+        kind,
+        element,
+        pad: None,
+        message,
+        debug: None,
+    }
+}
+
 /// Analyzes a remote media file to extract format, duration, and technical metadata.
 ///
 /// Creates a temporary GStreamer discovery pipeline to probe the media file
@@ -83,7 +341,7 @@ pub fn validate_pipeline_string(pipeline_string: &str) -> Result<(), String> {
 /// as much information as possible about the media file's characteristics.
 ///
 /// # Arguments
-/// * `url` - HTTP(S) URL of the media file to analyze
+/// * `url` - HTTP(S) URL or local path of the media file to analyze
 ///
 /// # Returns
 /// * `Ok(MediaInfo)` - Successfully extracted media information
@@ -107,15 +365,16 @@ pub fn validate_pipeline_string(pipeline_string: &str) -> Result<(), String> {
 /// println!("Duration: {} seconds", info.duration.unwrap_or(0));
 /// println!("Format: {}", info.format);
 /// ```
+#[tracing::instrument(name = "analyze_media", skip_all)]
 pub fn get_media_info(url: &str) -> anyhow::Result<MediaInfo> {
     // ---
 
     use gstreamer::MessageView;
 
     // Create a discovery pipeline - we'll probe the media without fully decoding
-    let pipeline_string = format!(
-        "souphttpsrc location={url} ! typefind ! identity signal-handoffs=false ! fakesink sync=false"
-    );
+    let source = source_element(url);
+    let pipeline_string =
+        format!("{source} ! typefind ! identity signal-handoffs=false ! fakesink sync=false");
 
     let pipeline = gstreamer::parse_launch(&pipeline_string)?
         .downcast::<gstreamer::Pipeline>()
@@ -146,13 +405,12 @@ pub fn get_media_info(url: &str) -> anyhow::Result<MediaInfo> {
                     pipeline.set_state(gstreamer::State::Null)?;
                     return Err(anyhow::anyhow!("Pipeline error: {}", err.error()));
                 }
-                MessageView::StateChanged(state_changed) => {
+                MessageView::StateChanged(state_changed)
                     if state_changed.src().map(|s| s == &pipeline).unwrap_or(false)
-                        && state_changed.current() == gstreamer::State::Paused
-                    {
-                        // Pipeline is now paused, we can query information
-                        break;
-                    }
+                        && state_changed.current() == gstreamer::State::Paused =>
+                {
+                    // Pipeline is now paused, we can query information
+                    break;
                 }
                 MessageView::AsyncDone(_) => {
                     // Pipeline has finished transitioning to PAUSED
@@ -235,7 +493,7 @@ pub fn get_media_info(url: &str) -> anyhow::Result<MediaInfo> {
 /// broad compatibility and reasonable quality/file size trade-offs.
 ///
 /// # Arguments
-/// * `source_url` - HTTP(S) URL of the source media file
+/// * `source_url` - HTTP(S) URL or local path of the source media file
 /// * `output_format` - Target format ("webm", "mp4", "avi")
 /// * `output_path` - Local filesystem path for the converted output file
 ///
@@ -260,22 +518,25 @@ pub fn get_media_info(url: &str) -> anyhow::Result<MediaInfo> {
 ///     "output.webm"
 /// )?;
 /// ```
+#[tracing::instrument(name = "build_pipeline", skip_all)]
 pub fn create_conversion_pipeline(
     source_url: &str,
     output_format: &str,
     output_path: &str,
 ) -> Result<String, String> {
     // ---
+    let source = source_element(source_url);
+    let output_path = quote(output_path);
 
     match output_format {
         "webm" => Ok(format!(
-            "souphttpsrc location={source_url} ! decodebin ! videoconvert ! vp8enc ! webmmux ! filesink location={output_path}"
+            "{source} ! decodebin ! videoconvert ! vp8enc ! webmmux ! filesink location={output_path}"
         )),
         "mp4" => Ok(format!(
-            "souphttpsrc location={source_url} ! decodebin ! videoconvert ! x264enc ! mp4mux ! filesink location={output_path}"
+            "{source} ! decodebin ! videoconvert ! x264enc ! mp4mux ! filesink location={output_path}"
         )),
         "avi" => Ok(format!(
-            "souphttpsrc location={source_url} ! decodebin ! videoconvert ! x264enc ! avimux ! filesink location={output_path}"
+            "{source} ! decodebin ! videoconvert ! x264enc ! avimux ! filesink location={output_path}"
         )),
         _ => Err(format!("Unsupported output format: {output_format}")),
     }
@@ -288,7 +549,7 @@ pub fn create_conversion_pipeline(
 /// a PNG image file.
 ///
 /// # Arguments
-/// * `source_url`  - HTTP(S) URL of the source video file
+/// * `source_url`  - HTTP(S) URL or local path of the source video file
 /// * `output_path` - Local filesystem path for the generated thumbnail
 /// * `width`       - Width of the thumbnail in pixels
 /// * `height`      - Height of the thumbnail in pixels
@@ -316,6 +577,7 @@ pub fn create_conversion_pipeline(
 ///     "00:01:30"
 /// );
 /// ```
+#[tracing::instrument(name = "build_pipeline", skip_all)]
 pub fn create_thumbnail_pipeline(
     source_url: &str,
     output_path: &str,
@@ -324,9 +586,11 @@ pub fn create_thumbnail_pipeline(
     _timestamp: &str,
 ) -> String {
     // ---
+    let source = source_element(source_url);
+    let output_path = quote(output_path);
 
     format!(
-        "souphttpsrc location={source_url} ! decodebin ! videoconvert ! videoscale ! video/x-raw,width={width},height={height} ! pngenc ! filesink location={output_path}"
+        "{source} ! decodebin ! videoconvert ! videoscale ! video/x-raw,width={width},height={height} ! pngenc ! filesink location={output_path}"
     )
 }
 
@@ -337,7 +601,7 @@ pub fn create_thumbnail_pipeline(
 /// is suitable for adaptive streaming to web browsers and mobile devices.
 ///
 /// # Arguments
-/// * `source_url` - HTTP(S) URL of the source media file
+/// * `source_url` - HTTP(S) URL or local path of the source media file
 /// * `output_dir` - Directory path where HLS segments and playlist will be created
 ///
 /// # Returns
@@ -352,6 +616,9 @@ pub fn create_thumbnail_pipeline(
 /// # Pipeline Structure
 /// `source → decode → convert → encode → mux → segment → output`
 ///
+/// A leaky branch after `videoconvert` feeds raw frames to a `fakesink`
+/// named `snapshot`, so running streams can serve frame snapshots.
+///
 /// # Streaming Characteristics
 /// - H.264 video encoding at 1000 kbps bitrate
 /// - MPEG-TS container format for segments
@@ -366,14 +633,177 @@ pub fn create_thumbnail_pipeline(
 /// );
 /// // Creates: /output/stream/segment_00001.ts, segment_00002.ts, ..., playlist.m3u8
 /// ```
+#[tracing::instrument(name = "build_pipeline", skip_all)]
 pub fn create_hls_stream_pipeline(source_url: &str, output_dir: &str) -> String {
     // ---
+    let source = source_element(source_url);
+    let segments = quote(&format!("{output_dir}/segment_%05d.ts"));
+    let playlist = quote(&format!("{output_dir}/playlist.m3u8"));
 
     format!(
-        "souphttpsrc location={source_url} ! decodebin ! videoconvert ! x264enc bitrate=1000 ! mpegtsmux ! hlssink location={output_dir}/segment_%05d.ts playlist-location={output_dir}/playlist.m3u8 max-files=10"
+        "{source} ! decodebin ! videoconvert ! tee name=t ! queue ! x264enc bitrate=1000 ! mpegtsmux ! hlssink location={segments} playlist-location={playlist} max-files=10 \
+         t. ! queue leaky=downstream max-size-buffers=1 ! fakesink name=snapshot"
     )
 }
 
+/// Validates a remote media source URL supplied with a job request.
+///
+/// # Returns
+/// * `Ok(())` - The URL is an absolute HTTP(S) URL
+/// * `Err(String)` - The URL is malformed or uses another scheme
+pub fn validate_source_url(source_url: &str) -> Result<(), String> {
+    // ---
+    match reqwest::Url::parse(source_url) {
+        Ok(url) if url.scheme() == "http" || url.scheme() == "https" => Ok(()),
+        Ok(url) => Err(format!(
+            "Source URL must use http or https, not {}",
+            url.scheme()
+        )),
+        Err(e) => Err(format!("Invalid source URL: {e}")),
+    }
+}
+
+/// Returns the GStreamer source element reading `source`.
+///
+/// HTTP(S) URLs are fetched with `souphttpsrc`; anything else is a local file
+/// path (such as a resolved upload) and is read with `filesrc`. The location
+/// is quoted, so it cannot inject further elements or properties.
+fn source_element(source: &str) -> String {
+    // ---
+    let location = quote(source);
+    if source.starts_with("http://") || source.starts_with("https://") {
+        format!("souphttpsrc location={location}")
+    } else {
+        format!("filesrc location={location}")
+    }
+}
+
+/// Detects the media type of a local file with GStreamer's `typefind`.
+///
+/// Only the first bytes of the file are read; nothing is decoded.
+///
+/// # Returns
+/// * `Ok(Some(String))` - Caps name of the detected type, e.g. `video/quicktime`
+/// * `Ok(None)` - No installed typefinder recognised the data
+/// * `Err(anyhow::Error)` - The probe pipeline could not be built or started
+pub fn sniff_media_type(path: &std::path::Path) -> anyhow::Result<Option<String>> {
+    // ---
+
+    use gstreamer::MessageView;
+
+    let pipeline = gstreamer::Pipeline::new();
+    let source = gstreamer::ElementFactory::make("filesrc")
+        .property("location", path.display().to_string())
+        .build()?;
+    let typefind = gstreamer::ElementFactory::make("typefind").build()?;
+    let sink = gstreamer::ElementFactory::make("fakesink").build()?;
+    pipeline.add_many([&source, &typefind, &sink])?;
+    gstreamer::Element::link_many([&source, &typefind, &sink])?;
+
+    pipeline.set_state(gstreamer::State::Paused)?;
+    let bus = pipeline.bus().expect("Pipeline without bus");
+
+    // Preroll completes once a type is found; typefind posts an error otherwise
+    let timeout = Duration::from_secs(5);
+    let start_time = std::time::Instant::now();
+
+    while start_time.elapsed() < timeout {
+        if let Some(msg) = bus.timed_pop(gstreamer::ClockTime::from_mseconds(100)) {
+            match msg.view() {
+                MessageView::Error(_) | MessageView::AsyncDone(_) => break,
+                _ => {}
+            }
+        }
+    }
+
+    let caps = typefind.property::<Option<gstreamer::Caps>>("caps");
+    pipeline.set_state(gstreamer::State::Null)?;
+
+    Ok(caps
+        .as_ref()
+        .and_then(|caps| caps.structure(0))
+        .map(|structure| structure.name().to_string()))
+}
+
+/// Returns true if a detected media type can plausibly be decoded as media.
+///
+/// Audio, video and image types are accepted, as are the container formats
+/// typefind reports under `application/`. Documents, archives, executables
+/// and text are not.
+///
+/// # Example
+/// ```rust
+/// assert!(is_media_type("video/quicktime"));
+/// assert!(!is_media_type("text/plain"));
+/// ```
+pub fn is_media_type(media_type: &str) -> bool {
+    // ---
+    const MEDIA_CONTAINERS: [&str; 5] = [
+        "application/ogg",
+        "application/x-id3",
+        "application/x-apetag",
+        "application/mxf",
+        "application/vnd.rn-realmedia",
+    ];
+
+    ["video/", "audio/", "image/"]
+        .iter()
+        .any(|prefix| media_type.starts_with(prefix))
+        || MEDIA_CONTAINERS.contains(&media_type)
+}
+
+/// Returns a short PCM WAV file that typefind recognises as `audio/x-wav`.
+///
+/// GStreamer's typefinders ship with gst-plugins-base; a minimal RIFF/WAVE
+/// typefinder is registered as well, so upload tests also run where those
+/// plugins are not installed.
+#[cfg(test)]
+pub(super) fn test_wav() -> Vec<u8> {
+    // ---
+    static REGISTER: std::sync::Once = std::sync::Once::new();
+
+    gstreamer::init().unwrap();
+    REGISTER.call_once(|| {
+        let caps = gstreamer::Caps::new_empty_simple("audio/x-wav");
+        gstreamer::TypeFind::register(
+            None,
+            "test-riff-wave",
+            gstreamer::Rank::Marginal,
+            Some("wav"),
+            Some(&caps.clone()),
+            move |typefind| {
+                if let Some(header) = typefind.peek(0, 12) {
+                    if header.starts_with(b"RIFF") && &header[8..] == b"WAVE" {
+                        typefind.suggest(gstreamer::TypeFindProbability::Maximum, &caps);
+                    }
+                }
+            },
+        )
+        .unwrap();
+    });
+
+    let samples = [128u8; 64];
+    let mut wav = Vec::new();
+    wav.extend_from_slice(b"RIFF");
+    wav.extend_from_slice(&(36 + samples.len() as u32).to_le_bytes());
+    wav.extend_from_slice(b"WAVEfmt ");
+    wav.extend_from_slice(&16u32.to_le_bytes());
+    // PCM, mono, 8000 Hz, 8000 bytes/s, 1 byte per frame, 8 bits per sample
+    for field in [1u16, 1] {
+        wav.extend_from_slice(&field.to_le_bytes());
+    }
+    for field in [8000u32, 8000] {
+        wav.extend_from_slice(&field.to_le_bytes());
+    }
+    for field in [1u16, 8] {
+        wav.extend_from_slice(&field.to_le_bytes());
+    }
+    wav.extend_from_slice(b"data");
+    wav.extend_from_slice(&(samples.len() as u32).to_le_bytes());
+    wav.extend_from_slice(&samples);
+    wav
+}
+
 #[cfg(test)]
 mod tests {
     // ---
@@ -405,6 +835,61 @@ mod tests {
         assert!(validate_pipeline_string("fakesrc ! identity ! fakesink").is_ok());
     }
 
+    #[test]
+    fn test_dry_run_pipeline_reports_diagnostics() {
+        // ---
+        ensure_gstreamer_init();
+
+        assert!(dry_run_pipeline("fakesrc num-buffers=1 ! fakesink", None).is_empty());
+        // Live sources do not preroll; building and linking still count
+        assert!(dry_run_pipeline("fakesrc is-live=true ! fakesink", None).is_empty());
+
+        let cases = [
+            ("fakesrc ! nosuchelement ! fakesink", DiagnosticKind::Syntax, None),
+            (
+                "fakesrc ! audio/x-raw,rate=8000 ! identity ! audio/x-raw,rate=16000 ! fakesink",
+                DiagnosticKind::NotLinked,
+                None,
+            ),
+            (
+                "fakesrc num-buffers=1 ! fakesink fakesink name=lonely",
+                DiagnosticKind::NotLinked,
+                Some("lonely"),
+            ),
+            (
+                "fakesrc num-buffers=1 ! capsfilter name=filter caps=video/x-raw,width=[1,10] ! fakesink",
+                DiagnosticKind::CapsNegotiation,
+                Some("filter"),
+            ),
+            (
+                "filesrc name=input location=/nonexistent/input.mp4 ! fakesink",
+                DiagnosticKind::Element,
+                Some("input"),
+            ),
+        ];
+        for (pipeline, kind, element) in cases {
+            let diagnostics = dry_run_pipeline(pipeline, None);
+            let first = diagnostics.first().expect(pipeline);
+            assert_eq!(first.severity, LogLevel::Error, "{pipeline}");
+            assert_eq!(first.kind, kind, "{pipeline}");
+            assert_eq!(first.element.as_deref(), element, "{pipeline}");
+        }
+    }
+
+    #[test]
+    fn test_dry_run_pipeline_redirects_file_sinks() {
+        // ---
+        ensure_gstreamer_init();
+        let output = std::env::temp_dir().join(format!("dry-run-{}.bin", uuid::Uuid::new_v4()));
+
+        let pipeline = format!(
+            "fakesrc num-buffers=1 ! filesink location={}",
+            output.display()
+        );
+        assert!(dry_run_pipeline(&pipeline, None).is_empty());
+        assert!(!output.exists());
+    }
+
     #[test]
     fn test_validate_pipeline_string_invalid_cases() {
         // ---
@@ -504,5 +989,79 @@ mod tests {
         assert!(pipeline.contains("hlssink"));
         assert!(pipeline.contains("/output/dir/segment_%05d.ts"));
         assert!(pipeline.contains("/output/dir/playlist.m3u8"));
+        assert!(pipeline
+            .contains("t. ! queue leaky=downstream max-size-buffers=1 ! fakesink name=snapshot"));
+    }
+
+    #[test]
+    fn test_local_sources_use_filesrc() {
+        // ---
+        let pipeline = create_hls_stream_pipeline("/var/uploads/abc", "/output/dir");
+        assert!(pipeline.starts_with("filesrc location=\"/var/uploads/abc\" !"));
+
+        let pipeline = create_conversion_pipeline("/var/uploads/abc", "webm", "out.webm").unwrap();
+        assert!(pipeline.starts_with("filesrc location=\"/var/uploads/abc\" !"));
+    }
+
+    #[test]
+    fn test_validate_source_url() {
+        // ---
+        assert!(validate_source_url("https://example.com/video.mp4").is_ok());
+        assert!(validate_source_url("http://localhost:9000/clip.webm").is_ok());
+
+        for source in [
+            "http/../../etc/passwd",
+            "https",
+            "/etc/passwd",
+            "file:///etc/passwd",
+            "ftp://example.com/video.mp4",
+        ] {
+            assert!(validate_source_url(source).is_err(), "accepted {source}");
+        }
+    }
+
+    #[test]
+    fn test_source_locations_are_quoted() {
+        // ---
+        gstreamer::init().unwrap();
+
+        let source = "https://example.com/a b.mp4 ! filesink location=/tmp/x";
+        let pipeline = create_thumbnail_pipeline(source, "/out/my \"thumb\".png", 64, 48, "0");
+        assert!(pipeline.starts_with(
+            "souphttpsrc location=\"https://example.com/a b.mp4 ! filesink location=/tmp/x\" !"
+        ));
+        assert!(pipeline.ends_with("filesink location=\"/out/my \\\"thumb\\\".png\""));
+
+        let element = gstreamer::parse_launch(&source_element("/var/uploads/a b\" c")).unwrap();
+        assert_eq!(
+            element.property::<Option<String>>("location").as_deref(),
+            Some("/var/uploads/a b\" c")
+        );
+    }
+
+    #[test]
+    fn test_is_media_type() {
+        // ---
+        assert!(is_media_type("video/x-matroska"));
+        assert!(is_media_type("audio/mpeg"));
+        assert!(is_media_type("image/png"));
+        assert!(is_media_type("application/ogg"));
+        assert!(!is_media_type("text/plain"));
+        assert!(!is_media_type("application/zip"));
+        assert!(!is_media_type("application/x-executable"));
+    }
+
+    #[test]
+    fn test_sniff_media_type_reads_local_files() {
+        // ---
+        ensure_gstreamer_init();
+
+        let path = std::env::temp_dir().join(format!("sniff-{}.bin", uuid::Uuid::new_v4()));
+        std::fs::write(&path, [0u8; 4096]).unwrap();
+
+        // Zeros match no typefinder; the probe must still finish cleanly
+        assert_eq!(sniff_media_type(&path).unwrap(), None);
+
+        let _ = std::fs::remove_file(&path);
     }
 }
@@ -0,0 +1,426 @@
+//! Signed completion webhooks with retrying delivery.
+//!
+//! When a pipeline with a `callback_url` reaches a terminal state, the runtime
+//! hands its record to the [`WebhookNotifier`], which POSTs a JSON
+//! [`WebhookPayload`] to the receiver in a background task. The payload
+//! describes the pipeline without its pipeline string or output paths, and
+//! links outputs the same way API responses do.
+//!
+//! # Signing
+//!
+//! When the server is started with `--webhook-secret`, the raw request body is
+//! signed with HMAC-SHA256 and the hex digest is sent as
+//! `X-Webhook-Signature: sha256=<hex>`. Receivers recompute the digest over the
+//! exact bytes they received and compare.
+//!
+//! # Retries
+//!
+//! Deliveries that fail to connect or receive a non-2xx response are retried
+//! with exponential backoff (1s, 2s, 4s, ...) up to the configured number of
+//! attempts. Every attempt is recorded on the pipeline's `webhook_deliveries`.
+//!
+//! # Trace Context
+//!
+//! Delivery runs in a `deliver_webhook` span continuing the trace of the
+//! pipeline's execution. When traces are exported, each request carries a
+//! W3C `traceparent` header so receivers can join the same trace.
+
+use chrono::Utc;
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+use std::path::Path;
+use std::time::Duration;
+use tracing::{info, info_span, warn, Instrument, Span};
+
+// ---
+
+// Import through gateway
+use crate::models::{
+    PipelineInfo, PipelineState, WebhookDelivery, WebhookPayload, WebhookPipeline,
+};
+
+// ---
+
+// Import from sibling modules
+use super::storage::public_link;
+use super::store::SharedPipelineStore;
+use super::telemetry::trace_headers;
+
+/// Header carrying the `sha256=<hex>` payload signature
+const SIGNATURE_HEADER: &str = "X-Webhook-Signature";
+
+/// Header carrying the event name, duplicated from the payload for routing
+const EVENT_HEADER: &str = "X-Webhook-Event";
+
+/// Delay before the first retry; doubled after every failed attempt
+const INITIAL_RETRY_DELAY: Duration = Duration::from_secs(1);
+
+/// Per-attempt timeout so a hanging receiver cannot stall delivery forever
+const DELIVERY_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Delivers completion webhooks for finished pipelines.
+///
+/// Cheap to clone; all clones share the same HTTP client and pipeline
+/// store, which is used to record delivery attempts.
+#[derive(Clone)]
+pub struct WebhookNotifier {
+    // ---
+    client: reqwest::Client,
+    secret: Option<String>,
+    max_attempts: u32,
+    public_url: Option<String>,
+    store: SharedPipelineStore,
+}
+
+impl WebhookNotifier {
+    // ---
+
+    /// Creates a notifier recording delivery attempts into `store`.
+    ///
+    /// # Arguments
+    /// * `store`        - Store updated with each delivery attempt
+    /// * `secret`       - HMAC key for payload signing; payloads are unsigned when None
+    /// * `max_attempts` - Total attempts per webhook, including the first (minimum 1)
+    /// * `public_url`   - Base URL of output links; links are relative when None
+    pub fn new(
+        store: SharedPipelineStore,
+        secret: Option<String>,
+        max_attempts: u32,
+        public_url: Option<String>,
+    ) -> Self {
+        // ---
+        let client = reqwest::Client::builder()
+            .timeout(DELIVERY_TIMEOUT)
+            .build()
+            .expect("Failed to build webhook HTTP client");
+
+        Self {
+            client,
+            secret,
+            max_attempts: max_attempts.max(1),
+            public_url,
+            store,
+        }
+    }
+
+    /// Schedules delivery of the completion webhook for a finished pipeline.
+    ///
+    /// Does nothing when the pipeline has no `callback_url`. Delivery runs in a
+    /// background task so callers never wait on the receiver.
+    pub fn notify(&self, pipeline: PipelineInfo) {
+        // ---
+        let Some(url) = pipeline.callback_url.clone() else {
+            return;
+        };
+
+        let span = info_span!(
+            parent: Span::current(),
+            "deliver_webhook",
+            pipeline.id = %pipeline.id
+        );
+        let notifier = self.clone();
+        tokio::spawn(async move { notifier.deliver(&url, pipeline).await }.instrument(span));
+    }
+
+    /// Sends the payload, retrying with exponential backoff until it is
+    /// acknowledged or the attempt budget is exhausted.
+    async fn deliver(&self, url: &str, pipeline: PipelineInfo) {
+        // ---
+        let pipeline_id = pipeline.id.clone();
+        let payload = build_payload(pipeline, self.public_url.as_deref());
+        let event = payload.event.clone();
+
+        let body = match serde_json::to_vec(&payload) {
+            Ok(body) => body,
+            Err(e) => {
+                warn!("Failed to serialize webhook for {}: {}", pipeline_id, e);
+                return;
+            }
+        };
+        let signature = self
+            .secret
+            .as_deref()
+            .map(|secret| sign_payload(secret, &body));
+
+        let mut delay = INITIAL_RETRY_DELAY;
+
+        for attempt in 1..=self.max_attempts {
+            // ---
+            let mut request = self
+                .client
+                .post(url)
+                .header(reqwest::header::CONTENT_TYPE, "application/json")
+                .header(EVENT_HEADER, &event)
+                .body(body.clone());
+
+            if let Some(signature) = &signature {
+                request = request.header(SIGNATURE_HEADER, signature);
+            }
+            for (name, value) in trace_headers(&Span::current()) {
+                request = request.header(name, value);
+            }
+
+            let delivery = match request.send().await {
+                Ok(response) if response.status().is_success() => WebhookDelivery {
+                    attempt,
+                    attempted_at: Utc::now().to_rfc3339(),
+                    status_code: Some(response.status().as_u16()),
+                    delivered: true,
+                    error: None,
+                },
+                Ok(response) => WebhookDelivery {
+                    attempt,
+                    attempted_at: Utc::now().to_rfc3339(),
+                    status_code: Some(response.status().as_u16()),
+                    delivered: false,
+                    error: Some(format!(
+                        "Receiver responded with HTTP {}",
+                        response.status().as_u16()
+                    )),
+                },
+                Err(e) => WebhookDelivery {
+                    attempt,
+                    attempted_at: Utc::now().to_rfc3339(),
+                    status_code: None,
+                    delivered: false,
+                    error: Some(e.to_string()),
+                },
+            };
+
+            let delivered = delivery.delivered;
+            self.record(&pipeline_id, delivery);
+
+            if delivered {
+                info!("Delivered {} webhook for {}", event, pipeline_id);
+                return;
+            }
+
+            if attempt < self.max_attempts {
+                tokio::time::sleep(delay).await;
+                delay *= 2;
+            }
+        }
+
+        warn!(
+            "Giving up on {} webhook for {} after {} attempts",
+            event, pipeline_id, self.max_attempts
+        );
+    }
+
+    /// Appends a delivery attempt to the pipeline record, if it still exists.
+    fn record(&self, pipeline_id: &str, delivery: WebhookDelivery) {
+        // ---
+        let result = self.store.update(pipeline_id, &mut |pipeline| {
+            pipeline.webhook_deliveries.push(delivery.clone())
+        });
+
+        if let Err(e) = result {
+            warn!(
+                "Failed to record webhook delivery for {}: {}",
+                pipeline_id, e
+            );
+        }
+    }
+}
+
+/// Builds the webhook payload describing a finished pipeline.
+///
+/// `public_url` is the configured `--public-url` base of output links.
+fn build_payload(pipeline: PipelineInfo, public_url: Option<&str>) -> WebhookPayload {
+    // ---
+    let (event, error) = match &pipeline.state {
+        PipelineState::Error(message) => ("pipeline.failed", Some(message.clone())),
+        _ => ("pipeline.stopped", None),
+    };
+
+    WebhookPayload {
+        event: event.to_string(),
+        outputs: output_locations(&pipeline, public_url),
+        pipeline: WebhookPipeline {
+            id: pipeline.id,
+            description: pipeline.description,
+            kind: pipeline.kind,
+            owner: pipeline.owner,
+            state: pipeline.state,
+            created_at: pipeline.created_at,
+            finished_at: pipeline.finished_at,
+            source_url: pipeline.source_url,
+            attempts: pipeline.attempts,
+        },
+        error,
+    }
+}
+
+/// Lists where receivers can fetch a finished pipeline's results.
+///
+/// These are the published locations (e.g. `s3://bucket/key`) when the
+/// outputs were uploaded, and otherwise the download links of the output
+/// files, built like every other link with [`public_link`]. Server-local
+/// paths are never sent, so receivers learn nothing about the server's
+/// directory layout.
+fn output_locations(pipeline: &PipelineInfo, public_url: Option<&str>) -> Vec<String> {
+    // ---
+    if !pipeline.published_outputs.is_empty() {
+        return pipeline.published_outputs.clone();
+    }
+
+    let mut names = Vec::new();
+    for output in pipeline.outputs.iter().map(Path::new) {
+        // ---
+        if output.is_dir() {
+            let mut files: Vec<String> = std::fs::read_dir(output)
+                .into_iter()
+                .flatten()
+                .filter_map(Result::ok)
+                .filter(|entry| entry.path().is_file())
+                .map(|entry| entry.file_name().to_string_lossy().into_owned())
+                .collect();
+            files.sort();
+            names.extend(files);
+        } else if let Some(name) = output.file_name() {
+            names.push(name.to_string_lossy().into_owned());
+        }
+    }
+
+    names
+        .into_iter()
+        .map(|name| {
+            public_link(
+                public_url,
+                &format!("/pipelines/{}/outputs/{name}", pipeline.id),
+            )
+        })
+        .collect()
+}
+
+/// Computes the `sha256=<hex>` HMAC-SHA256 signature of a webhook body.
+///
+/// # Arguments
+/// * `secret` - Shared secret configured with `--webhook-secret`
+/// * `body`   - Exact bytes of the request body
+///
+/// # Example
+/// ```rust
+/// let signature = sign_payload("secret", br#"{"event":"pipeline.stopped"}"#);
+/// assert!(signature.starts_with("sha256="));
+/// ```
+pub fn sign_payload(secret: &str, body: &[u8]) -> String {
+    // ---
+    let mut mac =
+        Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("HMAC accepts keys of any size");
+    mac.update(body);
+    format!("sha256={}", hex::encode(mac.finalize().into_bytes()))
+}
+
+/// Validates an optional callback URL supplied with a job request.
+///
+/// # Returns
+/// * `Ok(())` - No URL was supplied, or it is an absolute HTTP(S) URL
+/// * `Err(String)` - The URL is malformed or uses another scheme
+pub fn validate_callback_url(callback_url: Option<&str>) -> Result<(), String> {
+    // ---
+    let Some(callback_url) = callback_url else {
+        return Ok(());
+    };
+
+    match reqwest::Url::parse(callback_url) {
+        Ok(url) if url.scheme() == "http" || url.scheme() == "https" => Ok(()),
+        Ok(url) => Err(format!(
+            "Callback URL must use http or https, not {}",
+            url.scheme()
+        )),
+        Err(e) => Err(format!("Invalid callback URL: {e}")),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    // ---
+
+    use super::*;
+    use crate::models::PipelineKind;
+
+    #[test]
+    fn test_sign_payload_matches_rfc4231_vector() {
+        // ---
+        // RFC 4231 test case 2
+        let signature = sign_payload("Jefe", b"what do ya want for nothing?");
+        assert_eq!(
+            signature,
+            "sha256=5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843"
+        );
+    }
+
+    #[test]
+    fn test_validate_callback_url() {
+        // ---
+        assert!(validate_callback_url(None).is_ok());
+        assert!(validate_callback_url(Some("https://example.com/hook")).is_ok());
+        assert!(validate_callback_url(Some("http://localhost:9000/hook")).is_ok());
+
+        let result = validate_callback_url(Some("ftp://example.com/hook"));
+        assert!(result.unwrap_err().contains("http or https"));
+
+        let result = validate_callback_url(Some("not a url"));
+        assert!(result.unwrap_err().contains("Invalid callback URL"));
+    }
+
+    #[test]
+    fn test_build_payload_reports_errors() {
+        // ---
+        let pipeline = PipelineInfo {
+            outputs: vec!["out.webm".to_string()],
+            callback_url: Some("http://localhost/hook".to_string()),
+            ..PipelineInfo::test("abc", None, PipelineState::Error("boom".to_string()))
+        };
+
+        let payload = build_payload(pipeline.clone(), None);
+        assert_eq!(payload.event, "pipeline.failed");
+        assert_eq!(payload.error.as_deref(), Some("boom"));
+        assert_eq!(
+            payload.outputs,
+            vec!["/pipelines/abc/outputs/out.webm".to_string()]
+        );
+
+        let body = serde_json::to_value(&payload).unwrap();
+        assert_eq!(body["pipeline"]["id"], "abc");
+        assert!(body["pipeline"].get("outputs").is_none());
+        assert!(body["pipeline"].get("pipeline_string").is_none());
+
+        let payload = build_payload(pipeline, Some("https://media.example.com"));
+        assert_eq!(
+            payload.outputs,
+            vec!["https://media.example.com/pipelines/abc/outputs/out.webm".to_string()]
+        );
+    }
+
+    #[test]
+    fn test_payload_outputs_hide_local_paths() {
+        // ---
+        let dir = std::env::temp_dir().join(format!("webhook-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("segment_00001.ts"), b"data").unwrap();
+        std::fs::write(dir.join("playlist.m3u8"), b"data").unwrap();
+
+        let mut pipeline = PipelineInfo {
+            kind: PipelineKind::Stream,
+            outputs: vec![dir.display().to_string()],
+            ..PipelineInfo::test("abc", None, PipelineState::Stopped)
+        };
+        assert_eq!(
+            output_locations(&pipeline, None),
+            vec![
+                "/pipelines/abc/outputs/playlist.m3u8".to_string(),
+                "/pipelines/abc/outputs/segment_00001.ts".to_string()
+            ]
+        );
+
+        pipeline.published_outputs = vec!["s3://media/abc/playlist.m3u8".to_string()];
+        assert_eq!(
+            output_locations(&pipeline, None),
+            pipeline.published_outputs
+        );
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
@@ -7,10 +7,33 @@
 //! - `/health` - Server health and GStreamer version info
 //! - `/samples` - Sample media listing functionality  
 //! - `/analyze/{url}` - Media analysis endpoint (success and error cases)
+//! - Completion webhooks - signed delivery with retry to a local receiver
+//! - Persistent store - records survive a crash and interrupted jobs are failed
+//! - `/pipelines` listing - filters and cursor pagination
+//! - Output downloads - full, ranged and conditional requests; S3 publishing; job directory confinement
+//! - Retention - purge on delete, background reaper and disk watermarks
+//! - `/uploads` - raw and multipart uploads, resumable tus uploads; local source paths refused
+//! - API keys - authentication, scopes (including `templates:write`) and per-key pipeline and upload isolation
+//! - JWT bearer tokens - tenant-scoped pipeline visibility, separate from same-named API keys
+//! - Rate limits and quotas - 429 with Retry-After and remaining allowance headers
+//! - `/metrics` - Prometheus request, pipeline and GStreamer error metrics
+//! - Tracing - OTLP export to a local collector and trace context in webhooks
+//! - `/pipelines/{id}/logs` - captured GStreamer errors and lifecycle events; JSON log output
+//! - `/pipelines/{id}/graph` - DOT export of a prerolled pipeline, SVG and parameter errors
+//! - `/pipelines/{id}/topology` - elements, properties, pads, links and negotiated caps
+//! - `/pipelines/{id}/elements/{name}` - validated property updates on a running pipeline; sink paths refused
+//! - `/pipelines/{id}/position` and `/seek` - position queries, seeks, rate changes and refused seeks
+//! - `/pipelines/{id}/snapshot.jpg|png` - frame grabs from running pipelines and `/stream` jobs, missing frames and encoders
+//! - `/templates` - saving, validating and instantiating parameterized templates with escaped values
+//! - Structured `graph` pipelines - built from elements and links, run to completion, invalid graphs
+//! - `POST /pipelines?validate=deep` - preroll dry-runs with structured diagnostics
+//! - `/elements` - element factory listing by klass, pad templates and property details
+//! - Execution limits - default and requested runtime and output size limits, caps
+//! - Retries - automatic retries of covered GStreamer errors, `POST /pipelines/{id}/retry`
 //!
 //! ## Test Infrastructure
 //! - Uses reqwest for HTTP client functionality
-//! - Spawns server processes via `cargo run` with unique ports
+//! - Spawns the pre-built server binary with unique ports
 //! - Automatic server lifecycle management (startup, wait, cleanup)
 //! - DRY helpers for URL construction and server management
 //!
@@ -27,8 +50,11 @@
 //! pkill -f "media-pipeline-service.*--port 808[1-9]"
 //! ```
 
+use hmac::{Hmac, Mac};
 use serde_json::Value;
+use sha2::Sha256;
 use std::sync::atomic::{AtomicU16, Ordering};
+use std::sync::{Arc, Mutex};
 use std::time::Duration;
 use tokio::process::{Child, Command};
 use tokio::time::sleep;
@@ -62,23 +88,31 @@ impl TestServer {
     // ---
 
     async fn start() -> Self {
+        Self::start_with_args(&[]).await
+    }
+
+    async fn start_with_args(extra_args: &[&str]) -> Self {
         // ---
 
         let port = get_test_port();
         let base_url = format!("http://localhost:{port}");
-
-        // Use pre-built binary for faster, cleaner testing
-        let process = Command::new("cargo")
-            .args([
-                "run",
-                "--",
-                "--host",
-                "localhost",
-                "--port",
-                &port.to_string(),
-                "--color",
-                "never",
-            ])
+        let port_arg = port.to_string();
+
+        let mut args = vec![
+            "--host",
+            "localhost",
+            "--port",
+            &port_arg,
+            "--color",
+            "never",
+        ];
+        args.extend_from_slice(extra_args);
+
+        // Use pre-built binary for faster, cleaner testing; `cargo run` would
+        // inherit the test's CARGO_PKG_* variables and rebuild crates whose
+        // build scripts watch them
+        let process = Command::new(env!("CARGO_BIN_EXE_media-pipeline-service"))
+            .args(&args)
             .stdout(std::process::Stdio::piped()) // Capture for debugging
             .stderr(std::process::Stdio::piped()) // Capture for debugging
             .spawn()
@@ -140,7 +174,7 @@ async fn test_pipeline_lifecycle_integration() {
 
     let create_response = server
         .client
-        .post(&format!("{}/pipelines", server.base_url))
+        .post(format!("{}/pipelines", server.base_url))
         .header("Content-Type", "application/json")
         .json(&create_request)
         .send()
@@ -159,7 +193,7 @@ async fn test_pipeline_lifecycle_integration() {
     // Get specific pipeline
     let get_response = server
         .client
-        .get(&format!("{}/pipelines/{}", server.base_url, pipeline_id))
+        .get(format!("{}/pipelines/{}", server.base_url, pipeline_id))
         .send()
         .await
         .expect("Failed to get pipeline");
@@ -169,7 +203,7 @@ async fn test_pipeline_lifecycle_integration() {
     // List all pipelines and verify ours is there
     let list_response = server
         .client
-        .get(&format!("{}/pipelines", server.base_url))
+        .get(format!("{}/pipelines", server.base_url))
         .send()
         .await
         .expect("Failed to list pipelines");
@@ -181,7 +215,7 @@ async fn test_pipeline_lifecycle_integration() {
         .await
         .expect("Failed to parse list response");
 
-    assert!(pipelines
+    assert!(pipelines["pipelines"]
         .as_array()
         .unwrap()
         .iter()
@@ -190,7 +224,7 @@ async fn test_pipeline_lifecycle_integration() {
     // Stop the pipeline
     let delete_response = server
         .client
-        .delete(&format!("{}/pipelines/{}", server.base_url, pipeline_id))
+        .delete(format!("{}/pipelines/{}", server.base_url, pipeline_id))
         .send()
         .await
         .expect("Failed to stop pipeline");
@@ -200,7 +234,7 @@ async fn test_pipeline_lifecycle_integration() {
     // Verify pipeline is now stopped
     let final_get_response = server
         .client
-        .get(&format!("{}/pipelines/{}", server.base_url, pipeline_id))
+        .get(format!("{}/pipelines/{}", server.base_url, pipeline_id))
         .send()
         .await
         .expect("Failed to get stopped pipeline");
@@ -226,7 +260,7 @@ async fn test_convert_media_integration() {
 
     let response = server
         .client
-        .post(&format!("{}/convert", server.base_url))
+        .post(format!("{}/convert", server.base_url))
         .header("Content-Type", "application/json")
         .json(&convert_request)
         .send()
@@ -253,7 +287,7 @@ async fn test_convert_media_integration() {
     let pipeline_id = convert_response["pipeline_id"].as_str().unwrap();
     let pipeline_response = server
         .client
-        .get(&format!("{}/pipelines/{}", server.base_url, pipeline_id))
+        .get(format!("{}/pipelines/{}", server.base_url, pipeline_id))
         .send()
         .await
         .expect("Failed to get pipeline");
@@ -392,3 +426,2907 @@ async fn test_samples_endpoint() {
     // ---
     server.shutdown().await;
 }
+
+/// Webhook deliveries seen by the local receiver.
+type Received = Arc<Mutex<Vec<(Option<String>, Option<String>, Vec<u8>)>>>;
+
+/// Spawns a local webhook receiver that rejects the first delivery with 500
+/// and accepts later ones, recording every (signature, traceparent, body) it
+/// sees.
+async fn start_webhook_receiver() -> (String, Received) {
+    // ---
+
+    use axum::{body::Bytes, extract::State, http::HeaderMap, http::StatusCode, routing::post};
+
+    async fn receive(
+        State(received): State<Received>,
+        headers: HeaderMap,
+        body: Bytes,
+    ) -> StatusCode {
+        let header = |name: &str| {
+            headers
+                .get(name)
+                .and_then(|value| value.to_str().ok())
+                .map(str::to_string)
+        };
+
+        let mut received = received.lock().unwrap();
+        received.push((
+            header("x-webhook-signature"),
+            header("traceparent"),
+            body.to_vec(),
+        ));
+
+        if received.len() == 1 {
+            StatusCode::INTERNAL_SERVER_ERROR
+        } else {
+            StatusCode::OK
+        }
+    }
+
+    let received: Received = Arc::new(Mutex::new(Vec::new()));
+    let app = axum::Router::new()
+        .route("/hook", post(receive))
+        .with_state(received.clone());
+
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0")
+        .await
+        .expect("Failed to bind webhook receiver");
+    let url = format!("http://{}/hook", listener.local_addr().unwrap());
+    tokio::spawn(async move { axum::serve(listener, app).await });
+
+    (url, received)
+}
+
+#[tokio::test]
+async fn test_completion_webhook_signed_and_retried() {
+    // ---
+
+    let secret = "integration-secret";
+    let server = TestServer::start_with_args(&["--workers", "1", "--webhook-secret", secret]).await;
+    let (callback_url, received) = start_webhook_receiver().await;
+
+    let create_response = server
+        .client
+        .post(endpoint_url!(server.base_url, "pipelines"))
+        .json(&serde_json::json!({
+            "description": "Webhook test pipeline",
+            "pipeline": "fakesrc num-buffers=10 ! fakesink",
+            "callback_url": callback_url
+        }))
+        .send()
+        .await
+        .expect("Failed to create pipeline");
+
+    assert_eq!(create_response.status(), 200);
+    let created: Value = create_response.json().await.unwrap();
+    let pipeline_id = created["id"].as_str().unwrap().to_string();
+
+    // First attempt is rejected, the retry after ~1s is accepted
+    let start = std::time::Instant::now();
+    while received.lock().unwrap().len() < 2 && start.elapsed() < Duration::from_secs(15) {
+        sleep(Duration::from_millis(100)).await;
+    }
+
+    let deliveries = received.lock().unwrap().clone();
+    assert_eq!(
+        deliveries.len(),
+        2,
+        "Expected one failed and one retried delivery"
+    );
+
+    for (signature, _, body) in &deliveries {
+        let mut mac = Hmac::<Sha256>::new_from_slice(secret.as_bytes()).unwrap();
+        mac.update(body);
+        let expected = format!("sha256={}", hex::encode(mac.finalize().into_bytes()));
+        assert_eq!(signature.as_deref(), Some(expected.as_str()));
+
+        let payload: Value = serde_json::from_slice(body).unwrap();
+        assert_eq!(payload["event"], "pipeline.stopped");
+        assert_eq!(payload["pipeline"]["id"], pipeline_id.as_str());
+        assert_eq!(payload["pipeline"]["state"], "Stopped");
+        assert!(payload["pipeline"].get("pipeline_string").is_none());
+        assert!(payload["pipeline"].get("outputs").is_none());
+        assert!(payload["error"].is_null());
+    }
+
+    // Both attempts are recorded on the pipeline
+    let pipeline: Value = server
+        .client
+        .get(endpoint_url!(server.base_url, "pipelines", pipeline_id))
+        .send()
+        .await
+        .expect("Failed to get pipeline")
+        .json()
+        .await
+        .unwrap();
+
+    let attempts = pipeline["webhook_deliveries"].as_array().unwrap();
+    assert_eq!(attempts.len(), 2);
+    assert_eq!(attempts[0]["status_code"], 500);
+    assert_eq!(attempts[0]["delivered"], false);
+    assert_eq!(attempts[1]["status_code"], 200);
+    assert_eq!(attempts[1]["delivered"], true);
+
+    // ---
+    server.shutdown().await;
+}
+
+/// Polls a pipeline until its state name matches `expected` or times out.
+async fn wait_for_state(server: &TestServer, pipeline_id: &str, expected: &str) -> Value {
+    // ---
+
+    let start = std::time::Instant::now();
+    loop {
+        let pipeline: Value = server
+            .client
+            .get(endpoint_url!(server.base_url, "pipelines", pipeline_id))
+            .send()
+            .await
+            .expect("Failed to get pipeline")
+            .json()
+            .await
+            .unwrap();
+
+        let matches = pipeline["state"] == expected || pipeline["state"].get(expected).is_some();
+        if matches || start.elapsed() > Duration::from_secs(10) {
+            return pipeline;
+        }
+        sleep(Duration::from_millis(100)).await;
+    }
+}
+
+#[tokio::test]
+async fn test_sqlite_store_survives_restart() {
+    // ---
+
+    let database =
+        std::env::temp_dir().join(format!("media-pipeline-restart-{}.db", std::process::id()));
+    let _ = std::fs::remove_file(&database);
+    let database_arg = database.to_str().unwrap().to_string();
+    let args = ["--workers", "1", "--database", database_arg.as_str()];
+
+    // Start a pipeline that never finishes on its own, then kill the server
+    let server = TestServer::start_with_args(&args).await;
+    let created: Value = server
+        .client
+        .post(endpoint_url!(server.base_url, "pipelines"))
+        .json(&serde_json::json!({
+            "description": "Interrupted pipeline",
+            "pipeline": "fakesrc ! identity sleep-time=10000 ! fakesink"
+        }))
+        .send()
+        .await
+        .expect("Failed to create pipeline")
+        .json()
+        .await
+        .unwrap();
+    let pipeline_id = created["id"].as_str().unwrap().to_string();
+
+    let pipeline = wait_for_state(&server, &pipeline_id, "Playing").await;
+    assert_eq!(pipeline["state"], "Playing");
+    server.shutdown().await;
+
+    // The default restart policy fails the interrupted pipeline
+    let server = TestServer::start_with_args(&args).await;
+    let pipeline = wait_for_state(&server, &pipeline_id, "Error").await;
+    assert_eq!(pipeline["state"]["Error"], "interrupted by restart");
+    assert_eq!(pipeline["description"], "Interrupted pipeline");
+
+    // ---
+    server.shutdown().await;
+    let _ = std::fs::remove_file(&database);
+}
+
+#[tokio::test]
+async fn test_list_pipelines_filters_and_pages() {
+    // ---
+
+    let server = TestServer::start().await;
+
+    for description in ["Paging alpha", "Paging beta", "Paging gamma", "Other job"] {
+        let response = server
+            .client
+            .post(format!("{}/pipelines", server.base_url))
+            .json(&serde_json::json!({
+                "description": description,
+                "pipeline": "fakesrc ! fakesink"
+            }))
+            .send()
+            .await
+            .expect("Failed to create pipeline");
+        assert_eq!(response.status(), 200);
+    }
+
+    // Walk the description-filtered listing two at a time, oldest first
+    let mut descriptions = Vec::new();
+    let mut cursor: Option<String> = None;
+    loop {
+        let mut url = format!(
+            "{}/pipelines?description=PAGING&order=asc&limit=2",
+            server.base_url
+        );
+        if let Some(cursor) = &cursor {
+            url.push_str(&format!("&cursor={cursor}"));
+        }
+
+        let page: Value = server
+            .client
+            .get(url)
+            .send()
+            .await
+            .expect("Failed to list pipelines")
+            .json()
+            .await
+            .expect("Failed to parse list response");
+
+        for pipeline in page["pipelines"].as_array().unwrap() {
+            descriptions.push(pipeline["description"].as_str().unwrap().to_string());
+        }
+        match page["next_cursor"].as_str() {
+            Some(next) => cursor = Some(next.to_string()),
+            None => break,
+        }
+    }
+    assert_eq!(
+        descriptions,
+        vec!["Paging alpha", "Paging beta", "Paging gamma"]
+    );
+
+    // State filter excludes everything that is not stopped
+    let page: Value = server
+        .client
+        .get(format!("{}/pipelines?state=stopped", server.base_url))
+        .send()
+        .await
+        .expect("Failed to list pipelines")
+        .json()
+        .await
+        .expect("Failed to parse list response");
+    assert!(page["pipelines"].as_array().unwrap().is_empty());
+
+    // Invalid parameters are rejected
+    for query in [
+        "state=Bogus",
+        "limit=0",
+        "cursor=zz",
+        "created_after=yesterday",
+    ] {
+        let response = server
+            .client
+            .get(format!("{}/pipelines?{query}", server.base_url))
+            .send()
+            .await
+            .expect("Failed to list pipelines");
+        assert_eq!(response.status(), 400, "query {query} should be rejected");
+    }
+
+    // ---
+    server.shutdown().await;
+}
+
+#[tokio::test]
+async fn test_output_download_with_ranges() {
+    // ---
+
+    let server = TestServer::start_with_args(&["--workers", "1"]).await;
+
+    // Write 4 x 256 bytes to a file in the job directory through a custom pipeline
+    let created: Value = server
+        .client
+        .post(endpoint_url!(server.base_url, "pipelines"))
+        .json(&serde_json::json!({
+            "description": "Download test",
+            "pipeline": "fakesrc num-buffers=4 sizetype=fixed sizemax=256 filltype=pattern ! filesink location=download.bin"
+        }))
+        .send()
+        .await
+        .expect("Failed to create pipeline")
+        .json()
+        .await
+        .unwrap();
+    let pipeline_id = created["id"].as_str().unwrap().to_string();
+    assert_eq!(
+        created["outputs"][0],
+        format!("outputs/{pipeline_id}/download.bin")
+    );
+
+    let pipeline = wait_for_state(&server, &pipeline_id, "Stopped").await;
+    assert_eq!(pipeline["state"], "Stopped");
+
+    let download_url = format!("{}/pipelines/{}/output", server.base_url, pipeline_id);
+
+    // Full download
+    let response = server.client.get(&download_url).send().await.unwrap();
+    assert_eq!(response.status(), 200);
+    assert_eq!(
+        response.headers()["content-type"],
+        "application/octet-stream"
+    );
+    assert_eq!(response.headers()["content-length"], "1024");
+    assert_eq!(response.headers()["accept-ranges"], "bytes");
+    let etag = response.headers()["etag"].to_str().unwrap().to_string();
+    assert_eq!(response.bytes().await.unwrap().len(), 1024);
+
+    // Byte range
+    let response = server
+        .client
+        .get(&download_url)
+        .header("Range", "bytes=100-199")
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 206);
+    assert_eq!(response.headers()["content-range"], "bytes 100-199/1024");
+    assert_eq!(response.bytes().await.unwrap().len(), 100);
+
+    // Conditional request and unsatisfiable range
+    let response = server
+        .client
+        .get(&download_url)
+        .header("If-None-Match", &etag)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 304);
+
+    let response = server
+        .client
+        .get(&download_url)
+        .header("Range", "bytes=5000-")
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 416);
+    assert_eq!(response.headers()["content-range"], "bytes */1024");
+
+    // Named access and traversal protection
+    let response = server
+        .client
+        .get(format!(
+            "{}/pipelines/{}/outputs/download.bin",
+            server.base_url, pipeline_id
+        ))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 200);
+
+    let response = server
+        .client
+        .get(format!(
+            "{}/pipelines/{}/outputs/..",
+            server.base_url, pipeline_id
+        ))
+        .send()
+        .await
+        .unwrap();
+    assert_ne!(response.status(), 200);
+
+    // Custom pipelines cannot record outputs outside their job directory
+    for location in ["/etc/passwd", "../../etc/passwd"] {
+        let response = server
+            .client
+            .post(endpoint_url!(server.base_url, "pipelines"))
+            .json(&serde_json::json!({
+                "description": "Escaping output",
+                "pipeline": format!("fakesrc num-buffers=1 ! filesink location={location}")
+            }))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), 400, "accepted {location}");
+        let body: Value = response.json().await.unwrap();
+        assert!(body["details"]
+            .as_str()
+            .unwrap()
+            .contains("outside the job directory"));
+    }
+    let response = server
+        .client
+        .post(endpoint_url!(server.base_url, "pipelines"))
+        .json(&serde_json::json!({
+            "description": "Escaping graph output",
+            "graph": {
+                "elements": [
+                    { "factory": "fakesrc", "name": "src" },
+                    { "factory": "filesink", "name": "sink", "properties": { "location": "/etc/passwd" } }
+                ],
+                "links": [{ "from": "src", "to": "sink" }]
+            }
+        }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 400);
+
+    // Neither can sinks writing numbered files
+    let response = server
+        .client
+        .post(endpoint_url!(server.base_url, "pipelines"))
+        .json(&serde_json::json!({
+            "description": "Escaping numbered outputs",
+            "pipeline": "fakesrc num-buffers=1 ! multifilesink location=/tmp/x%d"
+        }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 400);
+
+    // ---
+    server.shutdown().await;
+}
+
+/// An object received by the fake S3 service: (path, authorization, body).
+type S3Object = (String, Option<String>, Vec<u8>);
+
+/// Spawns an in-process fake S3 endpoint accepting path-style PUTs.
+async fn start_fake_s3() -> (String, Arc<Mutex<Vec<S3Object>>>) {
+    // ---
+
+    use axum::{body::Bytes, extract::State, http::HeaderMap, http::StatusCode, http::Uri};
+
+    type Objects = Arc<Mutex<Vec<S3Object>>>;
+
+    async fn put_object(
+        State(objects): State<Objects>,
+        uri: Uri,
+        headers: HeaderMap,
+        body: Bytes,
+    ) -> StatusCode {
+        let authorization = headers
+            .get("authorization")
+            .and_then(|value| value.to_str().ok())
+            .map(str::to_string);
+
+        objects
+            .lock()
+            .unwrap()
+            .push((uri.path().to_string(), authorization, body.to_vec()));
+        StatusCode::OK
+    }
+
+    let objects: Objects = Arc::new(Mutex::new(Vec::new()));
+    let app = axum::Router::new()
+        .route("/*path", axum::routing::put(put_object))
+        .with_state(objects.clone());
+
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0")
+        .await
+        .expect("Failed to bind fake S3");
+    let url = format!("http://{}", listener.local_addr().unwrap());
+    tokio::spawn(async move { axum::serve(listener, app).await });
+
+    (url, objects)
+}
+
+#[tokio::test]
+async fn test_outputs_published_to_s3() {
+    // ---
+
+    let (endpoint, objects) = start_fake_s3().await;
+    let output_dir = std::env::temp_dir().join(format!("s3-staging-{}", std::process::id()));
+    let output_dir_arg = output_dir.display().to_string();
+
+    let server = TestServer::start_with_args(&[
+        "--workers",
+        "1",
+        "--output-dir",
+        &output_dir_arg,
+        "--s3-bucket",
+        "media",
+        "--s3-endpoint",
+        &endpoint,
+        "--s3-prefix",
+        "jobs/",
+        "--s3-access-key",
+        "test-key",
+        "--s3-secret-key",
+        "test-secret",
+    ])
+    .await;
+
+    let created: Value = server
+        .client
+        .post(endpoint_url!(server.base_url, "pipelines"))
+        .json(&serde_json::json!({
+            "description": "S3 upload test",
+            "pipeline": "fakesrc num-buffers=2 sizetype=fixed sizemax=512 ! filesink location=upload.bin"
+        }))
+        .send()
+        .await
+        .expect("Failed to create pipeline")
+        .json()
+        .await
+        .unwrap();
+    let pipeline_id = created["id"].as_str().unwrap().to_string();
+
+    let pipeline = wait_for_state(&server, &pipeline_id, "Stopped").await;
+    assert_eq!(pipeline["state"], "Stopped");
+
+    assert_eq!(
+        pipeline["published_outputs"][0],
+        format!("s3://media/jobs/{pipeline_id}/upload.bin")
+    );
+
+    let objects = objects.lock().unwrap().clone();
+    assert_eq!(objects.len(), 1);
+    let (path, authorization, body) = &objects[0];
+    assert_eq!(path, &format!("/media/jobs/{pipeline_id}/upload.bin"));
+    assert!(authorization
+        .as_deref()
+        .unwrap()
+        .starts_with("AWS4-HMAC-SHA256 Credential=test-key/"));
+    assert_eq!(body.len(), 1024);
+
+    // ---
+    server.shutdown().await;
+    let _ = std::fs::remove_dir_all(&output_dir);
+}
+
+/// Creates a custom pipeline writing 1 KiB to the file `name` in its job
+/// directory and waits for it to stop.
+///
+/// Returns the pipeline ID and the recorded output path.
+async fn run_file_pipeline(server: &TestServer, name: &str) -> (String, std::path::PathBuf) {
+    // ---
+    let created: Value = server
+        .client
+        .post(endpoint_url!(server.base_url, "pipelines"))
+        .json(&serde_json::json!({
+            "description": "Retention test",
+            "pipeline": format!(
+                "fakesrc num-buffers=4 sizetype=fixed sizemax=256 ! filesink location={name}"
+            )
+        }))
+        .send()
+        .await
+        .expect("Failed to create pipeline")
+        .json()
+        .await
+        .unwrap();
+    let pipeline_id = created["id"].as_str().unwrap().to_string();
+
+    let pipeline = wait_for_state(server, &pipeline_id, "Stopped").await;
+    assert_eq!(pipeline["state"], "Stopped");
+    assert!(pipeline["finished_at"].is_string());
+    let output = std::path::PathBuf::from(pipeline["outputs"][0].as_str().unwrap());
+    assert!(output.exists());
+    (pipeline_id, output)
+}
+
+#[tokio::test]
+async fn test_purge_deletes_outputs_and_record() {
+    // ---
+
+    let server = TestServer::start_with_args(&["--workers", "1"]).await;
+    let (pipeline_id, output) = run_file_pipeline(&server, "purge.bin").await;
+
+    let pipeline_url = format!("{}/pipelines/{}", server.base_url, pipeline_id);
+    let response = server
+        .client
+        .delete(format!("{pipeline_url}?purge=true"))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 200);
+    let body: Value = response.json().await.unwrap();
+    assert_eq!(body["message"], "Pipeline purged");
+
+    assert!(!output.exists());
+    let response = server.client.get(&pipeline_url).send().await.unwrap();
+    assert_eq!(response.status(), 404);
+
+    let response = server
+        .client
+        .delete(format!("{pipeline_url}?purge=true"))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 404);
+
+    // ---
+    server.shutdown().await;
+}
+
+#[tokio::test]
+async fn test_retention_reaper_expires_stopped_pipelines() {
+    // ---
+
+    let server = TestServer::start_with_args(&[
+        "--workers",
+        "1",
+        "--retention",
+        "stopped=1s",
+        "--reap-interval",
+        "1",
+    ])
+    .await;
+    let (pipeline_id, output) = run_file_pipeline(&server, "reaper.bin").await;
+
+    let pipeline_url = format!("{}/pipelines/{}", server.base_url, pipeline_id);
+    let deadline = std::time::Instant::now() + Duration::from_secs(10);
+    loop {
+        let response = server.client.get(&pipeline_url).send().await.unwrap();
+        if response.status() == 404 {
+            break;
+        }
+        assert!(
+            std::time::Instant::now() < deadline,
+            "Pipeline {pipeline_id} was not reaped"
+        );
+        sleep(Duration::from_millis(250)).await;
+    }
+    assert!(!output.exists());
+
+    // ---
+    server.shutdown().await;
+}
+
+#[tokio::test]
+async fn test_disk_watermark_refuses_new_jobs() {
+    // ---
+
+    // Any filesystem is at least 0% full, so every job is refused
+    let server = TestServer::start_with_args(&["--disk-high-watermark", "0"]).await;
+
+    let response = server
+        .client
+        .post(endpoint_url!(server.base_url, "pipelines"))
+        .json(&serde_json::json!({
+            "description": "Watermark test",
+            "pipeline": "fakesrc num-buffers=1 ! fakesink"
+        }))
+        .send()
+        .await
+        .expect("Failed to send request");
+    assert_eq!(response.status(), 507);
+    let body: Value = response.json().await.unwrap();
+    assert_eq!(body["error"], "Insufficient storage");
+
+    // ---
+    server.shutdown().await;
+}
+
+#[tokio::test]
+async fn test_media_upload_raw_and_multipart() {
+    // ---
+
+    let upload_dir = std::env::temp_dir().join(format!("uploads-{}", std::process::id()));
+    let upload_dir_arg = upload_dir.display().to_string();
+    let server = TestServer::start_with_args(&[
+        "--upload-dir",
+        &upload_dir_arg,
+        "--max-upload-size",
+        "4096",
+    ])
+    .await;
+
+    // Data no typefinder identifies is rejected and not kept
+    let response = server
+        .client
+        .post(format!("{}/uploads?filename=clip.bin", server.base_url))
+        .body(b"abcdef".to_vec())
+        .send()
+        .await
+        .expect("Failed to upload");
+    assert_eq!(response.status(), 415);
+    let body: Value = response.json().await.unwrap();
+    assert_eq!(body["error"], "Unsupported media type");
+    assert_eq!(
+        std::fs::read_dir(&upload_dir).map_or(0, |entries| entries.count()),
+        0
+    );
+
+    // Raw body upload; without a WAV typefinder it is rejected the same way
+    let wav = wav_bytes(2048);
+    let response = server
+        .client
+        .post(format!("{}/uploads?filename=clip.wav", server.base_url))
+        .body(wav.clone())
+        .send()
+        .await
+        .expect("Failed to upload");
+    if wav_typefinder_installed() {
+        assert_eq!(response.status(), 200);
+        let upload: Value = response.json().await.unwrap();
+        let upload_id = upload["id"].as_str().unwrap().to_string();
+        assert_eq!(upload["size"], 2048);
+        assert_eq!(upload["filename"], "clip.wav");
+        assert_eq!(upload["content_type"], "audio/x-wav");
+        assert_eq!(upload["source_url"], format!("upload://{upload_id}"));
+        assert_eq!(std::fs::read(upload_dir.join(&upload_id)).unwrap(), wav);
+
+        let fetched: Value = server
+            .client
+            .get(format!("{}/uploads/{}", server.base_url, upload_id))
+            .send()
+            .await
+            .unwrap()
+            .json()
+            .await
+            .unwrap();
+        assert_eq!(fetched["size"], 2048);
+
+        // Multipart upload
+        let form = reqwest::multipart::Form::new()
+            .text("note", "ignored")
+            .part(
+                "file",
+                reqwest::multipart::Part::bytes(wav_bytes(1000)).file_name("field.wav"),
+            );
+        let response = server
+            .client
+            .post(endpoint_url!(server.base_url, "uploads"))
+            .multipart(form)
+            .send()
+            .await
+            .expect("Failed to upload");
+        assert_eq!(response.status(), 200);
+        let upload: Value = response.json().await.unwrap();
+        assert_eq!(upload["size"], 1000);
+        assert_eq!(upload["filename"], "field.wav");
+    } else {
+        assert_eq!(response.status(), 415);
+    }
+
+    // Size limit
+    let response = server
+        .client
+        .post(endpoint_url!(server.base_url, "uploads"))
+        .body(vec![0u8; 8192])
+        .send()
+        .await
+        .expect("Failed to upload");
+    assert_eq!(response.status(), 413);
+
+    // Jobs reject unknown uploads
+    let response = server
+        .client
+        .post(endpoint_url!(server.base_url, "convert"))
+        .json(&serde_json::json!({
+            "source_url": "upload://00000000-0000-4000-8000-000000000000",
+            "output_format": "webm"
+        }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 400);
+    let body: Value = response.json().await.unwrap();
+    assert_eq!(body["error"], "Unknown upload");
+
+    // Jobs never read local paths dressed up as URLs
+    for (endpoint, source_url) in [
+        ("convert", "http/../../etc/passwd"),
+        ("thumbnail", "/etc/passwd"),
+        ("stream", "file:///etc/passwd"),
+    ] {
+        let response = server
+            .client
+            .post(endpoint_url!(server.base_url, endpoint))
+            .json(&serde_json::json!({
+                "source_url": source_url,
+                "output_format": "webm",
+                "stream_type": "hls"
+            }))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), 400, "{endpoint} accepted {source_url}");
+        let body: Value = response.json().await.unwrap();
+        assert_eq!(
+            body["error"],
+            "Source URL must be a valid HTTP(S) URL or upload://<id>"
+        );
+    }
+
+    // ---
+    server.shutdown().await;
+    let _ = std::fs::remove_dir_all(&upload_dir);
+}
+
+/// Builds a mono 8 kHz 8-bit PCM WAV file of `len` bytes.
+fn wav_bytes(len: usize) -> Vec<u8> {
+    // ---
+    assert!(len >= 44, "A WAV header alone takes 44 bytes");
+    let samples = (len - 44) as u32;
+    let mut wav = Vec::with_capacity(len);
+    wav.extend_from_slice(b"RIFF");
+    wav.extend_from_slice(&(36 + samples).to_le_bytes());
+    wav.extend_from_slice(b"WAVEfmt ");
+    wav.extend_from_slice(&16u32.to_le_bytes());
+    // PCM, mono, 8000 Hz, 8000 bytes/s, 1 byte per frame, 8 bits per sample
+    for field in [1u16, 1] {
+        wav.extend_from_slice(&field.to_le_bytes());
+    }
+    for field in [8000u32, 8000] {
+        wav.extend_from_slice(&field.to_le_bytes());
+    }
+    for field in [1u16, 8] {
+        wav.extend_from_slice(&field.to_le_bytes());
+    }
+    wav.extend_from_slice(b"data");
+    wav.extend_from_slice(&samples.to_le_bytes());
+    wav.resize(len, 128);
+    wav
+}
+
+/// Returns true when an installed typefinder recognises WAV data, i.e.
+/// when the server can accept the uploads built by [`wav_bytes`].
+fn wav_typefinder_installed() -> bool {
+    // ---
+    gstreamer::init().expect("Failed to initialize GStreamer");
+    gstreamer::TypeFindFactory::factories()
+        .iter()
+        .any(|factory| {
+            factory.caps().is_some_and(|caps| {
+                caps.iter()
+                    .any(|structure| structure.name() == "audio/x-wav")
+            })
+        })
+}
+
+/// Sends a tus HEAD request and returns `Upload-Offset`.
+async fn tus_offset(server: &TestServer, location: &str) -> u64 {
+    // ---
+    let response = server
+        .client
+        .head(format!("{}{}", server.base_url, location))
+        .header("Tus-Resumable", "1.0.0")
+        .send()
+        .await
+        .expect("Failed to query upload offset");
+    assert_eq!(response.status(), 200);
+    assert_eq!(response.headers()["cache-control"], "no-store");
+    response.headers()["upload-offset"]
+        .to_str()
+        .unwrap()
+        .parse()
+        .unwrap()
+}
+
+#[tokio::test]
+async fn test_tus_upload_resumes_after_interruption() {
+    // ---
+    use tokio::io::AsyncWriteExt;
+
+    let upload_dir = std::env::temp_dir().join(format!("tus-{}", std::process::id()));
+    let upload_dir_arg = upload_dir.display().to_string();
+    let server = TestServer::start_with_args(&["--upload-dir", &upload_dir_arg]).await;
+    let tus_url = endpoint_url!(server.base_url, "uploads/tus");
+    let data = wav_bytes(4096);
+
+    // Capabilities and version negotiation
+    let response = server
+        .client
+        .request(reqwest::Method::OPTIONS, &tus_url)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 204);
+    assert_eq!(response.headers()["tus-version"], "1.0.0");
+    assert_eq!(response.headers()["tus-extension"], "creation,termination");
+
+    let response = server
+        .client
+        .post(&tus_url)
+        .header("Upload-Length", "4096")
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 412);
+
+    // Create the upload; "cmVjb3JkaW5nLm1wNA==" is "recording.mp4"
+    let response = server
+        .client
+        .post(&tus_url)
+        .header("Tus-Resumable", "1.0.0")
+        .header("Upload-Length", "4096")
+        .header("Upload-Metadata", "filename cmVjb3JkaW5nLm1wNA==")
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 201);
+    assert_eq!(response.headers()["tus-resumable"], "1.0.0");
+    let location = response.headers()["location"].to_str().unwrap().to_string();
+    let upload_id = location.rsplit('/').next().unwrap().to_string();
+    assert_eq!(tus_offset(&server, &location).await, 0);
+
+    // Announce the whole file but drop the connection after 1500 bytes
+    let mut stream = tokio::net::TcpStream::connect(("localhost", server.port))
+        .await
+        .expect("Failed to connect");
+    let head = format!(
+        "PATCH {location} HTTP/1.1\r\nHost: localhost\r\nTus-Resumable: 1.0.0\r\n\
+         Upload-Offset: 0\r\nContent-Type: application/offset+octet-stream\r\n\
+         Content-Length: 4096\r\n\r\n"
+    );
+    stream.write_all(head.as_bytes()).await.unwrap();
+    stream.write_all(&data[..1500]).await.unwrap();
+    stream.flush().await.unwrap();
+    sleep(Duration::from_millis(300)).await;
+    drop(stream);
+
+    // The received bytes survive the interruption
+    let deadline = std::time::Instant::now() + Duration::from_secs(5);
+    while tus_offset(&server, &location).await != 1500 {
+        assert!(
+            std::time::Instant::now() < deadline,
+            "Partial PATCH was not kept"
+        );
+        sleep(Duration::from_millis(100)).await;
+    }
+    sleep(Duration::from_millis(200)).await;
+
+    // Resuming from a stale offset is refused
+    let response = server
+        .client
+        .patch(format!("{}{}", server.base_url, location))
+        .header("Tus-Resumable", "1.0.0")
+        .header("Upload-Offset", "0")
+        .header("Content-Type", "application/offset+octet-stream")
+        .body(data.clone())
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 409);
+    assert_eq!(response.headers()["upload-offset"], "1500");
+
+    // Resume from the reported offset
+    let response = server
+        .client
+        .patch(format!("{}{}", server.base_url, location))
+        .header("Tus-Resumable", "1.0.0")
+        .header("Upload-Offset", "1500")
+        .header("Content-Type", "application/offset+octet-stream")
+        .body(data[1500..].to_vec())
+        .send()
+        .await
+        .unwrap();
+    if wav_typefinder_installed() {
+        assert_eq!(response.status(), 204);
+        assert_eq!(response.headers()["upload-offset"], "4096");
+
+        // The completed upload is available as a job source
+        let upload: Value = server
+            .client
+            .get(format!("{}/uploads/{}", server.base_url, upload_id))
+            .send()
+            .await
+            .unwrap()
+            .json()
+            .await
+            .unwrap();
+        assert_eq!(upload["size"], 4096);
+        assert_eq!(upload["filename"], "recording.mp4");
+        assert_eq!(upload["source_url"], format!("upload://{upload_id}"));
+        assert_eq!(std::fs::read(upload_dir.join(&upload_id)).unwrap(), data);
+    } else {
+        // Completed data no typefinder identifies is discarded
+        assert_eq!(response.status(), 415);
+        let response = server
+            .client
+            .head(format!("{}{}", server.base_url, location))
+            .header("Tus-Resumable", "1.0.0")
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), 404);
+    }
+
+    // Termination discards an unfinished upload
+    let response = server
+        .client
+        .post(&tus_url)
+        .header("Tus-Resumable", "1.0.0")
+        .header("Upload-Length", "100")
+        .send()
+        .await
+        .unwrap();
+    let location = response.headers()["location"].to_str().unwrap().to_string();
+    let response = server
+        .client
+        .delete(format!("{}{}", server.base_url, location))
+        .header("Tus-Resumable", "1.0.0")
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 204);
+    let response = server
+        .client
+        .head(format!("{}{}", server.base_url, location))
+        .header("Tus-Resumable", "1.0.0")
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 404);
+
+    // ---
+    server.shutdown().await;
+    let _ = std::fs::remove_dir_all(&upload_dir);
+}
+
+#[tokio::test]
+async fn test_api_keys_scope_and_isolate_pipelines() {
+    // ---
+
+    use sha2::Digest;
+
+    let key_hash = |key: &str| format!("sha256:{}", hex::encode(Sha256::digest(key.as_bytes())));
+    let key_file = std::env::temp_dir().join(format!("api-keys-{}.json", std::process::id()));
+    std::fs::write(
+        &key_file,
+        serde_json::to_vec(&serde_json::json!({
+            "keys": [
+                {
+                    "id": "team-a",
+                    "key_hash": key_hash("key-a"),
+                    "scopes": ["pipelines:read", "pipelines:write", "custom_pipelines"]
+                },
+                {
+                    "id": "team-b",
+                    "key_hash": key_hash("key-b"),
+                    "scopes": ["pipelines:read", "pipelines:write"]
+                }
+            ]
+        }))
+        .unwrap(),
+    )
+    .unwrap();
+
+    let server = TestServer::start_with_args(&["--api-keys", key_file.to_str().unwrap()]).await;
+    let pipelines_url = endpoint_url!(server.base_url, "pipelines");
+    let create = serde_json::json!({
+        "description": "Owned pipeline",
+        "pipeline": "fakesrc num-buffers=1 ! fakesink"
+    });
+
+    // Public routes stay open; everything else needs a key
+    let response = server.client.get(&pipelines_url).send().await.unwrap();
+    assert_eq!(response.status(), 401);
+    let response = server
+        .client
+        .get(&pipelines_url)
+        .header("X-API-Key", "wrong")
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 401);
+
+    // Custom pipelines need their own scope
+    let response = server
+        .client
+        .post(&pipelines_url)
+        .header("X-API-Key", "key-b")
+        .json(&create)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 403);
+    let body: Value = response.json().await.unwrap();
+    assert_eq!(body["error"], "Insufficient scope");
+
+    let response = server
+        .client
+        .post(&pipelines_url)
+        .header("X-API-Key", "key-a")
+        .json(&create)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 200);
+    let created: Value = response.json().await.unwrap();
+    assert_eq!(created["owner"], "key:team-a");
+
+    // Shared templates need their own scope, even with custom_pipelines
+    let response = server
+        .client
+        .post(endpoint_url!(server.base_url, "templates"))
+        .header("X-API-Key", "key-a")
+        .json(&serde_json::json!({ "name": "shared", "template": "fakesrc ! fakesink" }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 403);
+    let response = server
+        .client
+        .delete(endpoint_url!(server.base_url, "templates", "shared"))
+        .header("X-API-Key", "key-a")
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 403);
+    let pipeline_url = format!("{pipelines_url}/{}", created["id"].as_str().unwrap());
+
+    // Another key can neither see nor stop the pipeline
+    let listing: Value = server
+        .client
+        .get(&pipelines_url)
+        .header("X-API-Key", "key-b")
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    assert!(listing["pipelines"].as_array().unwrap().is_empty());
+    for request in [
+        server.client.get(&pipeline_url),
+        server.client.delete(&pipeline_url),
+    ] {
+        let response = request.header("X-API-Key", "key-b").send().await.unwrap();
+        assert_eq!(response.status(), 404);
+    }
+
+    // The owner can
+    let listing: Value = server
+        .client
+        .get(&pipelines_url)
+        .header("X-API-Key", "key-a")
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(listing["pipelines"].as_array().unwrap().len(), 1);
+    let response = server
+        .client
+        .delete(&pipeline_url)
+        .header("X-API-Key", "key-a")
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 200);
+
+    // Uploads belong to the key that sent them (where WAV uploads are accepted)
+    if wav_typefinder_installed() {
+        let upload: Value = server
+            .client
+            .post(endpoint_url!(server.base_url, "uploads"))
+            .header("X-API-Key", "key-a")
+            .body(wav_bytes(256))
+            .send()
+            .await
+            .unwrap()
+            .json()
+            .await
+            .unwrap();
+        assert_eq!(upload["owner"], "key:team-a");
+        let upload_url = endpoint_url!(server.base_url, "uploads", upload["id"].as_str().unwrap());
+        let response = server
+            .client
+            .get(&upload_url)
+            .header("X-API-Key", "key-b")
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), 404);
+        let response = server
+            .client
+            .post(endpoint_url!(server.base_url, "convert"))
+            .header("X-API-Key", "key-b")
+            .json(&serde_json::json!({
+                "source_url": upload["source_url"],
+                "output_format": "webm"
+            }))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), 400);
+        let body: Value = response.json().await.unwrap();
+        assert_eq!(body["error"], "Unknown upload");
+        let response = server
+            .client
+            .get(&upload_url)
+            .header("X-API-Key", "key-a")
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), 200);
+    }
+
+    // So do resumable uploads
+    let response = server
+        .client
+        .post(endpoint_url!(server.base_url, "uploads/tus"))
+        .header("X-API-Key", "key-a")
+        .header("Tus-Resumable", "1.0.0")
+        .header("Upload-Length", "10")
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 201);
+    let tus_url = format!(
+        "{}{}",
+        server.base_url,
+        response.headers()["location"].to_str().unwrap()
+    );
+    for request in [
+        server.client.head(&tus_url),
+        server
+            .client
+            .patch(&tus_url)
+            .header("Upload-Offset", "0")
+            .header("Content-Type", "application/offset+octet-stream")
+            .body(vec![0u8; 10]),
+        server.client.delete(&tus_url),
+    ] {
+        let response = request
+            .header("X-API-Key", "key-b")
+            .header("Tus-Resumable", "1.0.0")
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), 404);
+    }
+    let response = server
+        .client
+        .head(&tus_url)
+        .header("X-API-Key", "key-a")
+        .header("Tus-Resumable", "1.0.0")
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 200);
+    assert_eq!(response.headers()["upload-offset"], "0");
+
+    // ---
+    server.shutdown().await;
+    let _ = std::fs::remove_file(&key_file);
+}
+
+#[tokio::test]
+async fn test_jwt_tenants_share_and_isolate_pipelines() {
+    // ---
+
+    use jsonwebtoken::{EncodingKey, Header};
+    use sha2::Digest;
+
+    // An API key named after a tenant
+    let key_file = std::env::temp_dir().join(format!("jwt-keys-{}.json", std::process::id()));
+    std::fs::write(
+        &key_file,
+        serde_json::to_vec(&serde_json::json!({
+            "keys": [{
+                "id": "acme",
+                "key_hash": format!("sha256:{}", hex::encode(Sha256::digest(b"acme-key"))),
+                "scopes": ["pipelines:read", "pipelines:write"]
+            }]
+        }))
+        .unwrap(),
+    )
+    .unwrap();
+
+    let server = TestServer::start_with_args(&[
+        "--jwt-secret",
+        "integration-secret",
+        "--api-keys",
+        key_file.to_str().unwrap(),
+    ])
+    .await;
+    let pipelines_url = endpoint_url!(server.base_url, "pipelines");
+
+    let token = |sub: &str, tenant: &str| {
+        let claims = serde_json::json!({
+            "sub": sub,
+            "tenant": tenant,
+            "scope": "pipelines:read pipelines:write custom_pipelines",
+            "exp": chrono::Utc::now().timestamp() + 300,
+        });
+        let key = EncodingKey::from_secret(b"integration-secret");
+        format!(
+            "Bearer {}",
+            jsonwebtoken::encode(&Header::default(), &claims, &key).unwrap()
+        )
+    };
+    let alice = token("alice", "acme");
+    let bob = token("bob", "acme");
+    let mallory = token("mallory", "globex");
+
+    let response = server
+        .client
+        .get(&pipelines_url)
+        .header("Authorization", "Bearer not-a-token")
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 401);
+    assert_eq!(response.headers()["www-authenticate"], "Bearer");
+
+    let created: Value = server
+        .client
+        .post(&pipelines_url)
+        .header("Authorization", &alice)
+        .json(&serde_json::json!({
+            "description": "Tenant pipeline",
+            "pipeline": "fakesrc num-buffers=1 ! fakesink"
+        }))
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(created["owner"], "tenant:acme");
+    let pipeline_url = format!("{pipelines_url}/{}", created["id"].as_str().unwrap());
+
+    // Another tenant cannot see or stop it
+    let listing: Value = server
+        .client
+        .get(&pipelines_url)
+        .header("Authorization", &mallory)
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    assert!(listing["pipelines"].as_array().unwrap().is_empty());
+    let response = server
+        .client
+        .delete(&pipeline_url)
+        .header("Authorization", &mallory)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 404);
+
+    // Neither can an API key sharing the tenant's name
+    let listing: Value = server
+        .client
+        .get(&pipelines_url)
+        .header("X-API-Key", "acme-key")
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    assert!(listing["pipelines"].as_array().unwrap().is_empty());
+    let response = server
+        .client
+        .get(&pipeline_url)
+        .header("X-API-Key", "acme-key")
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 404);
+
+    // A colleague in the same tenant can
+    let response = server
+        .client
+        .get(&pipeline_url)
+        .header("Authorization", &bob)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 200);
+    let response = server
+        .client
+        .delete(&pipeline_url)
+        .header("Authorization", &bob)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 200);
+
+    // ---
+    server.shutdown().await;
+    let _ = std::fs::remove_file(&key_file);
+}
+
+#[tokio::test]
+async fn test_rate_limits_and_running_quota() {
+    // ---
+
+    use jsonwebtoken::{EncodingKey, Header};
+
+    let server = TestServer::start_with_args(&[
+        "--jwt-secret",
+        "integration-secret",
+        "--rate-limit",
+        "api=2/10s",
+        "--max-running-per-owner",
+        "1",
+    ])
+    .await;
+    let pipelines_url = endpoint_url!(server.base_url, "pipelines");
+
+    let claims = serde_json::json!({
+        "sub": "alice",
+        "tenant": "acme",
+        "scope": "pipelines:read pipelines:write custom_pipelines",
+        "exp": chrono::Utc::now().timestamp() + 300,
+    });
+    let key = EncodingKey::from_secret(b"integration-secret");
+    let alice = format!(
+        "Bearer {}",
+        jsonwebtoken::encode(&Header::default(), &claims, &key).unwrap()
+    );
+
+    // Two listings fit the bucket, the third is refused
+    for remaining in ["1", "0"] {
+        let response = server
+            .client
+            .get(&pipelines_url)
+            .header("Authorization", &alice)
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), 200);
+        assert_eq!(response.headers()["x-ratelimit-limit"], "2");
+        assert_eq!(response.headers()["x-ratelimit-remaining"], remaining);
+    }
+    let response = server
+        .client
+        .get(&pipelines_url)
+        .header("Authorization", &alice)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 429);
+    let retry_after: u64 = response.headers()["retry-after"]
+        .to_str()
+        .unwrap()
+        .parse()
+        .unwrap();
+    assert!((1..=5).contains(&retry_after));
+
+    // Health checks are never limited
+    let response = server
+        .client
+        .get(endpoint_url!(server.base_url, "health"))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 200);
+
+    // Of concurrent submissions, exactly one endless pipeline gets the running quota
+    let body = serde_json::json!({
+        "description": "Endless pipeline",
+        "pipeline": "fakesrc ! fakesink"
+    });
+    let submissions: Vec<_> = (0..4)
+        .map(|_| {
+            let request = server
+                .client
+                .post(&pipelines_url)
+                .header("Authorization", &alice)
+                .json(&body);
+            tokio::spawn(request.send())
+        })
+        .collect();
+    let mut accepted = Vec::new();
+    for submission in submissions {
+        let response = submission.await.unwrap().unwrap();
+        if response.status().is_success() {
+            assert_eq!(response.headers()["x-quota-running-limit"], "1");
+            assert_eq!(response.headers()["x-quota-running-remaining"], "0");
+            accepted.push(response.json::<Value>().await.unwrap());
+        } else {
+            assert_eq!(response.status(), 429);
+            assert!(response.headers().contains_key("retry-after"));
+            let error: Value = response.json().await.unwrap();
+            assert_eq!(error["error"], "Quota exceeded");
+        }
+    }
+    assert_eq!(accepted.len(), 1, "Running quota overshot: {accepted:?}");
+    let created = &accepted[0];
+
+    // Stopping it is rate limited too, so wait for a token to refill
+    sleep(Duration::from_secs(6)).await;
+    let response = server
+        .client
+        .delete(format!(
+            "{pipelines_url}/{}",
+            created["id"].as_str().unwrap()
+        ))
+        .header("Authorization", &alice)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 200);
+
+    // ---
+    server.shutdown().await;
+}
+
+#[tokio::test]
+async fn test_metrics_endpoint_reports_requests_and_jobs() {
+    // ---
+
+    let server = TestServer::start_with_args(&["--workers", "1"]).await;
+    run_file_pipeline(&server, "metrics.bin").await;
+
+    let failed: Value = server
+        .client
+        .post(endpoint_url!(server.base_url, "pipelines"))
+        .json(&serde_json::json!({
+            "description": "Missing input",
+            "pipeline": "filesrc location=/nonexistent/input.mp4 ! fakesink"
+        }))
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    let pipeline = wait_for_state(&server, failed["id"].as_str().unwrap(), "Error").await;
+    assert!(pipeline["state"].get("Error").is_some());
+
+    let response = server
+        .client
+        .get(endpoint_url!(server.base_url, "metrics"))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 200);
+    assert!(response.headers()["content-type"]
+        .to_str()
+        .unwrap()
+        .starts_with("text/plain; version=0.0.4"));
+    let text = response.text().await.unwrap();
+
+    for expected in [
+        r#"media_http_requests_total{method="GET",route="/health",status="200"}"#,
+        r#"media_http_requests_total{method="POST",route="/pipelines",status="200"} 2"#,
+        r#"media_pipelines{kind="custom",state="Stopped"} 1"#,
+        r#"media_pipelines{kind="custom",state="Error"} 1"#,
+        r#"media_pipeline_queue_depth 0"#,
+        r#"media_pipeline_duration_seconds_count{kind="custom",outcome="success"} 1"#,
+        r#"media_pipeline_duration_seconds_count{kind="custom",outcome="failure"} 1"#,
+        r#"media_output_bytes_total{kind="custom"} 1024"#,
+        r#"media_gstreamer_errors_total{element="filesrc"} 1"#,
+    ] {
+        assert!(text.contains(expected), "missing {expected} in:\n{text}");
+    }
+
+    // ---
+    server.shutdown().await;
+}
+
+/// Spawns a local stand-in for an OTLP/HTTP collector that accepts every
+/// export to `/v1/traces` and records the raw protobuf bodies.
+async fn start_trace_collector() -> (String, Arc<Mutex<Vec<Vec<u8>>>>) {
+    // ---
+
+    use axum::{body::Bytes, extract::State, http::StatusCode, routing::post};
+
+    type Exports = Arc<Mutex<Vec<Vec<u8>>>>;
+
+    async fn collect(State(exports): State<Exports>, body: Bytes) -> StatusCode {
+        exports.lock().unwrap().push(body.to_vec());
+        StatusCode::OK
+    }
+
+    let exports: Exports = Arc::new(Mutex::new(Vec::new()));
+    let app = axum::Router::new()
+        .route("/v1/traces", post(collect))
+        .with_state(exports.clone());
+
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0")
+        .await
+        .expect("Failed to bind trace collector");
+    let url = format!("http://{}", listener.local_addr().unwrap());
+    tokio::spawn(async move { axum::serve(listener, app).await });
+
+    (url, exports)
+}
+
+/// Returns true when `needle` occurs anywhere in `haystack`.
+fn contains_bytes(haystack: &[u8], needle: &[u8]) -> bool {
+    haystack
+        .windows(needle.len())
+        .any(|window| window == needle)
+}
+
+#[tokio::test]
+async fn test_traces_exported_and_propagated_to_webhooks() {
+    // ---
+
+    let (collector_url, exports) = start_trace_collector().await;
+    let server =
+        TestServer::start_with_args(&["--workers", "1", "--otlp-endpoint", &collector_url]).await;
+    let (callback_url, received) = start_webhook_receiver().await;
+
+    // The caller's trace is continued by the service
+    let trace_id = "4bf92f3577b34da6a3ce929d0e0e4736";
+    let create_response = server
+        .client
+        .post(endpoint_url!(server.base_url, "pipelines"))
+        .header("traceparent", format!("00-{trace_id}-00f067aa0ba902b7-01"))
+        .json(&serde_json::json!({
+            "description": "Traced pipeline",
+            "pipeline": "fakesrc num-buffers=10 ! fakesink",
+            "callback_url": callback_url
+        }))
+        .send()
+        .await
+        .expect("Failed to create pipeline");
+    assert_eq!(create_response.status(), 200);
+    let created: Value = create_response.json().await.unwrap();
+    let pipeline_id = created["id"].as_str().unwrap().to_string();
+
+    // The receiver rejects the first delivery; both attempts carry the trace
+    let start = std::time::Instant::now();
+    while received.lock().unwrap().len() < 2 && start.elapsed() < Duration::from_secs(15) {
+        sleep(Duration::from_millis(100)).await;
+    }
+    let deliveries = received.lock().unwrap().clone();
+    assert_eq!(deliveries.len(), 2);
+    for (_, traceparent, _) in &deliveries {
+        let traceparent = traceparent.as_deref().expect("missing traceparent");
+        assert!(traceparent.starts_with(&format!("00-{trace_id}-")));
+    }
+
+    // Spans are exported in batches every few seconds; the request span is
+    // named after its route
+    let expected_spans: [&[u8]; 7] = [
+        b"POST /pipelines",
+        b"queue_wait",
+        b"execute_pipeline",
+        b"parse_pipeline",
+        b"run_pipeline",
+        b"publish_outputs",
+        b"deliver_webhook",
+    ];
+    let trace_bytes = hex::decode(trace_id).unwrap();
+    let start = std::time::Instant::now();
+    let exported = loop {
+        let exported = exports.lock().unwrap().concat();
+        let complete = expected_spans
+            .iter()
+            .all(|name| contains_bytes(&exported, name));
+        if complete || start.elapsed() > Duration::from_secs(20) {
+            break exported;
+        }
+        sleep(Duration::from_millis(250)).await;
+    };
+
+    for name in expected_spans {
+        assert!(
+            contains_bytes(&exported, name),
+            "span {} was not exported",
+            String::from_utf8_lossy(name)
+        );
+    }
+    assert!(contains_bytes(&exported, pipeline_id.as_bytes()));
+    assert!(contains_bytes(&exported, b"media-pipeline-service"));
+    assert!(contains_bytes(&exported, &trace_bytes));
+
+    // ---
+    server.shutdown().await;
+}
+
+#[tokio::test]
+async fn test_pipeline_logs_capture_bus_messages() {
+    // ---
+
+    use tokio::io::{AsyncBufReadExt, BufReader};
+
+    let mut server = TestServer::start_with_args(&["--workers", "1", "--log-format", "json"]).await;
+
+    // Every log line is a JSON object
+    let stdout = server.process.stdout.take().unwrap();
+    let mut lines = BufReader::new(stdout).lines();
+    let line = lines.next_line().await.unwrap().expect("no log output");
+    let record: Value = serde_json::from_str(&line).expect("log line is not JSON");
+    assert!(record["level"].is_string());
+    assert!(record["fields"]["message"].is_string());
+
+    // A failing pipeline reports the element error and its debug details
+    let failed: Value = server
+        .client
+        .post(endpoint_url!(server.base_url, "pipelines"))
+        .json(&serde_json::json!({
+            "description": "Missing input",
+            "pipeline": "filesrc location=/nonexistent/input.mp4 ! fakesink"
+        }))
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    let failed_id = failed["id"].as_str().unwrap();
+    wait_for_state(&server, failed_id, "Error").await;
+
+    let logs: Value = server
+        .client
+        .get(format!("{}/pipelines/{failed_id}/logs", server.base_url))
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(logs["pipeline_id"], failed_id);
+    assert_eq!(logs["dropped"], 0);
+    let entries = logs["entries"].as_array().unwrap();
+    let error = entries
+        .iter()
+        .find(|entry| {
+            let source = entry["source"].as_str().unwrap_or_default();
+            entry["level"] == "error" && source.starts_with("filesrc")
+        })
+        .expect("missing filesrc error");
+    assert!(error["message"].is_string());
+    assert!(error["debug"]
+        .as_str()
+        .unwrap()
+        .contains("/nonexistent/input.mp4"));
+
+    // A successful pipeline records its lifecycle
+    let succeeded: Value = server
+        .client
+        .post(endpoint_url!(server.base_url, "pipelines"))
+        .json(&serde_json::json!({
+            "description": "Short pipeline",
+            "pipeline": "fakesrc num-buffers=10 ! fakesink"
+        }))
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    let succeeded_id = succeeded["id"].as_str().unwrap();
+    wait_for_state(&server, succeeded_id, "Stopped").await;
+
+    let logs: Value = server
+        .client
+        .get(format!("{}/pipelines/{succeeded_id}/logs", server.base_url))
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    let messages: Vec<&str> = logs["entries"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|entry| entry["message"].as_str().unwrap())
+        .collect();
+    assert!(messages.contains(&"Pipeline is playing"));
+    assert!(messages.contains(&"End of stream reached"));
+
+    // Unknown pipelines have no logs
+    let missing = server
+        .client
+        .get(format!(
+            "{}/pipelines/00000000-0000-0000-0000-000000000000/logs",
+            server.base_url
+        ))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(missing.status(), 404);
+
+    // ---
+    server.shutdown().await;
+}
+
+#[tokio::test]
+async fn test_pipeline_graph_export() {
+    // ---
+
+    // Registry-only mode, so the pipeline is prerolled rather than run
+    let server = TestServer::start().await;
+
+    let created: Value = server
+        .client
+        .post(endpoint_url!(server.base_url, "pipelines"))
+        .json(&serde_json::json!({
+            "description": "Graph test pipeline",
+            "pipeline": "fakesrc num-buffers=1 ! capsfilter caps=audio/x-raw,rate=8000 ! fakesink"
+        }))
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    let graph_url = format!(
+        "{}/pipelines/{}/graph",
+        server.base_url,
+        created["id"].as_str().unwrap()
+    );
+
+    let response = server
+        .client
+        .get(format!(
+            "{graph_url}?format=dot&details=media-type,caps-details"
+        ))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 200);
+    assert!(response.headers()["content-type"]
+        .to_str()
+        .unwrap()
+        .starts_with("text/vnd.graphviz"));
+    assert_eq!(response.headers()["x-pipeline-graph-source"], "prerolled");
+    let dot = response.text().await.unwrap();
+    assert!(dot.starts_with("digraph pipeline"));
+    assert!(dot.contains("GstCapsFilter"));
+    assert!(
+        dot.contains("rate: 8000"),
+        "negotiated caps missing:\n{dot}"
+    );
+
+    // SVG needs Graphviz on the server
+    let response = server
+        .client
+        .get(format!("{graph_url}?format=svg"))
+        .send()
+        .await
+        .unwrap();
+    match response.status().as_u16() {
+        200 => assert_eq!(response.headers()["content-type"], "image/svg+xml"),
+        status => assert_eq!(status, 501),
+    }
+
+    for query in ["format=png", "details=bogus"] {
+        let response = server
+            .client
+            .get(format!("{graph_url}?{query}"))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), 400, "{query}");
+    }
+
+    // ---
+    server.shutdown().await;
+}
+
+#[tokio::test]
+async fn test_pipeline_topology() {
+    // ---
+
+    let server = TestServer::start().await;
+
+    let created: Value = server
+        .client
+        .post(endpoint_url!(server.base_url, "pipelines"))
+        .json(&serde_json::json!({
+            "description": "Topology test pipeline",
+            "pipeline": "fakesrc num-buffers=1 ! capsfilter caps=audio/x-raw,rate=8000 ! fakesink"
+        }))
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    let pipeline_id = created["id"].as_str().unwrap();
+
+    let response = server
+        .client
+        .get(format!(
+            "{}/pipelines/{pipeline_id}/topology",
+            server.base_url
+        ))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 200);
+    let topology: Value = response.json().await.unwrap();
+    assert_eq!(topology["pipeline_id"], pipeline_id);
+    assert_eq!(topology["source"], "prerolled");
+
+    // Elements in pipeline order, with typed properties
+    let elements = topology["elements"].as_array().unwrap();
+    let factories: Vec<&str> = elements
+        .iter()
+        .map(|element| element["factory"].as_str().unwrap())
+        .collect();
+    assert_eq!(factories, ["fakesrc", "capsfilter", "fakesink"]);
+    assert_eq!(elements[0]["properties"]["num-buffers"], 1);
+    assert_eq!(elements[0]["properties"]["is-live"], false);
+    assert_eq!(
+        elements[1]["properties"]["caps"],
+        "audio/x-raw, rate=(int)8000"
+    );
+
+    // Pads know their peers and negotiated caps
+    let capsfilter = elements[1]["name"].as_str().unwrap();
+    let sink = elements[2]["name"].as_str().unwrap();
+    let src_pad = elements[1]["pads"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .find(|pad| pad["direction"] == "src")
+        .unwrap();
+    assert_eq!(src_pad["peer"], format!("{sink}.sink"));
+    assert_eq!(src_pad["caps"], "audio/x-raw, rate=(int)8000");
+
+    let links = topology["links"].as_array().unwrap();
+    assert_eq!(links.len(), 2);
+    let link = links
+        .iter()
+        .find(|link| link["from"] == format!("{capsfilter}.src"))
+        .unwrap();
+    assert_eq!(link["to"], format!("{sink}.sink"));
+    assert_eq!(link["caps"], "audio/x-raw, rate=(int)8000");
+
+    // ---
+    server.shutdown().await;
+}
+
+#[tokio::test]
+async fn test_update_element_properties() {
+    // ---
+
+    let server = TestServer::start_with_args(&["--workers", "1"]).await;
+
+    let created: Value = server
+        .client
+        .post(endpoint_url!(server.base_url, "pipelines"))
+        .json(&serde_json::json!({
+            "description": "Live control test pipeline",
+            "pipeline": "fakesrc is-live=true ! identity name=gate sleep-time=1000 ! filesink name=out location=live.bin"
+        }))
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    let pipeline_id = created["id"].as_str().unwrap();
+    let running = wait_for_state(&server, pipeline_id, "Playing").await;
+    assert_eq!(running["state"], "Playing");
+
+    let element_url = format!("{}/pipelines/{pipeline_id}/elements/gate", server.base_url);
+    let patch = |url: &str, body: Value| server.client.patch(url).json(&body).send();
+
+    // Valid update reports old and new values
+    let response = patch(
+        &element_url,
+        serde_json::json!({ "properties": { "drop-probability": 0.5, "silent": false } }),
+    )
+    .await
+    .unwrap();
+    assert_eq!(response.status(), 200);
+    let update: Value = response.json().await.unwrap();
+    assert_eq!(update["element"], "gate");
+    assert_eq!(update["factory"], "identity");
+    let changes = update["properties"].as_array().unwrap();
+    assert_eq!(changes[0]["name"], "drop-probability");
+    assert_eq!(changes[0]["old_value"], 0.0);
+    assert_eq!(changes[0]["new_value"], 0.5);
+    assert_eq!(changes[1]["name"], "silent");
+    assert_eq!(changes[1]["old_value"], true);
+    assert_eq!(changes[1]["new_value"], false);
+
+    // Out of range, wrong type and unknown properties are rejected as a whole
+    for properties in [
+        serde_json::json!({ "drop-probability": 2.0 }),
+        serde_json::json!({ "silent": "no" }),
+        serde_json::json!({ "silent": true, "bitrate": 1000 }),
+    ] {
+        let response = patch(
+            &element_url,
+            serde_json::json!({ "properties": properties }),
+        )
+        .await
+        .unwrap();
+        assert_eq!(response.status(), 400, "accepted {properties}");
+    }
+    let topology: Value = server
+        .client
+        .get(format!(
+            "{}/pipelines/{pipeline_id}/topology",
+            server.base_url
+        ))
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    let gate = topology["elements"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .find(|element| element["name"] == "gate")
+        .unwrap();
+    assert_eq!(gate["properties"]["drop-probability"], 0.5);
+    assert_eq!(gate["properties"]["silent"], false);
+
+    // Sink paths stay where submission confined them
+    for location in ["/tmp/redirected-%05d.ts", "other.bin"] {
+        let response = patch(
+            &format!("{}/pipelines/{pipeline_id}/elements/out", server.base_url),
+            serde_json::json!({ "properties": { "location": location } }),
+        )
+        .await
+        .unwrap();
+        assert_eq!(response.status(), 400, "accepted {location}");
+        let body: Value = response.json().await.unwrap();
+        assert!(body["details"].as_str().unwrap().contains("names a file"));
+    }
+
+    let response = patch(
+        &format!(
+            "{}/pipelines/{pipeline_id}/elements/missing",
+            server.base_url
+        ),
+        serde_json::json!({ "properties": { "silent": true } }),
+    )
+    .await
+    .unwrap();
+    assert_eq!(response.status(), 404);
+
+    // Stopped pipelines can no longer be changed
+    server
+        .client
+        .delete(endpoint_url!(server.base_url, "pipelines", pipeline_id))
+        .send()
+        .await
+        .unwrap();
+    wait_for_state(&server, pipeline_id, "Stopped").await;
+    let response = patch(
+        &element_url,
+        serde_json::json!({ "properties": { "silent": true } }),
+    )
+    .await
+    .unwrap();
+    assert_eq!(response.status(), 409);
+
+    // ---
+    server.shutdown().await;
+}
+
+#[tokio::test]
+async fn test_seek_and_position() {
+    // ---
+
+    let server = TestServer::start_with_args(&["--workers", "1"]).await;
+
+    // 10 ms buffers played back in real time. Like most sources without a
+    // demuxer, fakesrc can only seek back to the start of its time segment.
+    let created: Value = server
+        .client
+        .post(endpoint_url!(server.base_url, "pipelines"))
+        .json(&serde_json::json!({
+            "description": "Seek test pipeline",
+            "pipeline": "fakesrc format=time sizetype=fixed sizemax=1000 datarate=100000 ! fakesink sync=true"
+        }))
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    let pipeline_id = created["id"].as_str().unwrap();
+    wait_for_state(&server, pipeline_id, "Playing").await;
+    sleep(Duration::from_millis(1500)).await;
+
+    let position_url = format!("{}/pipelines/{pipeline_id}/position", server.base_url);
+    let seek_url = format!("{}/pipelines/{pipeline_id}/seek", server.base_url);
+    let seek = |body: Value| server.client.post(&seek_url).json(&body).send();
+
+    let response = server.client.get(&position_url).send().await.unwrap();
+    assert_eq!(response.status(), 200);
+    let position: Value = response.json().await.unwrap();
+    assert_eq!(position["pipeline_id"], pipeline_id);
+    assert_eq!(position["rate"], 1.0);
+    assert_eq!(position["seekable"], true);
+    assert!(position["position"].as_f64().unwrap() >= 1.0);
+    assert!(position["duration"].is_null());
+
+    // Flushing seek back to the start at double speed
+    let response = seek(serde_json::json!({ "position": 0.0, "rate": 2.0, "accurate": true }))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 200);
+    let seeked: Value = response.json().await.unwrap();
+    assert_eq!(seeked["rate"], 2.0);
+    assert!(seeked["position"].as_f64().unwrap() < 1.0);
+
+    // Seeks the source cannot perform and invalid parameters
+    let response = seek(serde_json::json!({ "position": 60.0 })).await.unwrap();
+    assert_eq!(response.status(), 422);
+    for body in [
+        serde_json::json!({ "rate": 0.0 }),
+        serde_json::json!({ "position": -1.0 }),
+    ] {
+        let response = seek(body.clone()).await.unwrap();
+        assert_eq!(response.status(), 400, "accepted {body}");
+    }
+
+    // Stopped pipelines have no position
+    server
+        .client
+        .delete(endpoint_url!(server.base_url, "pipelines", pipeline_id))
+        .send()
+        .await
+        .unwrap();
+    wait_for_state(&server, pipeline_id, "Stopped").await;
+    let response = server.client.get(&position_url).send().await.unwrap();
+    assert_eq!(response.status(), 409);
+    let response = seek(serde_json::json!({ "position": 0.0 })).await.unwrap();
+    assert_eq!(response.status(), 409);
+
+    // ---
+    server.shutdown().await;
+}
+
+#[tokio::test]
+async fn test_pipeline_snapshot() {
+    // ---
+
+    let server = TestServer::start_with_args(&["--workers", "2"]).await;
+
+    let create = |description: &str, pipeline: &str| {
+        server
+            .client
+            .post(endpoint_url!(server.base_url, "pipelines"))
+            .json(&serde_json::json!({ "description": description, "pipeline": pipeline }))
+            .send()
+    };
+
+    // A live 4x4 RGB "video" feeding a preview branch
+    let video: Value = create(
+        "Snapshot test pipeline",
+        "fakesrc is-live=true sizetype=fixed sizemax=48 \
+         ! video/x-raw,format=RGB,width=4,height=4,framerate=10/1 ! fakesink name=preview",
+    )
+    .await
+    .unwrap()
+    .json()
+    .await
+    .unwrap();
+    let video_id = video["id"].as_str().unwrap();
+    let audio: Value = create(
+        "Snapshot test without video",
+        "fakesrc is-live=true ! fakesink",
+    )
+    .await
+    .unwrap()
+    .json()
+    .await
+    .unwrap();
+    let audio_id = audio["id"].as_str().unwrap();
+    wait_for_state(&server, video_id, "Playing").await;
+    wait_for_state(&server, audio_id, "Playing").await;
+    sleep(Duration::from_millis(300)).await;
+
+    // Encoding needs videoconvert and jpegenc/pngenc on the server
+    for (path, content_type, magic) in [
+        ("snapshot.jpg", "image/jpeg", &b"\xff\xd8"[..]),
+        ("snapshot.png?element=preview", "image/png", &b"\x89PNG"[..]),
+    ] {
+        let response = server
+            .client
+            .get(format!("{}/pipelines/{video_id}/{path}", server.base_url))
+            .send()
+            .await
+            .unwrap();
+        match response.status().as_u16() {
+            200 => {
+                assert_eq!(response.headers()["content-type"], content_type);
+                assert_eq!(response.headers()["cache-control"], "no-store");
+                assert!(response.bytes().await.unwrap().starts_with(magic));
+            }
+            status => assert_eq!(status, 501, "{path}"),
+        }
+    }
+
+    // No raw video frame, or no such element
+    for url in [
+        format!("{}/pipelines/{audio_id}/snapshot.jpg", server.base_url),
+        format!(
+            "{}/pipelines/{video_id}/snapshot.jpg?element=missing",
+            server.base_url
+        ),
+    ] {
+        let response = server.client.get(&url).send().await.unwrap();
+        assert_eq!(response.status(), 404, "{url}");
+    }
+
+    // HLS streams carry a raw branch for snapshots; creating and playing
+    // them needs the HLS plugins and network access on the server
+    let response = server
+        .client
+        .post(endpoint_url!(server.base_url, "stream"))
+        .json(&serde_json::json!({
+            "source_url": "https://commondatastorage.googleapis.com/gtv-videos-bucket/sample/BigBuckBunny.mp4",
+            "stream_type": "hls"
+        }))
+        .send()
+        .await
+        .unwrap();
+    if response.status() == 200 {
+        let stream: Value = response.json().await.unwrap();
+        let stream_id = stream["pipeline_id"].as_str().unwrap();
+        let stream_url = endpoint_url!(server.base_url, "pipelines", stream_id);
+        let get = || async {
+            let record: Value = server
+                .client
+                .get(&stream_url)
+                .send()
+                .await
+                .unwrap()
+                .json()
+                .await
+                .unwrap();
+            record
+        };
+        let mut record = get().await;
+        assert!(record["pipeline_string"]
+            .as_str()
+            .unwrap()
+            .contains("fakesink name=snapshot"));
+
+        let start = std::time::Instant::now();
+        while record["state"] == "Created" && start.elapsed() < Duration::from_secs(10) {
+            sleep(Duration::from_millis(100)).await;
+            record = get().await;
+        }
+        if record["state"] == "Playing" {
+            let snapshot_url = format!("{stream_url}/snapshot.jpg?element=snapshot");
+            let mut response = server.client.get(&snapshot_url).send().await.unwrap();
+            while response.status() == 404 && start.elapsed() < Duration::from_secs(20) {
+                sleep(Duration::from_millis(250)).await;
+                response = server.client.get(&snapshot_url).send().await.unwrap();
+            }
+            assert!(
+                [200, 501].contains(&response.status().as_u16()),
+                "{}",
+                response.status()
+            );
+        }
+        server.client.delete(&stream_url).send().await.unwrap();
+    } else {
+        assert_eq!(response.status(), 500);
+        let body: Value = response.json().await.unwrap();
+        assert_eq!(body["error"], "Generated invalid streaming pipeline");
+    }
+
+    // Stopped pipelines have no frames to grab
+    server
+        .client
+        .delete(endpoint_url!(server.base_url, "pipelines", video_id))
+        .send()
+        .await
+        .unwrap();
+    wait_for_state(&server, video_id, "Stopped").await;
+    let response = server
+        .client
+        .get(format!(
+            "{}/pipelines/{video_id}/snapshot.jpg",
+            server.base_url
+        ))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 409);
+
+    // ---
+    server
+        .client
+        .delete(endpoint_url!(server.base_url, "pipelines", audio_id))
+        .send()
+        .await
+        .unwrap();
+    server.shutdown().await;
+}
+
+#[tokio::test]
+async fn test_pipeline_templates() {
+    // ---
+
+    let template_dir = std::env::temp_dir().join(format!("templates-{}", std::process::id()));
+    let template_dir_arg = template_dir.display().to_string();
+    let server = TestServer::start_with_args(&["--template-dir", &template_dir_arg]).await;
+    let templates_url = endpoint_url!(server.base_url, "templates");
+    let save = |name: &str, template: &str| {
+        server
+            .client
+            .post(&templates_url)
+            .json(&serde_json::json!({ "name": name, "template": template }))
+            .send()
+    };
+
+    // Valid template is saved with its parsed parameters
+    let response = save(
+        "tagged",
+        "fakesrc num-buffers={{buffers:int:1..100}} ! identity name={{label}} ! fakesink",
+    )
+    .await
+    .unwrap();
+    assert_eq!(response.status(), 200);
+    let saved: Value = response.json().await.unwrap();
+    assert_eq!(saved["name"], "tagged");
+    assert_eq!(
+        saved["parameters"],
+        serde_json::json!([
+            { "name": "buffers", "type": "int", "min": 1, "max": 100 },
+            { "name": "label", "type": "string" }
+        ])
+    );
+
+    // Duplicates, malformed placeholders, invalid pipelines and names
+    let response = save("tagged", "fakesrc ! fakesink").await.unwrap();
+    assert_eq!(response.status(), 409);
+    for (name, template) in [
+        (
+            "broken",
+            "fakesrc num-buffers={{buffers:int:x..y}} ! fakesink",
+        ),
+        ("quoted", "fakesrc ! identity name=\"{{label}}\" ! fakesink"),
+        (
+            "inside-quotes",
+            "fakesrc ! filesink location=\"x {{path}}\"",
+        ),
+        ("unknown", "fakesrc ! nosuchelement{{id:int}} ! fakesink"),
+        ("../escape", "fakesrc ! fakesink"),
+    ] {
+        let response = save(name, template).await.unwrap();
+        assert_eq!(response.status(), 400, "accepted {name}");
+    }
+
+    // Listing and lookup
+    let listed: Value = server
+        .client
+        .get(&templates_url)
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(listed["templates"].as_array().unwrap().len(), 1);
+    let template_url = endpoint_url!(server.base_url, "templates", "tagged");
+    let response = server.client.get(&template_url).send().await.unwrap();
+    assert_eq!(response.status(), 200);
+
+    // Values are substituted as escaped literals and cannot add elements
+    let instantiate_url = format!("{template_url}/instantiate");
+    let label = "x\" ! fakesink name=\"extra";
+    let response = server
+        .client
+        .post(&instantiate_url)
+        .json(&serde_json::json!({ "parameters": { "buffers": 5, "label": label } }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 200);
+    let created: Value = response.json().await.unwrap();
+    assert_eq!(
+        created["pipeline_string"],
+        "fakesrc num-buffers=5 ! identity name=\"x\\\" ! fakesink name=\\\"extra\" ! fakesink"
+    );
+    let pipeline_id = created["id"].as_str().unwrap();
+    let topology: Value = server
+        .client
+        .get(format!(
+            "{}/pipelines/{pipeline_id}/topology",
+            server.base_url
+        ))
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    let elements = topology["elements"].as_array().unwrap();
+    let factories: Vec<&str> = elements
+        .iter()
+        .map(|element| element["factory"].as_str().unwrap())
+        .collect();
+    assert_eq!(factories, ["fakesrc", "identity", "fakesink"]);
+    assert_eq!(elements[1]["name"], label);
+
+    // Out of range, wrongly typed, missing and unknown values
+    for parameters in [
+        serde_json::json!({ "buffers": 500, "label": "a" }),
+        serde_json::json!({ "buffers": "5", "label": "a" }),
+        serde_json::json!({ "label": "a" }),
+        serde_json::json!({ "buffers": 5, "label": "a", "extra": 1 }),
+    ] {
+        let response = server
+            .client
+            .post(&instantiate_url)
+            .json(&serde_json::json!({ "parameters": parameters }))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), 400, "accepted {parameters}");
+    }
+    let response = server
+        .client
+        .post(format!("{}/templates/missing/instantiate", server.base_url))
+        .json(&serde_json::json!({ "parameters": {} }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 404);
+
+    // Deleted templates are gone
+    let response = server.client.delete(&template_url).send().await.unwrap();
+    assert_eq!(response.status(), 200);
+    let response = server.client.get(&template_url).send().await.unwrap();
+    assert_eq!(response.status(), 404);
+
+    // ---
+    server.shutdown().await;
+    let _ = std::fs::remove_dir_all(&template_dir);
+}
+
+#[tokio::test]
+async fn test_create_pipeline_from_graph() {
+    // ---
+
+    let server = TestServer::start_with_args(&["--workers", "1"]).await;
+    let create = |body: Value| {
+        server
+            .client
+            .post(endpoint_url!(server.base_url, "pipelines"))
+            .json(&body)
+            .send()
+    };
+    let elements = serde_json::json!([
+        { "factory": "fakesrc", "name": "src", "properties": { "num-buffers": 5 } },
+        { "factory": "tee", "name": "split" },
+        { "factory": "queue", "name": "q" },
+        { "factory": "fakesink", "name": "out", "properties": { "sync": false } }
+    ]);
+
+    // Elements are created by factory and linked as described
+    let response = create(serde_json::json!({
+        "description": "Graph test pipeline",
+        "graph": {
+            "elements": elements,
+            "links": [
+                { "from": "src", "to": "split", "caps": "audio/x-raw,rate=8000" },
+                { "from": "split.src_%u", "to": "q" },
+                { "from": "q", "to": "out.sink" }
+            ]
+        }
+    }))
+    .await
+    .unwrap();
+    assert_eq!(response.status(), 200);
+    let created: Value = response.json().await.unwrap();
+    assert_eq!(created["graph"]["elements"], elements);
+    assert_eq!(
+        created["pipeline_string"],
+        "fakesrc name=src num-buffers=5 tee name=split queue name=q fakesink name=out sync=false \
+         src. ! capsfilter caps=\"audio/x-raw,rate=8000\" ! split. split.src_%u ! q. q. ! out.sink"
+    );
+    let pipeline_id = created["id"].as_str().unwrap();
+    let finished = wait_for_state(&server, pipeline_id, "Stopped").await;
+    assert_eq!(finished["graph"], created["graph"]);
+
+    let topology: Value = server
+        .client
+        .get(format!(
+            "{}/pipelines/{pipeline_id}/topology",
+            server.base_url
+        ))
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    let mut names: Vec<&str> = topology["elements"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|element| element["name"].as_str().unwrap())
+        .filter(|name| !name.starts_with("capsfilter"))
+        .collect();
+    names.sort();
+    assert_eq!(names, ["out", "q", "split", "src"]);
+
+    // Names and values are never parsed as pipeline syntax
+    let invalid = [
+        serde_json::json!({ "description": "Both", "pipeline": "fakesrc ! fakesink",
+            "graph": { "elements": elements, "links": [{ "from": "src", "to": "out" }] } }),
+        serde_json::json!({ "description": "Neither" }),
+        serde_json::json!({ "description": "Injected name", "graph": {
+            "elements": [
+                { "factory": "fakesrc", "name": "src ! filesink location=/etc/x" },
+                { "factory": "fakesink", "name": "out" }
+            ],
+            "links": [{ "from": "src", "to": "out" }] } }),
+        serde_json::json!({ "description": "Unknown property", "graph": {
+            "elements": [
+                { "factory": "fakesrc", "name": "src", "properties": { "location": "x" } },
+                { "factory": "fakesink", "name": "out" }
+            ],
+            "links": [{ "from": "src", "to": "out" }] } }),
+        serde_json::json!({ "description": "Bad link", "graph": {
+            "elements": elements, "links": [{ "from": "out", "to": "src" }] } }),
+    ];
+    for body in invalid {
+        let response = create(body.clone()).await.unwrap();
+        assert_eq!(response.status(), 400, "accepted {body}");
+        let error: Value = response.json().await.unwrap();
+        assert_eq!(error["error"], "Invalid pipeline configuration");
+    }
+
+    // ---
+    server.shutdown().await;
+}
+
+#[tokio::test]
+async fn test_deep_pipeline_validation() {
+    // ---
+
+    let server = TestServer::start().await;
+    let create = |validate: &str, body: Value| {
+        server
+            .client
+            .post(format!("{}/pipelines?validate={validate}", server.base_url))
+            .json(&body)
+            .send()
+    };
+    let unlinked = serde_json::json!({
+        "description": "Unlinked sink",
+        "pipeline": "fakesrc num-buffers=1 ! fakesink fakesink name=lonely"
+    });
+
+    // Basic validation only parses, deep validation prerolls
+    let response = create("basic", unlinked.clone()).await.unwrap();
+    assert_eq!(response.status(), 200);
+    let response = create("deep", unlinked).await.unwrap();
+    assert_eq!(response.status(), 400);
+    let error: Value = response.json().await.unwrap();
+    assert_eq!(error["error"], "Invalid pipeline configuration");
+    assert_eq!(
+        error["details"],
+        "lonely: The input pad 'sink' is not linked"
+    );
+    assert_eq!(
+        error["diagnostics"],
+        serde_json::json!([{
+            "severity": "error",
+            "kind": "not_linked",
+            "element": "lonely",
+            "pad": "sink",
+            "message": "The input pad 'sink' is not linked",
+            "debug": null
+        }])
+    );
+
+    // Caps negotiation and element failures, for strings and graphs
+    let cases = [
+        (
+            serde_json::json!({
+                "description": "Unfixed caps",
+                "pipeline": "fakesrc num-buffers=1 ! capsfilter name=filter caps=video/x-raw,width=[1,10] ! fakesink"
+            }),
+            "caps_negotiation",
+            "filter",
+        ),
+        (
+            serde_json::json!({
+                "description": "Missing input",
+                "graph": {
+                    "elements": [
+                        { "factory": "filesrc", "name": "input",
+                          "properties": { "location": "/nonexistent/input.mp4" } },
+                        { "factory": "fakesink", "name": "out" }
+                    ],
+                    "links": [{ "from": "input", "to": "out" }]
+                }
+            }),
+            "element",
+            "input",
+        ),
+    ];
+    for (body, kind, element) in cases {
+        let response = create("deep", body.clone()).await.unwrap();
+        assert_eq!(response.status(), 400, "accepted {body}");
+        let error: Value = response.json().await.unwrap();
+        assert_eq!(error["diagnostics"][0]["kind"], kind, "{error}");
+        assert_eq!(error["diagnostics"][0]["element"], element, "{error}");
+    }
+
+    // Pipelines that preroll are accepted
+    let response = create(
+        "deep",
+        serde_json::json!({
+            "description": "Prerolls",
+            "pipeline": "fakesrc num-buffers=1 ! capsfilter caps=audio/x-raw,rate=8000 ! fakesink"
+        }),
+    )
+    .await
+    .unwrap();
+    assert_eq!(response.status(), 200);
+
+    let response = create(
+        "thorough",
+        serde_json::json!({ "description": "Bad mode", "pipeline": "fakesrc ! fakesink" }),
+    )
+    .await
+    .unwrap();
+    assert_eq!(response.status(), 400);
+
+    // ---
+    server.shutdown().await;
+}
+
+#[tokio::test]
+async fn test_element_catalog() {
+    // ---
+
+    let server = TestServer::start().await;
+    let get = |path: String| server.client.get(path).send();
+
+    // Listing filtered by klass components
+    let listed: Value = get(endpoint_url!(server.base_url, "elements?klass=sink"))
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    let elements = listed["elements"].as_array().unwrap();
+    assert!(elements.iter().any(|element| element["name"] == "fakesink"));
+    assert!(elements
+        .iter()
+        .all(|element| element["klass"].as_str().unwrap().contains("Sink")));
+    let listed: Value = get(endpoint_url!(
+        server.base_url,
+        "elements?klass=Codec/NoSuchKlass"
+    ))
+    .await
+    .unwrap()
+    .json()
+    .await
+    .unwrap();
+    assert_eq!(listed["elements"], serde_json::json!([]));
+
+    // Details with pad templates, property types, defaults and ranges
+    let response = get(endpoint_url!(server.base_url, "elements", "tee"))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 200);
+    let tee: Value = response.json().await.unwrap();
+    assert_eq!(tee["name"], "tee");
+    assert_eq!(tee["plugin"], "coreelements");
+    assert!(tee["pad_templates"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .any(|template| template["name"] == "src_%u" && template["presence"] == "request"));
+
+    let fakesrc: Value = get(endpoint_url!(server.base_url, "elements", "fakesrc"))
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    let property = |name: &str| {
+        fakesrc["properties"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .find(|property| property["name"] == name)
+            .cloned()
+            .unwrap()
+    };
+    let num_buffers = property("num-buffers");
+    assert_eq!(num_buffers["type_name"], "gint");
+    assert_eq!(num_buffers["default"], -1);
+    assert_eq!(num_buffers["minimum"], -1);
+    assert!(property("data")["values"]
+        .as_array()
+        .unwrap()
+        .contains(&serde_json::json!("allocate")));
+
+    // Unknown factories
+    let response = get(endpoint_url!(
+        server.base_url,
+        "elements/{}",
+        "nosuchelement"
+    ))
+    .await
+    .unwrap();
+    assert_eq!(response.status(), 404);
+
+    // ---
+    server.shutdown().await;
+}
+
+#[tokio::test]
+async fn test_execution_limits() {
+    // ---
+
+    let server = TestServer::start_with_args(&[
+        "--workers",
+        "2",
+        "--default-max-runtime-seconds",
+        "1",
+        "--max-runtime-seconds-cap",
+        "60",
+    ])
+    .await;
+    let pipelines_url = endpoint_url!(server.base_url, "pipelines");
+    let create = |body: Value| server.client.post(&pipelines_url).json(&body).send();
+
+    // The server default stops a pipeline that would run forever
+    let response = create(serde_json::json!({
+        "description": "Endless",
+        "pipeline": "fakesrc ! identity sleep-time=10000 ! fakesink"
+    }))
+    .await
+    .unwrap();
+    assert_eq!(response.status(), 200);
+    let created: Value = response.json().await.unwrap();
+    assert_eq!(created["max_runtime_seconds"], 1);
+    let pipeline_id = created["id"].as_str().unwrap();
+
+    let finished = wait_for_state(&server, pipeline_id, "Error").await;
+    let reason = finished["state"]["Error"].as_str().unwrap();
+    assert!(reason.contains("max_runtime_seconds"), "{finished}");
+
+    // Output size is checked while the pipeline writes
+    let response = create(serde_json::json!({
+        "description": "Large output",
+        "pipeline": "fakesrc sizetype=fixed sizemax=4096 filltype=zero ! identity sleep-time=1000 ! filesink location=large.bin",
+        "max_runtime_seconds": 30,
+        "max_output_bytes": 100000
+    }))
+    .await
+    .unwrap();
+    assert_eq!(response.status(), 200);
+    let created: Value = response.json().await.unwrap();
+    assert_eq!(created["max_output_bytes"], 100000);
+    let pipeline_id = created["id"].as_str().unwrap();
+
+    let finished = wait_for_state(&server, pipeline_id, "Error").await;
+    let reason = finished["state"]["Error"].as_str().unwrap();
+    assert!(reason.contains("max_output_bytes"), "{finished}");
+
+    // Limits above the caps or of zero are refused, for every job type
+    for body in [
+        serde_json::json!({
+            "description": "Too long",
+            "pipeline": "fakesrc ! fakesink",
+            "max_runtime_seconds": 61
+        }),
+        serde_json::json!({
+            "description": "Zero",
+            "pipeline": "fakesrc ! fakesink",
+            "max_output_bytes": 0
+        }),
+    ] {
+        let response = create(body.clone()).await.unwrap();
+        assert_eq!(response.status(), 400, "accepted {body}");
+        let error: Value = response.json().await.unwrap();
+        assert_eq!(error["error"], "Invalid execution limits");
+    }
+    let response = server
+        .client
+        .post(endpoint_url!(server.base_url, "convert"))
+        .json(&serde_json::json!({
+            "source_url": "https://example.com/video.mp4",
+            "output_format": "webm",
+            "max_runtime_seconds": 3600
+        }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 400);
+
+    // ---
+    server.shutdown().await;
+}
+
+#[tokio::test]
+async fn test_retry_policy() {
+    // ---
+
+    let server = TestServer::start_with_args(&["--workers", "1"]).await;
+    let pipelines_url = endpoint_url!(server.base_url, "pipelines");
+    let create = |body: Value| server.client.post(&pipelines_url).json(&body).send();
+    let missing = std::env::temp_dir().join(format!("retry-{}/missing.bin", std::process::id()));
+
+    // A covered error is retried until max_attempts is reached
+    let response = create(serde_json::json!({
+        "description": "Missing source",
+        "pipeline": format!("filesrc location={} ! fakesink", missing.display()),
+        "retry": {"max_attempts": 3, "backoff_seconds": 0.1, "retry_on": ["ResourceError"]}
+    }))
+    .await
+    .unwrap();
+    assert_eq!(response.status(), 200);
+    let created: Value = response.json().await.unwrap();
+    assert_eq!(created["retry"]["backoff_multiplier"], 2.0);
+    assert_eq!(created["attempts"], serde_json::json!([]));
+    let pipeline_id = created["id"].as_str().unwrap().to_string();
+
+    let finished = wait_for_state(&server, &pipeline_id, "Error").await;
+    let attempts = finished["attempts"].as_array().unwrap();
+    assert_eq!(attempts.len(), 3, "{finished}");
+    for attempt in attempts {
+        let code = attempt["error_code"].as_str().unwrap();
+        assert!(code.starts_with("ResourceError::"), "{finished}");
+    }
+    assert!(attempts[0]["retry_at"].is_string());
+    assert!(attempts[2]["retry_at"].is_null());
+    assert_eq!(attempts[2]["attempt"], 3);
+
+    // A manual retry runs the pipeline again and starts a new series
+    let retry_url = format!("{}/pipelines/{pipeline_id}/retry", server.base_url);
+    let response = server.client.post(&retry_url).send().await.unwrap();
+    assert_eq!(response.status(), 200);
+    let retried: Value = response.json().await.unwrap();
+    assert_eq!(retried["state"], "Created");
+    assert!(retried["finished_at"].is_null());
+
+    let finished = wait_for_state(&server, &pipeline_id, "Error").await;
+    assert_eq!(
+        finished["attempts"].as_array().unwrap().len(),
+        6,
+        "{finished}"
+    );
+
+    // Errors outside the policy fail on the first attempt
+    let response = create(serde_json::json!({
+        "description": "Not retried",
+        "pipeline": format!("filesrc location={} ! fakesink", missing.display()),
+        "retry": {"max_attempts": 3, "backoff_seconds": 0.1, "retry_on": ["StreamError"]}
+    }))
+    .await
+    .unwrap();
+    let created: Value = response.json().await.unwrap();
+    let finished = wait_for_state(&server, created["id"].as_str().unwrap(), "Error").await;
+    assert_eq!(
+        finished["attempts"].as_array().unwrap().len(),
+        1,
+        "{finished}"
+    );
+
+    // Only finished pipelines can be retried
+    let response = create(serde_json::json!({
+        "description": "Endless",
+        "pipeline": "fakesrc ! identity sleep-time=10000 ! fakesink"
+    }))
+    .await
+    .unwrap();
+    let created: Value = response.json().await.unwrap();
+    let endless_id = created["id"].as_str().unwrap();
+    let response = server
+        .client
+        .post(format!("{}/pipelines/{endless_id}/retry", server.base_url))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 409);
+    let response = server
+        .client
+        .post(format!("{}/pipelines/unknown/retry", server.base_url))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 404);
+
+    // Invalid policies are refused
+    for retry in [
+        serde_json::json!({"max_attempts": 0}),
+        serde_json::json!({"max_attempts": 2, "retry_on": ["NetworkError"]}),
+    ] {
+        let response = create(serde_json::json!({
+            "description": "Invalid retry",
+            "pipeline": "fakesrc ! fakesink",
+            "retry": retry
+        }))
+        .await
+        .unwrap();
+        assert_eq!(response.status(), 400, "accepted {retry}");
+        let error: Value = response.json().await.unwrap();
+        assert_eq!(error["error"], "Invalid retry policy");
+    }
+
+    // ---
+    server.shutdown().await;
+}